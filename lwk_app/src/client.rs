@@ -11,7 +11,7 @@ use serde_json::Value;
 use crate::error::Error;
 use crate::method::Method;
 use crate::reqwest_transport::ReqwestHttpTransport;
-use crate::{request, response};
+use crate::{redact, request, response};
 
 pub struct Client {
     client: jsonrpc::Client,
@@ -20,7 +20,7 @@ pub struct Client {
 impl Client {
     pub fn new(addr: SocketAddr) -> Result<Self, Error> {
         let url = format!("http://{addr}");
-        let transport = ReqwestHttpTransport::new(url, TIMEOUT);
+        let transport = ReqwestHttpTransport::new(url, TIMEOUT)?;
         let client = jsonrpc::Client::with_transport(transport);
         Ok(Self { client })
     }
@@ -37,9 +37,9 @@ impl Client {
         let params = req.map(|req| to_raw_value(&req)).transpose()?;
         let method = method.to_string();
         let request = self.client.build_request(&method, params.as_deref());
-        log::trace!("---> {}", serde_json::to_string(&request)?);
+        log::trace!("---> {}", redact(serde_json::to_value(&request)?));
         let response = self.client.send_request(request)?;
-        log::trace!("<--- {}", serde_json::to_string(&response)?);
+        log::trace!("<--- {}", redact(serde_json::to_value(&response)?));
         match response.result.as_ref() {
             Some(result) => Ok(serde_json::from_str(result.get())?),
             None => match response.error {
@@ -61,11 +61,32 @@ impl Client {
         &self,
         name: String,
         mnemonic: String,
+        passphrase: Option<String>,
         persist: bool,
     ) -> Result<response::Signer, Error> {
         let req = request::SignerLoadSoftware {
             name,
-            mnemonic,
+            mnemonic: Some(mnemonic),
+            mnemonic_file: None,
+            passphrase,
+            persist,
+        };
+        self.make_request(Method::SignerLoadSoftware, Some(req))
+    }
+
+    /// Load a software signer reading the mnemonic from `mnemonic_file` on the server machine
+    pub fn signer_load_software_file(
+        &self,
+        name: String,
+        mnemonic_file: String,
+        passphrase: Option<String>,
+        persist: bool,
+    ) -> Result<response::Signer, Error> {
+        let req = request::SignerLoadSoftware {
+            name,
+            mnemonic: None,
+            mnemonic_file: Some(mnemonic_file),
+            passphrase,
             persist,
         };
         self.make_request(Method::SignerLoadSoftware, Some(req))
@@ -76,11 +97,31 @@ impl Client {
         name: String,
         id: String,
         emulator: Option<SocketAddr>,
+        expected_fingerprint: Option<String>,
     ) -> Result<response::Signer, Error> {
-        let req = request::SignerLoadJade { name, id, emulator };
+        let req = request::SignerLoadJade {
+            name,
+            id,
+            emulator,
+            expected_fingerprint,
+        };
         self.make_request(Method::SignerLoadJade, Some(req))
     }
 
+    pub fn signer_load_ledger(
+        &self,
+        name: String,
+        emulator: Option<SocketAddr>,
+        expected_fingerprint: Option<String>,
+    ) -> Result<response::Signer, Error> {
+        let req = request::SignerLoadLedger {
+            name,
+            emulator,
+            expected_fingerprint,
+        };
+        self.make_request(Method::SignerLoadLedger, Some(req))
+    }
+
     pub fn signer_load_external(
         &self,
         name: String,
@@ -94,8 +135,17 @@ impl Client {
         self.make_request(Method::WalletList, None::<Box<RawValue>>)
     }
 
-    pub fn wallet_load(&self, descriptor: String, name: String) -> Result<response::Wallet, Error> {
-        let req = request::WalletLoad { descriptor, name };
+    pub fn wallet_load(
+        &self,
+        descriptor: String,
+        change_descriptor: Option<String>,
+        name: String,
+    ) -> Result<response::Wallet, Error> {
+        let req = request::WalletLoad {
+            descriptor,
+            change_descriptor,
+            name,
+        };
         self.make_request(Method::WalletLoad, Some(req))
     }
 
@@ -117,8 +167,13 @@ impl Client {
         &self,
         name: String,
         with_tickers: bool,
+        hide_amounts: bool,
     ) -> Result<response::WalletBalance, Error> {
-        let req = request::WalletBalance { name, with_tickers };
+        let req = request::WalletBalance {
+            name,
+            with_tickers,
+            hide_amounts,
+        };
         self.make_request(Method::WalletBalance, Some(req))
     }
 
@@ -140,6 +195,34 @@ impl Client {
         self.make_request(Method::WalletAddress, Some(req))
     }
 
+    /// Get `count` consecutive receive addresses starting from `start_index`
+    pub fn wallet_address_batch(
+        &self,
+        name: String,
+        start_index: Option<u32>,
+        count: u32,
+        with_text_qr: bool,
+        with_uri_qr: Option<u8>,
+    ) -> Result<response::WalletAddressBatch, Error> {
+        let req = request::WalletAddressBatch {
+            name,
+            start_index,
+            count,
+            with_text_qr,
+            with_uri_qr,
+        };
+        self.make_request(Method::WalletAddressBatch, Some(req))
+    }
+
+    pub fn wallet_address_info_batch(
+        &self,
+        name: String,
+        addresses: Vec<String>,
+    ) -> Result<response::WalletAddressInfoBatch, Error> {
+        let req = request::WalletAddressInfoBatch { name, addresses };
+        self.make_request(Method::WalletAddressInfoBatch, Some(req))
+    }
+
     pub fn wallet_send_many(
         &self,
         name: String,
@@ -156,6 +239,36 @@ impl Client {
         self.make_request(Method::WalletSendMany, Some(req))
     }
 
+    pub fn wallet_send_many_smart_fee(
+        &self,
+        name: String,
+        addressees: Vec<UnvalidatedRecipient>,
+        target_blocks: u32,
+    ) -> Result<response::WalletSendManySmartFee, Error> {
+        let req = request::WalletSendManySmartFee {
+            addressees: addressees.into_iter().map(unvalidate_addressee).collect(),
+            name,
+            target_blocks,
+        };
+        self.make_request(Method::WalletSendManySmartFee, Some(req))
+    }
+
+    pub fn wallet_send_to_payment(
+        &self,
+        name: String,
+        uri: String,
+        amount_override: Option<u64>,
+        fee_rate: Option<f32>,
+    ) -> Result<response::Pset, Error> {
+        let req = request::WalletSendToPayment {
+            name,
+            uri,
+            amount_override,
+            fee_rate,
+        };
+        self.make_request(Method::WalletSendToPayment, Some(req))
+    }
+
     pub fn wallet_drain(
         &self,
         name: String,
@@ -184,18 +297,36 @@ impl Client {
         self.make_request(Method::SignerSinglesigDescriptor, Some(req))
     }
 
+    pub fn signer_account_descriptor(
+        &self,
+        name: String,
+        account: u32,
+        descriptor_blinding_key: String,
+        singlesig_kind: String,
+    ) -> Result<response::SignerSinglesigDescriptor, Error> {
+        let req = request::SignerAccountDescriptor {
+            name,
+            account,
+            descriptor_blinding_key,
+            singlesig_kind,
+        };
+        self.make_request(Method::SignerAccountDescriptor, Some(req))
+    }
+
     pub fn wallet_multisig_descriptor(
         &self,
         descriptor_blinding_key: String,
         multisig_kind: String,
         threshold: u32,
         keyorigin_xpubs: Vec<String>,
+        signers: Vec<String>,
     ) -> Result<response::WalletMultisigDescriptor, Error> {
         let req = request::WalletMultisigDescriptor {
             descriptor_blinding_key,
             multisig_kind,
             threshold,
             keyorigin_xpubs,
+            signers,
         };
         self.make_request(Method::WalletMultisigDescriptor, Some(req))
     }
@@ -209,6 +340,10 @@ impl Client {
         self.make_request(Method::SignerXpub, Some(req))
     }
 
+    pub fn signer_xpub_kinds(&self) -> Result<response::SignerXpubKinds, Error> {
+        self.make_request(Method::SignerXpubKinds, None::<Box<RawValue>>)
+    }
+
     pub fn signer_register_multisig(
         &self,
         name: String,
@@ -237,11 +372,80 @@ impl Client {
         self.make_request(Method::WalletBroadcast, Some(req))
     }
 
+    pub fn wallet_preview_tx(
+        &self,
+        name: String,
+        pset: String,
+    ) -> Result<response::WalletPreviewTx, Error> {
+        let req = request::WalletPreviewTx { name, pset };
+        self.make_request(Method::WalletPreviewTx, Some(req))
+    }
+
+    pub fn wallet_annotate_pset(
+        &self,
+        name: String,
+        pset: String,
+    ) -> Result<response::Pset, Error> {
+        let req = request::WalletAnnotatePset { name, pset };
+        self.make_request(Method::WalletAnnotatePset, Some(req))
+    }
+
+    pub fn wallet_blind_pset(&self, name: String, pset: String) -> Result<response::Pset, Error> {
+        let req = request::WalletBlindPset { name, pset };
+        self.make_request(Method::WalletBlindPset, Some(req))
+    }
+
+    pub fn wallet_hydrate_pset(
+        &self,
+        name: String,
+        pset: String,
+        inputs: Vec<usize>,
+    ) -> Result<response::Pset, Error> {
+        let req = request::WalletHydratePset { name, pset, inputs };
+        self.make_request(Method::WalletHydratePset, Some(req))
+    }
+
+    pub fn wallet_proof_of_reserves(
+        &self,
+        name: String,
+        challenge: String,
+    ) -> Result<response::Pset, Error> {
+        let req = request::WalletProofOfReserves { name, challenge };
+        self.make_request(Method::WalletProofOfReserves, Some(req))
+    }
+
+    pub fn wallet_verify_proof_of_reserves(
+        &self,
+        name: String,
+        challenge: String,
+        pset: String,
+    ) -> Result<response::WalletVerifyProofOfReserves, Error> {
+        let req = request::WalletVerifyProofOfReserves {
+            name,
+            challenge,
+            pset,
+        };
+        self.make_request(Method::WalletVerifyProofOfReserves, Some(req))
+    }
+
     pub fn wallet_details(&self, name: String) -> Result<response::WalletDetails, Error> {
         let req = request::WalletDetails { name };
         self.make_request(Method::WalletDetails, Some(req))
     }
 
+    pub fn wallet_public_descriptor(
+        &self,
+        name: String,
+    ) -> Result<response::WalletPublicDescriptor, Error> {
+        let req = request::WalletPublicDescriptor { name };
+        self.make_request(Method::WalletPublicDescriptor, Some(req))
+    }
+
+    pub fn wallet_policy(&self, name: String) -> Result<response::WalletPolicy, Error> {
+        let req = request::WalletPolicy { name };
+        self.make_request(Method::WalletPolicy, Some(req))
+    }
+
     pub fn signer_details(&self, name: String) -> Result<response::SignerDetails, Error> {
         let req = request::SignerDetails { name };
         self.make_request(Method::SignerDetails, Some(req))
@@ -271,16 +475,75 @@ impl Client {
     }
 
     pub fn wallet_utxos(&self, name: String) -> Result<response::WalletUtxos, Error> {
-        let req = request::WalletUtxos { name };
+        self.wallet_utxos_filtered(name, None, None, false)
+    }
+
+    /// Get the wallet UTXOs of the given asset with at least `min_confirmations` confirmations
+    pub fn wallet_utxos_filtered(
+        &self,
+        name: String,
+        asset: Option<String>,
+        min_confirmations: Option<u32>,
+        with_derivation: bool,
+    ) -> Result<response::WalletUtxos, Error> {
+        let req = request::WalletUtxos {
+            name,
+            asset,
+            min_confirmations,
+            with_derivation,
+        };
         self.make_request(Method::WalletUtxos, Some(req))
     }
 
+    /// Apply a serialized [`lwk_wollet::Update`], returning the new tip of the wallet
+    pub fn wallet_apply_update(
+        &self,
+        name: String,
+        update_hex: String,
+    ) -> Result<response::WalletTip, Error> {
+        let req = request::WalletApplyUpdate { name, update_hex };
+        self.make_request(Method::WalletApplyUpdate, Some(req))
+    }
+
+    pub fn wallet_tip(&self, name: String) -> Result<response::WalletTip, Error> {
+        let req = request::WalletTip { name };
+        self.make_request(Method::WalletTip, Some(req))
+    }
+
+    /// Get the UTXOs below `threshold` satoshi not received from the wallet itself
+    pub fn wallet_dust_deposits(
+        &self,
+        name: String,
+        threshold: u64,
+    ) -> Result<response::WalletUtxos, Error> {
+        let req = request::WalletDustDeposits { name, threshold };
+        self.make_request(Method::WalletDustDeposits, Some(req))
+    }
+
     pub fn wallet_txs(
         &self,
         name: String,
         with_tickers: bool,
     ) -> Result<response::WalletTxs, Error> {
-        let req = request::WalletTxs { name, with_tickers };
+        self.wallet_txs_page(name, with_tickers, false, None, None)
+    }
+
+    /// Get a page of the wallet transactions, skipping the first `offset` ones
+    pub fn wallet_txs_page(
+        &self,
+        name: String,
+        with_tickers: bool,
+        hide_amounts: bool,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Result<response::WalletTxs, Error> {
+        let req = request::WalletTxs {
+            name,
+            with_tickers,
+            hide_amounts,
+            offset,
+            limit,
+        };
         self.make_request(Method::WalletTxs, Some(req))
     }
 
@@ -322,6 +585,54 @@ impl Client {
         self.make_request(Method::WalletSetAddrMemo, Some(req))
     }
 
+    pub fn wallet_set_meta(
+        &self,
+        name: String,
+        namespace: String,
+        key: String,
+        value: String,
+    ) -> Result<response::Empty, Error> {
+        let req = request::WalletSetMeta {
+            name,
+            namespace,
+            key,
+            value,
+        };
+        self.make_request(Method::WalletSetMeta, Some(req))
+    }
+
+    pub fn wallet_get_meta(
+        &self,
+        name: String,
+        namespace: String,
+        key: Option<String>,
+    ) -> Result<response::WalletGetMeta, Error> {
+        let req = request::WalletGetMeta {
+            name,
+            namespace,
+            key,
+        };
+        self.make_request(Method::WalletGetMeta, Some(req))
+    }
+
+    pub fn wallet_diagnose(&self, name: String) -> Result<response::WalletDiagnose, Error> {
+        let req = request::WalletDiagnose { name };
+        self.make_request(Method::WalletDiagnose, Some(req))
+    }
+
+    pub fn wallet_scan_scope(&self, name: String) -> Result<response::WalletScanScope, Error> {
+        let req = request::WalletScanScope { name };
+        self.make_request(Method::WalletScanScope, Some(req))
+    }
+
+    pub fn wallet_issued_assets(
+        &self,
+        name: String,
+    ) -> Result<response::WalletIssuedAssets, Error> {
+        let req = request::WalletIssuedAssets { name };
+        self.make_request(Method::WalletIssuedAssets, Some(req))
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn wallet_issue(
         &self,
@@ -345,6 +656,20 @@ impl Client {
         self.make_request(Method::WalletIssue, Some(req))
     }
 
+    pub fn wallet_issue_many(
+        &self,
+        name: String,
+        issuances: Vec<request::IssuanceSpec>,
+        fee_rate: Option<f32>,
+    ) -> Result<response::Pset, Error> {
+        let req = request::WalletIssueMany {
+            name,
+            issuances,
+            fee_rate,
+        };
+        self.make_request(Method::WalletIssueMany, Some(req))
+    }
+
     pub fn wallet_reissue(
         &self,
         name: String,
@@ -368,17 +693,55 @@ impl Client {
         name: String,
         asset: String,
         satoshi_asset: u64,
+        burn_all: bool,
         fee_rate: Option<f32>,
     ) -> Result<response::Pset, Error> {
         let req = request::WalletBurn {
             name,
             asset,
             satoshi_asset,
+            burn_all,
             fee_rate,
         };
         self.make_request(Method::WalletBurn, Some(req))
     }
 
+    pub fn wallet_pegout(
+        &self,
+        name: String,
+        mainchain_address: String,
+        satoshi: u64,
+        fee_rate: Option<f32>,
+    ) -> Result<response::Pset, Error> {
+        let req = request::WalletPegout {
+            name,
+            mainchain_address,
+            satoshi,
+            fee_rate,
+        };
+        self.make_request(Method::WalletPegout, Some(req))
+    }
+
+    pub fn wallet_claim_pegin(
+        &self,
+        name: String,
+        bitcoin_tx: String,
+        vout: u32,
+        txout_proof: String,
+        claim_script: String,
+        fee_rate: Option<f32>,
+    ) -> Result<response::Pset, Error> {
+        let req = request::WalletClaimPegin {
+            name,
+            bitcoin_tx,
+            vout,
+            txout_proof,
+            claim_script,
+            fee_rate,
+        };
+        self.make_request(Method::WalletClaimPegin, Some(req))
+    }
+
     pub fn asset_contract(
         &self,
         domain: String,
@@ -437,6 +800,22 @@ impl Client {
         self.make_request(Method::AssetPublish, Some(req))
     }
 
+    pub fn asset_verify(
+        &self,
+        asset_id: String,
+        contract: String,
+        issuance_tx: String,
+        domain_proof: bool,
+    ) -> Result<response::Empty, Error> {
+        let req = request::AssetVerify {
+            asset_id,
+            contract,
+            issuance_tx,
+            domain_proof,
+        };
+        self.make_request(Method::AssetVerify, Some(req))
+    }
+
     pub fn amp2_descriptor(&self, name: String) -> Result<response::Amp2Descriptor, Error> {
         let req = request::Amp2Descriptor { name };
         self.make_request(Method::Amp2Descriptor, Some(req))
@@ -452,6 +831,14 @@ impl Client {
         self.make_request(Method::Amp2Cosign, Some(req))
     }
 
+    pub fn amp2_cosign_status(
+        &self,
+        pset_or_id: String,
+    ) -> Result<response::Amp2CosignStatus, Error> {
+        let req = request::Amp2CosignStatus { pset_or_id };
+        self.make_request(Method::Amp2CosignStatus, Some(req))
+    }
+
     pub fn schema(&self, arg: Method, direction: request::Direction) -> Result<Value, Error> {
         let req = request::Schema {
             method: arg.to_string(),
@@ -465,6 +852,28 @@ impl Client {
         self.make_request(Method::SignerJadeId, Some(req))
     }
 
+    pub fn parse_payment(&self, uri: String) -> Result<response::ParsePayment, Error> {
+        let req = request::ParsePayment { uri };
+        self.make_request(Method::ParsePayment, Some(req))
+    }
+
+    pub fn pset_convert_version(
+        &self,
+        pset: String,
+        target: u32,
+    ) -> Result<response::PsetConvertVersion, Error> {
+        let req = request::PsetConvertVersion { pset, target };
+        self.make_request(Method::PsetConvertVersion, Some(req))
+    }
+
+    pub fn pset_blinding_status(
+        &self,
+        pset: String,
+    ) -> Result<response::PsetBlindingStatus, Error> {
+        let req = request::PsetBlindingStatus { pset };
+        self.make_request(Method::PsetBlindingStatus, Some(req))
+    }
+
     pub fn scan(&self) -> Result<Value, Error> {
         self.make_request(Method::Scan, None::<Box<RawValue>>)
     }
@@ -0,0 +1,45 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of the current time for the time-based logic of the app, such as cache expirations
+///
+/// Inject a [`MockClock`] to test expirations without waiting.
+pub trait Clock: Send + Sync {
+    /// Time elapsed since the unix epoch
+    fn now(&self) -> Duration;
+}
+
+/// The [`Clock`] reading the system time
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+/// A [`Clock`] whose time changes only when it's advanced
+#[derive(Debug, Default)]
+pub struct MockClock(Mutex<Duration>);
+
+impl MockClock {
+    /// Creates a `MockClock` at `now` since the unix epoch
+    pub fn new(now: Duration) -> Self {
+        Self(Mutex::new(now))
+    }
+
+    /// Move the time forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        *now += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
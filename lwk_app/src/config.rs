@@ -2,14 +2,20 @@ use lwk_common::electrum_ssl::LIQUID_SOCKET;
 use lwk_common::electrum_ssl::LIQUID_TESTNET_SOCKET;
 use lwk_jade::Network as JadeNetwork;
 use lwk_jade::TIMEOUT;
+use lwk_wollet::bitcoin::ScriptBuf;
+use lwk_wollet::clients::blocking::BlockchainBackend;
 use lwk_wollet::elements::AssetId;
 use lwk_wollet::ElementsNetwork;
 use std::fs;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::clock::{Clock, SystemClock};
+use crate::http::HttpClient;
+use crate::registry_cache::RegistryCache;
 use crate::{consts, Error};
 
 #[derive(Clone, Debug)]
@@ -30,6 +36,24 @@ pub struct Config {
     pub registry_url: String,
     pub timeout: Duration,
     pub scanning_interval: Duration,
+
+    /// Max number of concurrent outbound HTTP requests (registry, esplora...)
+    pub http_max_in_flight: usize,
+
+    /// How long data fetched from the registry is cached on disk, zero disables the cache
+    pub registry_cache_ttl: Duration,
+
+    /// The federation peg script needed to build pegin claims
+    ///
+    /// If `None` the current one is read from the last block header containing the full dynamic
+    /// federation parameters. Set it for regtest and custom federations.
+    pub fed_peg_script: Option<ScriptBuf>,
+
+    /// Also serve a REST facade over the wallet methods, eg `GET /api/wallet/{name}/balance`
+    pub rest: bool,
+
+    /// Compress large responses for clients accepting gzip or deflate
+    pub compression: bool,
 }
 
 impl Config {
@@ -46,6 +70,11 @@ impl Config {
             registry_url: "https://assets-testnet.blockstream.info/".into(),
             timeout: TIMEOUT,
             scanning_interval: consts::SCANNING_INTERVAL,
+            http_max_in_flight: consts::HTTP_MAX_IN_FLIGHT,
+            registry_cache_ttl: consts::REGISTRY_CACHE_TTL,
+            fed_peg_script: None,
+            rest: false,
+            compression: false,
         }
     }
 
@@ -62,6 +91,11 @@ impl Config {
             registry_url: "https://assets.blockstream.info/".into(),
             timeout: TIMEOUT,
             scanning_interval: consts::SCANNING_INTERVAL,
+            http_max_in_flight: consts::HTTP_MAX_IN_FLIGHT,
+            registry_cache_ttl: consts::REGISTRY_CACHE_TTL,
+            fed_peg_script: None,
+            rest: false,
+            compression: false,
         }
     }
 
@@ -83,6 +117,11 @@ impl Config {
             timeout: TIMEOUT,
             // Scan more frequently while testing
             scanning_interval: Duration::from_secs(1),
+            http_max_in_flight: consts::HTTP_MAX_IN_FLIGHT,
+            registry_cache_ttl: consts::REGISTRY_CACHE_TTL,
+            fed_peg_script: None,
+            rest: false,
+            compression: false,
         }
     }
 
@@ -116,6 +155,41 @@ impl Config {
         Ok(path)
     }
 
+    /// Returns the cache of the registry data, stored under datadir
+    pub fn registry_cache(&self) -> Result<RegistryCache, Error> {
+        self.registry_cache_with_clock(Arc::new(SystemClock))
+    }
+
+    /// Returns the cache of the registry data like [`Config::registry_cache()`], with the given
+    /// clock for the entries expiration
+    pub fn registry_cache_with_clock(&self, clock: Arc<dyn Clock>) -> Result<RegistryCache, Error> {
+        let mut path = self.datadir()?;
+        path.push("registry_cache");
+        Ok(RegistryCache::new(path, self.registry_cache_ttl, clock))
+    }
+
+    /// Set the federation peg script from its hex
+    pub fn set_fed_peg_script(&mut self, hex: &str) -> Result<(), Error> {
+        let script = ScriptBuf::from_hex(hex).map_err(|e| Error::Generic(e.to_string()))?;
+        self.fed_peg_script = Some(script);
+        Ok(())
+    }
+
+    /// Returns the federation peg script to use for pegin claims
+    ///
+    /// The configured one if any, otherwise it's fetched from the chain.
+    pub fn fed_peg_script(&self) -> Result<ScriptBuf, Error> {
+        if let Some(script) = &self.fed_peg_script {
+            return Ok(script.clone());
+        }
+        let mut client = self.electrum_client()?;
+        let tip = client.tip()?.height;
+        let header = lwk_wollet::pegin::fetch_last_full_header(&client, self.network, tip)?;
+        lwk_wollet::fed_peg_script(&header).ok_or_else(|| {
+            Error::Generic("Last full header does not contain the fedpegscript".into())
+        })
+    }
+
     /// True if Liquid mainnet
     pub fn is_mainnet(&self) -> bool {
         matches!(self.network, ElementsNetwork::Liquid)
@@ -128,8 +202,33 @@ impl Config {
         )
     }
 
+    pub fn http_client(&self) -> Result<HttpClient, Error> {
+        HttpClient::new(self.timeout, self.http_max_in_flight)
+    }
+
     pub fn electrum_client(&self) -> Result<lwk_wollet::ElectrumClient, Error> {
         // TODO cache it instead of recreating every time
         Ok(lwk_wollet::ElectrumClient::new(&self.electrum_url()?)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    #[test]
+    fn custom_fed_peg_script() {
+        // 1-of-1 multisig
+        let hex = "512102e5a0a4f4ba56b7e4b3e14ed1d3f2d1b9b7c1c3d5e3b9a9f1c2e7c6a1b2c3d4e551ae";
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut config = Config::default_regtest(tempdir.path().to_path_buf());
+        assert!(config.fed_peg_script.is_none());
+
+        config.set_fed_peg_script(hex).unwrap();
+        // The configured script is used without connecting to the backend
+        assert_eq!(config.fed_peg_script().unwrap().to_hex_string(), hex);
+
+        assert!(config.set_fed_peg_script("not hex").is_err());
+        assert_eq!(config.fed_peg_script().unwrap().to_hex_string(), hex);
+    }
+}
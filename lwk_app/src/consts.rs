@@ -6,3 +6,32 @@ pub const DEFAULT_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const SCANNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Max number of concurrent outbound HTTP requests
+pub const HTTP_MAX_IN_FLIGHT: usize = 4;
+
+pub const REGISTRY_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often the scanning thread removes the expired entries of the caches
+pub const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Max number of scan updates waiting to be applied, then the scanning thread waits
+pub const SCAN_UPDATES_BOUND: usize = 4;
+
+/// Number of addresses checked after the last used one when diagnosing a wallet scan
+pub const DIAGNOSE_GAP_LIMIT: u32 = 200;
+
+/// Max number of addresses derived by a single wallet_address_batch request
+pub const ADDRESS_BATCH_MAX: u32 = 1000;
+
+/// Max length in bytes of a wallet metadata namespace or key
+pub const META_MAX_KEY_LEN: usize = 64;
+
+/// Max length in bytes of a wallet metadata value
+pub const META_MAX_VALUE_LEN: usize = 1024;
+
+/// Max number of metadata entries per wallet, over all the namespaces
+pub const META_MAX_ENTRIES: usize = 1000;
+
+/// Fields of requests and responses never written to the logs
+pub const SECRET_FIELDS: &[&str] = &["mnemonic", "passphrase"];
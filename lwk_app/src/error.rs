@@ -62,6 +62,21 @@ pub enum Error {
     #[error(transparent)]
     QrError(#[from] lwk_common::QrError),
 
+    #[error(transparent)]
+    Payment(#[from] lwk_common::PaymentError),
+
+    #[error(transparent)]
+    Common(#[from] lwk_common::Error),
+
+    #[error("Payment is for a different network than the server ({0})")]
+    PaymentNetworkMismatch(String),
+
+    #[error("Payment of kind '{0}' cannot be paid on-chain, use a Boltz swap to pay it")]
+    PaymentNotOnchain(String),
+
+    #[error("Payment does not specify an amount, an amount override is required")]
+    PaymentMissingAmount,
+
     #[error("Wallet '{0}' does not exist")]
     WalletNotExist(String),
 
@@ -92,9 +107,21 @@ pub enum Error {
     #[error(transparent)]
     MethodNotExist(#[from] crate::method::MethodNotExist),
 
+    #[error("Signer fingerprint {actual} does not match the expected {expected}")]
+    FingerprintMismatch {
+        expected: lwk_wollet::bitcoin::bip32::Fingerprint,
+        actual: lwk_wollet::bitcoin::bip32::Fingerprint,
+    },
+
+    #[error("Exactly one of mnemonic and mnemonic_file must be specified")]
+    InvalidMnemonicSource,
+
     #[error("Poison error: {0}")]
     PoisonError(String),
 
+    #[error("The scanning thread has stopped, wallets won't be scanned")]
+    ScanningStopped,
+
     #[error("Feature \"serial\" is disabled, enable it to solve this error")]
     FeatSerialDisabled,
 
@@ -107,6 +134,12 @@ pub enum Error {
     #[error("Received stop command")]
     Stop,
 
+    #[error("Wallet metadata limit exceeded: {0}")]
+    MetaLimit(String),
+
+    #[error("Cannot derive more than {0} addresses in a batch")]
+    AddressBatchTooLarge(u32),
+
     // TODO remove into specific errors
     #[error("Generic error {0}")]
     Generic(String),
@@ -128,6 +161,9 @@ impl Error {
             Error::SignerAlreadyLoaded(_) => {
                 ImplementationDefinedCode::new(-32_011).expect("static")
             }
+            Error::FingerprintMismatch { .. } => {
+                ImplementationDefinedCode::new(-32_014).expect("static")
+            }
 
             _ => lwk_tiny_jrpc::error::GENERIC,
         }
@@ -138,6 +174,9 @@ impl Error {
         match self {
             Error::WalletNotExist(n) => Some(json!({"name": n.to_string()})),
             Error::SignerNotExist(n) => Some(json!({"name": n.to_string()})),
+            Error::FingerprintMismatch { expected, actual } => {
+                Some(json!({"expected": expected.to_string(), "actual": actual.to_string()}))
+            }
             _ => None,
         }
     }
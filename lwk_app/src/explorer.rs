@@ -1,3 +1,4 @@
+use crate::http::HttpClient;
 use crate::Error;
 use lwk_wollet::elements::encode::deserialize;
 use lwk_wollet::elements::hex::FromHex;
@@ -16,17 +17,25 @@ pub struct RegistryData {
     pub issuance_txin: OutPointS,
 }
 
-pub fn get_registry_data(registry_url: &str, asset: &AssetId) -> Result<RegistryData, Error> {
+pub fn get_registry_data(
+    client: &HttpClient,
+    registry_url: &str,
+    asset: &AssetId,
+) -> Result<RegistryData, Error> {
     let url = format!("{registry_url}{asset}");
     log::debug!("getting registry data {url}");
-    let data: RegistryData = reqwest::blocking::get(url)?.json()?;
+    let data: RegistryData = client.get_json(&url)?;
     Ok(data)
 }
 
-pub fn get_tx(esplora_api_url: &str, txid: &Txid) -> Result<Transaction, Error> {
+pub fn get_tx(
+    client: &HttpClient,
+    esplora_api_url: &str,
+    txid: &Txid,
+) -> Result<Transaction, Error> {
     let url = format!("{esplora_api_url}tx/{txid}/hex");
     log::debug!("getting tx {url}");
-    let tx_hex = reqwest::blocking::get(url)?.text()?;
+    let tx_hex = client.get_text(&url)?;
     log::debug!("got {tx_hex}");
     let bytes = Vec::<u8>::from_hex(&tx_hex)?;
     let tx = deserialize(&bytes)?;
@@ -0,0 +1,143 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::Error;
+
+/// HTTP client shared by all the outbound calls of the app (registry, esplora...)
+///
+/// Clones share the same connection pool and the same cap on the number of requests in flight,
+/// so that many concurrent calls don't hit the remote servers all at once.
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    client: reqwest::blocking::Client,
+    in_flight: Arc<InFlight>,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    max: usize,
+    current: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Held while a request is in flight, releases its slot when dropped
+struct Permit<'a>(&'a InFlight);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut current = self.0.current.lock().unwrap_or_else(|e| e.into_inner());
+        *current -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+impl HttpClient {
+    /// Create a client with the given per-request `timeout` and at most `max_in_flight` concurrent requests
+    pub fn new(timeout: Duration, max_in_flight: usize) -> Result<Self, Error> {
+        let client = reqwest::blocking::ClientBuilder::new()
+            .timeout(timeout)
+            .pool_max_idle_per_host(max_in_flight)
+            .build()?;
+        Ok(Self {
+            client,
+            in_flight: Arc::new(InFlight {
+                max: max_in_flight.max(1),
+                current: Mutex::new(0),
+                released: Condvar::new(),
+            }),
+        })
+    }
+
+    fn acquire(&self) -> Result<Permit<'_>, Error> {
+        let in_flight = self.in_flight.as_ref();
+        let mut current = in_flight.current.lock()?;
+        while *current >= in_flight.max {
+            current = in_flight.released.wait(current)?;
+        }
+        *current += 1;
+        Ok(Permit(in_flight))
+    }
+
+    /// Run `f` with the inner client, waiting if the max number of requests in flight is reached
+    ///
+    /// The slot is released only after `f` returns, so `f` should also consume the response body.
+    pub fn execute<T>(
+        &self,
+        f: impl FnOnce(&reqwest::blocking::Client) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let _permit = self.acquire()?;
+        f(&self.client)
+    }
+
+    pub fn get_text(&self, url: &str) -> Result<String, Error> {
+        self.execute(|c| Ok(c.get(url).send()?.text()?))
+    }
+
+    pub fn get_json<R: DeserializeOwned>(&self, url: &str) -> Result<R, Error> {
+        self.execute(|c| Ok(c.get(url).send()?.json()?))
+    }
+
+    pub fn post_json<R: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &impl Serialize,
+    ) -> Result<R, Error> {
+        self.execute(|c| Ok(c.post(url).json(body).send()?.json()?))
+    }
+
+    pub fn post_json_text(&self, url: &str, body: &impl Serialize) -> Result<String, Error> {
+        self.execute(|c| Ok(c.post(url).json(body).send()?.text()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::HttpClient;
+
+    #[test]
+    fn in_flight_cap() {
+        let max = 2;
+        let client = HttpClient::new(Duration::from_secs(1), max).unwrap();
+        let registry = client.clone();
+        let esplora = client.clone();
+        assert!(Arc::ptr_eq(&registry.in_flight, &esplora.in_flight));
+        let other = HttpClient::new(Duration::from_secs(1), max).unwrap();
+        assert!(!Arc::ptr_eq(&client.in_flight, &other.in_flight));
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let client = if i % 2 == 0 {
+                    registry.clone()
+                } else {
+                    esplora.clone()
+                };
+                let running = running.clone();
+                let peak = peak.clone();
+                std::thread::spawn(move || {
+                    client
+                        .execute(|_| {
+                            let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                            peak.fetch_max(n, Ordering::SeqCst);
+                            std::thread::sleep(Duration::from_millis(50));
+                            running.fetch_sub(1, Ordering::SeqCst);
+                            Ok(())
+                        })
+                        .unwrap();
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert!(peak.load(Ordering::SeqCst) <= max);
+    }
+}
@@ -11,19 +11,23 @@
 //!
 //! All the requests and responses data model are in the [`lwk_rpc_model`] crate.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::net::SocketAddr;
 use std::num::NonZeroU8;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread::{sleep, JoinHandle};
 use std::time::Duration;
 
 use lwk_common::{
-    address_to_text_qr, address_to_uri_qr, keyorigin_xpub_from_str, multisig_desc, singlesig_desc,
-    InvalidBipVariant, InvalidBlindingKeyVariant, InvalidMultisigVariant, InvalidSinglesigVariant,
-    Signer,
+    address_to_text_qr, address_to_uri_qr, keyorigin_xpub_from_str, multisig_desc, pset_issuances,
+    pset_signatures, singlesig_desc, singlesig_desc_account, InvalidBipVariant,
+    InvalidBlindingKeyVariant, InvalidMultisigVariant, InvalidSinglesigVariant, Payment,
+    PaymentError, PaymentKind, PsetBalance, PsetDetails, Signer,
 };
 use lwk_jade::derivation_path_to_vec;
 use lwk_jade::get_receive_address::Variant;
@@ -31,14 +35,14 @@ use lwk_jade::register_multisig::{JadeDescriptor, RegisterMultisigParams};
 use lwk_jade::Jade;
 use lwk_signer::{AnySigner, SwSigner};
 use lwk_tiny_jrpc::{tiny_http, JsonRpcServer, Request, Response};
-use lwk_wollet::amp2::Amp2;
+use lwk_wollet::amp2::{Amp2, CosignRef, CosignStatus};
 use lwk_wollet::bitcoin::bip32::Fingerprint;
 use lwk_wollet::bitcoin::XKeyIdentifier;
 use lwk_wollet::clients::blocking::BlockchainBackend;
 use lwk_wollet::elements::encode::serialize;
 use lwk_wollet::elements::hex::{FromHex, ToHex};
 use lwk_wollet::elements::pset::PartiallySignedTransaction;
-use lwk_wollet::elements::{Address, AssetId, Txid};
+use lwk_wollet::elements::{Address, AssetId, Script, Transaction, Txid};
 use lwk_wollet::elements_miniscript::descriptor::{Descriptor, DescriptorType, WshInner};
 use lwk_wollet::elements_miniscript::miniscript::decode::Terminal;
 use lwk_wollet::elements_miniscript::{DescriptorPublicKey, ForEachKey};
@@ -48,21 +52,29 @@ use serde_json::Value;
 
 use crate::explorer::{get_registry_data, get_tx};
 use crate::method::Method;
-use crate::state::{AppAsset, AppSigner, State};
+use crate::state::{AppAsset, AppSigner, RegistryAssetData, State};
 use lwk_rpc_model::{request, response};
 
 pub use client::Client;
+pub use clock::{Clock, MockClock, SystemClock};
 pub use config::Config;
 pub use error::Error;
+pub use lock_metrics::LockMetrics;
 pub use lwk_tiny_jrpc::RpcError;
 
 mod client;
+mod clock;
 mod config;
 pub mod consts;
 mod error;
 mod explorer;
+mod http;
+mod lock_metrics;
 pub mod method;
+mod registry_cache;
 mod reqwest_transport;
+mod rest;
+mod scan_loops;
 mod state;
 
 pub struct App {
@@ -74,6 +86,12 @@ pub struct App {
 
     /// Handle of the scanning thread
     scanning_handle: Option<JoinHandle<()>>,
+
+    /// Contention on the state lock caused by the scanning thread
+    lock_metrics: Arc<LockMetrics>,
+
+    /// Source of the current time, replaceable in tests
+    clock: Arc<dyn Clock>,
 }
 
 impl App {
@@ -85,9 +103,16 @@ impl App {
             config,
             scanning_handle: None,
             is_scanning: Arc::new(AtomicBool::new(false)),
+            lock_metrics: Default::default(),
+            clock: Arc::new(SystemClock),
         })
     }
 
+    /// Use `clock` as source of the current time, it must be called before [`App::run()`]
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
     fn apply_request(&self, client: &Client, line: &str) -> Result<(), Error> {
         let r: Request = serde_json::from_str(line)?;
         let method: Method = r.method.parse()?;
@@ -101,15 +126,16 @@ impl App {
         }
         let mut state = State {
             config: self.config.clone(),
+            http: self.config.http_client()?,
             wollets: Default::default(),
             signers: Default::default(),
             assets: Default::default(),
             tx_memos: Default::default(),
             addr_memos: Default::default(),
+            wallet_meta: Default::default(),
             do_persist: false,
-            scan_loops_started: 0,
-            scan_loops_completed: 0,
-            interrupt_wait: false,
+            scan_loops: Default::default(),
+            clock: self.clock.clone(),
         };
         state.insert_policy_asset();
         let state = Arc::new(Mutex::new(state));
@@ -120,6 +146,8 @@ impl App {
         // replied after 15 seconds, using 1 instead seems to not have that issue.
         let config = lwk_tiny_jrpc::Config::builder()
             .with_num_threads(NonZeroU8::new(1).expect("static"))
+            .with_rest(self.config.rest.then(rest::router))
+            .with_compression(self.config.compression)
             .build();
 
         let rpc = lwk_tiny_jrpc::JsonRpcServer::new(server, config, state.clone(), method_handler);
@@ -151,73 +179,134 @@ impl App {
         self.is_scanning.store(true, Ordering::Relaxed);
         let is_scanning = self.is_scanning.clone();
         let state_scanning = state.clone();
+        let scan_loops = state.lock().map_err(|e| e.to_string())?.scan_loops.clone();
+        let lock_metrics = self.lock_metrics.clone();
         let scanning_interval = self.config.scanning_interval;
         let stop_interval = Duration::from_millis(100);
         let mut interval = Duration::ZERO; // Do not wait in the first scan loop
-        let scanning_handle = std::thread::spawn(move || 'scan: loop {
-            // Sleep for scanning_interval, but check stop signal every stop_interval
-            'stop: loop {
-                if !is_scanning.load(Ordering::Relaxed) {
-                    break 'scan;
-                }
-                if interval == Duration::ZERO
-                    || state_scanning
-                        .lock()
-                        .map(|s| s.interrupt_wait)
-                        .unwrap_or(false)
-                {
-                    interval = scanning_interval; // Reset wait interval
-                    break 'stop;
+        let clock = self.clock.clone();
+        let mut last_maintenance: Option<Duration> = None;
+
+        // The updates are applied by another thread, each one in a short critical section, while
+        // the scanning thread goes on with the network calls for the other wallets. The channel
+        // is bounded, if the updates are not applied fast enough the scanning thread waits.
+        let (updates, updates_receiver) = sync_channel::<ScanMessage>(consts::SCAN_UPDATES_BOUND);
+        let state_applying = state.clone();
+        let applying_metrics = self.lock_metrics.clone();
+        let applying_handle = std::thread::spawn(move || {
+            for message in updates_receiver {
+                match message {
+                    ScanMessage::Update(name, update) => {
+                        let mut s = applying_metrics
+                            .lock(&state_applying)
+                            .expect("state lock poison");
+                        if let Ok(wollet) = s.wollets.get_mut(&name) {
+                            let _ = wollet.apply_update(*update);
+                        }
+                    }
+                    ScanMessage::Flush(done) => {
+                        let _ = done.send(());
+                    }
                 }
-                std::thread::sleep(stop_interval);
-                interval = interval.saturating_sub(stop_interval);
             }
+        });
 
-            let (wollets_names, config) = {
-                let mut s = state_scanning.lock().expect("state lock poison");
-                s.interrupt_wait = false;
-                s.scan_loops_started += 1;
-                let wollets_names: Vec<_> = s.wollets.iter().map(|e| e.0.to_owned()).collect();
-                let config = s.config.clone();
-                (wollets_names, config)
-            };
+        let scanning_handle = std::thread::spawn(move || {
+            'scan: loop {
+                // Wait scanning_interval or a scan request, checking the stop signal every
+                // stop_interval
+                'stop: loop {
+                    if !is_scanning.load(Ordering::Relaxed) {
+                        let _ = scan_loops.stop();
+                        break 'scan;
+                    }
+                    let wait = interval.min(stop_interval);
+                    if interval == Duration::ZERO || scan_loops.wait_request(wait).unwrap_or(false)
+                    {
+                        interval = scanning_interval; // Reset wait interval
+                        break 'stop;
+                    }
+                    interval = interval.saturating_sub(wait);
+                }
 
-            match config.electrum_client() {
-                Ok(mut electrum_client) => {
-                    for name in wollets_names {
-                        let state = match state_scanning
-                            .lock()
-                            .expect("state lock poison")
-                            .wollets
-                            .get(&name)
-                        {
-                            Ok(w) => w.state(),
-                            Err(_) => continue,
-                        };
-
-                        match electrum_client.full_scan(&state) {
-                            Ok(Some(update)) => {
-                                let mut s = state_scanning.lock().expect("state lock poison");
-                                let _ = match s.wollets.get_mut(&name) {
-                                    Ok(wollet) => wollet.apply_update(update),
-                                    Err(_) => continue,
-                                };
+                let generation = scan_loops.start().expect("scan loops lock poison");
+                // The state lock is held only to read the wallets, the network calls happen without
+                // it so that RPC handlers are not blocked
+                let (wollets_names, config) = {
+                    let s = lock_metrics
+                        .lock(&state_scanning)
+                        .expect("state lock poison");
+                    let wollets_names: Vec<_> = s.wollets.iter().map(|e| e.0.to_owned()).collect();
+                    let config = s.config.clone();
+                    (wollets_names, config)
+                };
+
+                match config.electrum_client() {
+                    Ok(mut electrum_client) => {
+                        for name in wollets_names {
+                            let state = match lock_metrics
+                                .lock(&state_scanning)
+                                .expect("state lock poison")
+                                .wollets
+                                .get(&name)
+                            {
+                                Ok(w) => w.state(),
+                                Err(_) => continue,
+                            };
+
+                            match electrum_client.full_scan(&state) {
+                                Ok(Some(update)) => {
+                                    let message = ScanMessage::Update(name, Box::new(update));
+                                    let _ = updates.send(message);
+                                }
+                                Ok(None) => (),
+                                Err(_) => continue,
                             }
-                            Ok(None) => (),
-                            Err(_) => continue,
+                        }
+
+                        // Complete the loop once its updates are applied, so that the waiting RPC
+                        // calls and the next loop see them
+                        let (done, applied) = channel();
+                        if updates.send(ScanMessage::Flush(done)).is_ok() {
+                            let _ = applied.recv();
                         }
                     }
-                }
-                Err(_) => {
-                    log::info!(
+                    Err(_) => {
+                        log::info!(
                         "Cannot create an electrum client, are we conected? Retrying in one sec"
                     );
-                    sleep(Duration::from_secs(1))
+                        sleep(Duration::from_secs(1))
+                    }
+                };
+
+                scan_loops
+                    .complete(generation)
+                    .expect("scan loops lock poison");
+
+                let now = clock.now();
+                if last_maintenance.map_or(true, |t| {
+                    now.saturating_sub(t) >= consts::MAINTENANCE_INTERVAL
+                }) {
+                    last_maintenance = Some(now);
+                    match config
+                        .registry_cache_with_clock(clock.clone())
+                        .and_then(|c| c.prune())
+                    {
+                        Ok(0) => (),
+                        Ok(n) => log::debug!("Pruned {n} expired registry cache entries"),
+                        Err(e) => log::warn!("Cannot prune the registry cache: {e}"),
+                    }
                 }
-            };
+                log::debug!(
+                    "Scan loop {generation} completed, state lock max wait {:?} max held {:?}",
+                    lock_metrics.max_wait(),
+                    lock_metrics.max_held()
+                );
+            }
 
-            let mut s = state_scanning.lock().expect("state lock poison");
-            s.scan_loops_completed += 1;
+            // Stop the applying thread once it has applied the pending updates
+            drop(updates);
+            let _ = applying_handle.join();
         });
         self.scanning_handle = Some(scanning_handle);
 
@@ -246,6 +335,11 @@ impl App {
         self.config.addr
     }
 
+    /// Time the scanning thread spent waiting for and holding the state lock
+    pub fn scan_lock_metrics(&self) -> &LockMetrics {
+        &self.lock_metrics
+    }
+
     pub fn join_threads(&mut self) -> Result<(), Error> {
         self.rpc
             .take()
@@ -262,6 +356,38 @@ impl App {
     }
 }
 
+/// Scan the wallet with the given CT descriptor once and return its balance and utxos
+///
+/// The scan happens in process against the blockchain backend in `config`, without starting the
+/// RPC server and without persisting anything in the data directory.
+pub fn sync_once(config: &Config, descriptor: &str) -> Result<response::WalletSyncOnce, Error> {
+    let desc: WolletDescriptor = descriptor.parse()?;
+    if desc.is_mainnet() != config.is_mainnet() {
+        return Err(Error::Generic("Descriptor is for the wrong network".into()));
+    }
+    let mut wollet = Wollet::without_persist(config.network, desc)?;
+    let mut electrum_client = config.electrum_client()?;
+    if let Some(update) = electrum_client.full_scan(&wollet.state())? {
+        wollet.apply_update(update)?;
+    }
+    let balance = wollet
+        .balance()?
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v as i64))
+        .collect();
+    let tip = wollet.tip().height();
+    let utxos = wollet
+        .utxos()?
+        .iter()
+        .map(|u| convert_utxo(u, tip))
+        .collect();
+    Ok(response::WalletSyncOnce {
+        balance,
+        utxos,
+        network: config.network.as_str().to_string(),
+    })
+}
+
 fn method_handler(
     request: Request,
     state: Arc<Mutex<State>>,
@@ -271,9 +397,9 @@ fn method_handler(
 
 fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Response, Error> {
     log::debug!(
-        "method: {} params: {:?} ",
+        "method: {} params: {} ",
         request.method.as_str(),
-        request.params
+        redact(request.params.clone().unwrap_or_default())
     );
     let method: Method = match request.method.as_str().parse() {
         Ok(method) => method,
@@ -315,7 +441,14 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let mut s = state.lock()?;
             // TODO recognize different name same descriptor?
 
-            let desc: WolletDescriptor = r.descriptor.parse()?;
+            let (desc, descriptor) = match r.change_descriptor {
+                Some(change) => {
+                    let desc = WolletDescriptor::from_descriptors(&r.descriptor, &change)?;
+                    let combined = desc.to_string();
+                    (desc, combined)
+                }
+                None => (r.descriptor.parse()?, r.descriptor),
+            };
             if desc.is_mainnet() != s.config.is_mainnet() {
                 return Err(Error::Generic("Descriptor is for the wrong network".into()));
             }
@@ -327,7 +460,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             Response::result(
                 request.id,
                 serde_json::to_value(response::Wallet {
-                    descriptor: r.descriptor,
+                    descriptor,
                     name: r.name,
                 })?,
             )
@@ -338,6 +471,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let removed = s.wollets.remove(&r.name)?;
             s.tx_memos.remove(&r.name);
             s.addr_memos.remove(&r.name);
+            s.wallet_meta.remove(&r.name);
             s.persist_all()?;
 
             Response::result(
@@ -345,7 +479,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 serde_json::to_value(response::WalletUnload {
                     unloaded: response::Wallet {
                         name: r.name,
-                        descriptor: removed.descriptor().to_string(),
+                        descriptor: removed.wollet_descriptor().to_string(),
                     },
                 })?,
             )
@@ -356,7 +490,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 .wollets
                 .iter()
                 .map(|(name, wollet)| response::Wallet {
-                    descriptor: wollet.descriptor().to_string(),
+                    descriptor: wollet.wollet_descriptor().to_string(),
                     name: name.clone(),
                 })
                 .collect();
@@ -366,7 +500,19 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
         Method::SignerLoadSoftware => {
             let r: request::SignerLoadSoftware = serde_json::from_value(params)?;
             let mut s = state.lock()?;
-            let signer = AppSigner::new_sw(&r.mnemonic, s.config.is_mainnet(), r.persist)?;
+            let passphrase = r.passphrase.as_deref();
+            let is_mainnet = s.config.is_mainnet();
+            let signer = match (r.mnemonic, r.mnemonic_file) {
+                (Some(mnemonic), None) => {
+                    AppSigner::new_sw(&mnemonic, passphrase, is_mainnet, r.persist)?
+                }
+                (None, Some(path)) => {
+                    let mnemonic = std::fs::read_to_string(&path)?;
+                    AppSigner::new_sw(mnemonic.trim(), passphrase, is_mainnet, r.persist)?
+                        .with_mnemonic_file(path)
+                }
+                _ => return Err(Error::InvalidMnemonicSource),
+            };
             let resp: response::Signer = signer_response_from(&r.name, &signer)?;
             s.signers.insert(&r.name, signer)?;
             if r.persist {
@@ -379,6 +525,17 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let mut s = state.lock()?;
             let id = XKeyIdentifier::from_str(&r.id).map_err(|e| e.to_string())?; // TODO remove map_err
             let signer = AppSigner::new_jade(id, r.emulator, s.config.jade_network())?;
+            check_fingerprint(&signer, r.expected_fingerprint.as_deref())?;
+            let resp: response::Signer = signer_response_from(&r.name, &signer)?;
+            s.signers.insert(&r.name, signer)?;
+            s.persist(&request)?;
+            Response::result(request.id, serde_json::to_value(resp)?)
+        }
+        Method::SignerLoadLedger => {
+            let r: request::SignerLoadLedger = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
+            let signer = AppSigner::new_ledger(r.emulator)?;
+            check_fingerprint(&signer, r.expected_fingerprint.as_deref())?;
             let resp: response::Signer = signer_response_from(&r.name, &signer)?;
             s.signers.insert(&r.name, signer)?;
             s.persist(&request)?;
@@ -400,6 +557,8 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let mut s = state.lock()?;
             let removed = s.signers.remove(&r.name)?;
             let signer: response::Signer = signer_response_from(&r.name, &removed)?;
+            // Software signers erase their secrets when dropped
+            drop(removed);
             s.persist_all()?;
             Response::result(
                 request.id,
@@ -425,6 +584,71 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let r = response::SignerList { signers };
             Response::result(request.id, serde_json::to_value(r)?)
         }
+        Method::WalletAddressBatch => {
+            let r: request::WalletAddressBatch = serde_json::from_value(params)?;
+            if r.count > consts::ADDRESS_BATCH_MAX {
+                return Err(Error::AddressBatchTooLarge(consts::ADDRESS_BATCH_MAX));
+            }
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let memos = s.addr_memos.for_wollet(&r.name);
+            let network = s.config.network.as_str().to_string();
+            // Like wallet_address the first unused index advances only once an address is used
+            let start = wollet.address(r.start_index)?.index();
+            let mut addresses = vec![];
+            for index in (start..).take(r.count as usize) {
+                let addr = wollet.address(Some(index))?;
+                let address = addr.address();
+                let text_qr = r
+                    .with_text_qr
+                    .then(|| address_to_text_qr(address))
+                    .transpose()?;
+                let uri_qr = r
+                    .with_uri_qr
+                    .map(|e| address_to_uri_qr(address, (e != 0).then_some(e)))
+                    .transpose()?;
+                addresses.push(response::WalletAddress {
+                    address: address.to_string(),
+                    index,
+                    memo: memos.get(address).cloned().unwrap_or_default(),
+                    text_qr,
+                    uri_qr,
+                    network: network.clone(),
+                });
+            }
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletAddressBatch { addresses })?,
+            )
+        }
+        Method::WalletAddressInfoBatch => {
+            let r: request::WalletAddressInfoBatch = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let params = s.config.network.address_params();
+            let wollet = s.wollets.get(&r.name)?;
+            let addresses = r
+                .addresses
+                .iter()
+                .map(|a| lwk_common::address::parse_address(a, params, false))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(lwk_wollet::Error::from)?;
+            let indexes = wollet.addresses_index(&addresses)?;
+            let addresses = r
+                .addresses
+                .into_iter()
+                .zip(indexes)
+                .map(|(address, index)| response::AddressInfo {
+                    address,
+                    is_mine: index.is_some(),
+                    path_index: index.map(|(chain, _)| chain.path_index()),
+                    index: index.map(|(_, i)| i),
+                })
+                .collect();
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletAddressInfoBatch { addresses })?,
+            )
+        }
         Method::WalletAddress => {
             let r: request::WalletAddress = serde_json::from_value(params)?;
             let mut s = state.lock()?;
@@ -517,6 +741,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                     memo,
                     text_qr,
                     uri_qr,
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -524,7 +749,8 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let r: request::WalletBalance = serde_json::from_value(params)?;
             let mut s = state.lock()?;
             let wollet = s.wollets.get_mut(&r.name)?;
-            let mut balance = wollet
+            let status = wollet.status();
+            let mut balance: HashMap<String, i64> = wollet
                 .balance()?
                 .into_iter()
                 .map(|(k, v)| (k.to_string(), v as i64))
@@ -532,10 +758,15 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             if r.with_tickers {
                 balance = s.replace_id_with_ticker(balance);
             }
-            Response::result(
-                request.id,
-                serde_json::to_value(response::WalletBalance { balance })?,
-            )
+            if r.hide_amounts {
+                balance.values_mut().for_each(|v| *v = amount_bucket(*v));
+            }
+            let value = serde_json::to_value(response::WalletBalance {
+                balance,
+                network: s.config.network.as_str().to_string(),
+            })?;
+            let etag = wallet_etag(status, &value);
+            Response::result(request.id, value).with_etag(etag)
         }
         Method::WalletSendMany => {
             let r: request::WalletSendMany = serde_json::from_value(params)?;
@@ -561,6 +792,74 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 request.id,
                 serde_json::to_value(response::Pset {
                     pset: tx.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletSendManySmartFee => {
+            let r: request::WalletSendManySmartFee = serde_json::from_value(params)?;
+
+            // Estimating the fee rate hits the network, don't hold the lock meanwhile
+            let config = state.lock()?.config.clone();
+            let electrum_client = config.electrum_client()?;
+            let (fee_rate, is_fallback) = smart_fee_rate(&electrum_client, r.target_blocks);
+
+            let mut s = state.lock()?;
+            let wollet: &mut Wollet = s.wollets.get_mut(&r.name)?;
+
+            let recipients: Vec<_> = r
+                .addressees
+                .into_iter()
+                .map(unvalidated_addressee)
+                .collect();
+            let mut tx = wollet
+                .tx_builder()
+                .set_unvalidated_recipients(&recipients)?
+                .fee_rate(Some(fee_rate))
+                .finish()?;
+
+            add_contracts(&mut tx, s.assets.iter());
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletSendManySmartFee {
+                    pset: tx.to_string(),
+                    fee_rate,
+                    is_fallback,
+                })?,
+            )
+        }
+        Method::WalletSendToPayment => {
+            let r: request::WalletSendToPayment = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
+            let payment = parse_payment(&r.uri, &s)?;
+            check_payment_network(&payment, &s.config)?;
+            let address = match payment.kind {
+                PaymentKind::LiquidAddress | PaymentKind::LiquidBip21 => {
+                    payment.address.expect("liquid payments have an address")
+                }
+                kind => return Err(Error::PaymentNotOnchain(kind.as_str().to_string())),
+            };
+            let satoshi = r
+                .amount_override
+                .or(payment.amount)
+                .ok_or(Error::PaymentMissingAmount)?;
+            let asset = payment
+                .asset
+                .unwrap_or_else(|| s.config.network.policy_asset());
+
+            let wollet: &mut Wollet = s.wollets.get_mut(&r.name)?;
+            let mut tx = wollet
+                .tx_builder()
+                .add_recipient(&address, satoshi, asset)?
+                .fee_rate(r.fee_rate)
+                .finish()?;
+
+            add_contracts(&mut tx, s.assets.iter());
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: tx.to_string(),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -582,6 +881,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 request.id,
                 serde_json::to_value(response::Pset {
                     pset: tx.to_string(),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -608,6 +908,35 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 serde_json::to_value(response::SignerSinglesigDescriptor { descriptor })?,
             )
         }
+        Method::SignerAccountDescriptor => {
+            let r: request::SignerAccountDescriptor = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
+            let is_mainnet = s.config.is_mainnet();
+
+            let signer = s.get_available_signer(&r.name)?;
+
+            let script_variant = r
+                .singlesig_kind
+                .parse()
+                .map_err(|e: InvalidSinglesigVariant| e.to_string())?;
+
+            let blinding_variant = r
+                .descriptor_blinding_key
+                .parse()
+                .map_err(|e: InvalidBlindingKeyVariant| e.to_string())?;
+
+            let descriptor = singlesig_desc_account(
+                signer,
+                script_variant,
+                blinding_variant,
+                is_mainnet,
+                r.account,
+            )?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::SignerSinglesigDescriptor { descriptor })?,
+            )
+        }
         Method::WalletMultisigDescriptor => {
             let r: request::WalletMultisigDescriptor = serde_json::from_value(params)?;
 
@@ -628,6 +957,16 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                         .map_err(|e| Error::Generic(e.to_string()))?,
                 );
             }
+            if !r.signers.is_empty() {
+                let mut s = state.lock()?;
+                let is_mainnet = s.config.is_mainnet();
+                for name in r.signers {
+                    let signer = s.get_available_signer(&name)?;
+                    let (key_source, xpub) =
+                        signer.xpub_with_origin(lwk_common::Bip::Bip87, is_mainnet)?;
+                    keyorigin_xpubs.push((Some(key_source), xpub));
+                }
+            }
 
             let descriptor = multisig_desc(
                 r.threshold,
@@ -676,6 +1015,21 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 serde_json::to_value(response::SignerXpub { keyorigin_xpub })?,
             )
         }
+        Method::SignerXpubKinds => {
+            let kinds = lwk_common::Bip::ALL
+                .iter()
+                .map(|bip| response::XpubKind {
+                    kind: bip.to_string(),
+                    path: bip.path_template(),
+                    singlesig: bip.is_singlesig(),
+                    multisig: bip.is_multisig(),
+                })
+                .collect();
+            Response::result(
+                request.id,
+                serde_json::to_value(response::SignerXpubKinds { kinds })?,
+            )
+        }
         Method::SignerSign => {
             let r: request::SignerSign = serde_json::from_value(params)?;
             let mut s = state.lock()?;
@@ -693,6 +1047,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 request.id,
                 serde_json::to_value(response::Pset {
                     pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -717,6 +1072,63 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 })?,
             )
         }
+        Method::WalletPreviewTx => {
+            let r: request::WalletPreviewTx = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            // Finalize a PSET parsed from the request, nothing is stored or broadcast
+            let mut pset =
+                PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
+            let tx = wollet.finalize(&mut pset)?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletPreviewTx {
+                    txid: tx.txid().to_string(),
+                    vsize: tx.vsize(),
+                    discount_vsize: tx.discount_vsize(),
+                    hex: serialize(&tx).to_hex(),
+                })?,
+            )
+        }
+        Method::WalletPublicDescriptor => {
+            let r: request::WalletPublicDescriptor = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let desc = wollet.wollet_descriptor();
+            desc.check_shareable()?;
+            let descriptor = desc.to_string();
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletPublicDescriptor { descriptor })?,
+            )
+        }
+        Method::WalletPolicy => {
+            let r: request::WalletPolicy = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let spend_paths = wollet
+                .wollet_descriptor()
+                .spend_paths()
+                .into_iter()
+                .map(|p| response::SpendPath {
+                    keys: p
+                        .keys
+                        .into_iter()
+                        .map(|k| response::KeysThreshold {
+                            threshold: k.threshold,
+                            keys: k.keys,
+                        })
+                        .collect(),
+                    older: p.older,
+                    after: p.after,
+                    hashes: p.hashes,
+                })
+                .collect();
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletPolicy { spend_paths })?,
+            )
+        }
         Method::WalletDetails => {
             let r: request::WalletDetails = serde_json::from_value(params)?;
             let mut s = state.lock()?;
@@ -749,6 +1161,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             if !has_unique_fingerprints {
                 warnings.push("wallet has multiple signers with the same fingerprint".into());
             }
+            warnings.extend(wollet.wollet_descriptor().key_origin_warnings());
 
             let signers: Vec<_> = wollet
                 .signers()
@@ -789,14 +1202,120 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 })?,
             )
         }
+        Method::WalletAnnotatePset => {
+            let r: request::WalletAnnotatePset = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let mut pset =
+                PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
+            wollet.annotate_pset(&mut pset)?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletBlindPset => {
+            let r: request::WalletBlindPset = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let mut pset =
+                PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
+            wollet.blind_pset(&mut pset)?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletHydratePset => {
+            let r: request::WalletHydratePset = serde_json::from_value(params)?;
+            let mut pset =
+                PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
+
+            // Download the previous transactions of the requested inputs without holding the lock
+            let mut txids = vec![];
+            for input in r.inputs.iter().filter_map(|i| pset.inputs().get(*i)) {
+                if !txids.contains(&input.previous_txid) {
+                    txids.push(input.previous_txid);
+                }
+            }
+            let txs = if txids.is_empty() {
+                vec![]
+            } else {
+                let config = state.lock()?.config.clone();
+                config.electrum_client()?.get_transactions(&txids)?
+            };
+
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            wollet.hydrate_pset_with_txs(&mut pset, &r.inputs, &txs)?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletProofOfReserves => {
+            let r: request::WalletProofOfReserves = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let pset = wollet.proof_of_reserves(&r.challenge)?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletVerifyProofOfReserves => {
+            let r: request::WalletVerifyProofOfReserves = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let pset = PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
+            let reserves = wollet
+                .verify_proof_of_reserves(&r.challenge, &pset)?
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect();
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletVerifyProofOfReserves { reserves })?,
+            )
+        }
         Method::WalletPsetDetails => {
             let r: request::WalletPsetDetails = serde_json::from_value(params)?;
             let mut s = state.lock()?;
             let wollet = s.wollets.get_mut(&r.name)?;
 
             let pset = PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
-            let details = wollet.get_details(&pset)?;
-            let mut warnings = vec![];
+            let (details, unsupported) = match wollet.get_details(&pset) {
+                Err(lwk_wollet::Error::PsetDetailsError(e)) if e.unsupported_input().is_some() => {
+                    (partial_details(&pset), Some(e.to_string()))
+                }
+                r => (r?, None),
+            };
+            let estimated_final_vsize = match &wollet.descriptor().descriptor {
+                Descriptor::Wsh(wsh) => match wsh.as_inner() {
+                    WshInner::Ms(ms) => match &ms.node {
+                        Terminal::Multi(threshold, _) => {
+                            wollet.estimate_multisig_final_vsize(&pset, *threshold).ok()
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            };
+            let mut warnings = details.balance.warnings().to_vec();
+            warnings.extend(unsupported);
             let has_signatures_from = details
                 .fingerprints_has()
                 .iter()
@@ -851,6 +1370,27 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                     asset_satoshi: e.asset_satoshi().unwrap_or(0),
                 })
                 .collect();
+            // Group by address the outputs sending different assets to the same address
+            let params = s.config.network.address_params();
+            let mut recipients: Vec<response::PsetRecipient> = vec![];
+            for recipient in details.balance.recipients() {
+                let address = recipient
+                    .address(params)
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| format!("{:x}", recipient.script_pubkey()));
+                let output = response::RecipientOutput {
+                    vout: recipient.vout(),
+                    asset: recipient.asset().map(|a| a.to_string()),
+                    satoshi: recipient.value(),
+                };
+                match recipients.iter_mut().find(|r| r.address == address) {
+                    Some(r) => r.outputs.push(output),
+                    None => recipients.push(response::PsetRecipient {
+                        address,
+                        outputs: vec![output],
+                    }),
+                }
+            }
 
             Response::result(
                 request.id,
@@ -861,29 +1401,94 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                     fee: details.balance.fee,
                     issuances,
                     reissuances,
+                    recipients,
+                    estimated_final_vsize,
                     warnings: warnings.join(", "),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
         Method::WalletUtxos => {
             let r: request::WalletUtxos = serde_json::from_value(params)?;
             let mut s = state.lock()?;
+            let asset = r.asset.as_deref().map(AssetId::from_str).transpose()?;
+            let wollet = s.wollets.get_mut(&r.name)?;
+            let tip = wollet.tip().height();
+            let descriptor = wollet.wollet_descriptor();
+            let mut utxos = vec![];
+            for u in wollet.utxos()? {
+                if asset.map_or(false, |a| u.unblinded.asset != a) {
+                    continue;
+                }
+                let mut utxo = convert_utxo(&u, tip);
+                if utxo.confirmations < r.min_confirmations.unwrap_or(0) {
+                    continue;
+                }
+                if r.with_derivation {
+                    let (derivation_path, key_origin) = utxo_derivation(&descriptor, &u)?;
+                    utxo.derivation_path = Some(derivation_path);
+                    utxo.key_origin = Some(key_origin);
+                }
+                utxos.push(utxo);
+            }
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletUtxos {
+                    utxos,
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletDustDeposits => {
+            let r: request::WalletDustDeposits = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
             let wollet = s.wollets.get_mut(&r.name)?;
-            let utxos: Vec<response::Utxo> = wollet.utxos()?.iter().map(convert_utxo).collect();
+            let tip = wollet.tip().height();
+            let utxos = wollet
+                .dust_deposits(r.threshold)?
+                .iter()
+                .map(|u| convert_utxo(u, tip))
+                .collect();
             Response::result(
                 request.id,
-                serde_json::to_value(response::WalletUtxos { utxos })?,
+                serde_json::to_value(response::WalletUtxos {
+                    utxos,
+                    network: s.config.network.as_str().to_string(),
+                })?,
             )
         }
+        Method::WalletApplyUpdate => {
+            let r: request::WalletApplyUpdate = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
+            let bytes =
+                Vec::<u8>::from_hex(&r.update_hex).map_err(|e| Error::Generic(e.to_string()))?;
+            let update =
+                lwk_wollet::Update::deserialize(&bytes).map_err(lwk_wollet::Error::from)?;
+            let wollet = s.wollets.get_mut(&r.name)?;
+            wollet.check_update(&update)?;
+            wollet.apply_update(update)?;
+            let tip = convert_tip(&wollet.tip(), &s.config);
+            Response::result(request.id, serde_json::to_value(tip)?)
+        }
+        Method::WalletTip => {
+            let r: request::WalletTip = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let tip = convert_tip(&wollet.tip(), &s.config);
+            Response::result(request.id, serde_json::to_value(tip)?)
+        }
         Method::WalletTxs => {
             let r: request::WalletTxs = serde_json::from_value(params)?;
             let mut s = state.lock()?;
             let explorer_url = s.config.explorer_url.clone();
             let memos = s.tx_memos.for_wollet(&r.name);
             let wollet = s.wollets.get_mut(&r.name)?;
+            let status = wollet.status();
             let mut txs: Vec<response::Tx> = wollet
                 .transactions()?
                 .iter()
+                .skip(r.offset.unwrap_or(0))
+                .take(r.limit.unwrap_or(usize::MAX))
                 .map(|tx| convert_tx(tx, &explorer_url, &memos))
                 .collect();
             if r.with_tickers {
@@ -891,10 +1496,15 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                     tx.balance = s.replace_id_with_ticker(tx.balance.clone());
                 }
             }
-            Response::result(
-                request.id,
-                serde_json::to_value(response::WalletTxs { txs })?,
-            )
+            if r.hide_amounts {
+                txs.iter_mut().for_each(hide_tx_amounts);
+            }
+            let value = serde_json::to_value(response::WalletTxs {
+                txs,
+                network: s.config.network.as_str().to_string(),
+            })?;
+            let etag = wallet_etag(status, &value);
+            Response::result(request.id, value).with_etag(etag)
         }
         Method::WalletTx => {
             let r: request::WalletTx = serde_json::from_value(params)?;
@@ -904,12 +1514,18 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let tx = if let Some(tx) = wollet.transaction(&txid)? {
                 tx.tx.clone()
             } else if r.from_explorer {
-                get_tx(&s.config.esplora_api_url, &txid)?
+                get_tx(&s.http, &s.config.esplora_api_url, &txid)?
             } else {
                 return Err(Error::WalletTxNotFound(r.txid, r.name));
             };
             let tx = serialize(&tx).to_hex();
-            Response::result(request.id, serde_json::to_value(response::WalletTx { tx })?)
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletTx {
+                    tx,
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
         }
         Method::WalletSetTxMemo => {
             let r: request::WalletSetTxMemo = serde_json::from_value(params)?;
@@ -933,13 +1549,163 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             s.persist(&request)?;
             Response::result(request.id, serde_json::to_value(response::Empty {})?)
         }
-        Method::WalletIssue => {
-            let r: request::WalletIssue = serde_json::from_value(params)?;
+        Method::WalletSetMeta => {
+            let r: request::WalletSetMeta = serde_json::from_value(params)?;
             let mut s = state.lock()?;
-            let wollet = s.wollets.get_mut(&r.name)?;
-            let tx = wollet
-                .tx_builder()
-                .issue_asset(
+            // Make sure the wallet exists
+            let _wollet = s.wollets.get(&r.name)?;
+            s.wallet_meta.set(&r.name, &r.namespace, &r.key, &r.value)?;
+            s.persist(&request)?;
+            Response::result(request.id, serde_json::to_value(response::Empty {})?)
+        }
+        Method::WalletGetMeta => {
+            let r: request::WalletGetMeta = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let _wollet = s.wollets.get(&r.name)?;
+            let mut entries = s.wallet_meta.get(&r.name, &r.namespace);
+            if let Some(key) = r.key {
+                entries.retain(|k, _| *k == key);
+            }
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletGetMeta { entries })?,
+            )
+        }
+        Method::WalletDiagnose => {
+            let r: request::WalletDiagnose = serde_json::from_value(params)?;
+            // Wait for a scan with the normal gap limit, so that the wallet is up to date
+            scan(&state)?;
+            let (descriptor, chains, config) = {
+                let s = state.lock()?;
+                let wollet = s.wollets.get(&r.name)?;
+                let descriptor = wollet.wollet_descriptor();
+                let mut chains = vec![(lwk_wollet::Chain::External, wollet.address(None)?.index())];
+                if descriptor.single_bitcoin_descriptors().len() > 1 {
+                    chains.push((lwk_wollet::Chain::Internal, wollet.change(None)?.index()));
+                }
+                (descriptor, chains, s.config.clone())
+            };
+
+            // The lock is released, the electrum requests don't block the other methods
+            let electrum_client = config.electrum_client()?;
+            let mut indexes = vec![];
+            let mut needed_gap_limits = vec![];
+            let mut txids = HashSet::new();
+            for (chain, last_unused) in chains {
+                let end = last_unused + consts::DIAGNOSE_GAP_LIMIT;
+                let scripts = (last_unused..end)
+                    .map(|i| descriptor.script_pubkey(chain, i))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let scripts: Vec<_> = scripts.iter().collect();
+                let histories = electrum_client.get_scripts_history(&scripts)?;
+                let max = histories
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, h)| !h.is_empty())
+                    .map(|(i, _)| last_unused + i as u32)
+                    .max();
+                txids.extend(histories.into_iter().flatten().map(|h| h.txid));
+                indexes.push(max);
+
+                // The scan looks for used addresses up to "gap limit" indexes after the last used
+                // one, which is the index before the first unused, or -1 if none is used.
+                if let Some(i) = max {
+                    needed_gap_limits.push((i + 1 - last_unused, i));
+                }
+            }
+
+            let external_index = indexes.first().copied().flatten();
+            let internal_index = indexes.get(1).copied().flatten();
+            let suggestion = needed_gap_limits.into_iter().max().map(|(needed, i)| {
+                format!("Transactions were found beyond the gap limit, up to address index {i}: the wallet needs a gap limit of at least {needed} to see them")
+            });
+            let mut txids: Vec<_> = txids.iter().map(ToString::to_string).collect();
+            txids.sort();
+            let r = response::WalletDiagnose {
+                gap_limit: consts::DIAGNOSE_GAP_LIMIT,
+                external_index,
+                internal_index,
+                txids,
+                suggestion,
+            };
+            Response::result(request.id, serde_json::to_value(r)?)
+        }
+        Method::WalletScanScope => {
+            let r: request::WalletScanScope = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let scope = s.wollets.get(&r.name)?.scan_scope();
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletScanScope {
+                    external_scanned: scope.external_scanned,
+                    internal_scanned: scope.internal_scanned,
+                })?,
+            )
+        }
+        Method::WalletIssuedAssets => {
+            let r: request::WalletIssuedAssets = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let wollet = s.wollets.get(&r.name)?;
+            let balance = wollet.balance()?;
+
+            // The (re)issuances done by the wallet are the ones in inputs spending wallet outputs
+            let wallet_inputs: HashSet<(Txid, u32)> = wollet
+                .transactions()?
+                .iter()
+                .flat_map(|tx| {
+                    tx.inputs
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, input)| input.is_some())
+                        .map(move |(vin, _)| (tx.txid, vin as u32))
+                })
+                .collect();
+            let (issuances, reissuances): (Vec<_>, Vec<_>) = wollet
+                .issuances()?
+                .into_iter()
+                .filter(|d| wallet_inputs.contains(&(d.txid, d.vin)))
+                .partition(|d| !d.is_reissuance);
+
+            let mut assets: BTreeMap<AssetId, response::IssuedAsset> = issuances
+                .iter()
+                .map(|d| {
+                    let contract = match s.get_asset(&d.asset) {
+                        Ok(AppAsset::RegistryAsset(data)) => Some(data.contract_str()),
+                        _ => None,
+                    };
+                    let issued = response::IssuedAsset {
+                        asset_id: d.asset.to_string(),
+                        token_id: d.token.to_string(),
+                        issued_supply: d.asset_amount,
+                        reissuance_token_balance: balance.get(&d.token).cloned().unwrap_or(0),
+                        contract,
+                    };
+                    (d.asset, issued)
+                })
+                .collect();
+            for d in reissuances {
+                if let Some(issued) = assets.get_mut(&d.asset) {
+                    issued.issued_supply = issued
+                        .issued_supply
+                        .zip(d.asset_amount)
+                        .map(|(supply, amount)| supply + amount);
+                }
+            }
+
+            Response::result(
+                request.id,
+                serde_json::to_value(response::WalletIssuedAssets {
+                    assets: assets.into_values().collect(),
+                })?,
+            )
+        }
+        Method::WalletIssue => {
+            let r: request::WalletIssue = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
+            let wollet = s.wollets.get_mut(&r.name)?;
+            let tx = wollet
+                .tx_builder()
+                .issue_asset(
                     r.satoshi_asset,
                     r.address_asset.map(|a| Address::from_str(&a)).transpose()?,
                     r.satoshi_token,
@@ -954,6 +1720,48 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 request.id,
                 serde_json::to_value(response::Pset {
                     pset: tx.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletIssueMany => {
+            let r: request::WalletIssueMany = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
+            let wollet = s.wollets.get_mut(&r.name)?;
+            let issuances = r
+                .issuances
+                .into_iter()
+                .map(|i| {
+                    Ok(lwk_wollet::IssuanceSpec {
+                        asset_sats: i.satoshi_asset,
+                        asset_receiver: i
+                            .address_asset
+                            .map(|a| Address::from_str(&a))
+                            .transpose()?,
+                        token_sats: i.satoshi_token,
+                        token_receiver: i
+                            .address_token
+                            .map(|a| Address::from_str(&a))
+                            .transpose()?,
+                        contract: i
+                            .contract
+                            .map(|c| lwk_wollet::Contract::from_str(&c))
+                            .transpose()?,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let mut pset = wollet
+                .tx_builder()
+                .issue_assets(issuances)?
+                .fee_rate(r.fee_rate)
+                .finish()?;
+
+            add_contracts(&mut pset, s.assets.iter());
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -961,7 +1769,17 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let r: request::WalletReissue = serde_json::from_value(params)?;
             let mut s = state.lock()?;
             let asset_id = AssetId::from_str(&r.asset)?;
-            let issuance_tx = s.get_issuance_tx(&asset_id);
+            let mut issuance_tx = s.get_issuance_tx(&asset_id);
+            if issuance_tx.is_none()
+                && !s.config.registry_url.is_empty()
+                && s.wollets.get(&r.name)?.issuance(&asset_id).is_err()
+            {
+                // The asset is unknown and was not issued by this wallet, try to get the issuance
+                // transaction from the registry
+                let (contract, tx) = fetch_registry_data(&s, &asset_id)?;
+                RegistryAssetData::new(asset_id, tx.clone(), contract)?;
+                issuance_tx = Some(tx);
+            }
             let wollet = s.wollets.get_mut(&r.name)?;
 
             let mut pset = wollet
@@ -980,6 +1798,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 request.id,
                 serde_json::to_value(response::Pset {
                     pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -989,9 +1808,88 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let asset_id = AssetId::from_str(&r.asset)?;
             let wollet = s.wollets.get_mut(&r.name)?;
 
+            let satoshi_asset = if r.burn_all {
+                if r.satoshi_asset != 0 {
+                    return Err(Error::Generic(
+                        "Cannot specify both satoshi_asset and burn_all".to_string(),
+                    ));
+                }
+                if asset_id == wollet.policy_asset() {
+                    // Fees are paid in L-BTC, we can't burn all of it
+                    return Err(Error::Generic(
+                        "Cannot burn all the policy asset".to_string(),
+                    ));
+                }
+                let balance = wollet.balance()?.get(&asset_id).cloned().unwrap_or(0);
+                if balance == 0 {
+                    return Err(Error::Generic(format!(
+                        "No balance of asset {asset_id} to burn"
+                    )));
+                }
+                balance
+            } else {
+                r.satoshi_asset
+            };
+
+            let mut pset = wollet
+                .tx_builder()
+                .add_burn(satoshi_asset, asset_id)?
+                .fee_rate(r.fee_rate)
+                .finish()?;
+
+            add_contracts(&mut pset, s.assets.iter());
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletPegout => {
+            let r: request::WalletPegout = serde_json::from_value(params)?;
+            let mut s = state.lock()?;
+            let wollet = s.wollets.get_mut(&r.name)?;
+
+            let mut pset = wollet
+                .tx_builder()
+                .pegout(&r.mainchain_address, r.satoshi)?
+                .fee_rate(r.fee_rate)
+                .finish()?;
+
+            add_contracts(&mut pset, s.assets.iter());
+            Response::result(
+                request.id,
+                serde_json::to_value(response::Pset {
+                    pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
+                })?,
+            )
+        }
+        Method::WalletClaimPegin => {
+            let r: request::WalletClaimPegin = serde_json::from_value(params)?;
+            let bitcoin_tx =
+                Vec::<u8>::from_hex(&r.bitcoin_tx).map_err(|e| Error::Generic(e.to_string()))?;
+            let bitcoin_tx = lwk_wollet::bitcoin::consensus::deserialize(&bitcoin_tx)
+                .map_err(|e| Error::Generic(e.to_string()))?;
+            let txout_proof = Vec::<u8>::from_hex(&r.txout_proof)?;
+            let claim_script = Script::from(Vec::<u8>::from_hex(&r.claim_script)?);
+
+            // Fetching the fedpegscript may hit the network, don't hold the lock meanwhile
+            let config = state.lock()?.config.clone();
+            let fed_peg_script = config.fed_peg_script()?;
+
+            let mut s = state.lock()?;
+            let wollet = s.wollets.get_mut(&r.name)?;
             let mut pset = wollet
                 .tx_builder()
-                .add_burn(r.satoshi_asset, asset_id)?
+                .claim_pegin(
+                    bitcoin_tx,
+                    r.vout,
+                    txout_proof,
+                    claim_script,
+                    &fed_peg_script,
+                )?
                 .fee_rate(r.fee_rate)
                 .finish()?;
 
@@ -1000,6 +1898,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 request.id,
                 serde_json::to_value(response::Pset {
                     pset: pset.to_string(),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -1028,6 +1927,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 serde_json::to_value(response::AssetDetails {
                     name: asset.name(),
                     ticker: asset.ticker(),
+                    network: s.config.network.as_str().to_string(),
                 })?,
             )
         }
@@ -1066,6 +1966,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let asset_id = lwk_wollet::elements::AssetId::from_str(&r.asset_id)
                 .map_err(|e| Error::Generic(e.to_string()))?;
             s.remove_asset(&asset_id)?;
+            s.registry_cache()?.remove(&asset_id)?;
             s.persist_all()?;
             Response::result(request.id, serde_json::to_value(response::Empty {})?)
         }
@@ -1076,10 +1977,8 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             if s.get_asset(&asset_id).is_ok() {
                 return Err(Error::AssetAlreadyInserted(r.asset_id));
             }
-            let registry_data = get_registry_data(&s.config.registry_url, &asset_id)?;
-            let txid = Txid::from_str(&registry_data.issuance_txin.txid)?;
-            let issuance_tx = get_tx(&s.config.esplora_api_url, &txid)?;
-            s.insert_asset(asset_id, issuance_tx, registry_data.contract)?;
+            let (contract, issuance_tx) = fetch_registry_data(&s, &asset_id)?;
+            s.insert_asset(asset_id, issuance_tx, contract)?;
             // convert the request to an AssetInsert to skip network calls
             let asset_insert_request = s.get_asset(&asset_id)?.request().expect("asset");
             s.persist(&asset_insert_request)?;
@@ -1133,13 +2032,11 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
             let s = state.lock()?;
             let asset = s.get_asset(&asset_id)?;
             if let AppAsset::RegistryAsset(asset) = asset {
-                let client = reqwest::blocking::Client::new();
                 let url = &s.config.registry_url;
                 let contract = asset.contract();
                 let data = serde_json::json!({"asset_id": asset_id, "contract": contract});
                 log::debug!("posting {data:?} as json to {url} ");
-                let response = client.post(url).json(&data).send()?;
-                let mut result = response.text()?;
+                let mut result = s.http.post_json_text(url, &data)?;
                 if result.contains("failed verifying linked entity") {
                     let domain = contract.entity.domain();
                     result = format!("https://{domain}/.well-known/liquid-asset-proof-{asset_id} must contain the following 'Authorize linking the domain name {domain} to the Liquid asset {asset_id}'");
@@ -1157,17 +2054,29 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 ));
             }
         }
+        Method::AssetVerify => {
+            let r: request::AssetVerify = serde_json::from_value(params)?;
+            let asset_id =
+                AssetId::from_str(&r.asset_id).map_err(|e| Error::Generic(e.to_string()))?;
+            let issuance_tx =
+                Vec::<u8>::from_hex(&r.issuance_tx).map_err(|e| Error::Generic(e.to_string()))?;
+            let issuance_tx: Transaction = lwk_wollet::elements::encode::deserialize(&issuance_tx)
+                .map_err(|e| Error::Generic(e.to_string()))?;
+            let contract = lwk_wollet::Contract::from_str(&r.contract)?;
+            contract.verify_against(asset_id, &issuance_tx)?;
+            if r.domain_proof {
+                let s = state.lock()?;
+                verify_domain_proof(&s, &contract, &asset_id)?;
+            }
+            Response::result(request.id, serde_json::to_value(response::Empty {})?)
+        }
         Method::Amp2Descriptor => {
             let r: request::Amp2Descriptor = serde_json::from_value(params)?;
             let mut s = state.lock()?;
-            if !matches!(s.config.network, lwk_wollet::ElementsNetwork::LiquidTestnet) {
-                return Err(Error::Generic(
-                    "AMP2 methods are not available for this network".into(),
-                ));
-            }
+            let amp2 = Amp2::for_network(&s.config.network)?;
+            let is_mainnet = s.config.is_mainnet();
             let signer = s.get_available_signer(&r.name)?;
-            let amp2 = Amp2::new_testnet();
-            let desc = amp2.descriptor_from_str(&amp2userkey(signer)?)?;
+            let desc = amp2.descriptor_from_str(&amp2userkey(signer, is_mainnet)?)?;
             let descriptor = desc.descriptor().to_string();
             Response::result(
                 request.id,
@@ -1177,14 +2086,10 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
         Method::Amp2Register => {
             let r: request::Amp2Register = serde_json::from_value(params)?;
             let mut s = state.lock()?;
-            if !matches!(s.config.network, lwk_wollet::ElementsNetwork::LiquidTestnet) {
-                return Err(Error::Generic(
-                    "AMP2 methods are not available for this network".into(),
-                ));
-            }
+            let amp2 = Amp2::for_network(&s.config.network)?;
+            let is_mainnet = s.config.is_mainnet();
             let signer = s.get_available_signer(&r.name)?;
-            let amp2 = Amp2::new_testnet();
-            let desc = amp2.descriptor_from_str(&amp2userkey(signer)?)?;
+            let desc = amp2.descriptor_from_str(&amp2userkey(signer, is_mainnet)?)?;
             let wid = amp2.blocking_register(desc)?.wid;
             Response::result(
                 request.id,
@@ -1194,12 +2099,7 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
         Method::Amp2Cosign => {
             let r: request::Amp2Cosign = serde_json::from_value(params)?;
             let s = state.lock()?;
-            if !matches!(s.config.network, lwk_wollet::ElementsNetwork::LiquidTestnet) {
-                return Err(Error::Generic(
-                    "AMP2 methods are not available for this network".into(),
-                ));
-            }
-            let amp2 = Amp2::new_testnet();
+            let amp2 = Amp2::for_network(&s.config.network)?;
             let pset = PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
             let pset = amp2.blocking_cosign(&pset)?.pset.to_string();
             Response::result(
@@ -1207,30 +2107,175 @@ fn inner_method_handler(request: Request, state: Arc<Mutex<State>>) -> Result<Re
                 serde_json::to_value(response::Amp2Cosign { pset })?,
             )
         }
+        Method::Amp2CosignStatus => {
+            let r: request::Amp2CosignStatus = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let amp2 = Amp2::for_network(&s.config.network)?;
+            let pset = PartiallySignedTransaction::from_str(&r.pset_or_id).ok();
+            let cosign_ref = match pset.as_ref() {
+                Some(pset) => CosignRef::Pset(pset),
+                None => CosignRef::Id(&r.pset_or_id),
+            };
+            let status = match amp2.blocking_cosign_status(cosign_ref)? {
+                CosignStatus::Pending => response::Amp2CosignStatus {
+                    cosigned: false,
+                    pset: None,
+                },
+                CosignStatus::Cosigned(pset) => response::Amp2CosignStatus {
+                    cosigned: true,
+                    pset: Some(pset.to_string()),
+                },
+            };
+            Response::result(request.id, serde_json::to_value(status)?)
+        }
+        Method::ParsePayment => {
+            let r: request::ParsePayment = serde_json::from_value(params)?;
+            let s = state.lock()?;
+            let payment = parse_payment(&r.uri, &s)?;
+            check_payment_network(&payment, &s.config)?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::ParsePayment {
+                    kind: payment.kind.as_str().to_string(),
+                    address: payment.address.map(|a| a.to_string()),
+                    amount: payment.amount,
+                    asset: payment.asset.map(|a| a.to_string()),
+                    invoice: payment.invoice,
+                    label: payment.label,
+                    message: payment.message,
+                })?,
+            )
+        }
+        Method::PsetConvertVersion => {
+            let r: request::PsetConvertVersion = serde_json::from_value(params)?;
+            let pset = PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
+            let converted = lwk_common::pset_convert_version(&pset, r.target)?;
+            Response::result(
+                request.id,
+                serde_json::to_value(response::PsetConvertVersion {
+                    pset: converted.to_string(),
+                    from_version: lwk_common::pset_version(&pset),
+                    version: lwk_common::pset_version(&converted),
+                })?,
+            )
+        }
+        Method::PsetBlindingStatus => {
+            let r: request::PsetBlindingStatus = serde_json::from_value(params)?;
+            let pset = PartiallySignedTransaction::from_str(&r.pset).map_err(|e| e.to_string())?;
+            let status = lwk_common::pset_blinding_status(&pset);
+            Response::result(
+                request.id,
+                serde_json::to_value(response::PsetBlindingStatus {
+                    all_outputs_blinded: status.all_outputs_blinded,
+                    missing_output_indices: status.missing_output_indices,
+                })?,
+            )
+        }
     };
     Ok(response)
 }
 
+/// ETag of a wallet response, changing with the wallet state and with the response content,
+/// which also depends on memos and tickers
+fn wallet_etag(status: u64, value: &Value) -> String {
+    let mut hasher = DefaultHasher::new();
+    status.hash(&mut hasher);
+    value.to_string().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
 fn scan(state: &Arc<Mutex<State>>) -> Result<(), Error> {
-    let required_scan_loops = {
-        let mut s = state.lock()?;
-        s.interrupt_wait = true;
-        // We want to wait for an _entire_ scan loop to be completed.
-        // So if we are scanning, wait for an additional scan loop.
-        let is_scanning = s.scan_loops_completed != s.scan_loops_started;
-        s.scan_loops_completed + is_scanning as u32
-    };
-    loop {
-        std::thread::sleep(std::time::Duration::from_millis(100));
-        let current_scan_loops = state.lock()?.scan_loops_completed;
-        if current_scan_loops > required_scan_loops {
-            break;
+    // Do not hold the state lock while waiting, the scanning thread needs it
+    let scan_loops = state.lock()?.scan_loops.clone();
+    scan_loops.request_and_wait()?;
+    Ok(())
+}
+
+/// Replace the secrets in a request or response with a placeholder, so that they can be logged
+pub(crate) fn redact(mut value: Value) -> Value {
+    match &mut value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                *v = if consts::SECRET_FIELDS.contains(&key.as_str()) {
+                    Value::String("<redacted>".into())
+                } else {
+                    redact(v.take())
+                };
+            }
+        }
+        Value::Array(values) => {
+            for v in values.iter_mut() {
+                *v = redact(v.take());
+            }
+        }
+        _ => {}
+    }
+    value
+}
+
+/// Messages from the scanning thread to the thread applying the updates
+enum ScanMessage {
+    /// Apply the update to the wallet with the given name
+    Update(String, Box<lwk_wollet::Update>),
+
+    /// Notify when all the previous updates have been applied
+    Flush(Sender<()>),
+}
+
+/// Check the signer has the `expected` fingerprint, if any
+///
+/// For jade loaded with the id only, the fingerprint is derived from the id since the device is
+/// not connected yet.
+fn check_fingerprint(signer: &AppSigner, expected: Option<&str>) -> Result<(), Error> {
+    if let Some(expected) = expected {
+        let expected =
+            Fingerprint::from_str(expected).map_err(|e| Error::Generic(e.to_string()))?;
+        let actual = signer.fingerprint()?;
+        if expected != actual {
+            return Err(Error::FingerprintMismatch { expected, actual });
         }
-        // TODO: fail if waited too much
     }
     Ok(())
 }
 
+/// Parse the payment, reading amounts of assets with the precision of the known assets
+fn parse_payment(uri: &str, s: &State) -> Result<Payment, Error> {
+    match lwk_common::parse_payment(uri) {
+        Err(PaymentError::AmountPrecisionUnknown(asset)) => match s.assets.precision(&asset) {
+            Some(precision) => Ok(lwk_common::parse_payment_with_precision(uri, precision)?),
+            None => Err(PaymentError::AmountPrecisionUnknown(asset).into()),
+        },
+        result => Ok(result?),
+    }
+}
+
+fn check_payment_network(payment: &Payment, config: &Config) -> Result<(), Error> {
+    let same_network = match &payment.address {
+        Some(address) => address.params == config.network.address_params(),
+        None => payment.is_mainnet() == config.is_mainnet(),
+    };
+    if same_network {
+        Ok(())
+    } else {
+        Err(Error::PaymentNetworkMismatch(
+            config.network.as_str().to_string(),
+        ))
+    }
+}
+
+/// The fee rate estimated by `client` to confirm within `target_blocks`, and whether the default
+/// fee rate has been used instead, because the estimate is not available
+fn smart_fee_rate<B: BlockchainBackend>(client: &B, target_blocks: u32) -> (f32, bool) {
+    match client.estimate_fee_rate(target_blocks) {
+        Ok(Some(fee_rate)) => (fee_rate.max(lwk_wollet::MIN_RELAY_FEE_RATE), false),
+        Ok(None) => (lwk_wollet::MIN_RELAY_FEE_RATE, true),
+        Err(e) => {
+            log::warn!("cannot estimate the fee rate, using the default: {e}");
+            (lwk_wollet::MIN_RELAY_FEE_RATE, true)
+        }
+    }
+}
+
 fn unvalidated_addressee(a: request::UnvalidatedAddressee) -> lwk_wollet::UnvalidatedRecipient {
     lwk_wollet::UnvalidatedRecipient {
         satoshi: a.satoshi,
@@ -1243,6 +2288,7 @@ fn signer_response_from(name: &str, signer: &AppSigner) -> Result<response::Sign
     Ok(response::Signer {
         name: name.to_string(),
         fingerprint: signer.fingerprint()?.to_string(),
+        type_: signer.type_(),
     })
 }
 
@@ -1254,6 +2300,7 @@ fn signer_details(name: &str, signer: &AppSigner) -> Result<response::SignerDeta
         xpub: signer.xpub()?.map(|x| x.to_string()),
         mnemonic: signer.mnemonic(),
         type_: signer.type_(),
+        capabilities: signer.capabilities(),
     })
 }
 
@@ -1275,17 +2322,69 @@ fn add_contracts<'a>(
     }
 }
 
-fn convert_utxo(u: &lwk_wollet::WalletTxOut) -> response::Utxo {
+/// Details of a PSET containing inputs not supported by [`lwk_common::pset_balance()`]
+///
+/// The balance can't be computed and it's left empty.
+fn partial_details(pset: &PartiallySignedTransaction) -> PsetDetails {
+    let fee = pset
+        .outputs()
+        .iter()
+        .filter(|o| o.script_pubkey.is_empty())
+        .filter_map(|o| o.amount)
+        .sum();
+    PsetDetails {
+        balance: PsetBalance::new(fee, Default::default()),
+        sig_details: pset_signatures(pset),
+        issuances: pset_issuances(pset),
+    }
+}
+
+fn convert_tip(tip: &lwk_wollet::Tip, config: &Config) -> response::WalletTip {
+    response::WalletTip {
+        height: tip.height(),
+        hash: tip.hash().to_string(),
+        timestamp: tip.timestamp(),
+        network: config.network.as_str().to_string(),
+    }
+}
+
+fn convert_utxo(u: &lwk_wollet::WalletTxOut, tip: u32) -> response::Utxo {
     response::Utxo {
         txid: u.outpoint.txid.to_string(),
         vout: u.outpoint.vout,
         height: u.height,
+        confirmations: u.height.map_or(0, |h| (tip + 1).saturating_sub(h)),
         script_pubkey: u.script_pubkey.to_hex(),
         asset: u.unblinded.asset.to_string(),
         value: u.unblinded.value,
+        derivation_path: None,
+        key_origin: None,
     }
 }
 
+/// The derivation path relative to the wallet xpubs and the key origins of the keys in the UTXO
+/// script pubkey
+fn utxo_derivation(
+    descriptor: &WolletDescriptor,
+    u: &lwk_wollet::WalletTxOut,
+) -> Result<(String, Vec<String>), Error> {
+    let definite_desc = descriptor.definite_descriptor(u.ext_int, u.wildcard_index)?;
+    let mut derivation_path = None;
+    let mut key_origin = vec![];
+    definite_desc.for_each_key(|k| {
+        if let Some(path) = k.full_derivation_path() {
+            key_origin.push(format!("[{}/{path}]", k.master_fingerprint()));
+        }
+        if let DescriptorPublicKey::XPub(x) = k.as_descriptor_public_key() {
+            derivation_path.get_or_insert_with(|| x.derivation_path.to_string());
+        }
+        true
+    });
+    let derivation_path =
+        derivation_path.ok_or_else(|| Error::Generic("Descriptor has no xpub".into()))?;
+    Ok((derivation_path, key_origin))
+}
+
 fn convert_tx(
     tx: &lwk_wollet::WalletTx,
     explorer_url: &str,
@@ -1309,9 +2408,61 @@ fn convert_tx(
     }
 }
 
-fn amp2userkey(signer: &AnySigner) -> Result<String, Error> {
+/// Round `sats` down to its order of magnitude, eg 1234 to 1000 and -56 to -10
+///
+/// Shows the size of an amount without revealing it.
+fn amount_bucket(sats: i64) -> i64 {
+    if sats == 0 {
+        return 0;
+    }
+    sats.signum() * 10i64.pow(sats.unsigned_abs().ilog10())
+}
+
+/// Replace the amounts of `tx` with their [`amount_bucket()`], keeping the assets involved
+///
+/// The unblinded url is removed since it contains the amounts.
+fn hide_tx_amounts(tx: &mut response::Tx) {
+    tx.balance.values_mut().for_each(|v| *v = amount_bucket(*v));
+    tx.fee = amount_bucket(tx.fee as i64) as u64;
+    tx.unblinded_url.clear();
+}
+
+/// Get the contract and the issuance transaction of the given asset from the registry
+///
+/// The data is cached, the issuance transaction is fetched from the esplora backend.
+fn fetch_registry_data(
+    s: &State,
+    asset_id: &AssetId,
+) -> Result<(lwk_wollet::Contract, Transaction), Error> {
+    s.registry_cache()?.get_or_fetch(asset_id, || {
+        let registry_data = get_registry_data(&s.http, &s.config.registry_url, asset_id)?;
+        let txid = Txid::from_str(&registry_data.issuance_txin.txid)?;
+        let issuance_tx = get_tx(&s.http, &s.config.esplora_api_url, &txid)?;
+        Ok((registry_data.contract, issuance_tx))
+    })
+}
+
+/// Check the contract domain authorizes the asset, as the registry does before accepting it
+fn verify_domain_proof(
+    s: &State,
+    contract: &lwk_wollet::Contract,
+    asset_id: &AssetId,
+) -> Result<(), Error> {
+    let domain = contract.entity.domain();
+    let url = format!("https://{domain}/.well-known/liquid-asset-proof-{asset_id}");
+    let expected =
+        format!("Authorize linking the domain name {domain} to the Liquid asset {asset_id}");
+    let proof = s.http.get_text(&url)?;
+    if !proof.contains(&expected) {
+        return Err(Error::Generic(format!(
+            "{url} must contain the following '{expected}'"
+        )));
+    }
+    Ok(())
+}
+
+fn amp2userkey(signer: &AnySigner, is_mainnet: bool) -> Result<String, Error> {
     let bip = lwk_common::Bip::Bip87;
-    let is_mainnet = false;
     Ok(signer.keyorigin_xpub(bip, is_mainnet)?)
 }
 
@@ -1322,6 +2473,10 @@ mod tests {
     use super::*;
 
     fn app_random_port() -> App {
+        app_random_port_with(|_| {})
+    }
+
+    fn app_random_port_with(f: impl FnOnce(&mut Config)) -> App {
         let addr = TcpListener::bind("127.0.0.1:0")
             .unwrap()
             .local_addr()
@@ -1329,6 +2484,7 @@ mod tests {
         let tempdir = tempfile::tempdir().unwrap();
         let mut config = Config::default_testnet(tempdir.path().to_path_buf());
         config.addr = addr;
+        f(&mut config);
         let mut app = App::new(config).unwrap();
         app.run().unwrap();
         app
@@ -1351,4 +2507,325 @@ mod tests {
         app.stop().unwrap();
         app.join_threads().unwrap();
     }
+
+    #[test]
+    fn network() {
+        let mut app = app_random_port();
+        let client = Client::new(app.addr()).unwrap();
+        let expected = "liquid-testnet";
+        assert_eq!(client.version().unwrap().network, expected);
+
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        client
+            .signer_load_software("s".into(), mnemonic.into(), None, false)
+            .unwrap();
+        let desc = client
+            .signer_singlesig_descriptor("s".into(), "slip77".into(), "wpkh".into())
+            .unwrap()
+            .descriptor;
+        client.wallet_load(desc, None, "w".into()).unwrap();
+
+        let r = client
+            .wallet_address("w".into(), None, None, false, None)
+            .unwrap();
+        assert_eq!(r.network, expected);
+        let r = client.wallet_balance("w".into(), false, false).unwrap();
+        assert_eq!(r.network, expected);
+        let r = client.wallet_utxos("w".into()).unwrap();
+        assert_eq!(r.network, expected);
+        let r = client.wallet_txs("w".into(), false).unwrap();
+        assert_eq!(r.network, expected);
+
+        // Responses of servers without the network field can still be parsed
+        let r: response::Pset = serde_json::from_str(r#"{"pset":""}"#).unwrap();
+        assert_eq!(r.network, "");
+
+        app.stop().unwrap();
+        app.join_threads().unwrap();
+    }
+
+    #[test]
+    fn rest() {
+        let mut app = app_random_port_with(|c| c.rest = true);
+        let client = Client::new(app.addr()).unwrap();
+        let base = format!("http://{}", app.addr());
+
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        client
+            .signer_load_software("s".into(), mnemonic.into(), None, false)
+            .unwrap();
+        let desc = client
+            .signer_singlesig_descriptor("s".into(), "slip77".into(), "wpkh".into())
+            .unwrap()
+            .descriptor;
+        client.wallet_load(desc, None, "w".into()).unwrap();
+
+        let get = |path: &str| {
+            let resp = reqwest::blocking::get(format!("{base}{path}")).unwrap();
+            (resp.status().as_u16(), resp.json::<Value>().unwrap())
+        };
+
+        let (status, r) = get("/api/wallet/w/balance");
+        assert_eq!(status, 200);
+        let expected = client.wallet_balance("w".into(), false, false).unwrap();
+        assert_eq!(r, serde_json::to_value(expected).unwrap());
+
+        let (_, r) = get("/api/wallet/w/txs");
+        let expected = client.wallet_txs("w".into(), false).unwrap();
+        assert_eq!(r, serde_json::to_value(expected).unwrap());
+
+        let (_, r) = get("/api/wallet/w/utxos");
+        let expected = client.wallet_utxos("w".into()).unwrap();
+        assert_eq!(r, serde_json::to_value(expected).unwrap());
+
+        let (_, r) = get("/api/wallet/w/address?index=2");
+        let expected = client
+            .wallet_address("w".into(), Some(2), None, false, None)
+            .unwrap();
+        assert_eq!(r, serde_json::to_value(expected).unwrap());
+
+        // Mutating methods with POST
+        let body = serde_json::json!({"namespace": "shop", "key": "order", "value": "1"});
+        let resp = reqwest::blocking::Client::new()
+            .post(format!("{base}/api/wallet/w/set-meta"))
+            .json(&body)
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let r = client
+            .wallet_get_meta("w".into(), "shop".into(), None)
+            .unwrap();
+        assert_eq!(r.entries["order"], "1");
+
+        // Errors
+        let (status, r) = get("/api/wallet/missing/balance");
+        assert_eq!(status, 400);
+        let message = r["message"].as_str().unwrap();
+        assert!(message.contains("Wallet 'missing' does not exist"));
+        let resp = reqwest::blocking::get(format!("{base}/api/wallet/w/unknown")).unwrap();
+        assert_eq!(resp.status(), 404);
+
+        app.stop().unwrap();
+        app.join_threads().unwrap();
+    }
+
+    #[test]
+    fn apply_update() {
+        // Without an electrum server the wallet changes only with the applied updates
+        let mut app = app_random_port_with(|c| c.electrum_url = "127.0.0.1:1".into());
+        let client = Client::new(app.addr()).unwrap();
+        let desc = "ct(slip77(9c8e4f05c7711a98c838be228bcb84924d4570ca53f35fa1c793e58841d47023),elwpkh([73c5da0a/84'/1'/0']tpubDC8msFGeGuwnKG9Upg7DM2b4DaRqg3CUZa5g8v2SRQ6K4NSkxUgd7HsL2XVWbVm39yBA4LAxysQAm397zwQSQoQgewGiYZqrA9DsP4zbQ1M/<0;1>/*))";
+        client.wallet_load(desc.into(), None, "w".into()).unwrap();
+        assert_eq!(client.wallet_tip("w".into()).unwrap().height, 0);
+
+        let bytes = lwk_test_util::update_test_vector_bytes();
+        let update = lwk_wollet::Update::deserialize(&bytes).unwrap();
+
+        // Updates with scripts not belonging to the wallet are rejected
+        let mut other = update.clone();
+        let child = lwk_wollet::bitcoin::bip32::ChildNumber::from_normal_idx(0).unwrap();
+        other.scripts.insert(
+            lwk_wollet::elements::Script::from(vec![0x51]),
+            (lwk_wollet::Chain::External, child),
+        );
+        let err = client
+            .wallet_apply_update("w".into(), other.serialize().unwrap().to_hex())
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("not derived from the wallet descriptor"));
+        assert_eq!(client.wallet_tip("w".into()).unwrap().height, 0);
+
+        let network = lwk_wollet::ElementsNetwork::LiquidTestnet;
+        let mut wollet = Wollet::without_persist(network, desc.parse().unwrap()).unwrap();
+        wollet.apply_update(update).unwrap();
+        let tip = client
+            .wallet_apply_update("w".into(), bytes.to_hex())
+            .unwrap();
+        assert_eq!(tip.height, 1);
+        assert_eq!(tip.hash, wollet.tip().hash().to_string());
+        let tip_after = client.wallet_tip("w".into()).unwrap();
+        assert_eq!(tip_after.hash, tip.hash);
+
+        let balance = client.wallet_balance("w".into(), false, false).unwrap();
+        let expected: HashMap<String, i64> = wollet
+            .balance()
+            .unwrap()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v as i64))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(balance.balance, expected);
+
+        app.stop().unwrap();
+        app.join_threads().unwrap();
+    }
+
+    #[test]
+    fn expected_fingerprint() {
+        let mut app = app_random_port();
+        let client = Client::new(app.addr()).unwrap();
+
+        // Without the device connected, the fingerprint is derived from the id
+        let id = "e3ebcc79ebfedb4f2ae34406827dc1c5cb48e11f";
+        let err = client
+            .signer_load_jade("j".into(), id.into(), None, Some("00000000".into()))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Signer fingerprint e3ebcc79 does not match the expected 00000000"));
+        let r = client
+            .signer_load_jade("j".into(), id.into(), None, Some("e3ebcc79".into()))
+            .unwrap();
+        assert_eq!(r.fingerprint, "e3ebcc79");
+
+        app.stop().unwrap();
+        app.join_threads().unwrap();
+    }
+
+    struct CaptureLogger(Mutex<Vec<String>>);
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger(Mutex::new(Vec::new()));
+
+    #[test]
+    fn mnemonic_not_logged() {
+        // The logger is global, if another logger has been installed the logs can't be checked
+        let capturing = log::set_logger(&LOGGER).is_ok();
+        if capturing {
+            log::set_max_level(log::LevelFilter::Trace);
+        }
+
+        let mut app = app_random_port();
+        let client = Client::new(app.addr()).unwrap();
+
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mnemonic");
+        std::fs::write(&path, format!("{mnemonic}\n")).unwrap();
+        let path = path.display().to_string();
+        let from_file = client
+            .signer_load_software_file("s1".into(), path.clone(), None, false)
+            .unwrap();
+        let inline = client
+            .signer_load_software("s2".into(), mnemonic.into(), None, false)
+            .unwrap();
+        assert_eq!(from_file.fingerprint, inline.fingerprint);
+        let passphrase = "correct horse battery staple";
+        let with_passphrase = client
+            .signer_load_software("s3".into(), mnemonic.into(), Some(passphrase.into()), false)
+            .unwrap();
+        assert_ne!(with_passphrase.fingerprint, inline.fingerprint);
+        let details = client.signer_details("s1".into()).unwrap();
+        assert_eq!(details.mnemonic.unwrap(), mnemonic);
+        let generated = client.signer_generate().unwrap().mnemonic;
+
+        let err = client
+            .signer_load_software_file("s4".into(), format!("{path}-missing"), None, false)
+            .unwrap_err();
+        assert!(!err.to_string().contains(mnemonic));
+
+        app.stop().unwrap();
+        app.join_threads().unwrap();
+
+        if !capturing {
+            return;
+        }
+        let logs = LOGGER.0.lock().unwrap();
+        assert!(logs.iter().any(|l| l.contains("signer_load_software")));
+        for line in logs.iter() {
+            assert!(!line.contains(mnemonic), "mnemonic logged: {line}");
+            assert!(!line.contains(&generated), "mnemonic logged: {line}");
+            assert!(!line.contains(passphrase), "passphrase logged: {line}");
+        }
+    }
+
+    /// A backend estimating fee rates, with an empty chain at a fixed tip
+    struct MockEstimator(Result<Option<f32>, String>);
+
+    impl BlockchainBackend for MockEstimator {
+        fn tip(&mut self) -> Result<lwk_wollet::elements::BlockHeader, lwk_wollet::Error> {
+            Ok(lwk_test_util::liquid_block_header_2_963_520())
+        }
+        fn broadcast(&self, tx: &Transaction) -> Result<Txid, lwk_wollet::Error> {
+            Ok(tx.txid())
+        }
+        fn get_transactions(&self, _: &[Txid]) -> Result<Vec<Transaction>, lwk_wollet::Error> {
+            Ok(vec![])
+        }
+        fn get_headers(
+            &self,
+            _: &[u32],
+            _: &HashMap<u32, lwk_wollet::elements::BlockHash>,
+        ) -> Result<Vec<lwk_wollet::elements::BlockHeader>, lwk_wollet::Error> {
+            Ok(vec![])
+        }
+        fn get_scripts_history(
+            &self,
+            scripts: &[&lwk_wollet::elements::Script],
+        ) -> Result<Vec<Vec<lwk_wollet::History>>, lwk_wollet::Error> {
+            Ok(vec![vec![]; scripts.len()])
+        }
+        fn estimate_fee_rate(&self, _: u32) -> Result<Option<f32>, lwk_wollet::Error> {
+            self.0.clone().map_err(lwk_wollet::Error::Generic)
+        }
+    }
+
+    #[test]
+    fn smart_fee() {
+        let min = lwk_wollet::MIN_RELAY_FEE_RATE;
+        assert_eq!(
+            smart_fee_rate(&MockEstimator(Ok(Some(250.0))), 2),
+            (250.0, false)
+        );
+        // Never below the minimum relay fee
+        assert_eq!(
+            smart_fee_rate(&MockEstimator(Ok(Some(1.0))), 2),
+            (min, false)
+        );
+        assert_eq!(smart_fee_rate(&MockEstimator(Ok(None)), 2), (min, true));
+        let err = MockEstimator(Err("unavailable".to_string()));
+        assert_eq!(smart_fee_rate(&err, 2), (min, true));
+    }
+
+    #[test]
+    fn hide_amounts() {
+        assert_eq!(amount_bucket(0), 0);
+        assert_eq!(amount_bucket(7), 1);
+        assert_eq!(amount_bucket(10), 10);
+        assert_eq!(amount_bucket(1234), 1000);
+        assert_eq!(amount_bucket(-56), -10);
+        assert_eq!(amount_bucket(i64::MIN), -1_000_000_000_000_000_000);
+
+        let mut tx = response::Tx {
+            txid: "txid".to_string(),
+            height: Some(10),
+            timestamp: Some(1_700_000_000),
+            balance: HashMap::from([("asset".to_string(), -123_456)]),
+            fee: 250,
+            type_: "outgoing".to_string(),
+            unblinded_url: "https://blockstream.info/liquid/tx/txid#blinded=...".to_string(),
+            memo: "rent".to_string(),
+        };
+        hide_tx_amounts(&mut tx);
+        assert_eq!(tx.balance.get("asset"), Some(&-100_000));
+        assert_eq!(tx.fee, 100);
+        assert!(tx.unblinded_url.is_empty());
+        assert_eq!(
+            (tx.txid.as_str(), tx.height, tx.timestamp),
+            ("txid", Some(10), Some(1_700_000_000))
+        );
+        assert_eq!((tx.type_.as_str(), tx.memo.as_str()), ("outgoing", "rent"));
+    }
 }
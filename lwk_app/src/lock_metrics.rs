@@ -0,0 +1,141 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::Error;
+
+/// Waits or holds longer than this are logged
+const SLOW_LOCK: Duration = Duration::from_millis(500);
+
+/// Time spent waiting for and holding a lock, accumulated over all the acquisitions
+///
+/// The hold time is what other threads contending the lock may have to wait.
+#[derive(Debug, Default)]
+pub struct LockMetrics {
+    acquisitions: AtomicU64,
+    wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+    held_micros: AtomicU64,
+    max_held_micros: AtomicU64,
+}
+
+/// A [`MutexGuard`] recording in [`LockMetrics`] how long it has been held when dropped
+pub struct MeteredGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    metrics: &'a LockMetrics,
+    acquired: Instant,
+}
+
+impl LockMetrics {
+    /// Lock `mutex`, recording the time spent waiting for it
+    pub fn lock<'a, T>(&'a self, mutex: &'a Mutex<T>) -> Result<MeteredGuard<'a, T>, Error> {
+        let start = Instant::now();
+        let guard = mutex.lock()?;
+        let acquired = Instant::now();
+        let wait = acquired - start;
+        if wait > SLOW_LOCK {
+            log::warn!("Waited {wait:?} for the state lock");
+        }
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        record(&self.wait_micros, &self.max_wait_micros, wait);
+        Ok(MeteredGuard {
+            guard,
+            metrics: self,
+            acquired,
+        })
+    }
+
+    /// Number of times the lock has been acquired
+    pub fn acquisitions(&self) -> u64 {
+        self.acquisitions.load(Ordering::Relaxed)
+    }
+
+    /// Total time spent waiting for the lock
+    pub fn wait(&self) -> Duration {
+        Duration::from_micros(self.wait_micros.load(Ordering::Relaxed))
+    }
+
+    /// Longest time spent waiting for the lock
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_micros(self.max_wait_micros.load(Ordering::Relaxed))
+    }
+
+    /// Total time the lock has been held
+    pub fn held(&self) -> Duration {
+        Duration::from_micros(self.held_micros.load(Ordering::Relaxed))
+    }
+
+    /// Longest time the lock has been held
+    pub fn max_held(&self) -> Duration {
+        Duration::from_micros(self.max_held_micros.load(Ordering::Relaxed))
+    }
+}
+
+fn record(total: &AtomicU64, max: &AtomicU64, elapsed: Duration) {
+    let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+    total.fetch_add(micros, Ordering::Relaxed);
+    max.fetch_max(micros, Ordering::Relaxed);
+}
+
+impl<T> Deref for MeteredGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for MeteredGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for MeteredGuard<'_, T> {
+    fn drop(&mut self) {
+        let held = self.acquired.elapsed();
+        if held > SLOW_LOCK {
+            log::warn!("Held the state lock for {held:?}");
+        }
+        record(
+            &self.metrics.held_micros,
+            &self.metrics.max_held_micros,
+            held,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::LockMetrics;
+
+    #[test]
+    fn lock_metrics() {
+        let metrics = Arc::new(LockMetrics::default());
+        let mutex = Arc::new(Mutex::new(0u32));
+
+        {
+            let mut guard = metrics.lock(&mutex).unwrap();
+            *guard += 1;
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(metrics.acquisitions(), 1);
+        assert!(metrics.max_held() >= Duration::from_millis(50));
+
+        // Another thread holds the lock, we have to wait
+        let guard = mutex.lock().unwrap();
+        let (m, mx) = (metrics.clone(), mutex.clone());
+        let handle = std::thread::spawn(move || *m.lock(&mx).unwrap());
+        std::thread::sleep(Duration::from_millis(50));
+        drop(guard);
+        assert_eq!(handle.join().unwrap(), 1);
+        assert_eq!(metrics.acquisitions(), 2);
+        assert!(metrics.max_wait() >= Duration::from_millis(40));
+        assert!(metrics.wait() >= metrics.max_wait());
+        assert!(metrics.held() >= metrics.max_held());
+    }
+}
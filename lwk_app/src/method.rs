@@ -24,33 +24,59 @@ pub enum Method {
     WalletUnload,
     WalletList,
     WalletDetails,
+    WalletPublicDescriptor,
+    WalletPolicy,
     WalletAddress,
+    WalletAddressBatch,
+    WalletAddressInfoBatch,
     WalletBalance,
     WalletUtxos,
+    WalletDustDeposits,
+    WalletApplyUpdate,
+    WalletTip,
     WalletTxs,
     WalletTx,
     WalletSendMany,
+    WalletSendManySmartFee,
+    WalletSendToPayment,
     WalletDrain,
     WalletIssue,
+    WalletIssueMany,
     WalletReissue,
     WalletBurn,
+    WalletPegout,
+    WalletClaimPegin,
     WalletCombine,
     WalletBroadcast,
+    WalletPreviewTx,
+    WalletAnnotatePset,
+    WalletBlindPset,
+    WalletHydratePset,
+    WalletProofOfReserves,
+    WalletVerifyProofOfReserves,
     WalletPsetDetails,
     WalletMultisigDescriptor,
     WalletSetTxMemo,
     WalletSetAddrMemo,
+    WalletSetMeta,
+    WalletGetMeta,
+    WalletDiagnose,
+    WalletScanScope,
+    WalletIssuedAssets,
     SignerGenerate,
     SignerJadeId,
     SignerLoadSoftware,
     SignerLoadJade,
+    SignerLoadLedger,
     SignerLoadExternal,
     SignerUnload,
     SignerList,
     SignerDetails,
     SignerXpub,
+    SignerXpubKinds,
     SignerSign,
     SignerSinglesigDescriptor,
+    SignerAccountDescriptor,
     SignerRegisterMultisig,
     AssetContract,
     AssetInsert,
@@ -59,9 +85,14 @@ pub enum Method {
     AssetDetails,
     AssetFromExplorer,
     AssetPublish,
+    AssetVerify,
     Amp2Descriptor,
     Amp2Register,
     Amp2Cosign,
+    Amp2CosignStatus,
+    ParsePayment,
+    PsetConvertVersion,
+    PsetBlindingStatus,
 }
 impl Method {
     pub(crate) fn schema(&self, direction: request::Direction) -> Result<Value, serde_json::Error> {
@@ -75,35 +106,65 @@ impl Method {
                 Method::WalletUnload => schema_for!(request::WalletUnload),
                 Method::WalletList => schema_for!(request::Empty),
                 Method::WalletDetails => schema_for!(request::WalletDetails),
+                Method::WalletPublicDescriptor => schema_for!(request::WalletPublicDescriptor),
+                Method::WalletPolicy => schema_for!(request::WalletPolicy),
                 Method::WalletAddress => schema_for!(request::WalletAddress),
+                Method::WalletAddressBatch => schema_for!(request::WalletAddressBatch),
+                Method::WalletAddressInfoBatch => schema_for!(request::WalletAddressInfoBatch),
                 Method::WalletBalance => schema_for!(request::WalletBalance),
                 Method::WalletUtxos => schema_for!(request::WalletUtxos),
+                Method::WalletDustDeposits => schema_for!(request::WalletDustDeposits),
+                Method::WalletApplyUpdate => schema_for!(request::WalletApplyUpdate),
+                Method::WalletTip => schema_for!(request::WalletTip),
                 Method::WalletTxs => schema_for!(request::WalletTxs),
                 Method::WalletTx => schema_for!(request::WalletTx),
                 Method::WalletSendMany => schema_for!(request::WalletSendMany),
+                Method::WalletSendManySmartFee => schema_for!(request::WalletSendManySmartFee),
+                Method::WalletSendToPayment => schema_for!(request::WalletSendToPayment),
                 Method::WalletDrain => schema_for!(request::WalletDrain),
                 Method::WalletIssue => schema_for!(request::WalletIssue),
+                Method::WalletIssueMany => schema_for!(request::WalletIssueMany),
                 Method::WalletReissue => schema_for!(request::WalletReissue),
                 Method::WalletBurn => schema_for!(request::WalletBurn),
+                Method::WalletPegout => schema_for!(request::WalletPegout),
+                Method::WalletClaimPegin => schema_for!(request::WalletClaimPegin),
                 Method::WalletCombine => schema_for!(request::WalletCombine),
                 Method::WalletBroadcast => schema_for!(request::WalletBroadcast),
+                Method::WalletPreviewTx => schema_for!(request::WalletPreviewTx),
+                Method::WalletAnnotatePset => schema_for!(request::WalletAnnotatePset),
+                Method::WalletBlindPset => schema_for!(request::WalletBlindPset),
+                Method::WalletHydratePset => schema_for!(request::WalletHydratePset),
+                Method::WalletProofOfReserves => schema_for!(request::WalletProofOfReserves),
+                Method::WalletVerifyProofOfReserves => {
+                    schema_for!(request::WalletVerifyProofOfReserves)
+                }
                 Method::WalletPsetDetails => schema_for!(request::WalletPsetDetails),
                 Method::WalletMultisigDescriptor => schema_for!(request::WalletMultisigDescriptor),
                 Method::WalletSetTxMemo => schema_for!(request::WalletSetTxMemo),
                 Method::WalletSetAddrMemo => schema_for!(request::WalletSetAddrMemo),
+                Method::WalletSetMeta => schema_for!(request::WalletSetMeta),
+                Method::WalletGetMeta => schema_for!(request::WalletGetMeta),
+                Method::WalletDiagnose => schema_for!(request::WalletDiagnose),
+                Method::WalletScanScope => schema_for!(request::WalletScanScope),
+                Method::WalletIssuedAssets => schema_for!(request::WalletIssuedAssets),
                 Method::SignerGenerate => schema_for!(request::Empty),
                 Method::SignerJadeId => schema_for!(request::Empty),
                 Method::SignerLoadSoftware => schema_for!(request::SignerLoadSoftware),
                 Method::SignerLoadJade => schema_for!(request::SignerLoadJade),
+                Method::SignerLoadLedger => schema_for!(request::SignerLoadLedger),
                 Method::SignerLoadExternal => schema_for!(request::SignerLoadExternal),
                 Method::SignerUnload => schema_for!(request::SignerUnload),
                 Method::SignerList => schema_for!(request::Empty),
                 Method::SignerDetails => schema_for!(request::SignerDetails),
                 Method::SignerXpub => schema_for!(request::SignerXpub),
+                Method::SignerXpubKinds => schema_for!(request::Empty),
                 Method::SignerSign => schema_for!(request::SignerSign),
                 Method::SignerSinglesigDescriptor => {
                     schema_for!(request::SignerSinglesigDescriptor)
                 }
+                Method::SignerAccountDescriptor => {
+                    schema_for!(request::SignerAccountDescriptor)
+                }
                 Method::SignerRegisterMultisig => schema_for!(request::SignerRegisterMultisig),
                 Method::AssetContract => schema_for!(request::AssetContract),
                 Method::AssetInsert => schema_for!(request::AssetInsert),
@@ -112,9 +173,14 @@ impl Method {
                 Method::AssetDetails => schema_for!(request::AssetDetails),
                 Method::AssetFromExplorer => schema_for!(request::AssetFromExplorer),
                 Method::AssetPublish => schema_for!(request::AssetPublish),
+                Method::AssetVerify => schema_for!(request::AssetVerify),
                 Method::Amp2Descriptor => schema_for!(request::Amp2Descriptor),
                 Method::Amp2Register => schema_for!(request::Amp2Register),
                 Method::Amp2Cosign => schema_for!(request::Amp2Cosign),
+                Method::Amp2CosignStatus => schema_for!(request::Amp2CosignStatus),
+                Method::ParsePayment => schema_for!(request::ParsePayment),
+                Method::PsetConvertVersion => schema_for!(request::PsetConvertVersion),
+                Method::PsetBlindingStatus => schema_for!(request::PsetBlindingStatus),
             },
             Direction::Response => match self {
                 Method::Schema => return serde_json::from_str(include_str!("../schema.json")),
@@ -125,35 +191,65 @@ impl Method {
                 Method::WalletUnload => schema_for!(response::WalletUnload),
                 Method::WalletList => schema_for!(response::WalletList),
                 Method::WalletDetails => schema_for!(response::WalletDetails),
+                Method::WalletPublicDescriptor => schema_for!(response::WalletPublicDescriptor),
+                Method::WalletPolicy => schema_for!(response::WalletPolicy),
                 Method::WalletAddress => schema_for!(response::WalletAddress),
+                Method::WalletAddressBatch => schema_for!(response::WalletAddressBatch),
+                Method::WalletAddressInfoBatch => schema_for!(response::WalletAddressInfoBatch),
                 Method::WalletBalance => schema_for!(response::WalletBalance),
                 Method::WalletUtxos => schema_for!(response::WalletUtxos),
+                Method::WalletDustDeposits => schema_for!(response::WalletUtxos),
+                Method::WalletApplyUpdate => schema_for!(response::WalletTip),
+                Method::WalletTip => schema_for!(response::WalletTip),
                 Method::WalletTxs => schema_for!(response::WalletTxs),
                 Method::WalletTx => schema_for!(response::WalletTx),
                 Method::WalletSendMany => schema_for!(response::Pset),
+                Method::WalletSendManySmartFee => schema_for!(response::WalletSendManySmartFee),
+                Method::WalletSendToPayment => schema_for!(response::Pset),
                 Method::WalletDrain => schema_for!(response::Pset),
                 Method::WalletIssue => schema_for!(response::Pset),
+                Method::WalletIssueMany => schema_for!(response::Pset),
                 Method::WalletReissue => schema_for!(response::Pset),
                 Method::WalletBurn => schema_for!(response::Pset),
+                Method::WalletPegout => schema_for!(response::Pset),
+                Method::WalletClaimPegin => schema_for!(response::Pset),
                 Method::WalletCombine => schema_for!(response::WalletCombine),
                 Method::WalletBroadcast => schema_for!(response::WalletBroadcast),
+                Method::WalletPreviewTx => schema_for!(response::WalletPreviewTx),
+                Method::WalletAnnotatePset => schema_for!(response::Pset),
+                Method::WalletBlindPset => schema_for!(response::Pset),
+                Method::WalletHydratePset => schema_for!(response::Pset),
+                Method::WalletProofOfReserves => schema_for!(response::Pset),
+                Method::WalletVerifyProofOfReserves => {
+                    schema_for!(response::WalletVerifyProofOfReserves)
+                }
                 Method::WalletPsetDetails => schema_for!(response::WalletPsetDetails),
                 Method::WalletMultisigDescriptor => schema_for!(response::WalletMultisigDescriptor),
                 Method::WalletSetTxMemo => schema_for!(response::Empty),
                 Method::WalletSetAddrMemo => schema_for!(response::Empty),
+                Method::WalletSetMeta => schema_for!(response::Empty),
+                Method::WalletGetMeta => schema_for!(response::WalletGetMeta),
+                Method::WalletDiagnose => schema_for!(response::WalletDiagnose),
+                Method::WalletScanScope => schema_for!(response::WalletScanScope),
+                Method::WalletIssuedAssets => schema_for!(response::WalletIssuedAssets),
                 Method::SignerGenerate => schema_for!(response::SignerGenerate),
                 Method::SignerJadeId => schema_for!(response::JadeId),
                 Method::SignerLoadSoftware => schema_for!(response::Signer),
                 Method::SignerLoadJade => schema_for!(response::Signer),
+                Method::SignerLoadLedger => schema_for!(response::Signer),
                 Method::SignerLoadExternal => schema_for!(response::Signer),
                 Method::SignerUnload => schema_for!(response::SignerUnload),
                 Method::SignerList => schema_for!(response::SignerList),
                 Method::SignerDetails => schema_for!(response::SignerDetails),
                 Method::SignerXpub => schema_for!(response::SignerXpub),
+                Method::SignerXpubKinds => schema_for!(response::SignerXpubKinds),
                 Method::SignerSign => schema_for!(response::Pset),
                 Method::SignerSinglesigDescriptor => {
                     schema_for!(response::SignerSinglesigDescriptor)
                 }
+                Method::SignerAccountDescriptor => {
+                    schema_for!(response::SignerSinglesigDescriptor)
+                }
                 Method::SignerRegisterMultisig => schema_for!(response::Empty),
                 Method::AssetContract => schema_for!(response::AssetContract),
                 Method::AssetInsert => schema_for!(response::Empty),
@@ -162,9 +258,14 @@ impl Method {
                 Method::AssetDetails => schema_for!(response::AssetDetails),
                 Method::AssetFromExplorer => schema_for!(request::Empty),
                 Method::AssetPublish => schema_for!(response::AssetPublish),
+                Method::AssetVerify => schema_for!(response::Empty),
                 Method::Amp2Descriptor => schema_for!(response::Amp2Descriptor),
                 Method::Amp2Register => schema_for!(response::Amp2Register),
                 Method::Amp2Cosign => schema_for!(response::Amp2Cosign),
+                Method::Amp2CosignStatus => schema_for!(response::Amp2CosignStatus),
+                Method::ParsePayment => schema_for!(response::ParsePayment),
+                Method::PsetConvertVersion => schema_for!(response::PsetConvertVersion),
+                Method::PsetBlindingStatus => schema_for!(response::PsetBlindingStatus),
             },
         })
     }
@@ -183,33 +284,59 @@ impl FromStr for Method {
             "wallet_unload" => Method::WalletUnload,
             "wallet_list" => Method::WalletList,
             "wallet_details" => Method::WalletDetails,
+            "wallet_public_descriptor" => Method::WalletPublicDescriptor,
+            "wallet_policy" => Method::WalletPolicy,
             "wallet_address" => Method::WalletAddress,
+            "wallet_address_batch" => Method::WalletAddressBatch,
+            "wallet_address_info_batch" => Method::WalletAddressInfoBatch,
             "wallet_balance" => Method::WalletBalance,
             "wallet_utxos" => Method::WalletUtxos,
+            "wallet_dust_deposits" => Method::WalletDustDeposits,
+            "wallet_apply_update" => Method::WalletApplyUpdate,
+            "wallet_tip" => Method::WalletTip,
             "wallet_txs" => Method::WalletTxs,
             "wallet_tx" => Method::WalletTx,
             "wallet_send_many" => Method::WalletSendMany,
+            "wallet_send_many_smart_fee" => Method::WalletSendManySmartFee,
+            "wallet_send_to_payment" => Method::WalletSendToPayment,
             "wallet_drain" => Method::WalletDrain,
             "wallet_issue" => Method::WalletIssue,
+            "wallet_issue_many" => Method::WalletIssueMany,
             "wallet_reissue" => Method::WalletReissue,
             "wallet_burn" => Method::WalletBurn,
+            "wallet_pegout" => Method::WalletPegout,
+            "wallet_claim_pegin" => Method::WalletClaimPegin,
             "wallet_combine" => Method::WalletCombine,
             "wallet_broadcast" => Method::WalletBroadcast,
+            "wallet_preview_tx" => Method::WalletPreviewTx,
+            "wallet_annotate_pset" => Method::WalletAnnotatePset,
+            "wallet_blind_pset" => Method::WalletBlindPset,
+            "wallet_hydrate_pset" => Method::WalletHydratePset,
+            "wallet_proof_of_reserves" => Method::WalletProofOfReserves,
+            "wallet_verify_proof_of_reserves" => Method::WalletVerifyProofOfReserves,
             "wallet_pset_details" => Method::WalletPsetDetails,
             "wallet_multisig_descriptor" => Method::WalletMultisigDescriptor,
             "wallet_set_tx_memo" => Method::WalletSetTxMemo,
             "wallet_set_addr_memo" => Method::WalletSetAddrMemo,
+            "wallet_set_meta" => Method::WalletSetMeta,
+            "wallet_get_meta" => Method::WalletGetMeta,
+            "wallet_diagnose" => Method::WalletDiagnose,
+            "wallet_scan_scope" => Method::WalletScanScope,
+            "wallet_issued_assets" => Method::WalletIssuedAssets,
             "signer_generate" => Method::SignerGenerate,
             "signer_jade_id" => Method::SignerJadeId,
             "signer_load_software" => Method::SignerLoadSoftware,
             "signer_load_jade" => Method::SignerLoadJade,
+            "signer_load_ledger" => Method::SignerLoadLedger,
             "signer_load_external" => Method::SignerLoadExternal,
             "signer_unload" => Method::SignerUnload,
             "signer_list" => Method::SignerList,
             "signer_details" => Method::SignerDetails,
             "signer_xpub" => Method::SignerXpub,
+            "signer_xpub_kinds" => Method::SignerXpubKinds,
             "signer_sign" => Method::SignerSign,
             "signer_singlesig_descriptor" => Method::SignerSinglesigDescriptor,
+            "signer_account_descriptor" => Method::SignerAccountDescriptor,
             "signer_register_multisig" => Method::SignerRegisterMultisig,
             "asset_contract" => Method::AssetContract,
             "asset_insert" => Method::AssetInsert,
@@ -218,9 +345,14 @@ impl FromStr for Method {
             "asset_details" => Method::AssetDetails,
             "asset_from_explorer" => Method::AssetFromExplorer,
             "asset_publish" => Method::AssetPublish,
+            "asset_verify" => Method::AssetVerify,
             "amp2_descriptor" => Method::Amp2Descriptor,
             "amp2_register" => Method::Amp2Register,
             "amp2_cosign" => Method::Amp2Cosign,
+            "amp2_cosign_status" => Method::Amp2CosignStatus,
+            "parse_payment" => Method::ParsePayment,
+            "pset_convert_version" => Method::PsetConvertVersion,
+            "pset_blinding_status" => Method::PsetBlindingStatus,
             _ => {
                 return Err(MethodNotExist {
                     name: s.to_string(),
@@ -241,33 +373,59 @@ impl std::fmt::Display for Method {
             Method::WalletUnload => "wallet_unload",
             Method::WalletList => "wallet_list",
             Method::WalletDetails => "wallet_details",
+            Method::WalletPublicDescriptor => "wallet_public_descriptor",
+            Method::WalletPolicy => "wallet_policy",
             Method::WalletAddress => "wallet_address",
+            Method::WalletAddressBatch => "wallet_address_batch",
+            Method::WalletAddressInfoBatch => "wallet_address_info_batch",
             Method::WalletBalance => "wallet_balance",
             Method::WalletUtxos => "wallet_utxos",
+            Method::WalletDustDeposits => "wallet_dust_deposits",
+            Method::WalletApplyUpdate => "wallet_apply_update",
+            Method::WalletTip => "wallet_tip",
             Method::WalletTxs => "wallet_txs",
             Method::WalletTx => "wallet_tx",
             Method::WalletSendMany => "wallet_send_many",
+            Method::WalletSendManySmartFee => "wallet_send_many_smart_fee",
+            Method::WalletSendToPayment => "wallet_send_to_payment",
             Method::WalletDrain => "wallet_drain",
             Method::WalletIssue => "wallet_issue",
+            Method::WalletIssueMany => "wallet_issue_many",
             Method::WalletReissue => "wallet_reissue",
             Method::WalletBurn => "wallet_burn",
+            Method::WalletPegout => "wallet_pegout",
+            Method::WalletClaimPegin => "wallet_claim_pegin",
             Method::WalletCombine => "wallet_combine",
             Method::WalletBroadcast => "wallet_broadcast",
+            Method::WalletPreviewTx => "wallet_preview_tx",
+            Method::WalletAnnotatePset => "wallet_annotate_pset",
+            Method::WalletBlindPset => "wallet_blind_pset",
+            Method::WalletHydratePset => "wallet_hydrate_pset",
+            Method::WalletProofOfReserves => "wallet_proof_of_reserves",
+            Method::WalletVerifyProofOfReserves => "wallet_verify_proof_of_reserves",
             Method::WalletPsetDetails => "wallet_pset_details",
             Method::WalletMultisigDescriptor => "wallet_multisig_descriptor",
             Method::WalletSetTxMemo => "wallet_set_tx_memo",
             Method::WalletSetAddrMemo => "wallet_set_addr_memo",
+            Method::WalletSetMeta => "wallet_set_meta",
+            Method::WalletGetMeta => "wallet_get_meta",
+            Method::WalletDiagnose => "wallet_diagnose",
+            Method::WalletScanScope => "wallet_scan_scope",
+            Method::WalletIssuedAssets => "wallet_issued_assets",
             Method::SignerGenerate => "signer_generate",
             Method::SignerJadeId => "signer_jade_id",
             Method::SignerLoadSoftware => "signer_load_software",
             Method::SignerLoadJade => "signer_load_jade",
+            Method::SignerLoadLedger => "signer_load_ledger",
             Method::SignerLoadExternal => "signer_load_external",
             Method::SignerUnload => "signer_unload",
             Method::SignerList => "signer_list",
             Method::SignerDetails => "signer_details",
             Method::SignerXpub => "signer_xpub",
+            Method::SignerXpubKinds => "signer_xpub_kinds",
             Method::SignerSign => "signer_sign",
             Method::SignerSinglesigDescriptor => "signer_singlesig_descriptor",
+            Method::SignerAccountDescriptor => "signer_account_descriptor",
             Method::SignerRegisterMultisig => "signer_register_multisig",
             Method::AssetContract => "asset_contract",
             Method::AssetInsert => "asset_insert",
@@ -276,9 +434,14 @@ impl std::fmt::Display for Method {
             Method::AssetDetails => "asset_details",
             Method::AssetFromExplorer => "asset_from_explorer",
             Method::AssetPublish => "asset_publish",
+            Method::AssetVerify => "asset_verify",
             Method::Amp2Descriptor => "amp2_descriptor",
             Method::Amp2Register => "amp2_register",
             Method::Amp2Cosign => "amp2_cosign",
+            Method::Amp2CosignStatus => "amp2_cosign_status",
+            Method::ParsePayment => "parse_payment",
+            Method::PsetConvertVersion => "pset_convert_version",
+            Method::PsetBlindingStatus => "pset_blinding_status",
         };
         write!(f, "{}", s)
     }
@@ -0,0 +1,229 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use lwk_wollet::elements::encode::{deserialize, serialize};
+use lwk_wollet::elements::hex::{FromHex, ToHex};
+use lwk_wollet::elements::{AssetId, Transaction};
+use lwk_wollet::Contract;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Clock;
+use crate::Error;
+
+/// On disk cache of the contracts and issuance transactions fetched from the registry
+///
+/// Entries older than `ttl` are considered expired, a zero `ttl` disables the cache.
+pub struct RegistryCache {
+    dir: PathBuf,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Seconds since the unix epoch when the entry has been fetched
+    fetched_at: u64,
+    contract: Contract,
+    issuance_tx: String,
+}
+
+impl RegistryCache {
+    pub fn new(dir: PathBuf, ttl: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self { dir, ttl, clock }
+    }
+
+    /// Seconds since the unix epoch
+    fn now(&self) -> u64 {
+        self.clock.now().as_secs()
+    }
+
+    fn path(&self, asset_id: &AssetId) -> PathBuf {
+        self.dir.join(format!("{asset_id}.json"))
+    }
+
+    /// Get the cached contract and issuance transaction, if present and not expired
+    pub fn get(&self, asset_id: &AssetId) -> Option<(Contract, Transaction)> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let content = fs::read_to_string(self.path(asset_id)).ok()?;
+        let entry: Entry = serde_json::from_str(&content).ok()?;
+        if self.is_expired(&entry) {
+            return None;
+        }
+        let bytes = Vec::<u8>::from_hex(&entry.issuance_tx).ok()?;
+        let tx = deserialize(&bytes).ok()?;
+        Some((entry.contract, tx))
+    }
+
+    pub fn insert(
+        &self,
+        asset_id: &AssetId,
+        contract: &Contract,
+        issuance_tx: &Transaction,
+    ) -> Result<(), Error> {
+        if self.ttl.is_zero() {
+            return Ok(());
+        }
+        let entry = Entry {
+            fetched_at: self.now(),
+            contract: contract.clone(),
+            issuance_tx: serialize(issuance_tx).to_hex(),
+        };
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(asset_id), serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, asset_id: &AssetId) -> Result<(), Error> {
+        match fs::remove_file(self.path(asset_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_expired(&self, entry: &Entry) -> bool {
+        self.now().saturating_sub(entry.fetched_at) >= self.ttl.as_secs()
+    }
+
+    /// Remove the expired and unreadable entries, returning how many have been removed
+    pub fn prune(&self) -> Result<usize, Error> {
+        let dir = match fs::read_dir(&self.dir) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            dir => dir?,
+        };
+        let mut removed = 0;
+        for entry in dir {
+            let path = entry?.path();
+            if path.extension().map_or(true, |e| e != "json") {
+                continue;
+            }
+            let expired = fs::read_to_string(&path)
+                .ok()
+                .and_then(|c| serde_json::from_str::<Entry>(&c).ok())
+                .map_or(true, |e| self.is_expired(&e));
+            if expired {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Get the cached data or call `fetch` and cache its result
+    pub fn get_or_fetch(
+        &self,
+        asset_id: &AssetId,
+        fetch: impl FnOnce() -> Result<(Contract, Transaction), Error>,
+    ) -> Result<(Contract, Transaction), Error> {
+        if let Some(cached) = self.get(asset_id) {
+            log::debug!("registry data for {asset_id} found in cache");
+            return Ok(cached);
+        }
+        let (contract, tx) = fetch()?;
+        self.insert(asset_id, &contract, &tx)?;
+        Ok((contract, tx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use lwk_wollet::elements::{AssetId, LockTime, Transaction};
+    use lwk_wollet::Contract;
+
+    use super::RegistryCache;
+    use crate::clock::{MockClock, SystemClock};
+
+    #[test]
+    fn registry_cache() {
+        let contract = "{\"entity\":{\"domain\":\"tether.to\"},\"issuer_pubkey\":\"0337cceec0beea0232ebe14cba0197a9fbd45fcf2ec946749de920e71434c2b904\",\"name\":\"Tether USD\",\"precision\":8,\"ticker\":\"USDt\",\"version\":0}";
+        let contract = Contract::from_str(contract).unwrap();
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        let asset_id =
+            AssetId::from_str("ce091c998b83c78bb71a632313ba3760f1763d9cfcffae02258ffa9865a37bd2")
+                .unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            Ok((contract.clone(), tx.clone()))
+        };
+
+        let clock = Arc::new(SystemClock);
+        let cache =
+            RegistryCache::new(tempdir.path().to_path_buf(), Duration::from_secs(60), clock);
+        let (c, t) = cache.get_or_fetch(&asset_id, fetch).unwrap();
+        assert_eq!((c, t), (contract.clone(), tx.clone()));
+        assert_eq!(fetches.get(), 1);
+
+        // Within the TTL there is no fetch
+        let (c, t) = cache.get_or_fetch(&asset_id, fetch).unwrap();
+        assert_eq!((c, t), (contract.clone(), tx.clone()));
+        assert_eq!(fetches.get(), 1);
+
+        // Removing invalidates the entry
+        cache.remove(&asset_id).unwrap();
+        cache.remove(&asset_id).unwrap();
+        cache.get_or_fetch(&asset_id, fetch).unwrap();
+        assert_eq!(fetches.get(), 2);
+
+        // Zero TTL disables the cache
+        let clock = Arc::new(SystemClock);
+        let cache = RegistryCache::new(tempdir.path().to_path_buf(), Duration::ZERO, clock);
+        cache.get_or_fetch(&asset_id, fetch).unwrap();
+        cache.get_or_fetch(&asset_id, fetch).unwrap();
+        assert_eq!(fetches.get(), 4);
+    }
+
+    #[test]
+    fn registry_cache_prune() {
+        let contract = "{\"entity\":{\"domain\":\"tether.to\"},\"issuer_pubkey\":\"0337cceec0beea0232ebe14cba0197a9fbd45fcf2ec946749de920e71434c2b904\",\"name\":\"Tether USD\",\"precision\":8,\"ticker\":\"USDt\",\"version\":0}";
+        let contract = Contract::from_str(contract).unwrap();
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+        let asset_id =
+            AssetId::from_str("ce091c998b83c78bb71a632313ba3760f1763d9cfcffae02258ffa9865a37bd2")
+                .unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let clock = Arc::new(MockClock::new(Duration::from_secs(1_700_000_000)));
+        let dir = tempdir.path().join("cache");
+        let cache = RegistryCache::new(dir.clone(), Duration::from_secs(60), clock.clone());
+        assert_eq!(cache.prune().unwrap(), 0);
+
+        cache.insert(&asset_id, &contract, &tx).unwrap();
+        std::fs::write(dir.join("garbage.json"), "{").unwrap();
+
+        // Only the unreadable entry is removed
+        assert_eq!(cache.prune().unwrap(), 1);
+        assert!(cache.get(&asset_id).is_some());
+
+        // Fast forward close to the TTL
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(cache.prune().unwrap(), 0);
+        assert!(cache.get(&asset_id).is_some());
+
+        // Fast forward past the TTL
+        clock.advance(Duration::from_secs(2));
+        assert!(cache.get(&asset_id).is_none());
+        assert_eq!(cache.prune().unwrap(), 1);
+        assert_eq!(cache.prune().unwrap(), 0);
+    }
+}
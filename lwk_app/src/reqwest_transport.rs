@@ -2,27 +2,26 @@ use std::{fmt, time::Duration};
 
 use jsonrpc::{Request, Response};
 
+use crate::http::HttpClient;
+
 #[derive(Clone, Debug)]
 pub struct ReqwestHttpTransport {
     /// URL of the RPC server.
     url: String,
-    /// timeout only supports second granularity.
-    timeout: Duration,
+    /// Client reused across requests, so that connections are pooled
+    client: HttpClient,
 }
 
 impl ReqwestHttpTransport {
-    pub fn new(url: String, timeout: Duration) -> Self {
-        ReqwestHttpTransport { url, timeout }
+    pub fn new(url: String, timeout: Duration) -> Result<Self, crate::Error> {
+        let client = HttpClient::new(timeout, crate::consts::HTTP_MAX_IN_FLIGHT)?;
+        Ok(ReqwestHttpTransport { url, client })
     }
     fn request<R>(&self, req: impl serde::Serialize) -> Result<R, crate::Error>
     where
         R: for<'a> serde::de::Deserialize<'a>,
     {
-        let client = reqwest::blocking::ClientBuilder::new()
-            .timeout(self.timeout)
-            .build()?;
-        let response = client.post(&self.url).json(&req).send()?;
-        Ok(response.json()?)
+        self.client.post_json(&self.url, &req)
     }
 }
 
@@ -0,0 +1,168 @@
+//! A REST facade over the JSON-RPC methods
+//!
+//! Paths are under [`PREFIX`]. Read methods are mapped to
+//! `GET /api/wallet/{name}/{balance,txs,utxos,address}`, query parameters are passed as request
+//! params. A few mutating wallet methods are mapped to `POST /api/wallet/{name}/{method}`, eg
+//! `POST /api/wallet/w1/set-tx-memo` calls `wallet_set_tx_memo`, with the params taken from the
+//! JSON body.
+
+use lwk_tiny_jrpc::tiny_http::Method as HttpMethod;
+use lwk_tiny_jrpc::{Request, RestRouter};
+use serde_json::{Map, Value};
+
+use crate::method::Method;
+
+/// The urls prefix of the REST paths, other urls are left to JSON-RPC and the served files
+pub(crate) const PREFIX: &str = "/api";
+
+pub(crate) fn router() -> RestRouter {
+    RestRouter::new(PREFIX, route)
+}
+
+fn route(http_method: &HttpMethod, url: &str, body: Option<Value>) -> Option<Request> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<String> = path.trim_matches('/').split('/').map(decode).collect();
+    let [kind, name, action] = &segments[..] else {
+        return None;
+    };
+    if kind != "wallet" {
+        return None;
+    }
+
+    let (method, mut params) = match http_method {
+        HttpMethod::Get => {
+            let (method, defaults) = match action.as_str() {
+                "balance" => (Method::WalletBalance, vec![("with_tickers", false)]),
+                "txs" => (Method::WalletTxs, vec![("with_tickers", false)]),
+                "utxos" => (Method::WalletUtxos, vec![]),
+                "address" => (Method::WalletAddress, vec![("with_text_qr", false)]),
+                _ => return None,
+            };
+            let mut params = Map::new();
+            for (key, value) in defaults {
+                params.insert(key.to_string(), Value::Bool(value));
+            }
+            for (key, value) in query.split('&').filter_map(|p| p.split_once('=')) {
+                let value = decode(value);
+                // Numbers and booleans are passed as such, anything else as a string
+                let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+                params.insert(decode(key), value);
+            }
+            (method, params)
+        }
+        HttpMethod::Post => {
+            let method = match action.as_str() {
+                "send-many" => Method::WalletSendMany,
+                "drain" => Method::WalletDrain,
+                "pset-details" => Method::WalletPsetDetails,
+                "broadcast" => Method::WalletBroadcast,
+                "set-tx-memo" => Method::WalletSetTxMemo,
+                "set-addr-memo" => Method::WalletSetAddrMemo,
+                "set-meta" => Method::WalletSetMeta,
+                _ => return None,
+            };
+            let params = match body {
+                Some(Value::Object(params)) => params,
+                None => Map::new(),
+                Some(_) => return None,
+            };
+            (method, params)
+        }
+        _ => return None,
+    };
+    params.insert("name".into(), Value::String(name.clone()));
+
+    Some(Request {
+        jsonrpc: "2.0".into(),
+        id: None,
+        method: method.to_string(),
+        params: Some(Value::Object(params)),
+    })
+}
+
+/// Percent-decode a path segment or a query component, `+` is decoded as a space
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use lwk_tiny_jrpc::tiny_http::Method as HttpMethod;
+    use serde_json::json;
+
+    use super::{decode, route};
+
+    #[test]
+    fn rest_routes() {
+        let r = route(&HttpMethod::Get, "/wallet/w1/balance", None).unwrap();
+        assert_eq!(r.method, "wallet_balance");
+        assert_eq!(
+            r.params.unwrap(),
+            json!({"name": "w1", "with_tickers": false})
+        );
+
+        let r = route(&HttpMethod::Get, "/wallet/w1/address?index=3", None).unwrap();
+        assert_eq!(r.method, "wallet_address");
+        assert_eq!(
+            r.params.unwrap(),
+            json!({"name": "w1", "index": 3, "with_text_qr": false})
+        );
+
+        let body = json!({"txid": "00", "memo": "m"});
+        let r = route(&HttpMethod::Post, "/wallet/w1/set-tx-memo", Some(body)).unwrap();
+        assert_eq!(r.method, "wallet_set_tx_memo");
+        assert_eq!(
+            r.params.unwrap(),
+            json!({"name": "w1", "txid": "00", "memo": "m"})
+        );
+
+        // Path and query are percent-decoded
+        let url = "/wallet/my%20wallet/address?index=3&text=a%26b+c";
+        let r = route(&HttpMethod::Get, url, None).unwrap();
+        assert_eq!(
+            r.params.unwrap(),
+            json!({"name": "my wallet", "index": 3, "text": "a&b c", "with_text_qr": false})
+        );
+
+        // Mutating methods are not available with GET
+        assert!(route(&HttpMethod::Get, "/wallet/w1/set-tx-memo", None).is_none());
+        assert!(route(&HttpMethod::Post, "/wallet/w1/missing", None).is_none());
+        assert!(route(&HttpMethod::Get, "/signer/s1/xpub", None).is_none());
+
+        // Only the listed methods are exposed
+        assert!(route(&HttpMethod::Post, "/wallet/w1/unload", None).is_none());
+        assert!(route(&HttpMethod::Post, "/wallet/w1/apply-update", None).is_none());
+    }
+
+    #[test]
+    fn rest_decode() {
+        assert_eq!(decode("a%2Fb%3d"), "a/b=");
+        assert_eq!(decode("a+b"), "a b");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz"), "%zz");
+        assert_eq!(decode("%C3%A9"), "é");
+    }
+}
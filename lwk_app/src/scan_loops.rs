@@ -0,0 +1,133 @@
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use crate::Error;
+
+/// Coordinates the scanning thread with the requests waiting for a scan
+///
+/// Every scan loop has a generation number, incremented when the loop starts. A caller of
+/// [`ScanLoops::request_and_wait()`] waits for the completion of the first loop started after
+/// the call, so that the wallets are scanned against a blockchain state which is at least as
+/// recent as the time of the call.
+#[derive(Debug, Default)]
+pub struct ScanLoops {
+    generations: Mutex<Generations>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Generations {
+    /// Generation of the last scan loop started
+    started: u64,
+
+    /// Generation of the last scan loop completed
+    completed: u64,
+
+    /// A scan has been requested, the scanning thread should not wait anymore
+    requested: bool,
+
+    /// The scanning thread has terminated, no more loops will be completed
+    stopped: bool,
+}
+
+impl ScanLoops {
+    /// Wait at most `timeout` for a scan request, returns true if a scan has been requested
+    pub fn wait_request(&self, timeout: Duration) -> Result<bool, Error> {
+        let generations = self.generations.lock()?;
+        let (generations, _) = self
+            .changed
+            .wait_timeout_while(generations, timeout, |g| !g.requested)
+            .map_err(|e| Error::PoisonError(e.to_string()))?;
+        Ok(generations.requested)
+    }
+
+    /// Mark the start of a scan loop, returning its generation
+    pub fn start(&self) -> Result<u64, Error> {
+        let mut generations = self.generations.lock()?;
+        generations.requested = false;
+        generations.started += 1;
+        Ok(generations.started)
+    }
+
+    /// Mark the completion of the scan loop with the given generation
+    pub fn complete(&self, generation: u64) -> Result<(), Error> {
+        let mut generations = self.generations.lock()?;
+        generations.completed = generation;
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// Mark the termination of the scanning thread, waking up the callers waiting for a scan
+    pub fn stop(&self) -> Result<(), Error> {
+        let mut generations = self.generations.lock()?;
+        generations.stopped = true;
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    /// Request a scan and wait for the completion of a scan loop started after this call
+    ///
+    /// Returns the generation of the completed scan loop.
+    pub fn request_and_wait(&self) -> Result<u64, Error> {
+        let mut generations = self.generations.lock()?;
+        // A loop in progress may have started before the wallets state we care about,
+        // so wait for the next one.
+        let required = generations.started + 1;
+        generations.requested = true;
+        self.changed.notify_all();
+        while generations.completed < required {
+            if generations.stopped {
+                return Err(Error::ScanningStopped);
+            }
+            generations = self.changed.wait(generations)?;
+        }
+        Ok(generations.completed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::ScanLoops;
+
+    #[test]
+    fn request_waits_next_generation() {
+        let scan_loops = Arc::new(ScanLoops::default());
+
+        // A loop just started, but it's not completed yet
+        let running = scan_loops.start().unwrap();
+        assert_eq!(running, 1);
+
+        let (sender, receiver) = mpsc::channel();
+        let waiting = scan_loops.clone();
+        let handle = std::thread::spawn(move || {
+            sender.send(waiting.request_and_wait().unwrap()).unwrap();
+        });
+
+        // The request wakes up the scanning thread
+        assert!(scan_loops.wait_request(Duration::from_secs(10)).unwrap());
+
+        // Completing the loop started before the request doesn't satisfy it
+        scan_loops.complete(running).unwrap();
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+
+        let next = scan_loops.start().unwrap();
+        assert!(!scan_loops.wait_request(Duration::ZERO).unwrap());
+        scan_loops.complete(next).unwrap();
+        assert_eq!(receiver.recv().unwrap(), 2);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn request_fails_when_stopped() {
+        let scan_loops = Arc::new(ScanLoops::default());
+        let waiting = scan_loops.clone();
+        let handle = std::thread::spawn(move || waiting.request_and_wait());
+        assert!(scan_loops.wait_request(Duration::from_secs(10)).unwrap());
+        scan_loops.stop().unwrap();
+        assert!(handle.join().unwrap().is_err());
+    }
+}
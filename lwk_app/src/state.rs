@@ -1,15 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::net::SocketAddr;
 use std::str::FromStr;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::Duration;
 
 use lwk_common::Signer;
 use lwk_jade::{Jade, Network};
-use lwk_rpc_model::request;
-use lwk_signer::AnySigner;
+use lwk_ledger::{Ledger, LiquidClient, TransportTcp};
+use lwk_rpc_model::{request, response};
 use lwk_signer::SwSigner;
+use lwk_signer::{AnySigner, SignerError};
 use lwk_tiny_jrpc::Request;
 use lwk_wollet::asset_ids;
 use lwk_wollet::bitcoin::bip32::{Fingerprint, Xpub};
@@ -22,8 +24,13 @@ use lwk_wollet::Contract;
 use lwk_wollet::Wollet;
 use serde::Serialize;
 
+use crate::clock::Clock;
 use crate::config::Config;
+use crate::consts;
+use crate::http::HttpClient;
 use crate::method::Method;
+use crate::registry_cache::RegistryCache;
+use crate::scan_loops::ScanLoops;
 use crate::Error;
 
 #[derive(Debug)]
@@ -40,13 +47,34 @@ enum AppSignerInner {
 pub struct AppSigner {
     inner: AppSignerInner,
     persist: bool,
+
+    /// For software signers loaded from a file, the path persisted in place of the mnemonic
+    mnemonic_file: Option<String>,
+
+    /// For Ledger signers, the address of the emulator, persisted to connect again
+    ledger_emulator: Option<SocketAddr>,
 }
 
 impl AppSigner {
-    pub fn new_sw(mnemonic: &str, is_mainnet: bool, persist: bool) -> Result<Self, Error> {
-        let sw = SwSigner::new(mnemonic, is_mainnet)?;
+    pub fn new_sw(
+        mnemonic: &str,
+        passphrase: Option<&str>,
+        is_mainnet: bool,
+        persist: bool,
+    ) -> Result<Self, Error> {
+        let sw = SwSigner::new_with_passphrase(mnemonic, passphrase, is_mainnet)?;
         let inner = AppSignerInner::AvailableSigner(AnySigner::Software(sw));
-        Ok(AppSigner { inner, persist })
+        Ok(AppSigner {
+            inner,
+            persist,
+            mnemonic_file: None,
+            ledger_emulator: None,
+        })
+    }
+
+    pub fn with_mnemonic_file(mut self, path: String) -> Self {
+        self.mnemonic_file = Some(path);
+        self
     }
 
     pub fn new_jade(
@@ -64,6 +92,37 @@ impl AppSigner {
         Ok(AppSigner {
             inner,
             persist: true,
+            mnemonic_file: None,
+            ledger_emulator: None,
+        })
+    }
+
+    pub fn new_ledger(emulator: Option<SocketAddr>) -> Result<Self, Error> {
+        let signer = match emulator {
+            Some(socket) => {
+                // The emulator is meant to be used only in testing, we don't aim to handle connection/disconnection
+                let transport =
+                    TransportTcp::from_socket(socket).map_err(|e| Error::Generic(e.to_string()))?;
+                let ledger = Ledger {
+                    client: LiquidClient::new(transport),
+                };
+                let id = ledger.identifier().map_err(SignerError::from)?;
+                AnySigner::Ledger(ledger, id)
+            }
+            #[cfg(feature = "serial")]
+            None => {
+                let ledger = Ledger::new_hid().map_err(SignerError::from)?;
+                let id = ledger.identifier().map_err(SignerError::from)?;
+                AnySigner::LedgerHid(ledger, id)
+            }
+            #[cfg(not(feature = "serial"))]
+            None => return Err(Error::FeatSerialDisabled),
+        };
+        Ok(AppSigner {
+            inner: AppSignerInner::AvailableSigner(signer),
+            persist: true,
+            mnemonic_file: None,
+            ledger_emulator: emulator,
         })
     }
 
@@ -71,6 +130,8 @@ impl AppSigner {
         AppSigner {
             inner: AppSignerInner::ExternalSigner(fingerprint),
             persist: false,
+            mnemonic_file: None,
+            ledger_emulator: None,
         }
     }
 
@@ -106,14 +167,33 @@ impl AppSigner {
         }
     }
 
+    /// The signer type, it's derived from the variant only, without any device I/O
     pub fn type_(&self) -> String {
         match &self.inner {
             AppSignerInner::ExternalSigner(_) => "external".into(),
             AppSignerInner::JadeId(_, _) => "jade-id".into(),
-            AppSignerInner::AvailableSigner(AnySigner::Software(_)) => "software".into(),
-            AppSignerInner::AvailableSigner(AnySigner::Jade(_, _)) => "jade".into(),
-            #[allow(unreachable_patterns)]
-            _ => todo!(),
+            AppSignerInner::AvailableSigner(s) => s.type_().into(),
+        }
+    }
+
+    /// The features supported by the signer, like [`AppSigner::type_()`] it doesn't communicate
+    /// with devices
+    ///
+    /// Available signers report what their implementation supports, a Jade not connected yet is
+    /// reported as a connected one.
+    pub fn capabilities(&self) -> response::SignerCapabilities {
+        let (sign_message, derive_xpub, slip77, taproot) = match &self.inner {
+            AppSignerInner::ExternalSigner(_) => (false, false, false, false),
+            AppSignerInner::JadeId(_, _) => (true, true, true, false),
+            AppSignerInner::AvailableSigner(s) => {
+                (s.can_sign_message(), true, true, s.can_sign_taproot())
+            }
+        };
+        response::SignerCapabilities {
+            sign_message,
+            derive_xpub,
+            slip77,
+            taproot,
         }
     }
 }
@@ -158,7 +238,7 @@ impl RegistryAssetData {
     }
 
     pub fn contract_str(&self) -> String {
-        serde_json::to_string(&self.contract).expect("contract")
+        self.contract.to_canonical_json().expect("contract")
     }
 
     pub fn contract(&self) -> &Contract {
@@ -264,25 +344,29 @@ pub struct TxMemos(HashMap<String, HashMap<Txid, String>>);
 #[derive(Default)]
 pub struct AddrMemos(HashMap<String, HashMap<Address, String>>);
 
+/// Arbitrary metadata per wallet, by namespace and key
+#[derive(Default)]
+pub struct WalletMeta(HashMap<String, BTreeMap<String, BTreeMap<String, String>>>);
+
 pub struct State {
     // TODO: config is read-only, so it's not useful to wrap it in a mutex.
     // Ideally it should be in _another_ struct accessible by method_handler.
     pub config: Config,
+    /// Client used for all the outbound HTTP calls
+    pub http: HttpClient,
     pub wollets: Wollets,
     pub signers: Signers,
     pub assets: Assets,
     pub tx_memos: TxMemos,
     pub addr_memos: AddrMemos,
+    pub wallet_meta: WalletMeta,
     pub do_persist: bool,
 
-    /// Number of scan loops started
-    pub scan_loops_started: u32,
+    /// Coordination with the scanning thread
+    pub scan_loops: Arc<ScanLoops>,
 
-    /// Number of scan loops completed
-    pub scan_loops_completed: u32,
-
-    /// Signal the scanning thread that we don't want to wait anymore
-    pub interrupt_wait: bool,
+    /// Source of the current time
+    pub clock: Arc<dyn Clock>,
 }
 
 impl Wollets {
@@ -388,6 +472,8 @@ impl Signers {
             let signer = AppSigner {
                 inner,
                 persist: true,
+                mnemonic_file: None,
+                ledger_emulator: None,
             };
             // replace the existing AppSignerInner::JadeId with AppSignerInner::AvailableSigner
             self.0.insert(name.to_string(), signer);
@@ -471,6 +557,15 @@ impl Assets {
     pub fn iter(&self) -> impl Iterator<Item = (&AssetId, &AppAsset)> {
         self.0.iter()
     }
+
+    /// The precision of the asset, if known, reissuance tokens have none
+    pub fn precision(&self, asset: &AssetId) -> Option<u8> {
+        match self.0.get(asset)? {
+            AppAsset::PolicyAsset(_) => Some(8),
+            AppAsset::RegistryAsset(d) => Some(d.contract.precision),
+            AppAsset::ReissuanceToken(_) => None,
+        }
+    }
 }
 
 impl TxMemos {
@@ -525,6 +620,81 @@ impl AddrMemos {
     }
 }
 
+impl WalletMeta {
+    /// The metadata of a wallet namespace
+    pub fn get(&self, wollet: &str, namespace: &str) -> BTreeMap<String, String> {
+        self.0
+            .get(wollet)
+            .and_then(|m| m.get(namespace))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Set a metadata value, an empty value removes the entry
+    pub fn set(
+        &mut self,
+        wollet: &str,
+        namespace: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<(), Error> {
+        if namespace.is_empty() || key.is_empty() {
+            return Err(Error::Generic(
+                "Metadata namespace and key cannot be empty".into(),
+            ));
+        }
+        if namespace.len() > consts::META_MAX_KEY_LEN || key.len() > consts::META_MAX_KEY_LEN {
+            return Err(Error::MetaLimit(format!(
+                "namespace and key must be at most {} bytes",
+                consts::META_MAX_KEY_LEN
+            )));
+        }
+        if value.len() > consts::META_MAX_VALUE_LEN {
+            return Err(Error::MetaLimit(format!(
+                "value must be at most {} bytes",
+                consts::META_MAX_VALUE_LEN
+            )));
+        }
+
+        let wollet_meta = self.0.entry(wollet.to_string()).or_default();
+        if value.is_empty() {
+            if let Some(entries) = wollet_meta.get_mut(namespace) {
+                entries.remove(key);
+                if entries.is_empty() {
+                    wollet_meta.remove(namespace);
+                }
+            }
+            return Ok(());
+        }
+
+        let exists = wollet_meta
+            .get(namespace)
+            .is_some_and(|entries| entries.contains_key(key));
+        let count: usize = wollet_meta.values().map(BTreeMap::len).sum();
+        if !exists && count >= consts::META_MAX_ENTRIES {
+            return Err(Error::MetaLimit(format!(
+                "wallet cannot have more than {} entries",
+                consts::META_MAX_ENTRIES
+            )));
+        }
+        wollet_meta
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, wollet: &str) {
+        self.0.remove(wollet);
+    }
+
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&String, &BTreeMap<String, BTreeMap<String, String>>)> {
+        self.0.iter()
+    }
+}
+
 impl State {
     pub fn insert_policy_asset(&mut self) {
         let asset_id = self.config.network.policy_asset();
@@ -600,13 +770,15 @@ impl State {
         Ok(())
     }
 
+    /// Returns the cache of the registry data, using the state clock
+    pub fn registry_cache(&self) -> Result<RegistryCache, Error> {
+        self.config.registry_cache_with_clock(self.clock.clone())
+    }
+
     pub fn persist_all(&mut self) -> Result<(), Error> {
         let path = self.config.state_path()?;
         let mut temp = path.clone();
-        let millis = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Clock may have gone backwards")
-            .as_millis();
+        let millis = self.clock.now().as_millis();
         temp.set_file_name(millis.to_string());
         let mut file = File::create(&temp)?;
         for req in self.as_requests()? {
@@ -626,6 +798,10 @@ impl State {
         for (n, w) in self.wollets.iter() {
             let params = request::WalletLoad {
                 descriptor: w.descriptor().to_string(),
+                change_descriptor: w
+                    .wollet_descriptor()
+                    .change_descriptor()
+                    .map(|d| d.to_string()),
                 name: n.to_string(),
             };
             let r = Request {
@@ -673,6 +849,27 @@ impl State {
             }
         }
 
+        // Wallet metadata
+        for (name, wollet_meta) in self.wallet_meta.iter() {
+            for (namespace, entries) in wollet_meta.iter() {
+                for (key, value) in entries.iter() {
+                    let params = request::WalletSetMeta {
+                        name: name.to_string(),
+                        namespace: namespace.to_string(),
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    };
+                    let r = Request {
+                        jsonrpc: "2.0".into(),
+                        id: None,
+                        method: Method::WalletSetMeta.to_string(),
+                        params: Some(serde_json::to_value(params)?),
+                    };
+                    requests.push(r);
+                }
+            }
+        }
+
         // Signers
         for (n, s) in self.signers.iter() {
             let (params, method) = match &s.inner {
@@ -681,17 +878,25 @@ impl State {
                         name: n.to_string(),
                         id: id.to_string(),
                         emulator: None, // ?
+                        expected_fingerprint: None,
                     };
                     (serde_json::to_value(params)?, Method::SignerLoadJade)
                 }
                 AppSignerInner::AvailableSigner(a) => match a {
                     AnySigner::Software(a) => {
+                        let mnemonic = match &s.mnemonic_file {
+                            Some(_) => None,
+                            None => Some(
+                                a.mnemonic()
+                                    .expect("we only create signers from mnemonic")
+                                    .to_string(),
+                            ),
+                        };
                         let params = request::SignerLoadSoftware {
                             name: n.to_string(),
-                            mnemonic: a
-                                .mnemonic()
-                                .expect("we only create signers from mnemonic")
-                                .to_string(),
+                            mnemonic,
+                            mnemonic_file: s.mnemonic_file.clone(),
+                            passphrase: a.passphrase().map(|p| p.to_string()),
                             persist: s.persist,
                         };
                         (serde_json::to_value(params)?, Method::SignerLoadSoftware)
@@ -701,11 +906,27 @@ impl State {
                             name: n.to_string(),
                             id: id.to_string(),
                             emulator: None, // ?
+                            expected_fingerprint: None,
                         };
                         (serde_json::to_value(params)?, Method::SignerLoadJade)
                     }
-                    #[allow(unreachable_patterns)]
-                    _ => todo!(),
+                    AnySigner::Ledger(_, _) => {
+                        let params = request::SignerLoadLedger {
+                            name: n.to_string(),
+                            emulator: s.ledger_emulator,
+                            expected_fingerprint: None,
+                        };
+                        (serde_json::to_value(params)?, Method::SignerLoadLedger)
+                    }
+                    #[cfg(feature = "serial")]
+                    AnySigner::LedgerHid(_, _) => {
+                        let params = request::SignerLoadLedger {
+                            name: n.to_string(),
+                            emulator: None,
+                            expected_fingerprint: None,
+                        };
+                        (serde_json::to_value(params)?, Method::SignerLoadLedger)
+                    }
                 },
                 AppSignerInner::ExternalSigner(f) => {
                     let params = request::SignerLoadExternal {
@@ -743,3 +964,73 @@ impl State {
         self.signers.get_available(name, timeout)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lwk_wollet::bitcoin::XKeyIdentifier;
+    use lwk_wollet::hashes::Hash;
+
+    use super::{AppSigner, WalletMeta};
+    use crate::{consts, Error};
+
+    #[test]
+    fn signer_type() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let signer = AppSigner::new_sw(mnemonic, None, false, false).unwrap();
+        assert_eq!(signer.type_(), "software");
+
+        let signer = AppSigner::new_external("11111111".parse().unwrap());
+        assert_eq!(signer.type_(), "external");
+
+        // No device is connected
+        let id = XKeyIdentifier::all_zeros();
+        let signer = AppSigner::new_jade(id, None, lwk_jade::Network::LocaltestLiquid).unwrap();
+        assert_eq!(signer.type_(), "jade-id");
+    }
+
+    #[test]
+    fn signer_capabilities() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let c = AppSigner::new_sw(mnemonic, None, false, false)
+            .unwrap()
+            .capabilities();
+        assert!(!c.sign_message && c.derive_xpub && c.slip77 && !c.taproot);
+
+        let c = AppSigner::new_external("11111111".parse().unwrap()).capabilities();
+        assert!(!c.sign_message && !c.derive_xpub && !c.slip77 && !c.taproot);
+
+        let id = XKeyIdentifier::all_zeros();
+        let c = AppSigner::new_jade(id, None, lwk_jade::Network::LocaltestLiquid)
+            .unwrap()
+            .capabilities();
+        assert!(c.sign_message && c.derive_xpub && c.slip77 && !c.taproot);
+    }
+
+    #[test]
+    fn wallet_meta_limits() {
+        let mut meta = WalletMeta::default();
+        meta.set("w", "orders", "id", "42").unwrap();
+        assert_eq!(meta.get("w", "orders").get("id").unwrap(), "42");
+        assert!(meta.get("other", "orders").is_empty());
+
+        let long = "x".repeat(consts::META_MAX_KEY_LEN + 1);
+        let err = meta.set("w", &long, "id", "42").unwrap_err();
+        assert!(matches!(err, Error::MetaLimit(_)));
+        let err = meta.set("w", "orders", &long, "42").unwrap_err();
+        assert!(matches!(err, Error::MetaLimit(_)));
+        let long = "x".repeat(consts::META_MAX_VALUE_LEN + 1);
+        let err = meta.set("w", "orders", "id", &long).unwrap_err();
+        assert!(matches!(err, Error::MetaLimit(_)));
+
+        for i in 1..consts::META_MAX_ENTRIES {
+            meta.set("w", "many", &i.to_string(), "v").unwrap();
+        }
+        let err = meta.set("w", "many", "one-more", "v").unwrap_err();
+        assert!(matches!(err, Error::MetaLimit(_)));
+        // Existing entries can be updated, and removed to make room
+        meta.set("w", "orders", "id", "43").unwrap();
+        meta.set("w", "orders", "id", "").unwrap();
+        assert!(meta.get("w", "orders").is_empty());
+        meta.set("w", "many", "one-more", "v").unwrap();
+    }
+}
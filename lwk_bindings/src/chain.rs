@@ -7,6 +7,12 @@ pub enum Chain {
 
     /// Internal address, used for the change
     Internal,
+
+    /// Further path of a multipath descriptor, after the external and internal ones
+    Path {
+        /// The index of the path, eg 2 for `.../<0;1;2>/*`
+        index: u32,
+    },
 }
 
 impl From<lwk_wollet::Chain> for Chain {
@@ -14,6 +20,7 @@ impl From<lwk_wollet::Chain> for Chain {
         match value {
             lwk_wollet::Chain::External => Chain::External,
             lwk_wollet::Chain::Internal => Chain::Internal,
+            lwk_wollet::Chain::Path(index) => Chain::Path { index },
         }
     }
 }
@@ -23,6 +30,7 @@ impl From<Chain> for lwk_wollet::Chain {
         match value {
             Chain::External => lwk_wollet::Chain::External,
             Chain::Internal => lwk_wollet::Chain::Internal,
+            Chain::Path { index } => lwk_wollet::Chain::Path(index),
         }
     }
 }
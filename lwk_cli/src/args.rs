@@ -1,8 +1,32 @@
-use std::{fmt::Display, net::SocketAddr, path::PathBuf};
+use std::{
+    convert::Infallible,
+    fmt::{Debug, Display},
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
+/// A secret argument, like a mnemonic, which is not shown in the debug output and in the logs
+#[derive(Clone)]
+pub struct Secret(pub String);
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Secret(s.to_string()))
+    }
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum Network {
     Mainnet,
@@ -51,9 +75,45 @@ pub enum CliCommand {
     /// `lwk_cli wallet details`
     Schema(SchemaArgs),
 
+    /// Parse a payment string: a Liquid address, a Liquid BIP21 URI or a Lightning invoice
+    ///
+    /// Fails if the payment is for a different network than the server's
+    ParsePayment {
+        /// The payment string, e.g. a scanned QR code
+        uri: String,
+    },
+
+    /// Convert a PSET to another version, returning also the version of the given PSET
+    ///
+    /// Fails if the conversion would lose fields, e.g. downgrading to version 0
+    PsetConvertVersion {
+        /// The PSET in base64
+        #[arg(long)]
+        pset: String,
+
+        /// The version to convert to
+        #[arg(long, default_value_t = 2)]
+        target: u32,
+    },
+
+    /// Check if the outputs of a PSET have been blinded, returning the ones that have not
+    ///
+    /// A PSET must be blinded before signing
+    PsetBlindingStatus {
+        /// The PSET in base64
+        #[arg(long)]
+        pset: String,
+    },
+
     #[clap(hide = true)]
     GenerateCompletion { shell: Shell },
 
+    /// Print the names of the wallets or signers loaded in the server
+    ///
+    /// Used by completion scripts to complete dynamic values such as `--wallet` and `--signer`
+    #[clap(hide = true)]
+    Complete { kind: CompleteKind },
+
     /// Generate bindings, this is here so that we have a unique binary across the workspace.
     /// The fields are just a copy of what you need in [`uniffi::uniffi_bindgen_main()`] so that
     /// this subcommand is compatible with that. To use any other option available there it must be
@@ -78,6 +138,8 @@ impl CliCommand {
             self,
             CliCommand::Server(crate::args::ServerArgs {
                 command: ServerCommand::Start { .. },
+            }) | CliCommand::Wallet(crate::args::WalletArgs {
+                command: WalletCommand::SyncOnce { .. },
             }) | CliCommand::GenerateCompletion { .. }
         )
     }
@@ -88,6 +150,8 @@ impl CliCommand {
             self,
             CliCommand::Server(crate::args::ServerArgs {
                 command: ServerCommand::Start { .. },
+            }) | CliCommand::Wallet(crate::args::WalletArgs {
+                command: WalletCommand::SyncOnce { .. },
             }) | CliCommand::GenerateCompletion { .. }
                 | CliCommand::Generate { .. }
         )
@@ -130,6 +194,9 @@ pub enum MainCommand {
     Asset(AssetSubCommands),
     Amp2(Amp2SubCommands),
     Schema,
+    ParsePayment,
+    PsetConvertVersion,
+    PsetBlindingStatus,
 }
 
 #[derive(Debug, Args)]
@@ -157,19 +224,42 @@ pub enum WalletSubCommandsEnum {
     Unload,
     List,
     Address,
+    AddressBatch,
+    AddressInfoBatch,
     Balance,
     Send,
+    SendToPayment,
+    SendSmartFee,
     Issue,
+    IssueMany,
     Reissue,
+    Pegout,
+    ClaimPegin,
     MultisigDesc,
     Broadcast,
+    PreviewTx,
+    AnnotatePset,
+    BlindPset,
+    HydratePset,
+    ProofOfReserves,
+    VerifyProofOfReserves,
     Details,
+    PublicDescriptor,
+    Policy,
     Combine,
     PsetDetails,
     Utxos,
+    DustDeposits,
+    ApplyUpdate,
+    Tip,
     Txs,
     SetTxMemo,
     SetAddrMemo,
+    SetMeta,
+    GetMeta,
+    Diagnose,
+    ScanScope,
+    IssuedAssets,
 }
 
 #[derive(Debug, Args)]
@@ -184,13 +274,16 @@ pub enum SignerSubCommandsEnum {
     JadeId,
     LoadSoftware,
     LoadJade,
+    LoadLedger,
     LoadExternal,
     Unload,
     Details,
     List,
     Sign,
     SinglesigDesc,
+    AccountDesc,
     Xpub,
+    XpubKinds,
 }
 
 #[derive(Debug, Args)]
@@ -207,6 +300,7 @@ pub enum AssetSubCommandsEnum {
     Insert,
     Remove,
     Publish,
+    Verify,
 }
 
 #[derive(Debug, Args)]
@@ -220,6 +314,13 @@ pub enum Amp2SubCommandsEnum {
     Descriptor,
     Register,
     Cosign,
+    CosignStatus,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum CompleteKind {
+    Wallets,
+    Signers,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -266,6 +367,10 @@ pub enum SignerCommand {
         /// The socket address to connect to jade emulator
         #[arg(long)]
         emulator: Option<SocketAddr>,
+
+        /// Fail if the device has a different fingerprint (4 bytes as 8 hex chars)
+        #[arg(long)]
+        expected_fingerprint: Option<String>,
     },
 
     /// Load a software signer giving it a name
@@ -273,8 +378,19 @@ pub enum SignerCommand {
         #[arg(short, long, env)]
         signer: String,
 
+        /// The mnemonic, prefer `--mnemonic-file` to avoid exposing it in the process arguments
+        #[arg(long, required_unless_present = "mnemonic_file")]
+        mnemonic: Option<Secret>,
+
+        /// File containing the mnemonic, read by the server which must run on the same machine
+        ///
+        /// Use `-` to read the mnemonic from the standard input instead.
+        #[arg(long, conflicts_with = "mnemonic")]
+        mnemonic_file: Option<PathBuf>,
+
+        /// Optional BIP39 passphrase, also known as 25th word
         #[arg(long)]
-        mnemonic: String, // TODO is it right to have the mnemonic as arg?
+        passphrase: Option<Secret>,
 
         /// Specify if the rpc-server should persist the signer
         ///
@@ -300,6 +416,21 @@ pub enum SignerCommand {
         emulator: Option<SocketAddr>,
     },
 
+    /// Load a Ledger signer giving it a name
+    LoadLedger {
+        #[arg(short, long, env)]
+        signer: String,
+
+        /// The socket address to connect to the Ledger emulator, if not set connect to a physical
+        /// device via USB HID (needs the `serial` feature)
+        #[arg(long)]
+        emulator: Option<SocketAddr>,
+
+        /// Fail if the device has a different fingerprint (4 bytes as 8 hex chars)
+        #[arg(long)]
+        expected_fingerprint: Option<String>,
+    },
+
     /// Load a signer (software, serial, external) giving it a name
     LoadExternal {
         #[arg(short, long, env)]
@@ -345,6 +476,22 @@ pub enum SignerCommand {
         kind: SinglesigKind,
     },
 
+    /// Prints the singlesig descriptor of the given account using this signer key
+    AccountDesc {
+        #[arg(short, long, env)]
+        signer: String,
+
+        /// The account number, eg 1 for `m/84h/<coin_type>h/1h`
+        #[arg(long)]
+        account: u32,
+
+        #[arg(long)]
+        descriptor_blinding_key: BlindingKeyKind,
+
+        #[arg(long)]
+        kind: SinglesigKind,
+    },
+
     /// Get an extended public key from the signer
     Xpub {
         #[arg(short, long, env)]
@@ -354,6 +501,9 @@ pub enum SignerCommand {
         kind: XpubKind,
     },
 
+    /// List the supported xpub kinds, with their derivation path
+    XpubKinds,
+
     /// Register a multisig wallet
     ///
     /// This is needed to correctly display change outputs Jade.
@@ -430,6 +580,11 @@ pub enum WalletCommand {
 
         #[arg(short, long)]
         descriptor: String,
+
+        /// Descriptor for the change outputs, if the wallet has separate receive and change
+        /// descriptors. The receive one must be passed in `--descriptor`.
+        #[arg(long)]
+        change_descriptor: Option<String>,
     },
 
     /// Unload a wallet
@@ -471,6 +626,45 @@ pub enum WalletCommand {
         with_uri_qr: Option<u8>,
     },
 
+    /// Get consecutive receive addresses from the given wallet name
+    ///
+    /// As for `address`, the first unused index advances only when an address receives funds.
+    AddressBatch {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The derivation index of the first address, the first unused if missing
+        #[arg(long)]
+        start_index: Option<u32>,
+
+        /// The number of addresses, at most 1000
+        #[arg(long)]
+        count: u32,
+
+        /// Returns a text-encoded qr of every address in the json
+        #[arg(long)]
+        with_text_qr: bool,
+
+        /// Returns a qr image of every address in the json,
+        /// the given number is the number of pixel per qr code module
+        #[arg(long)]
+        with_uri_qr: Option<u8>,
+    },
+
+    /// Check which of the given addresses belong to the wallet
+    ///
+    /// Only the addresses derived while scanning are recognized.
+    AddressInfoBatch {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The address to check, can be specified multiple times
+        #[arg(long, required = true)]
+        address: Vec<String>,
+    },
+
     /// Get the balance of the given wallet name
     Balance {
         /// Wallet name
@@ -480,6 +674,11 @@ pub enum WalletCommand {
         /// Replace asset ids with tickers when possible
         #[arg(long, action)]
         with_tickers: bool,
+
+        /// Round the amounts down to their order of magnitude, to share the balance without
+        /// revealing it
+        #[arg(long, action)]
+        hide_amounts: bool,
     },
 
     /// Create an unsigned transaction (PSET)
@@ -510,6 +709,47 @@ pub enum WalletCommand {
         enable_ct_discount: bool,
     },
 
+    /// Create an unsigned transaction (PSET) at the fee rate estimated by the server
+    ///
+    /// If the server can't estimate the fee rate, the default one is used.
+    SendSmartFee {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// Specify a recipient in the form "address:satoshi:asset_id"
+        ///
+        /// As in `wallet send`, can be specified multiple times.
+        #[arg(long, required = true)]
+        recipient: Vec<String>,
+
+        /// The number of blocks in which the transaction should confirm
+        #[arg(long, default_value_t = 2)]
+        target_blocks: u32,
+    },
+
+    /// Send to a Liquid address or a Liquid BIP21 URI
+    ///
+    /// Address, asset and amount are taken from the URI, if the asset is missing it defaults to
+    /// the network policy asset (L-BTC for liquid)
+    SendToPayment {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The payment string, e.g. a scanned QR code
+        #[arg(long)]
+        uri: String,
+
+        /// Amount in satoshi, overriding the one in the URI if any
+        #[arg(long)]
+        amount: Option<u64>,
+
+        /// Fee rate to use
+        #[arg(long)]
+        fee_rate: Option<f32>,
+    },
+
     /// Drain the wallet of the policy asset
     Drain {
         /// Wallet name
@@ -559,6 +799,24 @@ pub enum WalletCommand {
         fee_rate: Option<f32>,
     },
 
+    /// Issue multiple assets in a single transaction
+    IssueMany {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// An issuance as JSON, with the same fields of the `issue` command, e.g.
+        /// '{"satoshi_asset":1000,"satoshi_token":1,"contract":"{...}"}'.
+        /// Can be specified multiple times.
+        #[arg(long, required = true)]
+        issuance: Vec<String>,
+
+        // TODO default value
+        /// To optionally specify a fee
+        #[arg(long)]
+        fee_rate: Option<f32>,
+    },
+
     /// Reissue a previously issued asset, needs ownership of the issuance token
     Reissue {
         /// Wallet name
@@ -595,8 +853,32 @@ pub enum WalletCommand {
         asset: String,
 
         /// The number of units of the asset to burn
+        #[arg(long, required_unless_present = "burn_all")]
+        satoshi_asset: Option<u64>,
+
+        /// Burn the whole wallet balance of the asset
+        #[arg(long, conflicts_with = "satoshi_asset")]
+        burn_all: bool,
+
+        // TODO default value
+        /// To optionally specify a fee
         #[arg(long)]
-        satoshi_asset: u64,
+        fee_rate: Option<f32>,
+    },
+
+    /// Peg out L-BTC to a bitcoin mainchain address
+    Pegout {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The bitcoin address receiving the funds on the mainchain
+        #[arg(long)]
+        mainchain_address: String,
+
+        /// The number of satoshi to peg out
+        #[arg(long)]
+        satoshi: u64,
 
         // TODO default value
         /// To optionally specify a fee
@@ -604,6 +886,36 @@ pub enum WalletCommand {
         fee_rate: Option<f32>,
     },
 
+    /// Claim a pegin, converting bitcoin sent to a pegin address into L-BTC
+    ///
+    /// The federation peg script is the configured one, if any, otherwise it's fetched from the
+    /// chain.
+    ClaimPegin {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The mainchain transaction sending to the pegin address, in hex
+        #[arg(long)]
+        bitcoin_tx: String,
+
+        /// The output of the mainchain transaction sending to the pegin address
+        #[arg(long)]
+        vout: u32,
+
+        /// The proof of inclusion of the mainchain transaction, in hex
+        #[arg(long)]
+        txout_proof: String,
+
+        /// The wallet script pubkey committed in the pegin address, in hex
+        #[arg(long)]
+        claim_script: String,
+
+        /// To optionally specify a fee
+        #[arg(long)]
+        fee_rate: Option<f32>,
+    },
+
     /// Print a multisig descriptor
     MultisigDesc {
         #[arg(long)]
@@ -615,8 +927,12 @@ pub enum WalletCommand {
         #[arg(long)]
         threshold: u32,
 
-        #[arg(long, required = true)]
+        #[arg(long, required_unless_present = "signer")]
         keyorigin_xpub: Vec<String>,
+
+        /// Name of a loaded signer partecipating in the multisig, its bip87 xpub is used
+        #[arg(long)]
+        signer: Vec<String>,
     },
 
     /// Try to finalize the PSET and broadcast the transaction
@@ -633,6 +949,91 @@ pub enum WalletCommand {
         pset: String,
     },
 
+    /// Show the transaction that broadcasting a complete PSET would produce
+    PreviewTx {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The PSET in base64
+        #[arg(long)]
+        pset: String,
+    },
+
+    /// Add the wallet key derivations and scripts to a PSET created elsewhere
+    ///
+    /// Needed by signers to recognize the wallet inputs, if the PSET creator omitted them.
+    AnnotatePset {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The PSET in base64
+        #[arg(short, long)]
+        pset: String,
+    },
+
+    /// Blind the outputs of a PSET created elsewhere, adding their blind proofs
+    ///
+    /// Inputs not belonging to the wallet must be explicit, since their blinding factors are needed.
+    BlindPset {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The unblinded PSET in base64
+        #[arg(short, long)]
+        pset: String,
+    },
+
+    /// Add the missing previous outputs data to the inputs of a PSET
+    ///
+    /// Inputs spending wallet outputs without `witness_utxo` or `non_witness_utxo` get them from
+    /// the previous transactions. Other inputs are hydrated only if requested with `--input`,
+    /// downloading their previous transactions.
+    HydratePset {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The PSET in base64
+        #[arg(short, long)]
+        pset: String,
+
+        /// Index of another input to hydrate, can be specified multiple times
+        #[arg(long)]
+        input: Vec<usize>,
+    },
+
+    /// Create a proof of reserves of the wallet UTXOs, committing to a challenge
+    ///
+    /// The returned PSET spends all the wallet UTXOs and an input derived from the challenge, so
+    /// it can't be broadcast. Sign it with the wallet signers to complete the proof.
+    ProofOfReserves {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The challenge message
+        #[arg(short, long)]
+        challenge: String,
+    },
+
+    /// Verify a signed proof of reserves, returning the proven amounts
+    VerifyProofOfReserves {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The challenge message
+        #[arg(short, long)]
+        challenge: String,
+
+        /// The signed proof, a PSET in base64
+        #[arg(short, long)]
+        pset: String,
+    },
+
     /// Get detailed information about the wallet
     Details {
         /// Wallet name
@@ -640,6 +1041,23 @@ pub enum WalletCommand {
         wallet: String,
     },
 
+    /// Get the wallet descriptor, checking that it can be shared as a watch-only descriptor
+    ///
+    /// Only descriptors with a view key can be shared, wallets with a slip77 blinding key can't
+    /// be shared without the master blinding key.
+    PublicDescriptor {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+    },
+
+    /// Get the ways to spend from the wallet, with the keys, timelocks and hash locks they require
+    Policy {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+    },
+
     /// Combine PSETs
     Combine {
         /// Wallet name
@@ -673,6 +1091,52 @@ pub enum WalletCommand {
         /// Wallet name
         #[arg(short, long, env)]
         wallet: String,
+
+        /// Return only the UTXOs of this asset
+        #[arg(long)]
+        asset: Option<String>,
+
+        /// Return only the UTXOs with at least this number of confirmations
+        #[arg(long)]
+        min_confirmations: Option<u32>,
+
+        /// Include the derivation path and the key origins of every UTXO
+        #[arg(long)]
+        with_derivation: bool,
+    },
+
+    /// Get the small incoming L-BTC UTXOs not originating from the wallet itself
+    ///
+    /// These are likely unsolicited deposits, such as dust sent to link addresses together,
+    /// and should not be spent with the other UTXOs.
+    DustDeposits {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// Return only the L-BTC UTXOs with a value in satoshi below this
+        #[arg(long)]
+        threshold: u64,
+    },
+
+    /// Apply an update created outside the server, for instance by an external indexer
+    ///
+    /// The update must have been created from the current state of the wallet.
+    ApplyUpdate {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The serialized update in hex
+        #[arg(long)]
+        update_hex: String,
+    },
+
+    /// Get the tip of the chain scanned for the wallet
+    Tip {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
     },
 
     /// Get the wallet transactions
@@ -684,6 +1148,18 @@ pub enum WalletCommand {
         /// Replace asset ids with tickers when possible
         #[arg(long, action)]
         with_tickers: bool,
+
+        /// Round the amounts and the fees down to their order of magnitude and omit the
+        /// unblinded urls, to share the transactions without revealing the amounts
+        #[arg(long, action)]
+        hide_amounts: bool,
+
+        /// Fetch the transactions in pages of this size and print them as they arrive, one
+        /// JSON object per line
+        ///
+        /// Keeps the memory bounded for wallets with many transactions
+        #[arg(long)]
+        page_size: Option<usize>,
     },
 
     /// Get a transaction
@@ -730,6 +1206,76 @@ pub enum WalletCommand {
         #[arg(long)]
         memo: String,
     },
+
+    /// Set a wallet metadata value, eg an order id or a customer reference
+    SetMeta {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The namespace
+        #[arg(long)]
+        namespace: String,
+
+        /// The key within the namespace
+        #[arg(short, long)]
+        key: String,
+
+        /// The value to set, empty to remove the entry
+        #[arg(long)]
+        value: String,
+    },
+
+    /// Get the wallet metadata in a namespace
+    GetMeta {
+        /// Wallet name
+        #[arg(short, long, env)]
+        wallet: String,
+
+        /// The namespace
+        #[arg(long)]
+        namespace: String,
+
+        /// Return only the entry with this key
+        #[arg(short, long)]
+        key: Option<String>,
+    },
+
+    /// Look for transactions beyond the gap limit, which are not found by the normal scan
+    ///
+    /// Useful when the wallet balance is lower than expected
+    Diagnose {
+        /// Wallet name
+        #[arg(short, long)]
+        wallet: String,
+    },
+
+    /// Number of scripts derived by a full scan of the wallet, for each chain
+    ScanScope {
+        /// Wallet name
+        #[arg(short, long)]
+        wallet: String,
+    },
+
+    /// Assets issued by the wallet, with their supply and the reissuance tokens owned
+    IssuedAssets {
+        /// Wallet name
+        #[arg(short, long)]
+        wallet: String,
+    },
+
+    /// Scan a wallet once and print its balance and utxos, without a running server
+    ///
+    /// Useful for one-off checks, for instance from cron jobs.
+    SyncOnce {
+        /// CT descriptor of the wallet
+        #[arg(short, long)]
+        descriptor: String,
+
+        /// Electrum URL, if not specified a reasonable default is used according to the network
+        #[arg(short, long)]
+        electrum_url: Option<String>,
+    },
 }
 
 #[derive(Debug, Args)]
@@ -827,6 +1373,27 @@ pub enum AssetCommand {
         #[arg(short, long)]
         asset: String,
     },
+
+    /// Verify the contract commits to the asset, without inserting it
+    ///
+    /// The asset id is recomputed from the contract and the issuance prevout.
+    Verify {
+        /// Asset ID in hex
+        #[arg(short, long)]
+        asset: String,
+
+        /// The JSON contract
+        #[arg(long)]
+        contract: String,
+
+        /// The issuance transaction in hex
+        #[arg(long)]
+        issuance_tx: String,
+
+        /// Also check the proof on the contract domain authorizes the asset
+        #[arg(long)]
+        domain_proof: bool,
+    },
 }
 
 #[derive(Debug, Args)]
@@ -857,6 +1424,13 @@ pub enum Amp2Command {
         #[arg(short, long)]
         pset: String,
     },
+
+    /// Ask AMP2 server whether it has cosigned a PSET
+    CosignStatus {
+        /// The PSET sent for cosigning or the id of the cosign request
+        #[arg(short, long)]
+        pset_or_id: String,
+    },
 }
 
 #[derive(Debug, Args)]
@@ -895,6 +1469,24 @@ pub enum ServerCommand {
         /// Interval between blockchain scans (seconds)
         #[arg(long)]
         scanning_interval: Option<u64>,
+
+        /// Federation peg script in hex, used to build pegin claims
+        ///
+        /// If not specified the current one is read from the chain,
+        /// set it for regtest or custom federations
+        #[arg(long)]
+        fed_peg_script: Option<String>,
+
+        /// Also serve a REST facade over the wallet methods
+        ///
+        /// E.g. `GET /api/wallet/{name}/balance` or `POST /api/wallet/{name}/set-tx-memo`
+        /// with the request params as JSON body
+        #[arg(long)]
+        rest: bool,
+
+        /// Compress large responses for clients sending `Accept-Encoding: gzip` or `deflate`
+        #[arg(long)]
+        compression: bool,
     },
 
     /// Wait until an entire blockchain scan has been completed
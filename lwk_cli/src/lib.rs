@@ -14,7 +14,8 @@ use lwk_app::Config;
 use serde_json::Value;
 
 use crate::args::{
-    Amp2Command, AssetCommand, CliCommand, Network, ServerCommand, SignerCommand, WalletCommand,
+    Amp2Command, AssetCommand, CliCommand, CompleteKind, Network, ServerCommand, SignerCommand,
+    WalletCommand,
 };
 pub use args::Cli;
 
@@ -80,6 +81,9 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                     datadir,
                     timeout,
                     scanning_interval,
+                    fed_peg_script,
+                    rest,
+                    compression,
                 } => {
                     let (tx, rx) = std::sync::mpsc::channel();
                     let _ = ctrlc::try_set_handler(move || {
@@ -109,6 +113,11 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                     if let Some(url) = esplora_api_url {
                         config.esplora_api_url = url;
                     };
+                    if let Some(hex) = fed_peg_script {
+                        config.set_fed_peg_script(&hex)?;
+                    };
+                    config.rest = rest;
+                    config.compression = compression;
 
                     #[cfg(feature = "registry")]
                     if let Some(url) = registry_url {
@@ -170,18 +179,46 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
             SignerCommand::LoadSoftware {
                 signer,
                 mnemonic,
+                mnemonic_file,
+                passphrase,
                 persist,
             } => {
                 let persist = persist.expect("required");
-                let j = client.signer_load_software(signer, mnemonic, persist)?;
+                let passphrase = passphrase.map(|p| p.0);
+                let path = mnemonic_file.unwrap_or_default();
+                let j = match mnemonic {
+                    Some(mnemonic) => {
+                        client.signer_load_software(signer, mnemonic.0, passphrase, persist)?
+                    }
+                    None if path.as_os_str() == "-" => {
+                        let mut mnemonic = String::new();
+                        std::io::stdin().read_line(&mut mnemonic)?;
+                        let mnemonic = mnemonic.trim().to_string();
+                        client.signer_load_software(signer, mnemonic, passphrase, persist)?
+                    }
+                    None => {
+                        // The server may run in a different working directory
+                        let path = std::fs::canonicalize(path)?.display().to_string();
+                        client.signer_load_software_file(signer, path, passphrase, persist)?
+                    }
+                };
                 serde_json::to_value(j)?
             }
             SignerCommand::LoadJade {
                 signer,
                 id,
                 emulator,
+                expected_fingerprint,
+            } => {
+                let j = client.signer_load_jade(signer, id, emulator, expected_fingerprint)?;
+                serde_json::to_value(j)?
+            }
+            SignerCommand::LoadLedger {
+                signer,
+                emulator,
+                expected_fingerprint,
             } => {
-                let j = client.signer_load_jade(signer, id, emulator)?;
+                let j = client.signer_load_ledger(signer, emulator, expected_fingerprint)?;
                 serde_json::to_value(j)?
             }
             SignerCommand::LoadExternal {
@@ -212,18 +249,40 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 )?;
                 serde_json::to_value(r)?
             }
+            SignerCommand::AccountDesc {
+                signer,
+                account,
+                descriptor_blinding_key,
+                kind,
+            } => {
+                let r = client.signer_account_descriptor(
+                    signer,
+                    account,
+                    descriptor_blinding_key.to_string(),
+                    kind.to_string(),
+                )?;
+                serde_json::to_value(r)?
+            }
             SignerCommand::Xpub { signer, kind } => {
                 let r = client.signer_xpub(signer, kind.to_string())?;
                 serde_json::to_value(r)?
             }
+            SignerCommand::XpubKinds => {
+                let r = client.signer_xpub_kinds()?;
+                serde_json::to_value(r)?
+            }
             SignerCommand::RegisterMultisig { signer, wallet } => {
                 let r = client.signer_register_multisig(signer, wallet)?;
                 serde_json::to_value(r)?
             }
         },
         CliCommand::Wallet(a) => match a.command {
-            WalletCommand::Load { descriptor, wallet } => {
-                let r = client.wallet_load(descriptor, wallet)?;
+            WalletCommand::Load {
+                descriptor,
+                change_descriptor,
+                wallet,
+            } => {
+                let r = client.wallet_load(descriptor, change_descriptor, wallet)?;
                 serde_json::to_value(r)?
             }
             WalletCommand::Unload { wallet } => {
@@ -233,8 +292,9 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
             WalletCommand::Balance {
                 wallet,
                 with_tickers,
+                hide_amounts,
             } => {
-                let r = client.wallet_balance(wallet, with_tickers)?;
+                let r = client.wallet_balance(wallet, with_tickers, hide_amounts)?;
                 serde_json::to_value(r)?
             }
             WalletCommand::Send {
@@ -255,6 +315,31 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                     client.wallet_send_many(wallet, addressees, fee_rate, enable_ct_discount)?;
                 serde_json::to_value(r)?
             }
+            WalletCommand::SendSmartFee {
+                wallet,
+                recipient,
+                target_blocks,
+            } => {
+                let mut addressees = vec![];
+                for rec in recipient {
+                    addressees.push(
+                        rec.parse()
+                            .with_context(|| "error parsing recipient argument")?,
+                    );
+                }
+
+                let r = client.wallet_send_many_smart_fee(wallet, addressees, target_blocks)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::SendToPayment {
+                wallet,
+                uri,
+                amount,
+                fee_rate,
+            } => {
+                let r = client.wallet_send_to_payment(wallet, uri, amount, fee_rate)?;
+                serde_json::to_value(r)?
+            }
             WalletCommand::Drain {
                 wallet,
                 address,
@@ -273,6 +358,26 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 let r = client.wallet_address(wallet, index, signer, with_text_qr, with_uri_qr)?;
                 serde_json::to_value(r)?
             }
+            WalletCommand::AddressBatch {
+                wallet,
+                start_index,
+                count,
+                with_text_qr,
+                with_uri_qr,
+            } => {
+                let r = client.wallet_address_batch(
+                    wallet,
+                    start_index,
+                    count,
+                    with_text_qr,
+                    with_uri_qr,
+                )?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::AddressInfoBatch { wallet, address } => {
+                let r = client.wallet_address_info_batch(wallet, address)?;
+                serde_json::to_value(r)?
+            }
             WalletCommand::List => serde_json::to_value(client.wallet_list()?)?,
             WalletCommand::Issue {
                 wallet,
@@ -294,6 +399,18 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 )?;
                 serde_json::to_value(r)?
             }
+            WalletCommand::IssueMany {
+                wallet,
+                issuance,
+                fee_rate,
+            } => {
+                let issuances = issuance
+                    .iter()
+                    .map(|i| serde_json::from_str(i))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let r = client.wallet_issue_many(wallet, issuances, fee_rate)?;
+                serde_json::to_value(r)?
+            }
             WalletCommand::Reissue {
                 wallet,
                 asset,
@@ -309,9 +426,38 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 wallet,
                 asset,
                 satoshi_asset,
+                burn_all,
+                fee_rate,
+            } => {
+                let satoshi_asset = satoshi_asset.unwrap_or(0);
+                let r = client.wallet_burn(wallet, asset, satoshi_asset, burn_all, fee_rate)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::Pegout {
+                wallet,
+                mainchain_address,
+                satoshi,
+                fee_rate,
+            } => {
+                let r = client.wallet_pegout(wallet, mainchain_address, satoshi, fee_rate)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::ClaimPegin {
+                wallet,
+                bitcoin_tx,
+                vout,
+                txout_proof,
+                claim_script,
                 fee_rate,
             } => {
-                let r = client.wallet_burn(wallet, asset, satoshi_asset, fee_rate)?;
+                let r = client.wallet_claim_pegin(
+                    wallet,
+                    bitcoin_tx,
+                    vout,
+                    txout_proof,
+                    claim_script,
+                    fee_rate,
+                )?;
                 serde_json::to_value(r)?
             }
             WalletCommand::MultisigDesc {
@@ -319,12 +465,14 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 kind,
                 threshold,
                 keyorigin_xpub,
+                signer,
             } => {
                 let r = client.wallet_multisig_descriptor(
                     descriptor_blinding_key.to_string(),
                     kind.to_string(),
                     threshold,
                     keyorigin_xpub,
+                    signer,
                 )?;
                 serde_json::to_value(r)?
             }
@@ -336,10 +484,50 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 let r = client.wallet_broadcast(wallet, dry_run, pset)?;
                 serde_json::to_value(r)?
             }
+            WalletCommand::AnnotatePset { wallet, pset } => {
+                let r = client.wallet_annotate_pset(wallet, pset)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::BlindPset { wallet, pset } => {
+                let r = client.wallet_blind_pset(wallet, pset)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::HydratePset {
+                wallet,
+                pset,
+                input,
+            } => {
+                let r = client.wallet_hydrate_pset(wallet, pset, input)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::ProofOfReserves { wallet, challenge } => {
+                let r = client.wallet_proof_of_reserves(wallet, challenge)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::VerifyProofOfReserves {
+                wallet,
+                challenge,
+                pset,
+            } => {
+                let r = client.wallet_verify_proof_of_reserves(wallet, challenge, pset)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::PreviewTx { wallet, pset } => {
+                let r = client.wallet_preview_tx(wallet, pset)?;
+                serde_json::to_value(r)?
+            }
             WalletCommand::Details { wallet } => {
                 let r = client.wallet_details(wallet)?;
                 serde_json::to_value(r)?
             }
+            WalletCommand::Policy { wallet } => {
+                let r = client.wallet_policy(wallet)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::PublicDescriptor { wallet } => {
+                let r = client.wallet_public_descriptor(wallet)?;
+                serde_json::to_value(r)?
+            }
             WalletCommand::Combine { wallet, pset } => {
                 let r = client.wallet_combine(wallet, pset)?;
                 serde_json::to_value(r)?
@@ -352,15 +540,56 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 let r = client.wallet_pset_details(wallet, pset, with_tickers)?;
                 serde_json::to_value(r)?
             }
-            WalletCommand::Utxos { wallet } => {
-                let r = client.wallet_utxos(wallet)?;
+            WalletCommand::Utxos {
+                wallet,
+                asset,
+                min_confirmations,
+                with_derivation,
+            } => {
+                let r = client.wallet_utxos_filtered(
+                    wallet,
+                    asset,
+                    min_confirmations,
+                    with_derivation,
+                )?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::DustDeposits { wallet, threshold } => {
+                let r = client.wallet_dust_deposits(wallet, threshold)?;
                 serde_json::to_value(r)?
             }
+            WalletCommand::ApplyUpdate { wallet, update_hex } => {
+                let r = client.wallet_apply_update(wallet, update_hex)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::Tip { wallet } => {
+                let r = client.wallet_tip(wallet)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::Txs {
+                wallet,
+                with_tickers,
+                hide_amounts,
+                page_size: Some(page_size),
+            } => {
+                let stdout = std::io::stdout();
+                stream_wallet_txs(
+                    &client,
+                    &wallet,
+                    with_tickers,
+                    hide_amounts,
+                    page_size,
+                    &mut stdout.lock(),
+                )?;
+                Value::Null
+            }
             WalletCommand::Txs {
                 wallet,
                 with_tickers,
+                hide_amounts,
+                page_size: None,
             } => {
-                let r = client.wallet_txs(wallet, with_tickers)?;
+                let r = client.wallet_txs_page(wallet, with_tickers, hide_amounts, None, None)?;
                 serde_json::to_value(r)?
             }
             WalletCommand::Tx {
@@ -383,6 +612,54 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 let r = client.wallet_set_addr_memo(wallet, address, memo)?;
                 serde_json::to_value(r)?
             }
+            WalletCommand::SetMeta {
+                wallet,
+                namespace,
+                key,
+                value,
+            } => {
+                let r = client.wallet_set_meta(wallet, namespace, key, value)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::GetMeta {
+                wallet,
+                namespace,
+                key,
+            } => {
+                let r = client.wallet_get_meta(wallet, namespace, key)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::Diagnose { wallet } => {
+                let r = client.wallet_diagnose(wallet)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::ScanScope { wallet } => {
+                let r = client.wallet_scan_scope(wallet)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::IssuedAssets { wallet } => {
+                let r = client.wallet_issued_assets(wallet)?;
+                serde_json::to_value(r)?
+            }
+            WalletCommand::SyncOnce {
+                descriptor,
+                electrum_url,
+            } => {
+                // Nothing is persisted, the datadir is not used
+                let datadir = std::path::PathBuf::new();
+                let mut config = match args.network {
+                    Network::Mainnet => Config::default_mainnet(datadir),
+                    Network::Testnet => Config::default_testnet(datadir),
+                    Network::Regtest => Config::default_regtest(datadir),
+                };
+                if let Some(url) = electrum_url {
+                    config.electrum_url = url;
+                } else if let Network::Regtest = args.network {
+                    anyhow::bail!("on regtest you have to specify --electrum-url");
+                };
+                let r = lwk_app::sync_once(&config, &descriptor)?;
+                serde_json::to_value(r)?
+            }
         },
         CliCommand::Asset(a) => match a.command {
             AssetCommand::Contract {
@@ -428,6 +705,15 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 let r = client.asset_publish(asset)?;
                 serde_json::to_value(r)?
             }
+            AssetCommand::Verify {
+                asset,
+                contract,
+                issuance_tx,
+                domain_proof,
+            } => {
+                let r = client.asset_verify(asset, contract, issuance_tx, domain_proof)?;
+                serde_json::to_value(r)?
+            }
         },
         CliCommand::Amp2(a) => match a.command {
             Amp2Command::Descriptor { signer } => {
@@ -442,8 +728,19 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
                 let r = client.amp2_cosign(pset)?;
                 serde_json::to_value(r)?
             }
+            Amp2Command::CosignStatus { pset_or_id } => {
+                let r = client.amp2_cosign_status(pset_or_id)?;
+                serde_json::to_value(r)?
+            }
         },
         CliCommand::Schema(a) => schema::schema(a, client)?,
+        CliCommand::ParsePayment { uri } => serde_json::to_value(client.parse_payment(uri)?)?,
+        CliCommand::PsetConvertVersion { pset, target } => {
+            serde_json::to_value(client.pset_convert_version(pset, target)?)?
+        }
+        CliCommand::PsetBlindingStatus { pset } => {
+            serde_json::to_value(client.pset_blinding_status(pset)?)?
+        }
         CliCommand::GenerateCompletion { shell } => {
             let mut result = vec![];
             clap_complete::generate(shell, &mut Cli::command(), "lwk_cli", &mut result);
@@ -451,6 +748,24 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
             Value::String(s)
         }
 
+        CliCommand::Complete { kind } => {
+            let names: Vec<String> = match kind {
+                CompleteKind::Wallets => client
+                    .wallet_list()?
+                    .wallets
+                    .into_iter()
+                    .map(|w| w.name)
+                    .collect(),
+                CompleteKind::Signers => client
+                    .signer_list()?
+                    .signers
+                    .into_iter()
+                    .map(|s| s.name)
+                    .collect(),
+            };
+            serde_json::to_value(names)?
+        }
+
         #[cfg(feature = "bindings")]
         CliCommand::Generate { .. } => {
             uniffi::uniffi_bindgen_main();
@@ -458,3 +773,42 @@ pub fn inner_main(args: args::Cli) -> anyhow::Result<Value> {
         }
     })
 }
+
+/// Write the wallet transactions to `out`, one JSON object per line, fetching them from the
+/// server in pages of `page_size`
+///
+/// Returns the number of pages fetched. Transactions arriving while paging may shift the pages,
+/// causing a transaction to be written twice or skipped.
+pub fn stream_wallet_txs<W: std::io::Write>(
+    client: &lwk_app::Client,
+    wallet: &str,
+    with_tickers: bool,
+    hide_amounts: bool,
+    page_size: usize,
+    out: &mut W,
+) -> anyhow::Result<usize> {
+    if page_size == 0 {
+        return Err(anyhow!("Page size must be greater than 0"));
+    }
+    let mut offset = 0;
+    let mut pages = 0;
+    loop {
+        let page = client.wallet_txs_page(
+            wallet.to_string(),
+            with_tickers,
+            hide_amounts,
+            Some(offset),
+            Some(page_size),
+        )?;
+        pages += 1;
+        for tx in page.txs.iter() {
+            serde_json::to_writer(&mut *out, tx)?;
+            writeln!(out)?;
+        }
+        out.flush()?;
+        if page.txs.len() < page_size {
+            return Ok(pages);
+        }
+        offset += page_size;
+    }
+}
@@ -17,6 +17,9 @@ fn main() -> anyhow::Result<()> {
             }
         }
     };
-    println!("{:#}", value);
+    // Streaming commands write their output as they go
+    if !value.is_null() {
+        println!("{:#}", value);
+    }
     Ok(())
 }
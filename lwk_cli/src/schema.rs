@@ -16,6 +16,13 @@ pub(crate) fn schema(a: SchemaArgs, client: Client) -> Result<Value, anyhow::Err
             MainCommand::Asset(s) => client.schema(s.command.into(), Direction::Request)?,
             MainCommand::Amp2(s) => client.schema(s.command.into(), Direction::Request)?,
             MainCommand::Schema => client.schema(Method::Schema, Direction::Request)?,
+            MainCommand::ParsePayment => client.schema(Method::ParsePayment, Direction::Request)?,
+            MainCommand::PsetConvertVersion => {
+                client.schema(Method::PsetConvertVersion, Direction::Request)?
+            }
+            MainCommand::PsetBlindingStatus => {
+                client.schema(Method::PsetBlindingStatus, Direction::Request)?
+            }
         },
         DirectionCommand::Response(res) => match res.command {
             MainCommand::Server(w) => client.schema(w.command.into(), Direction::Response)?,
@@ -24,6 +31,15 @@ pub(crate) fn schema(a: SchemaArgs, client: Client) -> Result<Value, anyhow::Err
             MainCommand::Asset(s) => client.schema(s.command.into(), Direction::Response)?,
             MainCommand::Amp2(s) => client.schema(s.command.into(), Direction::Response)?,
             MainCommand::Schema => client.schema(Method::Schema, Direction::Response)?,
+            MainCommand::ParsePayment => {
+                client.schema(Method::ParsePayment, Direction::Response)?
+            }
+            MainCommand::PsetConvertVersion => {
+                client.schema(Method::PsetConvertVersion, Direction::Response)?
+            }
+            MainCommand::PsetBlindingStatus => {
+                client.schema(Method::PsetBlindingStatus, Direction::Response)?
+            }
         },
     })
 }
@@ -44,19 +60,42 @@ impl From<WalletSubCommandsEnum> for Method {
             WalletSubCommandsEnum::Unload => Method::WalletUnload,
             WalletSubCommandsEnum::List => Method::WalletList,
             WalletSubCommandsEnum::Address => Method::WalletAddress,
+            WalletSubCommandsEnum::AddressBatch => Method::WalletAddressBatch,
+            WalletSubCommandsEnum::AddressInfoBatch => Method::WalletAddressInfoBatch,
             WalletSubCommandsEnum::Balance => Method::WalletBalance,
             WalletSubCommandsEnum::Send => Method::WalletSendMany,
+            WalletSubCommandsEnum::SendToPayment => Method::WalletSendToPayment,
+            WalletSubCommandsEnum::SendSmartFee => Method::WalletSendManySmartFee,
             WalletSubCommandsEnum::Issue => Method::WalletIssue,
+            WalletSubCommandsEnum::IssueMany => Method::WalletIssueMany,
             WalletSubCommandsEnum::Reissue => Method::WalletReissue,
+            WalletSubCommandsEnum::Pegout => Method::WalletPegout,
+            WalletSubCommandsEnum::ClaimPegin => Method::WalletClaimPegin,
             WalletSubCommandsEnum::MultisigDesc => Method::WalletMultisigDescriptor,
             WalletSubCommandsEnum::Broadcast => Method::WalletBroadcast,
+            WalletSubCommandsEnum::PreviewTx => Method::WalletPreviewTx,
+            WalletSubCommandsEnum::AnnotatePset => Method::WalletAnnotatePset,
+            WalletSubCommandsEnum::BlindPset => Method::WalletBlindPset,
+            WalletSubCommandsEnum::HydratePset => Method::WalletHydratePset,
+            WalletSubCommandsEnum::ProofOfReserves => Method::WalletProofOfReserves,
+            WalletSubCommandsEnum::VerifyProofOfReserves => Method::WalletVerifyProofOfReserves,
             WalletSubCommandsEnum::Details => Method::WalletDetails,
+            WalletSubCommandsEnum::PublicDescriptor => Method::WalletPublicDescriptor,
+            WalletSubCommandsEnum::Policy => Method::WalletPolicy,
             WalletSubCommandsEnum::Combine => Method::WalletCombine,
             WalletSubCommandsEnum::PsetDetails => Method::WalletPsetDetails,
             WalletSubCommandsEnum::Utxos => Method::WalletUtxos,
+            WalletSubCommandsEnum::DustDeposits => Method::WalletDustDeposits,
+            WalletSubCommandsEnum::ApplyUpdate => Method::WalletApplyUpdate,
+            WalletSubCommandsEnum::Tip => Method::WalletTip,
             WalletSubCommandsEnum::Txs => Method::WalletTxs,
             WalletSubCommandsEnum::SetTxMemo => Method::WalletSetTxMemo,
             WalletSubCommandsEnum::SetAddrMemo => Method::WalletSetAddrMemo,
+            WalletSubCommandsEnum::SetMeta => Method::WalletSetMeta,
+            WalletSubCommandsEnum::GetMeta => Method::WalletGetMeta,
+            WalletSubCommandsEnum::Diagnose => Method::WalletDiagnose,
+            WalletSubCommandsEnum::ScanScope => Method::WalletScanScope,
+            WalletSubCommandsEnum::IssuedAssets => Method::WalletIssuedAssets,
         }
     }
 }
@@ -68,13 +107,16 @@ impl From<SignerSubCommandsEnum> for Method {
             SignerSubCommandsEnum::JadeId => Method::SignerJadeId,
             SignerSubCommandsEnum::LoadSoftware => Method::SignerLoadSoftware,
             SignerSubCommandsEnum::LoadJade => Method::SignerLoadJade,
+            SignerSubCommandsEnum::LoadLedger => Method::SignerLoadLedger,
             SignerSubCommandsEnum::LoadExternal => Method::SignerLoadExternal,
             SignerSubCommandsEnum::Unload => Method::SignerUnload,
             SignerSubCommandsEnum::Details => Method::SignerDetails,
             SignerSubCommandsEnum::List => Method::SignerList,
             SignerSubCommandsEnum::Sign => Method::SignerSign,
             SignerSubCommandsEnum::SinglesigDesc => Method::SignerSinglesigDescriptor,
+            SignerSubCommandsEnum::AccountDesc => Method::SignerAccountDescriptor,
             SignerSubCommandsEnum::Xpub => Method::SignerXpub,
+            SignerSubCommandsEnum::XpubKinds => Method::SignerXpubKinds,
         }
     }
 }
@@ -88,6 +130,7 @@ impl From<AssetSubCommandsEnum> for Method {
             AssetSubCommandsEnum::Insert => Method::AssetInsert,
             AssetSubCommandsEnum::Remove => Method::AssetRemove,
             AssetSubCommandsEnum::Publish => Method::AssetPublish,
+            AssetSubCommandsEnum::Verify => Method::AssetVerify,
         }
     }
 }
@@ -98,6 +141,7 @@ impl From<Amp2SubCommandsEnum> for Method {
             Amp2SubCommandsEnum::Descriptor => Method::Amp2Descriptor,
             Amp2SubCommandsEnum::Register => Method::Amp2Register,
             Amp2SubCommandsEnum::Cosign => Method::Amp2Cosign,
+            Amp2SubCommandsEnum::CosignStatus => Method::Amp2CosignStatus,
         }
     }
 }
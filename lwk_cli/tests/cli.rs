@@ -4,14 +4,21 @@ use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     process::{Child, Command, Stdio},
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
     thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
 use clap::{Parser, ValueEnum};
 use elements::encode::serialize;
 use elements::hex::ToHex;
 use elements::{pset::PartiallySignedTransaction, Address};
-use lwk_containers::{testcontainers::clients, JadeEmulator, EMULATOR_PORT};
+use lwk_containers::{
+    testcontainers::clients, JadeEmulator, LedgerEmulator, EMULATOR_PORT, LEDGER_EMULATOR_PORT,
+};
 use serde_json::Value;
 
 use lwk_cli::{
@@ -76,6 +83,22 @@ fn setup_cli(
     Option<RegistryProc>,
 ) {
     let server = lwk_test_util::setup_with_esplora();
+    setup_cli_with_server(server, with_registry, "")
+}
+
+/// Start the cli server connected to `server`, with additional `server start` params
+fn setup_cli_with_server(
+    server: TestElectrumServer,
+    with_registry: bool,
+    extra_params: &str,
+) -> (
+    JoinHandle<()>,
+    TempDir,
+    String,
+    String,
+    TestElectrumServer,
+    Option<RegistryProc>,
+) {
     let tmp = tempfile::tempdir().unwrap();
     let datadir = tmp.path().display().to_string();
 
@@ -117,8 +140,9 @@ fn setup_cli(
     let addr = get_available_addr().unwrap();
 
     let cli = format!("cli --addr {addr} -n regtest");
-    let params =
-        format!("--datadir {datadir} --electrum-url {electrum_url} {registry_url} {esplora_url}");
+    let params = format!(
+        "--datadir {datadir} --electrum-url {electrum_url} {registry_url} {esplora_url} {extra_params}"
+    );
 
     let t = {
         let cli = cli.clone();
@@ -232,6 +256,11 @@ fn address(cli: &str, wallet: &str) -> String {
     get_str(&r, "address").to_string()
 }
 
+fn addr_at(cli: &str, wallet: &str, index: u32) -> String {
+    let r = sh(&format!("{cli} wallet address -w {wallet} --index {index}"));
+    get_str(&r, "address").to_string()
+}
+
 fn addr_memo(cli: &str, w: &str, i: u32) -> String {
     let r = sh(&format!("{cli} wallet address --wallet {w} --index {i}"));
     get_str(&r, "memo").to_string()
@@ -364,6 +393,11 @@ fn test_start_stop_persist() {
     let r = sh(&format!("{cli} signer details -s s1"));
     assert_eq!(get_str(&r, "mnemonic"), mnemonic);
     assert_eq!(get_str(&r, "type"), "software");
+    let c = r.get("capabilities").unwrap();
+    assert!(!c.get("sign_message").unwrap().as_bool().unwrap());
+    assert!(c.get("derive_xpub").unwrap().as_bool().unwrap());
+    assert!(c.get("slip77").unwrap().as_bool().unwrap());
+    assert!(!c.get("taproot").unwrap().as_bool().unwrap());
     let r = sh(&format!("{cli} signer details -s s2"));
     assert!(r.get("mnemonic").is_none());
     assert_eq!(get_str(&r, "type"), "external");
@@ -371,6 +405,22 @@ fn test_start_stop_persist() {
     assert!(r.get("mnemonic").is_none());
     assert_eq!(get_str(&r, "type"), "jade-id");
 
+    let r = sh(&format!("{cli} signer list"));
+    // The signers are not listed in a specific order
+    let mut types: Vec<_> = r
+        .get("signers")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| (get_str(s, "name"), get_str(s, "type")))
+        .collect();
+    types.sort();
+    assert_eq!(
+        types,
+        [("s1", "software"), ("s2", "external"), ("s3", "jade-id")]
+    );
+
     let desc = "ct(c25deb86fa11e49d651d7eae27c220ef930fbd86ea023eebfa73e54875647963,elwpkh(tpubD6NzVbkrYhZ4Was8nwnZi7eiWUNJq2LFpPSCMQLioUfUtT1e72GkRbmVeRAZc26j5MRUz2hRLsaVHJfs6L7ppNfLUrm9btQTuaEsLrT7D87/*))#q9cypnmc";
     sh(&format!("{cli} wallet load --wallet custody -d {desc}"));
     sh(&format!(r#"{cli} wallet unload --wallet custody"#)); // Verify unloads are handled
@@ -451,6 +501,57 @@ fn test_start_stop_persist() {
     t.join().unwrap();
 }
 
+#[test]
+fn test_signer_load_software_file() {
+    let (t, tmp, cli, params, _server, _) = setup_cli(false);
+
+    let mnemonic = lwk_test_util::TEST_MNEMONIC;
+    let path = tmp.path().join("mnemonic.txt");
+    fs::write(&path, format!("{mnemonic}\n")).unwrap();
+    let path = path.display();
+
+    let r = sh(&format!(
+        "{cli} signer load-software --persist true --mnemonic-file {path} --signer s1"
+    ));
+    let fingerprint = get_str(&r, "fingerprint").to_string();
+    let r = sh(&format!(
+        r#"{cli} signer load-software --persist false --mnemonic "{mnemonic}" --signer s2"#
+    ));
+    assert_eq!(get_str(&r, "fingerprint"), fingerprint);
+    let r = sh(&format!(
+        r#"{cli} signer load-software --persist false --mnemonic-file {path} --passphrase "25th word" --signer s4"#
+    ));
+    assert_ne!(get_str(&r, "fingerprint"), fingerprint);
+
+    let err = sh_err(&format!(
+        r#"{cli} signer load-software --persist false --mnemonic-file {path}-missing --signer s3"#
+    ));
+    assert!(!err.contains(mnemonic));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+
+    // The path is persisted, not the mnemonic
+    let state = fs::read_to_string(tmp.path().join("liquid-regtest").join("state.json")).unwrap();
+    assert!(state.contains("mnemonic.txt"));
+    assert!(!state.contains(mnemonic));
+
+    let t = {
+        let cli = cli.clone();
+        let params = params.clone();
+        std::thread::spawn(move || {
+            sh(&format!("{cli} server start {params}"));
+        })
+    };
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+
+    let r = sh(&format!("{cli} signer details -s s1"));
+    assert_eq!(get_str(&r, "fingerprint"), fingerprint);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
 #[test]
 fn test_signer_load_unload_list() {
     let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
@@ -564,6 +665,33 @@ fn test_wallet_load_unload_list() {
     t.join().unwrap();
 }
 
+#[test]
+fn test_complete() {
+    let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
+
+    let r = sh(&format!("{cli} complete wallets"));
+    assert_eq!(r.as_array().unwrap().len(), 0);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    singlesig_wallet(&cli, "w2", "s1", "elip151", "wpkh");
+
+    let r = sh(&format!("{cli} complete wallets"));
+    let names: HashSet<_> = r
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_str().unwrap())
+        .collect();
+    assert_eq!(names, HashSet::from(["w1", "w2"]));
+
+    let r = sh(&format!("{cli} complete signers"));
+    assert_eq!(r, serde_json::json!(["s1"]));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
 #[test]
 fn test_wallet_memos() {
     let (t, _tmp, cli, params, server, _) = setup_cli(false);
@@ -758,6 +886,17 @@ fn test_wallet_details() {
     assert_eq!(get_str(&signers[0], "name"), "s1");
     assert_eq!(get_str(&signers[1], "name"), "s1");
 
+    // Key origin inconsistent with the xpub, the last derivation step is missing
+    let desc = remove_checksum(desc_ss);
+    let end = desc.find(']').unwrap();
+    let start = desc[..end].rfind('/').unwrap();
+    let desc_bad_origin = format!("{}{}", &desc[..start], &desc[end..]);
+    sh(&format!(
+        "{cli} wallet load --wallet bad_origin -d {desc_bad_origin}"
+    ));
+    let r = sh(&format!("{cli} wallet details --wallet bad_origin"));
+    assert!(get_str(&r, "warnings").contains("key origin"));
+
     sh(&format!("{cli} server stop"));
     t.join().unwrap();
 }
@@ -851,6 +990,19 @@ fn test_issue() {
 
     let r = sh(&format!("{cli} wallet tx -w w1 -t {issuance_txid}"));
     let tx = get_str(&r, "tx");
+    sh(&format!(
+        "{cli} asset verify --asset {asset} --contract '{contract}' --issuance-tx {tx}"
+    ));
+    let spoofed = contract.replace("EXMP", "EXMQ");
+    let r = sh_err(&format!(
+        "{cli} asset verify --asset {asset} --contract '{spoofed}' --issuance-tx {tx}"
+    ));
+    assert!(r.contains("Contract does not commit to asset id"));
+    let r = sh_err(&format!(
+        "{cli} asset verify --asset {token} --contract '{contract}' --issuance-tx {tx}"
+    ));
+    assert!(r.contains("Missing issuance"));
+
     sh(&format!(
         "{cli} asset insert --asset {asset} --contract '{contract}' --issuance-tx {tx}"
     ));
@@ -896,6 +1048,11 @@ fn test_issue() {
     complete(&cli, "w1", get_str(&r, "pset"), &["s1"]);
     assert_eq!(asset_balance_post - 1, get_balance(&cli, "w1", asset));
 
+    let err = sh_err(&format!(
+        "{cli} wallet burn -w w1 --asset {policy_asset} --burn-all"
+    ));
+    assert!(err.contains("Cannot burn all the policy asset"));
+
     let r = sh(&format!("{cli} wallet utxos --wallet w1"));
     assert_eq!(get_len(&r, "utxos"), 4);
 
@@ -966,6 +1123,64 @@ fn test_issue() {
     t.join().unwrap();
 }
 
+#[test]
+fn test_issue_many() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+
+    let mut issuances = String::new();
+    let mut contracts = vec![];
+    for (name, ticker) in [("first", "FRST"), ("second", "SCND")] {
+        let r = sh(&format!("{cli} asset contract --domain example.com --issuer-pubkey 035d0f7b0207d9cc68870abfef621692bce082084ed3ca0c1ae432dd12d889be01 --name {name} --ticker {ticker}"));
+        let contract = serde_json::to_string(&r).unwrap();
+        let issuance = serde_json::json!({
+            "satoshi_asset": 1000,
+            "satoshi_token": 1,
+            "contract": contract,
+        });
+        issuances.push_str(&format!(" --issuance '{issuance}'"));
+        contracts.push(contract);
+    }
+
+    // Each issuance needs a distinct input
+    fund(&server, &cli, "w1", 1_000_000);
+    let err = sh_err(&format!("{cli} wallet issue-many --wallet w1{issuances}"));
+    assert!(err.contains("Issuing 2 assets requires 2 inputs, but only 1 are available"));
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let r = sh(&format!("{cli} wallet issue-many --wallet w1{issuances}"));
+    let pset = get_str(&r, "pset");
+    let pset_unsigned: PartiallySignedTransaction = pset.parse().unwrap();
+
+    let r = sh(&format!("{cli} wallet pset-details --wallet w1 -p {pset}"));
+    let issuances = r.get("issuances").unwrap().as_array().unwrap();
+    assert_eq!(issuances.len(), 2);
+    let mut assets = vec![];
+    for (vin, (issuance, contract)) in issuances.iter().zip(contracts.iter()).enumerate() {
+        assert_eq!(issuance.get("vin").unwrap().as_u64().unwrap(), vin as u64);
+        let asset = get_str(issuance, "asset").to_string();
+        let asset_id = elements::AssetId::from_str(&asset).unwrap();
+        let metadata = pset_unsigned.get_asset_metadata(asset_id).unwrap().unwrap();
+        let metadata_contract: Value = serde_json::from_str(metadata.contract()).unwrap();
+        assert_eq!(
+            metadata_contract,
+            serde_json::from_str::<Value>(contract).unwrap()
+        );
+        assets.push(asset);
+    }
+    assert_ne!(assets[0], assets[1]);
+
+    complete(&cli, "w1", pset, &["s1"]);
+    for asset in assets.iter() {
+        assert_eq!(get_balance(&cli, "w1", asset), 1000);
+    }
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
 #[test]
 fn test_jade_emulator() {
     let (t, _tmp, cli, _params, server, _) = setup_cli(false);
@@ -979,8 +1194,12 @@ fn test_jade_emulator() {
     let identifier = result.get("identifier").unwrap().as_str().unwrap();
     assert_eq!(identifier, "e3ebcc79ebfedb4f2ae34406827dc1c5cb48e11f");
 
+    let err = sh_err(&format!(
+        "{cli} signer load-jade --signer emul --id {identifier} --emulator {jade_addr} --expected-fingerprint 00000000"
+    ));
+    assert!(err.contains("Signer fingerprint e3ebcc79 does not match the expected 00000000"));
     sh(&format!(
-        "{cli} signer load-jade --signer emul --id {identifier}  --emulator {jade_addr}"
+        "{cli} signer load-jade --signer emul --id {identifier}  --emulator {jade_addr} --expected-fingerprint e3ebcc79"
     ));
     let r = sh(&format!("{cli} signer details -s emul"));
     assert!(r.get("id").is_some());
@@ -1016,6 +1235,61 @@ fn test_jade_emulator() {
     t.join().unwrap();
 }
 
+#[test]
+fn test_ledger_emulator() {
+    let (t, _tmp, cli, params, _server, _) = setup_cli(false);
+
+    let docker = clients::Cli::default();
+    let container = docker.run(LedgerEmulator::new().unwrap());
+    let port = container.get_host_port_ipv4(LEDGER_EMULATOR_PORT);
+    let ledger_addr = format!("127.0.0.1:{}", port);
+
+    // Without the emulator address, physical devices are searched via USB HID
+    let err = sh_err(&format!("{cli} signer load-ledger --signer ledger"));
+    if !cfg!(feature = "serial") {
+        assert!(err.contains("Feature \"serial\" is disabled"));
+    }
+
+    let err = sh_err(&format!(
+        "{cli} signer load-ledger --signer ledger --emulator {ledger_addr} --expected-fingerprint 00000000"
+    ));
+    assert!(err.contains("Signer fingerprint f5acc2fd does not match the expected 00000000"));
+    let r = sh(&format!(
+        "{cli} signer load-ledger --signer ledger --emulator {ledger_addr} --expected-fingerprint f5acc2fd"
+    ));
+    assert_eq!(get_str(&r, "fingerprint"), "f5acc2fd");
+    assert_eq!(get_str(&r, "type"), "ledger");
+
+    let r = sh(&format!("{cli} signer details --signer ledger"));
+    let c = r.get("capabilities").unwrap();
+    assert!(c.get("sign_message").unwrap().as_bool().unwrap());
+    assert!(c.get("derive_xpub").unwrap().as_bool().unwrap());
+    assert!(c.get("slip77").unwrap().as_bool().unwrap());
+    assert!(!c.get("taproot").unwrap().as_bool().unwrap());
+
+    let r = sh(&format!("{cli} signer xpub --signer ledger --kind bip84"));
+    let keyorigin_xpub = get_str(&r, "keyorigin_xpub");
+    assert!(keyorigin_xpub.starts_with("[f5acc2fd/84h/1h/0h]tpub"));
+
+    // The signer is loaded again after a restart, connecting to the same emulator
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+    let t = {
+        let cli = cli.clone();
+        let params = params.clone();
+        std::thread::spawn(move || {
+            sh(&format!("{cli} server start {params}"));
+        })
+    };
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    let r = sh(&format!("{cli} signer xpub --signer ledger --kind bip84"));
+    assert_eq!(get_str(&r, "keyorigin_xpub"), keyorigin_xpub);
+
+    sh(&format!("{cli} server stop"));
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    t.join().unwrap();
+}
+
 #[test]
 fn test_commands() {
     let (t, _tmp, cli, _params, server, _) = setup_cli(false);
@@ -1128,6 +1402,12 @@ fn test_multisig() {
     let desc = r.get("descriptor").unwrap().as_str().unwrap();
     sh(&format!("{cli} wallet load --wallet multi -d {desc}"));
 
+    // Using the signers names gives the same keys
+    let r = sh(&format!("{cli} wallet multisig-desc --descriptor-blinding-key elip151 --kind wsh --threshold 2 --keyorigin-xpub {keyorigin_xpub1} --keyorigin-xpub {keyorigin_xpub2}"));
+    let desc_elip151 = get_str(&r, "descriptor").to_string();
+    let r = sh(&format!("{cli} wallet multisig-desc --descriptor-blinding-key elip151 --kind wsh --threshold 2 --signer s1 --signer s2"));
+    assert_eq!(get_str(&r, "descriptor"), desc_elip151);
+
     fund(&server, &cli, "multi", 1_000_000);
 
     let node_address = server.elementsd_getnewaddress();
@@ -1340,6 +1620,61 @@ fn test_registry_publish() {
     t.join().unwrap();
 }
 
+#[cfg_attr(
+    not(feature = "registry"),
+    ignore = "require registry `server` executable in path"
+)]
+#[test]
+fn test_reissue_from_registry() {
+    let (t, _tmp, cli, _params, server, _registry) = setup_cli(true);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let r = sh(&format!("{cli} asset contract --domain example.com --issuer-pubkey 035d0f7b0207d9cc68870abfef621692bce082084ed3ca0c1ae432dd12d889be01 --name example --ticker EXMP"));
+    let contract = serde_json::to_string(&r).unwrap();
+    let r = sh(&format!(
+        "{cli} wallet issue --wallet w1 --satoshi-asset 1000 --satoshi-token 1 --contract '{contract}'"
+    ));
+    let pset = get_str(&r, "pset");
+    let (asset, token) = asset_ids_from_issuance_pset(&cli, "w1", pset);
+    let r = sh(&format!("{cli} signer sign --signer s1 --pset {pset}"));
+    let pset_signed: PartiallySignedTransaction = get_str(&r, "pset").parse().unwrap();
+    sh(&format!(
+        "{cli} wallet broadcast --wallet w1 --pset {pset_signed}"
+    ));
+    server.elementsd_generate(2);
+    wait_ms(6_000); // otherwise registry may find the issuance tx unconfirmed, wait_tx is not enough
+    sh(&format!("{cli} server scan"));
+
+    let tx = serialize(&pset_signed.extract_tx().unwrap()).to_hex();
+    sh(&format!(
+        "{cli} asset insert --asset {asset} --contract '{contract}' --issuance-tx {tx}"
+    ));
+    sh(&format!("{cli} asset publish --asset {asset}"));
+
+    // Move the reissuance token to a wallet that did not issue the asset
+    sw_signer(&cli, "s2");
+    singlesig_wallet(&cli, "w2", "s2", "slip77", "wpkh");
+    fund(&server, &cli, "w2", 1_000_000);
+    let w2_addr = address(&cli, "w2");
+    let txid = send(&cli, "w1", &w2_addr, &token, 1, &["s1"]);
+    wait_tx(&cli, "w2", &txid);
+
+    // The issuance tx is not in the local state anymore, it's fetched from the registry
+    sh(&format!("{cli} asset remove --asset {asset}"));
+    sh(&format!("{cli} asset remove --asset {token}"));
+    let r = sh(&format!(
+        "{cli} wallet reissue --wallet w2 --asset {asset} --satoshi-asset 1"
+    ));
+    complete(&cli, "w2", get_str(&r, "pset"), &["s2"]);
+    assert_eq!(1, get_balance(&cli, "w2", &asset));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
 #[test]
 fn test_elip151() {
     let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
@@ -1448,79 +1783,1619 @@ fn test_start_errors() {
 }
 
 #[test]
-fn test_send_all() {
+fn test_burn_all() {
     let (t, _tmp, cli, _params, server, _) = setup_cli(false);
 
-    sw_signer(&cli, "sw");
-    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
-    let signers = &["sw"];
-
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
     fund(&server, &cli, "w1", 1_000_000);
 
-    let node_address = server.elementsd_getnewaddress();
     let r = sh(&format!(
-        "{cli} wallet drain -w w1 --address {node_address}"
+        "{cli} wallet issue --wallet w1 --satoshi-asset 1000 --satoshi-token 1"
     ));
-    complete(&cli, "w1", get_str(&r, "pset"), signers);
-    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
-    assert_eq!(get_balance(&cli, "w1", policy_asset), 0);
+    let pset = get_str(&r, "pset");
+    let (asset, _token) = asset_ids_from_issuance_pset(&cli, "w1", pset);
+    complete(&cli, "w1", pset, &["s1"]);
+    assert_eq!(get_balance(&cli, "w1", &asset), 1000);
+
+    let r = sh(&format!(
+        "{cli} wallet burn -w w1 --asset {asset} --burn-all"
+    ));
+    complete(&cli, "w1", get_str(&r, "pset"), &["s1"]);
+    sh(&format!("{cli} server scan"));
+    let r = sh(&format!("{cli} wallet balance --wallet w1"));
+    let balance = r.get("balance").unwrap().as_object().unwrap();
+    assert_eq!(balance.get(&asset).and_then(|v| v.as_u64()).unwrap_or(0), 0);
+
+    // Nothing left to burn
+    let err = sh_err(&format!(
+        "{cli} wallet burn -w w1 --asset {asset} --burn-all"
+    ));
+    assert!(err.contains("No balance of asset"));
 
     sh(&format!("{cli} server stop"));
     t.join().unwrap();
 }
 
 #[test]
-fn test_ct_discount() {
+fn test_pegout() {
     let (t, _tmp, cli, _params, server, _) = setup_cli(false);
 
-    sw_signer(&cli, "sw");
-    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
-    let signers = &["sw"];
-
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
     fund(&server, &cli, "w1", 1_000_000);
 
-    let address = server.elementsd_getnewaddress();
-    let sats = 1_000;
-    let recipient = format!(" --recipient {address}:{sats}");
-
-    // Without CT discount
-    let r = sh(&format!("{cli} wallet send -w w1 {recipient}"));
-    let pset = get_str(&r, "pset");
-    complete(&cli, "w1", pset, signers);
-    let r = sh(&format!("{cli} wallet pset-details --wallet w1 -p {pset}"));
-    let fee_no_ct_discount = r.get("fee").unwrap().as_u64().unwrap();
-
-    // With CT discount
+    let script = elements::bitcoin::ScriptBuf::new();
+    let mainchain_address =
+        elements::bitcoin::Address::p2wsh(&script, elements::bitcoin::Network::Regtest);
     let r = sh(&format!(
-        "{cli} wallet send -w w1 {recipient} --enable-ct-discount"
+        "{cli} wallet pegout -w w1 --mainchain-address {mainchain_address} --satoshi 10000"
     ));
-    let pset = get_str(&r, "pset");
-    complete(&cli, "w1", pset, signers);
-    let r = sh(&format!("{cli} wallet pset-details --wallet w1 -p {pset}"));
-    let fee_ct_discount = r.get("fee").unwrap().as_u64().unwrap();
+    complete(&cli, "w1", get_str(&r, "pset"), &["s1"]);
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    assert!(get_balance(&cli, "w1", policy_asset) < 1_000_000 - 10_000);
 
-    assert_eq!(fee_no_ct_discount, 250);
-    assert_eq!(fee_ct_discount, 26);
+    let err = sh_err(&format!(
+        "{cli} wallet pegout -w w1 --mainchain-address invalid --satoshi 10000"
+    ));
+    assert!(err.contains("Invalid mainchain address"));
 
     sh(&format!("{cli} server stop"));
     t.join().unwrap();
 }
 
 #[test]
-fn test_amp2() {
+fn test_claim_pegin() {
     let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
 
-    sw_signer(&cli, "sw");
-    let err = sh_err(&format!("{cli} amp2 descriptor -s sw"));
-    assert!(err.contains("AMP2 methods are not available for this network"));
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
 
-    let err = sh_err(&format!("{cli} amp2 register -s sw"));
-    assert!(err.contains("AMP2 methods are not available for this network"));
+    let claim_script = "0014d0c4a3ef09e997b6e99e397e518fe3e41a118ca1";
+    let err = sh_err(&format!(
+        "{cli} wallet claim-pegin -w w1 --bitcoin-tx 0 --vout 0 --txout-proof 00 --claim-script {claim_script}"
+    ));
+    assert!(err.contains("hex"), "{err}");
 
-    let err = sh_err(&format!("{cli} amp2 cosign -p fake_pset"));
-    assert!(err.contains("AMP2 methods are not available for this network"));
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
 
-    // TODO: proper e2e tests with regtest AMP2
+#[test]
+fn test_claim_pegin_custom_fed_peg_script() {
+    // In regtest the fed peg script is not in the block headers, it must be set
+    let server = lwk_test_util::setup_with_bitcoind();
+    let fed_peg_script = server.elementsd_fedpegscript().to_hex_string();
+    let extra_params = format!("--fed-peg-script {fed_peg_script}");
+    let (t, _tmp, cli, _params, server, _) = setup_cli_with_server(server, false, &extra_params);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+
+    // Send bitcoin to the pegin address committing to a wallet script
+    let claim_script = Address::from_str(&address(&cli, "w1"))
+        .unwrap()
+        .script_pubkey()
+        .as_bytes()
+        .to_hex();
+    let mainchain_address = server.elementsd_tweakfedpegscript(&claim_script);
+    server.bitcoind_generate(101);
+    let satoshi = 100_000_000;
+    let txid = server.bitcoind_sendtoaddress(&mainchain_address, satoshi);
+    let tx = server.bitcoind_getrawtransaction(txid);
+    let vout = tx
+        .output
+        .iter()
+        .position(|o| o.script_pubkey == mainchain_address.script_pubkey())
+        .unwrap();
+    server.bitcoind_generate(101);
+    let proof = server.bitcoind_gettxoutproof(txid);
+    server.elementsd_generate(2);
+
+    let bitcoin_tx = elements::bitcoin::consensus::encode::serialize_hex(&tx);
+    let r = sh(&format!(
+        "{cli} wallet claim-pegin -w w1 --bitcoin-tx {bitcoin_tx} --vout {vout} --txout-proof {proof} --claim-script {claim_script}"
+    ));
+    let pset: PartiallySignedTransaction = get_str(&r, "pset").parse().unwrap();
+    assert!(pset.inputs()[0].is_pegin());
+    complete(&cli, "w1", &pset.to_string(), &["s1"]);
+
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let balance = get_balance(&cli, "w1", policy_asset);
+    assert!(balance > 0 && balance < satoshi, "{balance}");
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_pset_details_unsupported_inputs() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let node_address = server.elementsd_getnewaddress();
+    let r = sh(&format!(
+        "{cli} wallet send -w w1 --recipient {node_address}:1000"
+    ));
+    let pset: PartiallySignedTransaction = get_str(&r, "pset").parse().unwrap();
+    let r = sh(&format!("{cli} wallet pset-details -w w1 -p {pset}"));
+    let fee = r.get("fee").unwrap().as_u64().unwrap();
+    let vin = pset.inputs().len();
+
+    // Add an input we don't own with a blinded issuance
+    let secp = elements::secp256k1_zkp::Secp256k1::new();
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let asset = elements::AssetId::from_str(policy_asset).unwrap();
+    let generator = elements::secp256k1_zkp::Generator::new_unblinded(&secp, asset.into_tag());
+    let comm = elements::secp256k1_zkp::PedersenCommitment::new_unblinded(&secp, 10, generator);
+    let mut input = pset.inputs()[0].clone();
+    input.previous_output_index += 100;
+    input.bip32_derivation.clear();
+    input.issuance_value_comm = Some(comm);
+    let mut pset_issuance = pset.clone();
+    pset_issuance.add_input(input);
+
+    let r = sh(&format!(
+        "{cli} wallet pset-details -w w1 -p {pset_issuance}"
+    ));
+    let warnings = get_str(&r, "warnings");
+    assert!(warnings.contains(&format!("Input #{vin} has a blinded issuance")));
+    assert_eq!(r.get("fee").unwrap().as_u64().unwrap(), fee);
+    let balance = r.get("balance").unwrap().as_object().unwrap();
+    assert!(balance.get(policy_asset).unwrap().as_i64().unwrap() < 0);
+    let issuances = r.get("issuances").unwrap().as_array().unwrap();
+    assert_eq!(issuances.len(), 1);
+    assert!(issuances[0]
+        .get("is_confidential")
+        .unwrap()
+        .as_bool()
+        .unwrap());
+
+    // Add a pegin input with a blinded value
+    let mut input = pset.inputs()[0].clone();
+    input.previous_output_index |= 1 << 30;
+    input.bip32_derivation.clear();
+    let mut pset_pegin = pset.clone();
+    pset_pegin.add_input(input);
+
+    let r = sh(&format!("{cli} wallet pset-details -w w1 -p {pset_pegin}"));
+    let warnings = get_str(&r, "warnings");
+    assert!(warnings.contains(&format!("Input #{vin} has a pegin")));
+    assert_eq!(r.get("fee").unwrap().as_u64().unwrap(), fee);
+    assert!(r.get("balance").unwrap().as_object().unwrap().is_empty());
+    assert_eq!(get_len(&r, "missing_signatures_from"), 1);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_send_all() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
+    let signers = &["sw"];
+
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let node_address = server.elementsd_getnewaddress();
+    let r = sh(&format!(
+        "{cli} wallet drain -w w1 --address {node_address}"
+    ));
+    complete(&cli, "w1", get_str(&r, "pset"), signers);
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    assert_eq!(get_balance(&cli, "w1", policy_asset), 0);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_diagnose() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
+
+    let r = sh(&format!("{cli} wallet diagnose -w w1"));
+    assert_eq!(get_len(&r, "txids"), 0);
+    assert!(r.get("external_index").is_none());
+    assert!(r.get("suggestion").is_none());
+
+    let r = sh(&format!("{cli} wallet scan-scope -w w1"));
+    assert_eq!(r.get("external_scanned").unwrap().as_u64().unwrap(), 20);
+    assert_eq!(r.get("internal_scanned").unwrap().as_u64().unwrap(), 20);
+
+    // Fund an address within the gap limit, the wallet sees it
+    let r = sh(&format!("{cli} wallet address -w w1 --index 5"));
+    let addr = Address::from_str(get_str(&r, "address")).unwrap();
+    server.elementsd_sendtoaddress(&addr, 10_000, None);
+    server.elementsd_generate(1);
+    sh(&format!("{cli} server scan"));
+    assert_eq!(txs(&cli, "w1").len(), 1);
+
+    // Fund an address beyond the gap limit, counted from the last used address
+    let r = sh(&format!("{cli} wallet address -w w1 --index 30"));
+    let addr = Address::from_str(get_str(&r, "address")).unwrap();
+    let txid = server.elementsd_sendtoaddress(&addr, 10_000, None);
+    server.elementsd_generate(1);
+    sh(&format!("{cli} server scan"));
+    assert_eq!(txs(&cli, "w1").len(), 1);
+
+    let r = sh(&format!("{cli} wallet diagnose -w w1"));
+    assert_eq!(r.get("external_index").unwrap().as_u64().unwrap(), 30);
+    assert!(r.get("internal_index").is_none());
+    assert_eq!(get_len(&r, "txids"), 1);
+    assert_eq!(
+        r.get("txids").unwrap()[0].as_str().unwrap(),
+        txid.to_string()
+    );
+    assert!(get_str(&r, "suggestion").contains("at least 25"), "{r}");
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_load_change_descriptor() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    singlesig_wallet(&cli, "w1", "sw", "slip77", "shwpkh");
+    let r = sh(&format!(
+        "{cli} signer singlesig-desc -s sw --descriptor-blinding-key slip77 --kind wpkh"
+    ));
+    let desc = get_str(&r, "descriptor").split('#').next().unwrap();
+    let external = desc.replace("/<0;1>/*", "/0/*");
+    let internal = desc.replace("/<0;1>/*", "/1/*");
+
+    let err = sh_err(&format!(
+        "{cli} wallet load -w w2 -d '{internal}' --change-descriptor '{external}'"
+    ));
+    assert!(err.contains("External and internal descriptors must have the same keys"));
+
+    let r = sh(&format!(
+        "{cli} wallet load -w w2 -d '{external}' --change-descriptor '{internal}'"
+    ));
+    assert!(get_str(&r, "descriptor").contains("/<0;1>/*"));
+
+    // Funds received on the external chain
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    fund(&server, &cli, "w2", 1_000_000);
+    assert_eq!(get_balance(&cli, "w2", policy_asset), 1_000_000);
+
+    // The change goes to the internal chain and it's found by the wallet
+    let addr = address(&cli, "w1");
+    send(&cli, "w2", &addr, policy_asset, 10_000, &["sw"]);
+    let balance = get_balance(&cli, "w2", policy_asset);
+    assert!(balance > 0 && balance < 990_000);
+    assert_eq!(txs(&cli, "w2").len(), 2);
+
+    // Change descriptor with a different script
+    sw_signer(&cli, "sw2");
+    let r = sh(&format!(
+        "{cli} signer singlesig-desc -s sw2 --descriptor-blinding-key slip77 --kind wpkh"
+    ));
+    let desc = get_str(&r, "descriptor").split('#').next().unwrap();
+    let external = desc.replace("/<0;1>/*", "/0/*");
+    let internal = format!(
+        "{})",
+        desc.replace("/<0;1>/*", "/1/*")
+            .replace("elwpkh(", "elsh(wpkh(")
+    );
+    let r = sh(&format!(
+        "{cli} wallet load -w w3 -d '{external}' --change-descriptor '{internal}'"
+    ));
+    let descriptor = get_str(&r, "descriptor");
+    assert!(descriptor.contains("elwpkh("));
+    assert!(descriptor.contains("elsh(wpkh("));
+
+    fund(&server, &cli, "w3", 1_000_000);
+    assert_eq!(get_balance(&cli, "w3", policy_asset), 1_000_000);
+
+    // The change goes to the separate change descriptor and it's found by the wallet
+    send(&cli, "w3", &addr, policy_asset, 10_000, &["sw2"]);
+    let balance = get_balance(&cli, "w3", policy_asset);
+    assert!(balance > 0 && balance < 990_000);
+    assert_eq!(txs(&cli, "w3").len(), 2);
+
+    // A change descriptor with other keys is rejected
+    let r = sh(&format!(
+        "{cli} signer singlesig-desc -s sw --descriptor-blinding-key slip77 --kind shwpkh"
+    ));
+    let other_keys = get_str(&r, "descriptor")
+        .split('#')
+        .next()
+        .unwrap()
+        .replace("/<0;1>/*", "/1/*");
+    let err = sh_err(&format!(
+        "{cli} wallet load -w w4 -d '{external}' --change-descriptor '{other_keys}'"
+    ));
+    assert!(err.contains("External and internal descriptors must have the same keys"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_signer_account_descriptor() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    let account_desc = |account: u32| {
+        let r = sh(&format!(
+            "{cli} signer account-desc -s sw --account {account} --descriptor-blinding-key slip77 --kind wpkh"
+        ));
+        get_str(&r, "descriptor").to_string()
+    };
+    let desc0 = account_desc(0);
+    let desc1 = account_desc(1);
+    assert_ne!(desc0, desc1);
+    assert!(desc0.contains("/84h/1h/0h]"));
+    assert!(desc1.contains("/84h/1h/1h]"));
+
+    // Account 0 is the default singlesig descriptor
+    let r = sh(&format!(
+        "{cli} signer singlesig-desc -s sw --descriptor-blinding-key slip77 --kind wpkh"
+    ));
+    assert_eq!(get_str(&r, "descriptor"), desc0);
+
+    sh(&format!("{cli} wallet load -w w0 -d {desc0}"));
+    sh(&format!("{cli} wallet load -w w1 -d {desc1}"));
+    assert_ne!(address(&cli, "w0"), address(&cli, "w1"));
+
+    // The signer can spend from other accounts
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    fund(&server, &cli, "w1", 1_000_000);
+    let addr = address(&cli, "w0");
+    send(&cli, "w1", &addr, policy_asset, 10_000, &["sw"]);
+    assert_eq!(get_balance(&cli, "w0", policy_asset), 10_000);
+
+    let err = sh_err(&format!(
+        "{cli} signer account-desc -s sw --account 2147483648 --descriptor-blinding-key slip77 --kind wpkh"
+    ));
+    // Accounts are hardened derivation steps, so they must be below 2^31
+    assert!(err.contains("InvalidChildNumber(2147483648)"), "{err}");
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+/// Proxy to an electrum server, forwarding the first request of each connection while the
+/// following ones are held as long as `stall` is set, counting them in `stalled`
+fn stalling_electrum_proxy(
+    electrum_url: &str,
+    stall: Arc<AtomicBool>,
+    stalled: Arc<AtomicUsize>,
+) -> String {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let electrum_url = electrum_url.to_string();
+    std::thread::spawn(move || {
+        for client in listener.incoming() {
+            let Ok(client) = client else { continue };
+            let mut upstream = std::net::TcpStream::connect(&electrum_url).unwrap();
+            let mut upstream_reader = upstream.try_clone().unwrap();
+            let mut client_writer = client.try_clone().unwrap();
+            std::thread::spawn(move || std::io::copy(&mut upstream_reader, &mut client_writer));
+            let (stall, stalled) = (stall.clone(), stalled.clone());
+            std::thread::spawn(move || {
+                for (i, line) in BufReader::new(client).lines().enumerate() {
+                    let Ok(line) = line else { break };
+                    if i > 0 && stall.load(Ordering::SeqCst) {
+                        stalled.fetch_add(1, Ordering::SeqCst);
+                        while stall.load(Ordering::SeqCst) {
+                            std::thread::sleep(Duration::from_millis(10));
+                        }
+                    }
+                    if writeln!(upstream, "{line}").is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    addr
+}
+
+#[test]
+fn test_scan_does_not_hold_state_lock() {
+    let (t, _tmp, cli, params, server, _) = setup_cli(false);
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+
+    // Restart the server connecting to electrum through the proxy
+    let stall = Arc::new(AtomicBool::new(false));
+    let stalled = Arc::new(AtomicUsize::new(0));
+    let electrum_url = &server.electrs.electrum_url;
+    let proxy = stalling_electrum_proxy(electrum_url, stall.clone(), stalled.clone());
+    let params = params.replace(electrum_url.as_str(), &proxy);
+    let cli = format!("cli --addr {} -n regtest", get_available_addr().unwrap());
+    let t = {
+        let cli = cli.clone();
+        std::thread::spawn(move || {
+            sh(&format!(
+                "{cli} server start --scanning-interval 1 {params}"
+            ));
+        })
+    };
+    std::thread::sleep(Duration::from_millis(100));
+
+    sw_signer(&cli, "sw");
+    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    // Hold the electrum requests of the next scan, made after reading the wallet state
+    stall.store(true, Ordering::SeqCst);
+    let start = Instant::now();
+    while stalled.load(Ordering::SeqCst) == 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(60),
+            "scan not started"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // While the scan is in progress the wallet methods are served
+    let (sender, receiver) = std::sync::mpsc::channel();
+    {
+        let cli = cli.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(sh(&format!("{cli} wallet balance -w w1")));
+        });
+    }
+    let r = receiver
+        .recv_timeout(Duration::from_secs(30))
+        .expect("the state lock is held during the scan");
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let balance = r.get("balance").unwrap().get(policy_asset).unwrap();
+    assert_eq!(balance.as_u64().unwrap(), 1_000_000);
+
+    // Once the electrum requests are released the scan completes
+    stall.store(false, Ordering::SeqCst);
+    sh(&format!("{cli} server scan"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_parse_payment() {
+    let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
+    let addr = address(&cli, "w1");
+
+    let r = sh(&format!("{cli} parse-payment {addr}"));
+    assert_eq!(get_str(&r, "kind"), "liquid_address");
+    assert_eq!(get_str(&r, "address"), addr);
+
+    let asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let uri = format!("liquidnetwork:{addr}?amount=0.001&assetid={asset}");
+    let r = sh(&format!("{cli} parse-payment '{uri}'"));
+    assert_eq!(get_str(&r, "kind"), "liquid_bip21");
+    assert_eq!(get_str(&r, "address"), addr);
+    assert_eq!(r.get("amount").unwrap().as_u64().unwrap(), 100_000);
+    assert_eq!(get_str(&r, "asset"), asset);
+    assert!(r.get("invoice").is_none());
+    assert!(r.get("label").is_none());
+
+    let uri = format!("liquidnetwork:{addr}?assetid={asset}&label=Shop&message=Order%2042");
+    let r = sh(&format!("{cli} parse-payment '{uri}'"));
+    assert_eq!(get_str(&r, "kind"), "liquid_bip21");
+    assert!(r.get("amount").is_none());
+    assert_eq!(get_str(&r, "asset"), asset);
+    assert_eq!(get_str(&r, "label"), "Shop");
+    assert_eq!(get_str(&r, "message"), "Order 42");
+
+    let invoice = "lnbcrt10u1pjq3xyzpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypq";
+    let r = sh(&format!("{cli} parse-payment lightning:{invoice}"));
+    assert_eq!(get_str(&r, "kind"), "lightning_invoice");
+    assert_eq!(get_str(&r, "invoice"), invoice);
+    assert_eq!(r.get("amount").unwrap().as_u64().unwrap(), 1_000);
+    assert!(r.get("address").is_none());
+
+    // The server is on regtest
+    let testnet_addr = "tlq1qq2xvpcvfup5j8zscjq05u2wxxjcyewk7979f3mmz5l7uw5pqmx6xf5xy50hsn6vhkm5euwt72x878eq6zxx2z58hd7zrsg9qn";
+    let err = sh_err(&format!("{cli} parse-payment liquidnetwork:{testnet_addr}"));
+    assert!(err.contains("Payment is for a different network than the server"));
+    let err = sh_err(&format!("{cli} parse-payment lnbc10u1pjq3xyz"));
+    assert!(err.contains("Payment is for a different network than the server"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_send_to_payment() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
+    singlesig_wallet(&cli, "w2", "sw", "slip77", "shwpkh");
+    let signers = &["sw"];
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let addr = address(&cli, "w2");
+    let uri = format!("liquidnetwork:{addr}?amount=0.0001&assetid={policy_asset}");
+    let r = sh(&format!("{cli} wallet send-to-payment -w w1 --uri '{uri}'"));
+    complete(&cli, "w1", get_str(&r, "pset"), signers);
+    assert_eq!(get_balance(&cli, "w2", policy_asset), 10_000);
+
+    // Without amount in the URI
+    let addr = address(&cli, "w2");
+    let err = sh_err(&format!("{cli} wallet send-to-payment -w w1 --uri {addr}"));
+    assert!(err.contains("an amount override is required"));
+    let r = sh(&format!(
+        "{cli} wallet send-to-payment -w w1 --uri {addr} --amount 5000"
+    ));
+    complete(&cli, "w1", get_str(&r, "pset"), signers);
+    assert_eq!(get_balance(&cli, "w2", policy_asset), 15_000);
+
+    let invoice = "lnbcrt10u1pjq3xyzpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypq";
+    let err = sh_err(&format!(
+        "{cli} wallet send-to-payment -w w1 --uri {invoice}"
+    ));
+    assert!(err.contains("use a Boltz swap to pay it"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_ct_discount() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    singlesig_wallet(&cli, "w1", "sw", "slip77", "wpkh");
+    let signers = &["sw"];
+
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let address = server.elementsd_getnewaddress();
+    let sats = 1_000;
+    let recipient = format!(" --recipient {address}:{sats}");
+
+    // Without CT discount
+    let r = sh(&format!("{cli} wallet send -w w1 {recipient}"));
+    let pset = get_str(&r, "pset");
+    complete(&cli, "w1", pset, signers);
+    let r = sh(&format!("{cli} wallet pset-details --wallet w1 -p {pset}"));
+    let fee_no_ct_discount = r.get("fee").unwrap().as_u64().unwrap();
+
+    // With CT discount
+    let r = sh(&format!(
+        "{cli} wallet send -w w1 {recipient} --enable-ct-discount"
+    ));
+    let pset = get_str(&r, "pset");
+    complete(&cli, "w1", pset, signers);
+    let r = sh(&format!("{cli} wallet pset-details --wallet w1 -p {pset}"));
+    let fee_ct_discount = r.get("fee").unwrap().as_u64().unwrap();
+
+    assert_eq!(fee_no_ct_discount, 250);
+    assert_eq!(fee_ct_discount, 26);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_amp2() {
+    let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
+
+    sw_signer(&cli, "sw");
+    let err = sh_err(&format!("{cli} amp2 descriptor -s sw"));
+    assert!(err.contains("AMP2 methods are not available for this network"));
+
+    let err = sh_err(&format!("{cli} amp2 register -s sw"));
+    assert!(err.contains("AMP2 methods are not available for this network"));
+
+    let err = sh_err(&format!("{cli} amp2 cosign -p fake_pset"));
+    assert!(err.contains("AMP2 methods are not available for this network"));
+
+    let err = sh_err(&format!("{cli} amp2 cosign-status -p fake_id"));
+    assert!(err.contains("AMP2 methods are not available for this network"));
+
+    // TODO: proper e2e tests with regtest AMP2
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_signer_xpub_kinds() {
+    let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
+
+    let r = sh(&format!("{cli} signer xpub-kinds"));
+    let kinds = r.get("kinds").unwrap().as_array().unwrap();
+    let names: Vec<_> = kinds.iter().map(|k| get_str(k, "kind")).collect();
+    assert_eq!(names, ["bip84", "bip49", "bip87"]);
+    assert_eq!(get_str(&kinds[0], "path"), "m/84h/<coin_type>h/0h");
+    assert!(kinds[0].get("singlesig").unwrap().as_bool().unwrap());
+    assert!(kinds[2].get("multisig").unwrap().as_bool().unwrap());
+    assert!(!kinds[2].get("singlesig").unwrap().as_bool().unwrap());
+
+    // Every listed kind is accepted by signer xpub
+    sw_signer(&cli, "sw");
+    for kind in names {
+        let r = sh(&format!("{cli} signer xpub --signer sw --kind {kind}"));
+        assert!(!get_str(&r, "keyorigin_xpub").is_empty());
+    }
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_meta() {
+    let (t, _tmp, cli, params, _server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    singlesig_wallet(&cli, "w2", "s1", "slip77", "shwpkh");
+
+    let get_meta = |wallet: &str, namespace: &str| {
+        let r = sh(&format!(
+            "{cli} wallet get-meta -w {wallet} --namespace {namespace}"
+        ));
+        r.get("entries").unwrap().as_object().unwrap().clone()
+    };
+    assert!(get_meta("w1", "shop").is_empty());
+
+    sh(&format!(
+        "{cli} wallet set-meta -w w1 --namespace shop -k order --value 1234"
+    ));
+    sh(&format!(
+        "{cli} wallet set-meta -w w1 --namespace shop -k customer --value alice"
+    ));
+    sh(&format!(
+        "{cli} wallet set-meta -w w1 --namespace other -k order --value 5678"
+    ));
+    let entries = get_meta("w1", "shop");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries["order"], "1234");
+    assert_eq!(get_meta("w1", "other")["order"], "5678");
+    assert!(get_meta("w2", "shop").is_empty());
+
+    let r = sh(&format!(
+        "{cli} wallet get-meta -w w1 --namespace shop -k customer"
+    ));
+    assert_eq!(r.get("entries").unwrap().as_object().unwrap().len(), 1);
+
+    // Remove an entry
+    sh(&format!(
+        "{cli} wallet set-meta -w w1 --namespace other -k order --value ''"
+    ));
+    assert!(get_meta("w1", "other").is_empty());
+
+    let long = "x".repeat(2000);
+    let err = sh_err(&format!(
+        "{cli} wallet set-meta -w w1 --namespace shop -k note --value {long}"
+    ));
+    assert!(err.contains("Wallet metadata limit exceeded"));
+
+    let err = sh_err(&format!(
+        "{cli} wallet set-meta -w w3 --namespace shop -k order --value 1"
+    ));
+    assert!(err.contains("Wallet 'w3' does not exist"));
+
+    // Metadata survives a restart
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+    let t = {
+        let cli = cli.clone();
+        let params = params.clone();
+        std::thread::spawn(move || {
+            sh(&format!("{cli} server start {params}"));
+        })
+    };
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    let entries = get_meta("w1", "shop");
+    assert_eq!(entries["order"], "1234");
+    assert_eq!(entries["customer"], "alice");
+
+    // And it's cleared on unload
+    sh(&format!("{cli} wallet unload --wallet w1"));
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    assert!(get_meta("w1", "shop").is_empty());
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_txs_paging() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    for _ in 0..5 {
+        fund(&server, &cli, "w1", 100_000);
+    }
+    let all = txs(&cli, "w1");
+    assert_eq!(all.len(), 5);
+
+    let addr: std::net::SocketAddr = cli.split(' ').nth(2).unwrap().parse().unwrap();
+    let client = lwk_app::Client::new(addr).unwrap();
+
+    let mut out = vec![];
+    let pages = lwk_cli::stream_wallet_txs(&client, "w1", false, false, 2, &mut out).unwrap();
+    assert_eq!(pages, 3);
+    let lines: Vec<Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    let txids = |v: &[Value]| -> Vec<String> {
+        v.iter().map(|tx| get_str(tx, "txid").to_string()).collect()
+    };
+    assert_eq!(txids(&lines), txids(&all));
+
+    // A page never contains more than the requested transactions
+    let r = client
+        .wallet_txs_page("w1".into(), false, false, Some(4), Some(2))
+        .unwrap();
+    assert_eq!(r.txs.len(), 1);
+
+    let err = lwk_cli::stream_wallet_txs(&client, "w1", false, false, 0, &mut vec![]).unwrap_err();
+    assert!(err.to_string().contains("Page size"));
+
+    // The streaming mode doesn't return a value, rows are written to stdout
+    let r = sh(&format!("{cli} wallet txs --wallet w1 --page-size 2"));
+    assert!(r.is_null());
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_preview_tx() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let addr = address(&cli, "w1");
+    let recipient = format!("--recipient {addr}:1000:{policy_asset}");
+    let r = sh(&format!("{cli} wallet send -w w1 {recipient}"));
+    let pset = get_str(&r, "pset").to_string();
+
+    // The PSET must be complete
+    let err = sh_err(&format!("{cli} wallet preview-tx -w w1 --pset {pset}"));
+    assert!(err.contains("could not satisfy"), "{err}");
+
+    let r = sh(&format!("{cli} signer sign -s s1 --pset {pset}"));
+    let pset = get_str(&r, "pset").to_string();
+    let preview = sh(&format!("{cli} wallet preview-tx -w w1 --pset {pset}"));
+    let vsize = preview.get("vsize").unwrap().as_u64().unwrap();
+    let discount_vsize = preview.get("discount_vsize").unwrap().as_u64().unwrap();
+    assert!(discount_vsize < vsize);
+
+    let r = sh(&format!("{cli} wallet broadcast -w w1 --pset {pset}"));
+    let txid = get_str(&r, "txid");
+    assert_eq!(get_str(&preview, "txid"), txid);
+    wait_tx(&cli, "w1", txid);
+
+    let r = sh(&format!("{cli} wallet tx -w w1 --txid {txid}"));
+    assert_eq!(get_str(&r, "tx"), get_str(&preview, "hex"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_rename_keeps_cache() {
+    // The wallet cache directory is derived from the descriptor, not from the wallet name,
+    // so loading the same descriptor under another name reuses the synced state.
+    let (t, tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    let r = sh(&format!(
+        "{cli} signer singlesig-desc -s s1 --descriptor-blinding-key slip77 --kind wpkh"
+    ));
+    let desc = get_str(&r, "descriptor").to_string();
+    sh(&format!("{cli} wallet load -w old -d {desc}"));
+    fund(&server, &cli, "old", 1_000_000);
+
+    let enc_cache = tmp.path().join("liquid-regtest").join("enc_cache");
+    let dirs = || fs::read_dir(&enc_cache).unwrap().count();
+    assert_eq!(dirs(), 1);
+
+    sh(&format!("{cli} wallet unload -w old"));
+    sh(&format!("{cli} wallet load -w new -d {desc}"));
+    assert_eq!(dirs(), 1);
+
+    // The transactions are available without waiting for a scan
+    let r = sh(&format!("{cli} wallet balance -w new"));
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let balance = r.get("balance").unwrap().get(policy_asset).unwrap();
+    assert_eq!(balance.as_u64().unwrap(), 1_000_000);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_pset_convert_version() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let addr = address(&cli, "w1");
+    let recipient = format!("--recipient {addr}:1000:{policy_asset}");
+    let r = sh(&format!("{cli} wallet send -w w1 {recipient}"));
+    let pset = get_str(&r, "pset").to_string();
+
+    let r = sh(&format!(
+        "{cli} pset-convert-version --pset {pset} --target 2"
+    ));
+    assert_eq!(r.get("from_version").unwrap().as_u64().unwrap(), 2);
+    assert_eq!(r.get("version").unwrap().as_u64().unwrap(), 2);
+    assert_eq!(get_str(&r, "pset"), pset);
+
+    let err = sh_err(&format!(
+        "{cli} pset-convert-version --pset {pset} --target 0"
+    ));
+    assert!(err.contains("Cannot convert PSET from version 2 to version 0"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_pset_blinding_status() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let addr = address(&cli, "w1");
+    let recipient = format!("--recipient {addr}:1000:{policy_asset}");
+    let r = sh(&format!("{cli} wallet send -w w1 {recipient}"));
+    let pset = get_str(&r, "pset").to_string();
+
+    // Blinded PSET
+    let r = sh(&format!("{cli} pset-blinding-status --pset {pset}"));
+    assert!(r.get("all_outputs_blinded").unwrap().as_bool().unwrap());
+    let missing = r.get("missing_output_indices").unwrap().as_array().unwrap();
+    assert!(missing.is_empty());
+
+    // Unblinded PSET, the outputs with a blinding key have no commitments
+    let mut pset = PartiallySignedTransaction::from_str(&pset).unwrap();
+    let mut expected = vec![];
+    for (idx, output) in pset.outputs_mut().iter_mut().enumerate() {
+        if output.blinding_key.is_some() {
+            output.asset_comm = None;
+            output.amount_comm = None;
+            expected.push(idx as u64);
+        }
+    }
+    assert!(!expected.is_empty());
+    let r = sh(&format!("{cli} pset-blinding-status --pset {pset}"));
+    assert!(!r.get("all_outputs_blinded").unwrap().as_bool().unwrap());
+    let missing: Vec<_> = r
+        .get("missing_output_indices")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i.as_u64().unwrap())
+        .collect();
+    assert_eq!(missing, expected);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+/// Minimal HTTP GET returning the status code and the ETag header
+fn http_get(addr: &str, path: &str, if_none_match: Option<&str>) -> (u16, Option<String>) {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    let mut request = format!("GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n");
+    if let Some(etag) = if_none_match {
+        request.push_str(&format!("If-None-Match: {etag}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+    let etag = response
+        .lines()
+        .take_while(|l| !l.is_empty())
+        .find_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            name.eq_ignore_ascii_case("etag")
+                .then(|| value.trim().to_string())
+        });
+    (status, etag)
+}
+
+/// Minimal HTTP POST of a JSON body returning the response headers and body
+fn http_post(addr: &str, body: &str, accept_encoding: Option<&str>) -> (String, Vec<u8>) {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    let mut request = format!(
+        "POST / HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+        body.len()
+    );
+    if let Some(encoding) = accept_encoding {
+        request.push_str(&format!("Accept-Encoding: {encoding}\r\n"));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = vec![];
+    stream.read_to_end(&mut response).unwrap();
+
+    let end = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+    let headers = String::from_utf8(response[..end].to_vec()).unwrap();
+    (headers, response[end + 4..].to_vec())
+}
+
+#[test]
+fn test_server_compression() {
+    let (t, _tmp, cli, params, _server, _) = setup_cli(false);
+    let addr = cli.split(' ').nth(2).unwrap().to_string();
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"schema","params":{"method":"wallet_txs","direction":"response"}}"#;
+    let is_gzip = |headers: &str| {
+        headers
+            .to_ascii_lowercase()
+            .contains("content-encoding: gzip")
+    };
+
+    // Compression is disabled by default
+    let (headers, plain) = http_post(&addr, body, Some("gzip"));
+    assert!(!is_gzip(&headers), "{headers}");
+    assert!(
+        plain.len() >= 1024,
+        "the response is too small to be compressed"
+    );
+
+    // Restart the server with compression
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+    let t = {
+        let cli = cli.clone();
+        let params = params.clone();
+        std::thread::spawn(move || {
+            sh(&format!(
+                "{cli} server start --scanning-interval 1 --compression {params}"
+            ));
+        })
+    };
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+
+    let (headers, compressed) = http_post(&addr, body, Some("gzip"));
+    assert!(is_gzip(&headers), "{headers}");
+    assert!(compressed.len() < plain.len());
+    let (headers, _) = http_post(&addr, body, None);
+    assert!(!is_gzip(&headers), "{headers}");
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_rest_etag() {
+    let (t, _tmp, cli, params, server, _) = setup_cli(false);
+
+    // Restart the server with the REST facade
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+    let t = {
+        let cli = cli.clone();
+        let params = params.clone();
+        std::thread::spawn(move || {
+            sh(&format!(
+                "{cli} server start --scanning-interval 1 --rest {params}"
+            ));
+        })
+    };
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    let addr = cli.split(' ').nth(2).unwrap().to_string();
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    for path in ["/api/wallet/w1/balance", "/api/wallet/w1/txs"] {
+        let (status, etag) = http_get(&addr, path, None);
+        assert_eq!(status, 200);
+        let etag = etag.unwrap();
+
+        // The wallet did not change
+        let (status, same) = http_get(&addr, path, Some(&etag));
+        assert_eq!(status, 304);
+        assert_eq!(same.unwrap(), etag);
+    }
+
+    let (_, balance_etag) = http_get(&addr, "/api/wallet/w1/balance", None);
+    let (_, txs_etag) = http_get(&addr, "/api/wallet/w1/txs", None);
+    fund(&server, &cli, "w1", 1_000_000);
+    let (status, etag) = http_get(&addr, "/api/wallet/w1/balance", balance_etag.as_deref());
+    assert_eq!(status, 200);
+    assert_ne!(etag, balance_etag);
+    let (status, _) = http_get(&addr, "/api/wallet/w1/txs", txs_etag.as_deref());
+    assert_eq!(status, 200);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_sync_once() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    let r = sh(&format!(
+        "{cli} signer singlesig-desc -s s1 --descriptor-blinding-key slip77 --kind wpkh"
+    ));
+    let desc = get_str(&r, "descriptor").to_string();
+    sh(&format!("{cli} wallet load -w w1 -d {desc}"));
+    fund(&server, &cli, "w1", 1_000_000);
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let balance = get_balance(&cli, "w1", policy_asset);
+
+    // The server is not needed
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+
+    let electrum_url = &server.electrs.electrum_url;
+    let r = sh(&format!(
+        "{cli} wallet sync-once -d {desc} --electrum-url {electrum_url}"
+    ));
+    let b = r.get("balance").unwrap().as_object().unwrap();
+    assert_eq!(b.get(policy_asset).unwrap().as_u64().unwrap(), balance);
+    let utxos = r.get("utxos").unwrap().as_array().unwrap();
+    assert_eq!(utxos.len(), 1);
+    assert_eq!(get_str(&r, "network"), "liquid-regtest");
+
+    let err = sh_err(&format!("{cli} wallet sync-once -d {desc}"));
+    assert!(err.contains("--electrum-url"));
+}
+
+#[test]
+fn test_wallet_issued_assets() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let r = sh(&format!("{cli} wallet issued-assets -w w1"));
+    assert!(r.get("assets").unwrap().as_array().unwrap().is_empty());
+
+    let r = sh(&format!(
+        "{cli} wallet issue --wallet w1 --satoshi-asset 1000 --satoshi-token 1"
+    ));
+    let pset = get_str(&r, "pset");
+    let (asset1, token1) = asset_ids_from_issuance_pset(&cli, "w1", pset);
+    complete(&cli, "w1", pset, &["s1"]);
+
+    let r = sh(&format!(
+        "{cli} wallet issue --wallet w1 --satoshi-asset 2000 --satoshi-token 2"
+    ));
+    let pset = get_str(&r, "pset");
+    let (asset2, token2) = asset_ids_from_issuance_pset(&cli, "w1", pset);
+    complete(&cli, "w1", pset, &["s1"]);
+
+    let r = sh(&format!(
+        "{cli} wallet reissue --wallet w1 --asset {asset1} --satoshi-asset 10"
+    ));
+    complete(&cli, "w1", get_str(&r, "pset"), &["s1"]);
+
+    let r = sh(&format!("{cli} wallet issued-assets -w w1"));
+    let assets = r.get("assets").unwrap().as_array().unwrap();
+    assert_eq!(assets.len(), 2);
+    for (asset, token, supply, tokens) in [(&asset1, &token1, 1010, 1), (&asset2, &token2, 2000, 2)]
+    {
+        let issued = assets
+            .iter()
+            .find(|a| get_str(a, "asset_id") == asset)
+            .unwrap();
+        assert_eq!(get_str(issued, "token_id"), token);
+        assert_eq!(issued.get("issued_supply").unwrap().as_u64(), Some(supply));
+        let balance = issued.get("reissuance_token_balance").unwrap();
+        assert_eq!(balance.as_u64(), Some(tokens));
+        assert!(issued.get("contract").unwrap().is_null());
+    }
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_annotate_pset() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let addr = address(&cli, "w1");
+    let recipient = format!("--recipient {addr}:1000:{policy_asset}");
+    let r = sh(&format!("{cli} wallet send -w w1 {recipient}"));
+    let pset = get_str(&r, "pset");
+
+    // Annotating a PSET which already has the wallet details keeps it signable
+    let r = sh(&format!("{cli} wallet annotate-pset -w w1 -p {pset}"));
+    complete(&cli, "w1", get_str(&r, "pset"), &["s1"]);
+
+    let err = sh_err(&format!("{cli} wallet annotate-pset -w w1 -p invalid"));
+    assert!(err.contains("Base64"), "{err}");
+
+    // The PSET from send is already blinded
+    let err = sh_err(&format!("{cli} wallet blind-pset -w w1 -p {pset}"));
+    assert!(err.contains("is already blinded"), "{err}");
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_utxos_filter() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    // An unconfirmed UTXO
+    let addr = Address::from_str(&address(&cli, "w1")).unwrap();
+    let txid = server
+        .elementsd_sendtoaddress(&addr, 10_000, None)
+        .to_string();
+    wait_tx(&cli, "w1", &txid);
+
+    let r = sh(&format!("{cli} wallet utxos -w w1"));
+    let utxos = r.get("utxos").unwrap().as_array().unwrap();
+    assert_eq!(utxos.len(), 2);
+    let unconfirmed = utxos.iter().find(|u| get_str(u, "txid") == txid).unwrap();
+    assert_eq!(unconfirmed.get("confirmations").unwrap().as_u64(), Some(0));
+
+    let r = sh(&format!("{cli} wallet utxos -w w1 --min-confirmations 1"));
+    let utxos = r.get("utxos").unwrap().as_array().unwrap();
+    assert_eq!(utxos.len(), 1);
+    assert_ne!(get_str(&utxos[0], "txid"), txid);
+    assert!(utxos[0].get("confirmations").unwrap().as_u64().unwrap() >= 2);
+
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let r = sh(&format!("{cli} wallet utxos -w w1 --asset {policy_asset}"));
+    assert_eq!(get_len(&r, "utxos"), 2);
+    let other = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+    let r = sh(&format!("{cli} wallet utxos -w w1 --asset {other}"));
+    assert_eq!(get_len(&r, "utxos"), 0);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_utxos_derivation() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    let fingerprint = keyorigin(&cli, "s1", "bip84")[1..9].to_string();
+
+    let r = sh(&format!("{cli} wallet address -w w1 --index 3"));
+    let addr = Address::from_str(get_str(&r, "address")).unwrap();
+    let txid = server
+        .elementsd_sendtoaddress(&addr, 10_000, None)
+        .to_string();
+    server.elementsd_generate(1);
+    wait_tx(&cli, "w1", &txid);
+
+    let r = sh(&format!("{cli} wallet utxos -w w1"));
+    let utxos = r.get("utxos").unwrap().as_array().unwrap();
+    assert!(utxos[0].get("derivation_path").is_none());
+    assert!(utxos[0].get("key_origin").is_none());
+
+    let r = sh(&format!("{cli} wallet utxos -w w1 --with-derivation"));
+    let utxos = r.get("utxos").unwrap().as_array().unwrap();
+    assert_eq!(utxos.len(), 1);
+    let utxo = &utxos[0];
+    assert_eq!(
+        get_str(utxo, "script_pubkey"),
+        addr.script_pubkey().to_hex()
+    );
+    assert_eq!(get_str(utxo, "derivation_path"), "0/3");
+    let key_origin = utxo.get("key_origin").unwrap().as_array().unwrap();
+    assert_eq!(key_origin.len(), 1);
+    let key_origin = key_origin[0].as_str().unwrap();
+    assert!(key_origin.starts_with(&format!("[{fingerprint}/84")));
+    assert!(key_origin.ends_with("/0/3]"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_address_info_batch() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    sw_signer(&cli, "s2");
+    singlesig_wallet(&cli, "w2", "s2", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let mine: Vec<_> = (0..3).map(|i| addr_at(&cli, "w1", i)).collect();
+    let foreign = addr_at(&cli, "w2", 0);
+    // Beyond the scanned range, addresses are looked up, not derived until a gap
+    let not_scanned = addr_at(&cli, "w1", 1000);
+
+    let mut addresses = mine.clone();
+    addresses.push(foreign.clone());
+    addresses.push(not_scanned.clone());
+    // Many addresses in a single call
+    addresses.extend(std::iter::repeat(foreign.clone()).take(500));
+    let args: String = addresses
+        .iter()
+        .map(|a| format!(" --address {a}"))
+        .collect();
+    let r = sh(&format!("{cli} wallet address-info-batch -w w1{args}"));
+    let infos = r.get("addresses").unwrap().as_array().unwrap();
+    assert_eq!(infos.len(), addresses.len());
+    for (i, info) in infos.iter().enumerate() {
+        assert_eq!(get_str(info, "address"), addresses[i]);
+        let is_mine = info.get("is_mine").unwrap().as_bool().unwrap();
+        assert_eq!(is_mine, i < 3, "{info}");
+        if is_mine {
+            assert_eq!(info.get("index").unwrap().as_u64().unwrap(), i as u64);
+            assert_eq!(info.get("path_index").unwrap().as_u64().unwrap(), 0);
+        } else {
+            assert!(info.get("index").is_none());
+        }
+    }
+
+    let err = sh_err(&format!(
+        "{cli} wallet address-info-batch -w w1 --address invalid"
+    ));
+    assert!(err.contains("Invalid address"), "{err}");
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_address_batch() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+
+    let r = sh(&format!(
+        "{cli} wallet address-batch -w w1 --start-index 3 --count 5 --with-text-qr"
+    ));
+    let addresses = r.get("addresses").unwrap().as_array().unwrap();
+    assert_eq!(addresses.len(), 5);
+    for (i, a) in addresses.iter().enumerate() {
+        let index = 3 + i as u32;
+        assert_eq!(a.get("index").unwrap().as_u64().unwrap(), index as u64);
+        assert_eq!(get_str(a, "address"), addr_at(&cli, "w1", index));
+        assert!(!get_str(a, "text_qr").is_empty());
+        assert!(a.get("uri_qr").is_none());
+    }
+
+    // Without a start index the batch begins at the first unused address
+    let r = sh(&format!("{cli} wallet address-batch -w w1 --count 2"));
+    let addresses = r.get("addresses").unwrap().as_array().unwrap();
+    assert_eq!(get_str(&addresses[0], "address"), addr_at(&cli, "w1", 0));
+    assert_eq!(get_str(&addresses[1], "address"), addr_at(&cli, "w1", 1));
+
+    fund(&server, &cli, "w1", 1_000_000);
+    let r = sh(&format!("{cli} wallet address -w w1"));
+    let first_unused = r.get("index").unwrap().as_u64().unwrap();
+    assert!(first_unused > 0);
+    let r = sh(&format!("{cli} wallet address-batch -w w1 --count 1"));
+    let addresses = r.get("addresses").unwrap().as_array().unwrap();
+    assert_eq!(
+        addresses[0].get("index").unwrap().as_u64().unwrap(),
+        first_unused
+    );
+
+    let r = sh(&format!("{cli} wallet address-batch -w w1 --count 0"));
+    assert!(r.get("addresses").unwrap().as_array().unwrap().is_empty());
+
+    let err = sh_err(&format!("{cli} wallet address-batch -w w1 --count 1001"));
+    assert!(err.contains("Cannot derive more than 1000 addresses"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_policy() {
+    let (t, _tmp, cli, _params, _server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    sw_signer(&cli, "s2");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    multisig_wallet(&cli, "w2", 2, &["s1", "s2"], "slip77-rand");
+
+    let r = sh(&format!("{cli} wallet policy -w w1"));
+    let paths = r.get("spend_paths").unwrap().as_array().unwrap();
+    assert_eq!(paths.len(), 1);
+    let keys = paths[0].get("keys").unwrap().as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].get("threshold").unwrap().as_u64().unwrap(), 1);
+    assert!(paths[0].get("older").is_none());
+
+    let r = sh(&format!("{cli} wallet policy -w w2"));
+    let paths = r.get("spend_paths").unwrap().as_array().unwrap();
+    assert_eq!(paths.len(), 1);
+    let keys = paths[0].get("keys").unwrap().as_array().unwrap();
+    assert_eq!(keys[0].get("threshold").unwrap().as_u64().unwrap(), 2);
+    assert_eq!(keys[0].get("keys").unwrap().as_array().unwrap().len(), 2);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_public_descriptor() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    singlesig_wallet(&cli, "w2", "s1", "elip151", "wpkh");
+
+    let err = sh_err(&format!("{cli} wallet public-descriptor -w w1"));
+    assert!(
+        err.contains("can't be shared without the master blinding key"),
+        "{err}"
+    );
+
+    fund(&server, &cli, "w2", 1_000_000);
+    let balance = sh(&format!("{cli} wallet balance -w w2"));
+
+    let r = sh(&format!("{cli} wallet public-descriptor -w w2"));
+    let public = get_str(&r, "descriptor");
+    assert!(!public.contains("slip77"), "{public}");
+
+    // The public form is a watch-only wallet seeing the same balance
+    sh(&format!("{cli} wallet unload -w w2"));
+    sh(&format!("{cli} wallet load -w w3 -d {public}"));
+    sh(&format!("{cli} server scan"));
+    let r = sh(&format!("{cli} wallet balance -w w3"));
+    assert_eq!(r.get("balance"), balance.get("balance"));
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_send_smart_fee() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let node_address = server.elementsd_getnewaddress();
+    let recipient = format!("--recipient {node_address}:1000");
+    let r = sh(&format!(
+        "{cli} wallet send-smart-fee -w w1 {recipient} --target-blocks 2"
+    ));
+    let fee_rate = r.get("fee_rate").unwrap().as_f64().unwrap() as f32;
+    assert!(fee_rate >= 100.0);
+    assert!(r.get("is_fallback").unwrap().is_boolean());
+
+    // The PSET pays the returned fee rate
+    let pset: PartiallySignedTransaction = get_str(&r, "pset").parse().unwrap();
+    let vsize = pset.extract_tx().unwrap().vsize() as f32;
+    let fee = pset.outputs().last().unwrap().amount.unwrap() as f32;
+    let actual = 1000.0 * fee / vsize;
+    assert!(
+        (actual - fee_rate).abs() / fee_rate < 0.08,
+        "{actual} {fee_rate}"
+    );
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_send_assets_same_address() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    sw_signer(&cli, "s2");
+    singlesig_wallet(&cli, "w2", "s2", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let r = sh(&format!(
+        "{cli} wallet issue --wallet w1 --satoshi-asset 1000 --satoshi-token 1"
+    ));
+    let pset = get_str(&r, "pset");
+    let (asset, _token) = asset_ids_from_issuance_pset(&cli, "w1", pset);
+    complete(&cli, "w1", pset, &["s1"]);
+
+    // Pay two assets to the same address
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let addr = address(&cli, "w2");
+    let recipients =
+        format!("--recipient {addr}:1000:{policy_asset} --recipient {addr}:10:{asset}");
+    let r = sh(&format!("{cli} wallet send --wallet w1 {recipients}"));
+    let pset = get_str(&r, "pset");
+
+    // One output per asset, reported under a single recipient
+    let r = sh(&format!("{cli} wallet pset-details --wallet w1 -p {pset}"));
+    let recipients = r.get("recipients").unwrap().as_array().unwrap();
+    assert_eq!(recipients.len(), 1);
+    assert_eq!(get_str(&recipients[0], "address"), addr);
+    let outputs = recipients[0].get("outputs").unwrap().as_array().unwrap();
+    assert_eq!(outputs.len(), 2);
+    let mut sent: Vec<_> = outputs
+        .iter()
+        .map(|o| {
+            let asset = get_str(o, "asset").to_string();
+            (asset, o.get("satoshi").unwrap().as_u64().unwrap())
+        })
+        .collect();
+    sent.sort();
+    let mut expected = vec![(policy_asset.to_string(), 1000), (asset.clone(), 10)];
+    expected.sort();
+    assert_eq!(sent, expected);
+
+    complete(&cli, "w1", pset, &["s1"]);
+    assert_eq!(get_balance(&cli, "w2", &asset), 10);
+    assert_eq!(get_balance(&cli, "w2", policy_asset), 1000);
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_proof_of_reserves() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+
+    let challenge = "audit-42";
+    let r = sh(&format!(
+        "{cli} wallet proof-of-reserves -w w1 --challenge {challenge}"
+    ));
+    let pset = get_str(&r, "pset");
+    let err = sh_err(&format!(
+        "{cli} wallet verify-proof-of-reserves -w w1 --challenge {challenge} --pset {pset}"
+    ));
+    assert!(err.contains("not enough signatures"), "{err}");
+
+    let r = sh(&format!("{cli} signer sign --signer s1 --pset {pset}"));
+    let pset = get_str(&r, "pset");
+    let r = sh(&format!(
+        "{cli} wallet verify-proof-of-reserves -w w1 --challenge {challenge} --pset {pset}"
+    ));
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let reserves = r.get("reserves").unwrap().as_object().unwrap();
+    assert_eq!(
+        reserves.get(policy_asset).unwrap().as_u64().unwrap(),
+        1_000_000
+    );
+    assert_eq!(get_balance(&cli, "w1", policy_asset), 1_000_000);
+
+    let err = sh_err(&format!(
+        "{cli} wallet verify-proof-of-reserves -w w1 --challenge other --pset {pset}"
+    ));
+    assert!(err.contains("challenge"), "{err}");
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_hide_amounts() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_234_567);
+
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let r = sh(&format!("{cli} wallet balance -w w1 --hide-amounts"));
+    let balance = r.get("balance").unwrap().as_object().unwrap();
+    assert_eq!(
+        balance.get(policy_asset).unwrap().as_i64().unwrap(),
+        1_000_000
+    );
+
+    let txs = txs(&cli, "w1");
+    let r = sh(&format!("{cli} wallet txs -w w1 --hide-amounts"));
+    let hidden = r.get("txs").unwrap().as_array().unwrap();
+    assert_eq!(hidden.len(), txs.len());
+    for (tx, hidden) in txs.iter().zip(hidden) {
+        // Same structure
+        for key in ["txid", "height", "timestamp", "type"] {
+            assert_eq!(tx.get(key), hidden.get(key));
+        }
+        let assets = |t: &Value| {
+            let mut assets: Vec<_> = t
+                .get("balance")
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect();
+            assets.sort();
+            assets
+        };
+        assert_eq!(assets(tx), assets(hidden));
+
+        // Without the amounts
+        assert!(get_str(hidden, "unblinded_url").is_empty());
+        assert!(!get_str(tx, "unblinded_url").is_empty());
+    }
+    let balance = hidden[0].get("balance").unwrap();
+    assert_eq!(
+        balance.get(policy_asset).unwrap().as_i64().unwrap(),
+        1_000_000
+    );
+
+    sh(&format!("{cli} server stop"));
+    t.join().unwrap();
+}
+
+#[test]
+fn test_wallet_dust_deposits() {
+    let (t, _tmp, cli, _params, server, _) = setup_cli(false);
+
+    sw_signer(&cli, "s1");
+    singlesig_wallet(&cli, "w1", "s1", "slip77", "wpkh");
+    fund(&server, &cli, "w1", 1_000_000);
+    let dust_deposits = |threshold: u64| {
+        let r = sh(&format!(
+            "{cli} wallet dust-deposits -w w1 --threshold {threshold}"
+        ));
+        r.get("utxos").unwrap().as_array().unwrap().clone()
+    };
+    assert!(dust_deposits(1_000).is_empty());
+
+    // A small payment to ourselves is not a dust deposit
+    let policy_asset = "5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225";
+    let own_address = address(&cli, "w1");
+    send(&cli, "w1", &own_address, policy_asset, 500, &["s1"]);
+    assert!(dust_deposits(1_000).is_empty());
+
+    // An unsolicited small deposit is
+    fund(&server, &cli, "w1", 546);
+    let dust = dust_deposits(1_000);
+    assert_eq!(dust.len(), 1);
+    assert_eq!(dust[0].get("value").unwrap().as_u64().unwrap(), 546);
+    assert!(dust_deposits(546).is_empty());
+
+    // A small deposit of another asset is not, its amount is not comparable with the threshold
+    let asset = server.elementsd_issueasset(10_000);
+    let addr = Address::from_str(&address(&cli, "w1")).unwrap();
+    let txid = server
+        .elementsd_sendtoaddress(&addr, 100, Some(asset))
+        .to_string();
+    server.elementsd_generate(2);
+    wait_tx(&cli, "w1", &txid);
+    assert_eq!(dust_deposits(1_000).len(), 1);
 
     sh(&format!("{cli} server stop"));
     t.join().unwrap();
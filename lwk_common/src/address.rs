@@ -0,0 +1,195 @@
+//! Helpers to parse addresses, and to split a confidential address in its parts and to combine
+//! them back
+
+use std::str::FromStr;
+
+use elements::bitcoin::base58;
+use elements::bitcoin::bech32::primitives::decode as bech32;
+use elements::blech32::decode as blech32;
+use elements::secp256k1_zkp::PublicKey;
+use elements::{Address, AddressParams};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("Address {0} is already confidential")]
+    AlreadyConfidential(String),
+}
+
+/// Errors parsing an address with [`parse_address()`]
+#[derive(Error, Debug)]
+pub enum AddressParseError {
+    #[error("Address {address} is for {found}, but {expected} is expected")]
+    WrongNetwork {
+        address: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    #[error("Address {0} is not confidential, a confidential address is required")]
+    NotConfidential(String),
+
+    #[error("Address {0} has an invalid checksum, it might have been mistyped")]
+    InvalidChecksum(String),
+
+    #[error("Invalid address {0}: {1}")]
+    Invalid(String, elements::AddressError),
+}
+
+fn network_name(params: &AddressParams) -> &'static str {
+    if params == &AddressParams::LIQUID {
+        "liquid"
+    } else if params == &AddressParams::LIQUID_TESTNET {
+        "liquid-testnet"
+    } else {
+        "elements"
+    }
+}
+
+/// Parse an address for the network of `params`, optionally requiring it to be confidential
+///
+/// Unlike [`Address::parse_with_params()`], errors tell addresses of another network and
+/// mistyped addresses apart.
+pub fn parse_address(
+    s: &str,
+    params: &'static AddressParams,
+    require_confidential: bool,
+) -> Result<Address, AddressParseError> {
+    let s = s.trim();
+    let address = Address::from_str(s).map_err(|e| {
+        let is_checksum = matches!(
+            e,
+            elements::AddressError::Base58(base58::Error::IncorrectChecksum(_))
+                | elements::AddressError::Bech32(bech32::SegwitHrpstringError::Checksum(_))
+                | elements::AddressError::Blech32(blech32::SegwitHrpstringError::Checksum(_))
+        );
+        if is_checksum {
+            AddressParseError::InvalidChecksum(s.to_string())
+        } else {
+            AddressParseError::Invalid(s.to_string(), e)
+        }
+    })?;
+    if address.params != params {
+        return Err(AddressParseError::WrongNetwork {
+            address: s.to_string(),
+            expected: network_name(params),
+            found: network_name(address.params),
+        });
+    }
+    if require_confidential && !address.is_blinded() {
+        return Err(AddressParseError::NotConfidential(s.to_string()));
+    }
+    Ok(address)
+}
+
+/// Split an address in the unconfidential address and the blinding public key, if any
+pub fn split_confidential(address: &Address) -> (Address, Option<PublicKey>) {
+    (address.to_unconfidential(), address.blinding_pubkey)
+}
+
+/// Combine an unconfidential address with a blinding public key, the inverse of [`split_confidential()`]
+///
+/// Errors if `unconfidential` already has a blinding key, to avoid silently replacing it.
+pub fn combine_confidential(
+    unconfidential: &Address,
+    blinding_pubkey: PublicKey,
+) -> Result<Address, AddressError> {
+    if unconfidential.is_blinded() {
+        return Err(AddressError::AlreadyConfidential(
+            unconfidential.to_string(),
+        ));
+    }
+    Ok(unconfidential.to_confidential(blinding_pubkey))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use elements::{Address, AddressParams};
+
+    use super::{
+        combine_confidential, parse_address, split_confidential, AddressError, AddressParseError,
+    };
+
+    const TESTNET: &str = "tlq1qq2xvpcvfup5j8zscjq05u2wxxjcyewk7979f3mmz5l7uw5pqmx6xf5xy50hsn6vhkm5euwt72x878eq6zxx2z58hd7zrsg9qn";
+
+    #[test]
+    fn split_and_combine() {
+        let addresses = [
+            "lq1qqf8er278e6nyvuwtgf39e6ewvdcnjupn9a86rzpx655y5lhkt0walu3djf9cklkxd3ryld97hu8h3xepw7sh2rlu7q45dcew5",
+            "VJLAQiChRTcVDXEBKrRnSBnGccJLxNg45zW8cuDwkhbxb8NVFkb4U2QMWAzot4idqhLMWjtZ7SXA4nrA",
+            "tlq1qq2xvpcvfup5j8zscjq05u2wxxjcyewk7979f3mmz5l7uw5pqmx6xf5xy50hsn6vhkm5euwt72x878eq6zxx2z58hd7zrsg9qn",
+        ];
+        for s in addresses {
+            let address = Address::from_str(s).unwrap();
+            let (unconfidential, blinding_pubkey) = split_confidential(&address);
+            let blinding_pubkey = blinding_pubkey.unwrap();
+            assert!(!unconfidential.is_blinded());
+            assert_eq!(unconfidential.params, address.params);
+            assert_eq!(unconfidential.script_pubkey(), address.script_pubkey());
+
+            let combined = combine_confidential(&unconfidential, blinding_pubkey).unwrap();
+            assert_eq!(combined, address);
+            assert_eq!(combined.to_string(), s);
+
+            let err = combine_confidential(&address, blinding_pubkey).unwrap_err();
+            assert_eq!(err, AddressError::AlreadyConfidential(s.to_string()));
+        }
+
+        let address = Address::from_str(addresses[0]).unwrap();
+        assert_eq!(address.params, &AddressParams::LIQUID);
+        let address = Address::from_str(addresses[2]).unwrap();
+        assert_eq!(address.params, &AddressParams::LIQUID_TESTNET);
+
+        // Unconfidential addresses have no blinding key
+        let (unconfidential, _) = split_confidential(&address);
+        assert_eq!(split_confidential(&unconfidential).1, None);
+    }
+
+    #[test]
+    fn parse() {
+        let testnet = &AddressParams::LIQUID_TESTNET;
+        let address = parse_address(TESTNET, testnet, true).unwrap();
+        assert_eq!(address.to_string(), TESTNET);
+
+        let err = parse_address(TESTNET, &AddressParams::LIQUID, true).unwrap_err();
+        assert!(matches!(
+            err,
+            AddressParseError::WrongNetwork {
+                expected: "liquid",
+                found: "liquid-testnet",
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("liquid-testnet"), "{err}");
+
+        let unconfidential = address.to_unconfidential().to_string();
+        parse_address(&unconfidential, testnet, false).unwrap();
+        let err = parse_address(&unconfidential, testnet, true).unwrap_err();
+        assert!(matches!(err, AddressParseError::NotConfidential(_)));
+
+        // Change the last character, breaking the checksum
+        let mut mistyped = TESTNET.to_string();
+        mistyped.pop();
+        mistyped.push('p');
+        let err = parse_address(&mistyped, testnet, true).unwrap_err();
+        assert!(
+            matches!(err, AddressParseError::InvalidChecksum(_)),
+            "{err}"
+        );
+
+        // Also for unconfidential addresses
+        let mut mistyped = unconfidential.clone();
+        let last = mistyped.pop().unwrap();
+        mistyped.push(if last == 'p' { 'q' } else { 'p' });
+        let err = parse_address(&mistyped, testnet, false).unwrap_err();
+        assert!(
+            matches!(err, AddressParseError::InvalidChecksum(_)),
+            "{err}"
+        );
+
+        let err = parse_address("not an address", testnet, true).unwrap_err();
+        assert!(matches!(err, AddressParseError::Invalid(_, _)), "{err}");
+    }
+}
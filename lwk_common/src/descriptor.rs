@@ -14,11 +14,22 @@ pub fn singlesig_desc<S: Signer>(
     script_variant: Singlesig,
     blinding_variant: DescriptorBlindingKey,
     is_mainnet: bool,
+) -> Result<String, String> {
+    singlesig_desc_account(signer, script_variant, blinding_variant, is_mainnet, 0)
+}
+
+/// Like [`singlesig_desc()`] but for the given account, eg `m/84h/<coin_type>h/<account>h`
+pub fn singlesig_desc_account<S: Signer>(
+    signer: &S,
+    script_variant: Singlesig,
+    blinding_variant: DescriptorBlindingKey,
+    is_mainnet: bool,
+    account: u32,
 ) -> Result<String, String> {
     let coin_type = if is_mainnet { 1776 } else { 1 };
     let (prefix, path, suffix) = match script_variant {
-        Singlesig::Wpkh => ("elwpkh", format!("84h/{coin_type}h/0h"), ""),
-        Singlesig::ShWpkh => ("elsh(wpkh", format!("49h/{coin_type}h/0h"), ")"),
+        Singlesig::Wpkh => ("elwpkh", format!("84h/{coin_type}h/{account}h"), ""),
+        Singlesig::ShWpkh => ("elsh(wpkh", format!("49h/{coin_type}h/{account}h"), ")"),
     };
 
     let fingerprint = signer.fingerprint().map_err(|e| format!("{:?}", e))?;
@@ -179,6 +190,35 @@ pub enum Bip {
     Bip87,
 }
 
+impl Bip {
+    /// All the supported variants
+    pub const ALL: [Bip; 3] = [Bip::Bip84, Bip::Bip49, Bip::Bip87];
+
+    /// The purpose, the first hardened step of the derivation path
+    pub fn purpose(&self) -> u32 {
+        match self {
+            Bip::Bip84 => 84,
+            Bip::Bip49 => 49,
+            Bip::Bip87 => 87,
+        }
+    }
+
+    /// The derivation path of the account xpub, eg `m/84h/<coin_type>h/0h`
+    pub fn path_template(&self) -> String {
+        format!("m/{}h/<coin_type>h/0h", self.purpose())
+    }
+
+    /// Whether the xpub is meant for singlesig wallets
+    pub fn is_singlesig(&self) -> bool {
+        matches!(self, Bip::Bip84 | Bip::Bip49)
+    }
+
+    /// Whether the xpub is meant for multisig wallets
+    pub fn is_multisig(&self) -> bool {
+        matches!(self, Bip::Bip87)
+    }
+}
+
 impl std::fmt::Display for Bip {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -231,6 +271,21 @@ mod test {
 
     use super::Bip;
 
+    #[test]
+    fn bip_all() {
+        for bip in Bip::ALL {
+            // Exhaustive match, fails to compile if a variant is added
+            let expected = match bip {
+                Bip::Bip84 => "m/84h/<coin_type>h/0h",
+                Bip::Bip49 => "m/49h/<coin_type>h/0h",
+                Bip::Bip87 => "m/87h/<coin_type>h/0h",
+            };
+            assert_eq!(bip.path_template(), expected);
+            assert_ne!(bip.is_singlesig(), bip.is_multisig());
+            assert_eq!(Bip::from_str(&bip.to_string()).unwrap(), bip);
+        }
+    }
+
     #[test]
     fn roundtrip_bip() {
         for el in ["bip49", "bip84", "bip87"] {
@@ -8,12 +8,9 @@ pub enum Error {
         previous_outpoint: OutPoint,
     },
 
-    #[error("Input #{idx} has a pegin, but it's not supported")]
+    #[error("Input #{idx} has a pegin, but its asset or value is not explicit")]
     InputPeginUnsupported { idx: usize },
 
-    #[error("Input #{idx} has a blinded issuance, but it's not supported")]
-    InputBlindedIssuance { idx: usize },
-
     #[error("Input #{idx} is not blinded")]
     InputNotBlinded { idx: usize },
 
@@ -63,9 +60,23 @@ pub enum Error {
     #[error("Private blinding key not available")]
     MissingPrivateBlindingKey,
 
+    #[error("Cannot convert PSET from version {from} to version {to} without losing fields")]
+    PsetVersionConversion { from: u32, to: u32 },
+
     #[error(transparent)]
     DescConversion(#[from] elements_miniscript::descriptor::ConversionError),
 
     #[error(transparent)]
     Miniscript(#[from] elements_miniscript::Error),
 }
+
+impl Error {
+    /// If the error is caused by an input using a feature not supported by
+    /// [`crate::pset_balance()`], returns the index of such input
+    pub fn unsupported_input(&self) -> Option<usize> {
+        match self {
+            Error::InputPeginUnsupported { idx } => Some(*idx),
+            _ => None,
+        }
+    }
+}
@@ -9,27 +9,35 @@
 //!
 //!  To avoid circular dependencies this crate must not depend on other crate of the workspace
 
+pub mod address;
 mod descriptor;
 mod error;
 mod keyorigin_xpub;
 mod model;
+mod payment;
 pub mod precision;
 mod qr;
 mod segwit;
 mod signer;
+mod summary;
 
 pub use crate::descriptor::{
-    multisig_desc, singlesig_desc, Bip, DescriptorBlindingKey, InvalidBipVariant,
-    InvalidBlindingKeyVariant, InvalidMultisigVariant, InvalidSinglesigVariant, Multisig,
-    Singlesig,
+    multisig_desc, singlesig_desc, singlesig_desc_account, Bip, DescriptorBlindingKey,
+    InvalidBipVariant, InvalidBlindingKeyVariant, InvalidMultisigVariant, InvalidSinglesigVariant,
+    Multisig, Singlesig,
 };
 pub use crate::error::Error;
 pub use crate::keyorigin_xpub::{keyorigin_xpub_from_str, InvalidKeyOriginXpub};
 pub use crate::model::*;
+pub use crate::payment::{
+    bip353_dns_name, parse_bip353_records, parse_payment, parse_payment_with_precision, Payment,
+    PaymentError, PaymentKind,
+};
 pub use crate::precision::Precision;
 pub use crate::qr::*;
 pub use crate::segwit::is_provably_segwit;
 pub use crate::signer::Signer;
+pub use crate::summary::pset_summary;
 
 use elements::confidential::{Asset, Value};
 use elements_miniscript::confidential::bare::tweak_private_key;
@@ -127,9 +135,29 @@ pub fn pset_balance(
     pset: &PartiallySignedTransaction,
     descriptor: &ConfidentialDescriptor<DescriptorPublicKey>,
 ) -> Result<PsetBalance, Error> {
+    pset_balance_with_scripts(pset, descriptor, |_| false)
+}
+
+/// Like [`pset_balance()`], but inputs and outputs are also considered owned if
+/// `is_wallet_script` returns true for their script pubkey
+///
+/// PSETs created by view-only wallets might not have the `bip32_derivation` used to recognize
+/// the wallet inputs and outputs, in this case match the script pubkeys against the ones derived
+/// from the descriptor.
+pub fn pset_balance_with_scripts(
+    pset: &PartiallySignedTransaction,
+    descriptor: &ConfidentialDescriptor<DescriptorPublicKey>,
+    is_wallet_script: impl Fn(&Script) -> bool,
+) -> Result<PsetBalance, Error> {
+    let is_owned = |script_pubkey: &Script, bip32_derivation: &BTreeMap<PublicKey, KeySource>| {
+        is_mine(script_pubkey, descriptor, bip32_derivation).unwrap_or(false)
+            || is_wallet_script(script_pubkey)
+    };
     let secp = Secp256k1::new();
     let mut balances: BTreeMap<AssetId, i64> = BTreeMap::new();
     let mut fee: Option<u64> = None;
+    let mut warnings = vec![];
+    let mut recipients = vec![];
     for (idx, input) in pset.inputs().iter().enumerate() {
         match input.witness_utxo.as_ref() {
             None => {
@@ -143,25 +171,33 @@ pub fn pset_balance(
                 });
             }
             Some(txout) => {
-                if !is_mine(&txout.script_pubkey, descriptor, &input.bip32_derivation)
-                    .unwrap_or(false)
-                {
-                    // Ignore outputs we don't own
+                if input.is_pegin() {
+                    // Pegins claim bitcoin locked on the mainchain, they do not spend wallet
+                    // utxos, the claimed value is accounted in the outputs receiving it.
+                    if txout.asset.explicit().is_none() || txout.value.explicit().is_none() {
+                        return Err(Error::InputPeginUnsupported { idx });
+                    }
                     continue;
                 }
 
-                if input.is_pegin() {
-                    return Err(Error::InputPeginUnsupported { idx });
-                }
                 if input.has_issuance() {
                     let issuance = input.asset_issuance();
                     if issuance.amount.is_confidential()
                         || issuance.inflation_keys.is_confidential()
                     {
-                        return Err(Error::InputBlindedIssuance { idx });
+                        // We can't know the issued amounts, but the outputs we own receiving
+                        // them are unblinded and accounted below.
+                        warnings.push(format!(
+                            "Input #{idx} has a blinded issuance, issued amounts are unknown"
+                        ));
                     }
                 }
 
+                if !is_owned(&txout.script_pubkey, &input.bip32_derivation) {
+                    // Ignore outputs we don't own
+                    continue;
+                }
+
                 // We expect the input to be blinded
                 let (asset_comm, amount_comm) = match (txout.asset, txout.value) {
                     (Asset::Confidential(g), Value::Confidential(c)) => (g, c),
@@ -208,8 +244,27 @@ pub fn pset_balance(
             continue;
         }
 
-        if !is_mine(&output.script_pubkey, descriptor, &output.bip32_derivation).unwrap_or(false) {
-            // Ignore outputs we don't own
+        if !is_owned(&output.script_pubkey, &output.bip32_derivation) {
+            // Several assets can be sent to the same address, each in its own output, but the
+            // same asset in several outputs is likely a mistake of the creator
+            let duplicate = recipients.iter().find(|r: &&PsetRecipient| {
+                r.script_pubkey == output.script_pubkey
+                    && r.asset.is_some()
+                    && r.asset == output.asset
+            });
+            if let Some(r) = duplicate {
+                warnings.push(format!(
+                    "Outputs #{} and #{idx} send the same asset to the same address",
+                    r.vout
+                ));
+            }
+            recipients.push(PsetRecipient {
+                vout: idx as u32,
+                script_pubkey: output.script_pubkey.clone(),
+                blinding_pubkey: output.blinding_key,
+                asset: output.asset,
+                value: output.amount,
+            });
             continue;
         }
 
@@ -263,7 +318,12 @@ pub fn pset_balance(
     }
     let fee = fee.ok_or(Error::MissingFee)?;
 
-    Ok(PsetBalance { fee, balances })
+    Ok(PsetBalance {
+        fee,
+        balances,
+        warnings,
+        recipients,
+    })
 }
 
 pub fn pset_signatures(pset: &PartiallySignedTransaction) -> Vec<PsetSignatures> {
@@ -291,6 +351,50 @@ pub fn pset_issuances(pset: &PartiallySignedTransaction) -> Vec<Issuance> {
     pset.inputs().iter().map(Issuance::new).collect()
 }
 
+/// Check if the outputs of the PSET have been blinded, so that it can be signed
+///
+/// Outputs are expected to be blinded if they have a blinding key, the others, such as the fee
+/// output or outputs to unconfidential addresses, are left explicit.
+/// Partially blinded outputs, having only one of the commitments, are considered not blinded.
+pub fn pset_blinding_status(pset: &PartiallySignedTransaction) -> PsetBlindingStatus {
+    let missing_output_indices: Vec<_> = pset
+        .outputs()
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| o.blinding_key.is_some())
+        .filter(|(_, o)| o.asset_comm.is_none() || o.amount_comm.is_none())
+        .map(|(idx, _)| idx)
+        .collect();
+    PsetBlindingStatus {
+        all_outputs_blinded: missing_output_indices.is_empty(),
+        missing_output_indices,
+    }
+}
+
+/// The PSET version, as in the global version field
+pub fn pset_version(pset: &PartiallySignedTransaction) -> u32 {
+    pset.global.version
+}
+
+/// Convert a PSET to the `target` version
+///
+/// PSETs are always decoded with the version 2 layout, with the transaction fields split into
+/// the inputs and outputs maps, so converting to version 2 never loses data.
+/// Version 0 needs the global unsigned transaction instead, which cannot hold the version 2
+/// fields such as the explicit values and their proofs, so downgrades are rejected.
+pub fn pset_convert_version(
+    pset: &PartiallySignedTransaction,
+    target: u32,
+) -> Result<PartiallySignedTransaction, Error> {
+    let from = pset_version(pset);
+    if target != 2 {
+        return Err(Error::PsetVersionConversion { from, to: target });
+    }
+    let mut pset = pset.clone();
+    pset.global.version = target;
+    Ok(pset)
+}
+
 /// Create the same burn script that Elements Core wallet creates
 pub fn burn_script() -> Script {
     Builder::new().push_opcode(OP_RETURN).into_script()
@@ -298,10 +402,17 @@ pub fn burn_script() -> Script {
 
 #[cfg(test)]
 mod test {
-    use elements::{pset::PartiallySignedTransaction, AssetId};
+    use std::collections::HashSet;
+
+    use elements::hashes::Hash;
+    use elements::secp256k1_zkp::{Generator, PedersenCommitment, Secp256k1};
+    use elements::{pset::PartiallySignedTransaction, AssetId, Txid};
     use elements_miniscript::{ConfidentialDescriptor, DescriptorPublicKey};
 
-    use crate::pset_balance;
+    use crate::{
+        pset_balance, pset_balance_with_scripts, pset_blinding_status, pset_convert_version,
+        pset_version, Error,
+    };
 
     #[test]
     fn test_pset_details() {
@@ -322,4 +433,144 @@ mod test {
         let v = balance.balances.get(&asset_id).unwrap();
         assert_eq!(*v, -1);
     }
+
+    #[test]
+    fn test_pset_details_view_only() {
+        let asset_id_str = "38fca2d939696061a8f76d4e6b5eecd54e3b4221c846f24a6b279e79952850a5";
+        let asset_id: AssetId = asset_id_str.parse().unwrap();
+        let desc_str = include_str!("../test_data/pset_details/descriptor");
+        let desc: ConfidentialDescriptor<DescriptorPublicKey> = desc_str.parse().unwrap();
+        let pset_str = include_str!("../test_data/pset_details/pset2.base64");
+        let mut pset: PartiallySignedTransaction = pset_str.parse().unwrap();
+        let expected = pset_balance(&pset, &desc).unwrap();
+
+        // A PSET created by a view-only wallet, without key derivations
+        for input in pset.inputs_mut() {
+            input.bip32_derivation.clear();
+        }
+        for output in pset.outputs_mut() {
+            output.bip32_derivation.clear();
+        }
+        let balance = pset_balance(&pset, &desc).unwrap();
+        assert!(balance.balances.get(&asset_id).is_none());
+
+        // The scripts derived from the descriptor, without the signer keys
+        let mut scripts = HashSet::new();
+        for d in desc.descriptor.clone().into_single_descriptors().unwrap() {
+            for i in 0..50 {
+                scripts.insert(d.at_derivation_index(i).unwrap().script_pubkey());
+            }
+        }
+        let balance = pset_balance_with_scripts(&pset, &desc, |s| scripts.contains(s)).unwrap();
+        assert_eq!(balance.balances, expected.balances);
+        assert_eq!(balance.fee, expected.fee);
+        assert_eq!(*balance.balances.get(&asset_id).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_pset_version() {
+        let pset_str = include_str!("../test_data/pset_details/pset.base64");
+        let pset: PartiallySignedTransaction = pset_str.parse().unwrap();
+        assert_eq!(pset_version(&pset), 2);
+
+        let converted = pset_convert_version(&pset, 2).unwrap();
+        assert_eq!(converted, pset);
+        assert_eq!(converted.to_string(), pset_str.trim());
+
+        let err = pset_convert_version(&pset, 0).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PsetVersionConversion { from: 2, to: 0 }
+        ));
+    }
+
+    #[test]
+    fn test_pset_details_blinded_issuance() {
+        let asset_id_str = "38fca2d939696061a8f76d4e6b5eecd54e3b4221c846f24a6b279e79952850a5";
+        let asset_id: AssetId = asset_id_str.parse().unwrap();
+        let desc_str = include_str!("../test_data/pset_details/descriptor");
+        let desc: ConfidentialDescriptor<DescriptorPublicKey> = desc_str.parse().unwrap();
+        let pset_str = include_str!("../test_data/pset_details/pset2.base64");
+        let mut pset: PartiallySignedTransaction = pset_str.parse().unwrap();
+        let balance = pset_balance(&pset, &desc).unwrap();
+        assert!(balance.warnings().is_empty());
+
+        // Add an input we don't own with a blinded issuance
+        let secp = Secp256k1::new();
+        let generator = Generator::new_unblinded(&secp, asset_id.into_tag());
+        let mut input = pset.inputs()[0].clone();
+        input.previous_txid = Txid::all_zeros();
+        input.bip32_derivation.clear();
+        input.issuance_value_comm = Some(PedersenCommitment::new_unblinded(&secp, 10, generator));
+        pset.add_input(input);
+
+        let blinded = pset_balance(&pset, &desc).unwrap();
+        assert_eq!(blinded.balances, balance.balances);
+        assert_eq!(blinded.fee, balance.fee);
+        assert_eq!(blinded.warnings().len(), 1);
+        assert!(blinded.warnings()[0].contains("Input #2 has a blinded issuance"));
+    }
+
+    #[test]
+    fn test_pset_details_recipients() {
+        let desc_str = include_str!("../test_data/pset_details/descriptor");
+        let desc: ConfidentialDescriptor<DescriptorPublicKey> = desc_str.parse().unwrap();
+        let pset_str = include_str!("../test_data/pset_details/pset2.base64");
+        let mut pset: PartiallySignedTransaction = pset_str.parse().unwrap();
+        let balance = pset_balance(&pset, &desc).unwrap();
+        assert!(balance.warnings().is_empty());
+        let n = balance.recipients().len();
+        assert!(n > 0);
+        let vout = balance.recipients()[0].vout() as usize;
+
+        // Another asset sent to the same address is not a mistake
+        let asset_a = AssetId::from_slice(&[1; 32]).unwrap();
+        let asset_b = AssetId::from_slice(&[2; 32]).unwrap();
+        pset.outputs_mut()[vout].asset = Some(asset_a);
+        let mut output = pset.outputs()[vout].clone();
+        output.asset = Some(asset_b);
+        pset.add_output(output.clone());
+        let balance = pset_balance(&pset, &desc).unwrap();
+        assert!(balance.warnings().is_empty());
+        let recipients = balance.recipients();
+        assert_eq!(recipients.len(), n + 1);
+        assert_eq!(recipients[0].script_pubkey(), recipients[n].script_pubkey());
+        assert_eq!(recipients[n].asset(), Some(asset_b));
+
+        // The same asset sent twice to the same address is reported
+        pset.add_output(output);
+        let balance = pset_balance(&pset, &desc).unwrap();
+        assert_eq!(balance.recipients().len(), n + 2);
+        assert_eq!(balance.warnings().len(), 1);
+        assert!(balance.warnings()[0].contains("send the same asset to the same address"));
+    }
+
+    #[test]
+    fn test_pset_blinding_status() {
+        let pset_str = include_str!("../test_data/pset_details/pset2.base64");
+        let mut pset: PartiallySignedTransaction = pset_str.parse().unwrap();
+        let status = pset_blinding_status(&pset);
+        assert!(status.all_outputs_blinded);
+        assert!(status.missing_output_indices.is_empty());
+
+        // An output to a confidential address which has not been blinded
+        let idx = pset
+            .outputs()
+            .iter()
+            .position(|o| o.blinding_key.is_some())
+            .unwrap();
+        let mut output = pset.outputs()[idx].clone();
+        output.asset_comm = None;
+        output.amount_comm = None;
+        pset.add_output(output.clone());
+        let status = pset_blinding_status(&pset);
+        assert!(!status.all_outputs_blinded);
+        assert_eq!(status.missing_output_indices, vec![pset.n_outputs() - 1]);
+
+        // Without a blinding key the output is not expected to be blinded
+        output.blinding_key = None;
+        pset.add_output(output);
+        let status = pset_blinding_status(&pset);
+        assert_eq!(status.missing_output_indices, vec![pset.n_outputs() - 2]);
+    }
 }
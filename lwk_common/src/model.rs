@@ -4,13 +4,89 @@ use elements_miniscript::elements::bitcoin::{
 };
 use elements_miniscript::elements::pset::Input;
 use elements_miniscript::elements::secp256k1_zkp::ZERO_TWEAK;
-use elements_miniscript::elements::{AssetId, AssetIssuance, OutPoint, Txid};
+use elements_miniscript::elements::{
+    Address, AddressParams, AssetId, AssetIssuance, OutPoint, Script, Txid,
+};
 use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Debug, Clone)]
 pub struct PsetBalance {
     pub fee: u64,
     pub balances: BTreeMap<AssetId, i64>,
+    pub(crate) warnings: Vec<String>,
+    pub(crate) recipients: Vec<PsetRecipient>,
+}
+
+impl PsetBalance {
+    /// A balance without warnings and recipients
+    pub fn new(fee: u64, balances: BTreeMap<AssetId, i64>) -> Self {
+        Self {
+            fee,
+            balances,
+            warnings: vec![],
+            recipients: vec![],
+        }
+    }
+
+    /// Parts of the PSET that could not be taken into account, such as blinded issuances
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Outputs not belonging to the wallet, excluding the fee
+    pub fn recipients(&self) -> &[PsetRecipient] {
+        &self.recipients
+    }
+}
+
+/// An output of the PSET sent outside the wallet
+///
+/// Outputs carry a single asset, sending several assets to the same address results in several
+/// outputs with the same `script_pubkey`.
+#[derive(Debug, Clone)]
+pub struct PsetRecipient {
+    pub(crate) vout: u32,
+    pub(crate) script_pubkey: Script,
+    pub(crate) blinding_pubkey: Option<PublicKey>,
+    pub(crate) asset: Option<AssetId>,
+    pub(crate) value: Option<u64>,
+}
+
+impl PsetRecipient {
+    /// Index of the output in the transaction
+    pub fn vout(&self) -> u32 {
+        self.vout
+    }
+
+    pub fn script_pubkey(&self) -> &Script {
+        &self.script_pubkey
+    }
+
+    /// None if the output is blinded and the PSET doesn't contain the explicit asset
+    pub fn asset(&self) -> Option<AssetId> {
+        self.asset
+    }
+
+    /// None if the output is blinded and the PSET doesn't contain the explicit value
+    pub fn value(&self) -> Option<u64> {
+        self.value
+    }
+
+    /// The address receiving the output, None for scripts without an address form
+    pub fn address(&self, params: &'static AddressParams) -> Option<Address> {
+        let blinder = self.blinding_pubkey.map(|k| k.inner);
+        Address::from_script(&self.script_pubkey, blinder, params)
+    }
+}
+
+/// Whether the outputs of a PSET have been blinded, see [`crate::pset_blinding_status()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsetBlindingStatus {
+    /// True if every output to be blinded has its asset and value commitments
+    pub all_outputs_blinded: bool,
+
+    /// Indexes of the outputs with a blinding key but without the commitments
+    pub missing_output_indices: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
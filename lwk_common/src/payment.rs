@@ -0,0 +1,512 @@
+use std::str::FromStr;
+
+use elements::{Address, AddressParams, AssetId};
+
+use crate::precision::Precision;
+
+/// The kind of payment contained in a payment string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentKind {
+    /// A plain Liquid address
+    LiquidAddress,
+
+    /// A Liquid BIP21 URI, eg `liquidnetwork:<address>?amount=...&assetid=...`
+    LiquidBip21,
+
+    /// A BOLT11 Lightning invoice, with or without the `lightning:` prefix
+    LightningInvoice,
+}
+
+impl PaymentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentKind::LiquidAddress => "liquid_address",
+            PaymentKind::LiquidBip21 => "liquid_bip21",
+            PaymentKind::LightningInvoice => "lightning_invoice",
+        }
+    }
+}
+
+/// A payment string parsed by [`parse_payment()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payment {
+    /// The kind of the payment
+    pub kind: PaymentKind,
+
+    /// The Liquid address to pay, not present for Lightning invoices
+    pub address: Option<Address>,
+
+    /// The amount in satoshi, if specified
+    pub amount: Option<u64>,
+
+    /// The asset to pay, if specified
+    pub asset: Option<AssetId>,
+
+    /// The Lightning invoice, for Lightning invoices and for BIP21 URIs with a `lightning`
+    /// fallback, as in unified QR codes
+    pub invoice: Option<String>,
+
+    /// The BIP21 label for the recipient, percent-decoded
+    pub label: Option<String>,
+
+    /// The BIP21 message describing the payment, percent-decoded
+    pub message: Option<String>,
+
+    is_mainnet: bool,
+}
+
+impl Payment {
+    /// Whether the payment is meant for mainnet
+    ///
+    /// For Liquid payments it's derived from the address, for Lightning invoices from the
+    /// currency prefix
+    pub fn is_mainnet(&self) -> bool {
+        self.is_mainnet
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PaymentError {
+    #[error("Unrecognized payment string '{0}'")]
+    Unrecognized(String),
+
+    #[error("Invalid address in payment: {0}")]
+    Address(#[from] elements::AddressError),
+
+    #[error("Invalid amount '{0}' in payment")]
+    Amount(String),
+
+    #[error("Invalid asset '{0}' in payment")]
+    Asset(String),
+
+    #[error("Invalid asset precision {0}")]
+    Precision(u8),
+
+    #[error("The precision of asset {0} is needed to read the amount in payment")]
+    AmountPrecisionUnknown(AssetId),
+
+    #[error("Invalid Lightning invoice '{0}'")]
+    Invoice(String),
+
+    #[error("Unknown required parameter '{0}' in payment")]
+    UnknownRequiredParameter(String),
+
+    #[error("Invalid percent-encoding in '{0}'")]
+    Encoding(String),
+
+    #[error("Invalid BIP353 name '{0}'")]
+    Bip353Name(String),
+
+    #[error("BIP353 records don't contain a single Liquid BIP21 URI, found {0}")]
+    Bip353Records(usize),
+}
+
+const LIQUID_SCHEMES: [&str; 2] = ["liquidnetwork:", "liquidtestnet:"];
+
+/// Parse a payment string: a Liquid address, a Liquid BIP21 URI or a Lightning invoice
+///
+/// BIP21 amounts are in BTC units (eg `amount=0.0001`) and converted to satoshi.
+/// Amounts of other assets depend on the asset precision, which is unknown here, so BIP21 URIs
+/// with an amount and an `assetid` other than the Liquid or Liquid Testnet policy asset fail
+/// with [`PaymentError::AmountPrecisionUnknown`], use [`parse_payment_with_precision()`] for them.
+/// Lightning invoices are not fully decoded: only the network and the amount are read from the
+/// human readable part.
+pub fn parse_payment(s: &str) -> Result<Payment, PaymentError> {
+    parse_payment_inner(s, None)
+}
+
+/// Like [`parse_payment()`], but BIP21 amounts of an asset other than the policy asset are
+/// converted to satoshi with the given asset `precision`
+pub fn parse_payment_with_precision(s: &str, precision: u8) -> Result<Payment, PaymentError> {
+    let precision = Precision::new(precision).map_err(|_| PaymentError::Precision(precision))?;
+    parse_payment_inner(s, Some(precision))
+}
+
+fn parse_payment_inner(s: &str, precision: Option<Precision>) -> Result<Payment, PaymentError> {
+    let s = s.trim();
+    let lower = s.to_ascii_lowercase();
+
+    if let Some(scheme) = LIQUID_SCHEMES.iter().find(|p| lower.starts_with(*p)) {
+        return parse_bip21(&s[scheme.len()..], precision);
+    }
+
+    let invoice = lower.strip_prefix("lightning:").unwrap_or(&lower);
+    if invoice.starts_with("ln") {
+        return parse_invoice(invoice);
+    }
+
+    match Address::from_str(s) {
+        Ok(address) => Ok(Payment {
+            kind: PaymentKind::LiquidAddress,
+            is_mainnet: is_mainnet(&address),
+            address: Some(address),
+            amount: None,
+            asset: None,
+            invoice: None,
+            label: None,
+            message: None,
+        }),
+        Err(_) => Err(PaymentError::Unrecognized(s.to_string())),
+    }
+}
+
+fn is_mainnet(address: &Address) -> bool {
+    address.params == &AddressParams::LIQUID
+}
+
+/// The policy asset of the address network, unknown for regtest where it depends on the node
+fn policy_asset(address: &Address) -> Option<AssetId> {
+    let hex = if address.params == &AddressParams::LIQUID {
+        "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d"
+    } else if address.params == &AddressParams::LIQUID_TESTNET {
+        "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49"
+    } else {
+        return None;
+    };
+    Some(AssetId::from_str(hex).expect("valid asset id"))
+}
+
+fn parse_bip21(s: &str, precision: Option<Precision>) -> Result<Payment, PaymentError> {
+    let (address, query) = s.split_once('?').unwrap_or((s, ""));
+    let address = Address::from_str(address)?;
+    let mut amount = None;
+    let mut asset = None;
+    let mut label = None;
+    let mut message = None;
+    let mut invoice = None;
+    for param in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        match key {
+            // The parameters can be in any order, the amount is converted once the asset is known
+            "amount" => amount = Some(value),
+            "assetid" => {
+                let id =
+                    AssetId::from_str(value).map_err(|_| PaymentError::Asset(value.to_string()))?;
+                asset = Some(id);
+            }
+            "label" | "message" => {
+                let decoded = percent_decode(value)
+                    .ok_or_else(|| PaymentError::Encoding(value.to_string()))?;
+                if key == "label" {
+                    label = Some(decoded);
+                } else {
+                    message = Some(decoded);
+                }
+            }
+            "lightning" => {
+                let lightning = parse_invoice(&value.to_ascii_lowercase())?;
+                if lightning.is_mainnet != is_mainnet(&address) {
+                    return Err(PaymentError::Invoice(value.to_string()));
+                }
+                invoice = lightning.invoice;
+            }
+            // Per BIP21 unknown parameters must be rejected if prefixed with `req-`
+            _ if key.starts_with("req-") => {
+                return Err(PaymentError::UnknownRequiredParameter(key.to_string()))
+            }
+            _ => {}
+        }
+    }
+    let amount = match amount {
+        Some(value) => {
+            let precision = match asset {
+                Some(asset) if Some(asset) != policy_asset(&address) => {
+                    precision.ok_or(PaymentError::AmountPrecisionUnknown(asset))?
+                }
+                _ => Precision::new(8).expect("8 is the max precision"),
+            };
+            let sats = precision
+                .string_to_sats(value)
+                .ok()
+                .and_then(|sats| u64::try_from(sats).ok())
+                .ok_or_else(|| PaymentError::Amount(value.to_string()))?;
+            Some(sats)
+        }
+        None => None,
+    };
+    Ok(Payment {
+        kind: PaymentKind::LiquidBip21,
+        is_mainnet: is_mainnet(&address),
+        address: Some(address),
+        amount,
+        asset,
+        invoice,
+        label,
+        message,
+    })
+}
+
+/// Decode the percent-encoded characters of a URI query value, eg `%20` to a space
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn parse_invoice(invoice: &str) -> Result<Payment, PaymentError> {
+    let err = || PaymentError::Invoice(invoice.to_string());
+    // The human readable part is before the last separator '1'
+    let hrp = &invoice[..invoice.rfind('1').ok_or_else(err)?];
+    let hrp = hrp.strip_prefix("ln").ok_or_else(err)?;
+    // Longer prefixes first, since "bc" is a prefix of "bcrt"
+    let (is_mainnet, amount) = ["bcrt", "tbs", "tb", "bc"]
+        .iter()
+        .find_map(|c| hrp.strip_prefix(c).map(|a| (*c == "bc", a)))
+        .ok_or_else(err)?;
+
+    let amount = if amount.is_empty() {
+        None
+    } else {
+        let (digits, multiplier) = match amount.chars().last() {
+            Some(m @ ('m' | 'u' | 'n' | 'p')) => (&amount[..amount.len() - 1], Some(m)),
+            _ => (amount, None),
+        };
+        let value: u64 = digits.parse().map_err(|_| err())?;
+        // Amount in millisatoshi, the multipliers are relative to 1 BTC
+        let msats = match multiplier {
+            None => value.checked_mul(100_000_000_000),
+            Some('m') => value.checked_mul(100_000_000),
+            Some('u') => value.checked_mul(100_000),
+            Some('n') => value.checked_mul(100),
+            _ => Some(value / 10),
+        };
+        Some(msats.ok_or_else(err)? / 1_000)
+    };
+
+    Ok(Payment {
+        kind: PaymentKind::LightningInvoice,
+        address: None,
+        amount,
+        asset: None,
+        invoice: Some(invoice.to_string()),
+        label: None,
+        message: None,
+        is_mainnet,
+    })
+}
+
+/// The DNS name to query for the TXT records of a BIP353 name, eg `matt@mattcorallo.com` or
+/// `₿matt@mattcorallo.com` becomes `matt.user._bitcoin-payment.mattcorallo.com`
+///
+/// The lookup is left to the caller, which must validate the DNSSEC signatures of the records
+/// before passing them to [`parse_bip353_records()`].
+pub fn bip353_dns_name(name: &str) -> Result<String, PaymentError> {
+    let err = || PaymentError::Bip353Name(name.to_string());
+    let trimmed = name.trim();
+    let trimmed = trimmed.strip_prefix('₿').unwrap_or(trimmed);
+    let (user, domain) = trimmed.split_once('@').ok_or_else(err)?;
+    let valid = |s: &str| {
+        !s.is_empty()
+            && s.split('.').all(|label| !label.is_empty())
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    if !valid(user) || !valid(domain) {
+        return Err(err());
+    }
+    Ok(format!("{user}.user._bitcoin-payment.{domain}"))
+}
+
+/// Parse the TXT records of a BIP353 name into a payment
+///
+/// Records whose strings have been concatenated are expected, as returned by resolvers.
+/// Exactly one record must be a Liquid BIP21 URI, other records, like `bitcoin:` URIs, are
+/// ignored.
+pub fn parse_bip353_records(records: &[String]) -> Result<Payment, PaymentError> {
+    let uris: Vec<_> = records
+        .iter()
+        .map(|r| r.trim())
+        .filter(|r| {
+            let lower = r.to_ascii_lowercase();
+            LIQUID_SCHEMES.iter().any(|s| lower.starts_with(s))
+        })
+        .collect();
+    match uris.as_slice() {
+        [uri] => parse_payment(uri),
+        _ => Err(PaymentError::Bip353Records(uris.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR: &str = "tlq1qq2xvpcvfup5j8zscjq05u2wxxjcyewk7979f3mmz5l7uw5pqmx6xf5xy50hsn6vhkm5euwt72x878eq6zxx2z58hd7zrsg9qn";
+
+    #[test]
+    fn liquid_address() {
+        let p = parse_payment(ADDR).unwrap();
+        assert_eq!(p.kind, PaymentKind::LiquidAddress);
+        assert_eq!(p.address.as_ref().unwrap().to_string(), ADDR);
+        assert!(!p.is_mainnet());
+    }
+
+    #[test]
+    fn liquid_bip21() {
+        let asset = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+        let uri = format!("liquidnetwork:{ADDR}?amount=0.0001&assetid={asset}&label=test");
+        let p = parse_payment(&uri).unwrap();
+        assert_eq!(p.kind, PaymentKind::LiquidBip21);
+        assert_eq!(p.address.as_ref().unwrap().to_string(), ADDR);
+        assert_eq!(p.amount, Some(10_000));
+        assert_eq!(p.asset.unwrap().to_string(), asset);
+        assert!(!p.is_mainnet());
+
+        let p = parse_payment(&format!("liquidtestnet:{ADDR}")).unwrap();
+        assert_eq!(p.amount, None);
+        assert_eq!(p.asset, None);
+
+        // The amount is optional also when the asset is specified
+        let p = parse_payment(&format!("liquidnetwork:{ADDR}?assetid={asset}")).unwrap();
+        assert_eq!(p.kind, PaymentKind::LiquidBip21);
+        assert_eq!(p.amount, None);
+        assert_eq!(p.asset.unwrap().to_string(), asset);
+        assert_eq!(p.label, None);
+        assert_eq!(p.message, None);
+
+        let err = parse_payment(&format!("liquidnetwork:{ADDR}?amount=x")).unwrap_err();
+        assert!(matches!(err, PaymentError::Amount(_)));
+
+        // The amount of other assets needs their precision, whatever the parameters order
+        let other = "38fca2d939696061a8f76d4e6b5eecd54e3b4221c846f24a6b279e79952850a5";
+        for uri in [
+            format!("liquidnetwork:{ADDR}?amount=1.5&assetid={other}"),
+            format!("liquidnetwork:{ADDR}?assetid={other}&amount=1.5"),
+        ] {
+            let err = parse_payment(&uri).unwrap_err();
+            assert!(
+                matches!(err, PaymentError::AmountPrecisionUnknown(a) if a.to_string() == other)
+            );
+            let p = parse_payment_with_precision(&uri, 2).unwrap();
+            assert_eq!(p.amount, Some(150));
+            assert_eq!(p.asset.unwrap().to_string(), other);
+        }
+        let p = parse_payment(&format!("liquidnetwork:{ADDR}?assetid={other}")).unwrap();
+        assert_eq!(p.amount, None);
+        // The policy asset precision is known
+        let p = parse_payment_with_precision(&uri, 2).unwrap();
+        assert_eq!(p.amount, Some(10_000));
+    }
+
+    #[test]
+    fn scheme_case_insensitive() {
+        for scheme in ["LiquidNetwork", "LIQUIDTESTNET", "liquidNetwork"] {
+            let p = parse_payment(&format!("{scheme}:{ADDR}?amount=1")).unwrap();
+            assert_eq!(p.kind, PaymentKind::LiquidBip21);
+            assert_eq!(p.address.unwrap().to_string(), ADDR);
+        }
+
+        // The address keeps its case, mixed case bech32 is invalid
+        let mixed = format!("{}{}", &ADDR[..10], ADDR[10..].to_uppercase());
+        let err = parse_payment(&format!("LiquidNetwork:{mixed}")).unwrap_err();
+        assert!(matches!(err, PaymentError::Address(_)));
+    }
+
+    #[test]
+    fn liquid_bip21_label_message() {
+        let uri =
+            format!("liquidnetwork:{ADDR}?label=Luke-Jr&message=Donation%20for%20project%20xyz");
+        let p = parse_payment(&uri).unwrap();
+        assert_eq!(p.label.as_deref(), Some("Luke-Jr"));
+        assert_eq!(p.message.as_deref(), Some("Donation for project xyz"));
+
+        let p = parse_payment(&format!("liquidnetwork:{ADDR}?amount=1")).unwrap();
+        assert_eq!(p.label, None);
+        assert_eq!(p.message, None);
+
+        let err = parse_payment(&format!("liquidnetwork:{ADDR}?label=%zz")).unwrap_err();
+        assert!(matches!(err, PaymentError::Encoding(_)));
+
+        // Unknown parameters are ignored, unless they are required
+        parse_payment(&format!(
+            "liquidnetwork:{ADDR}?somethingyoudontunderstand=50"
+        ))
+        .unwrap();
+        let err = parse_payment(&format!("liquidnetwork:{ADDR}?req-somethingelse=50")).unwrap_err();
+        assert!(
+            matches!(err, PaymentError::UnknownRequiredParameter(k) if k == "req-somethingelse")
+        );
+    }
+
+    #[test]
+    fn bip353() {
+        let expected = "matt.user._bitcoin-payment.mattcorallo.com";
+        assert_eq!(bip353_dns_name("₿matt@mattcorallo.com").unwrap(), expected);
+        assert_eq!(bip353_dns_name("matt@mattcorallo.com").unwrap(), expected);
+        for invalid in [
+            "matt",
+            "@mattcorallo.com",
+            "matt@",
+            "ma tt@x.com",
+            "matt@x..com",
+        ] {
+            let err = bip353_dns_name(invalid).unwrap_err();
+            assert!(matches!(err, PaymentError::Bip353Name(_)), "{invalid}");
+        }
+
+        let uri = format!("liquidnetwork:{ADDR}?amount=0.0001");
+        let records = vec!["bitcoin:?lno=lno1xyz".to_string(), uri.clone()];
+        let p = parse_bip353_records(&records).unwrap();
+        assert_eq!(p.kind, PaymentKind::LiquidBip21);
+        assert_eq!(p.address.unwrap().to_string(), ADDR);
+        assert_eq!(p.amount, Some(10_000));
+
+        let err = parse_bip353_records(&records[..1]).unwrap_err();
+        assert!(matches!(err, PaymentError::Bip353Records(0)));
+        let err = parse_bip353_records(&[uri.clone(), uri]).unwrap_err();
+        assert!(matches!(err, PaymentError::Bip353Records(2)));
+        let err = parse_bip353_records(&["liquidnetwork:invalid".to_string()]).unwrap_err();
+        assert!(matches!(err, PaymentError::Address(_)));
+    }
+
+    #[test]
+    fn lightning_invoice() {
+        let invoice = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+        let p = parse_payment(&format!("lightning:{}", invoice.to_uppercase())).unwrap();
+        assert_eq!(p.kind, PaymentKind::LightningInvoice);
+        assert_eq!(p.invoice.as_deref(), Some(invoice));
+        assert_eq!(p.amount, Some(250_000));
+        assert!(p.address.is_none());
+        assert!(p.is_mainnet());
+
+        let p = parse_payment("lntb1pvjluezpp5").unwrap();
+        assert_eq!(p.amount, None);
+        assert!(!p.is_mainnet());
+
+        // Unified QR code, a BIP21 URI with a Lightning fallback
+        let invoice = "lntb10u1pvjluezpp5";
+        let uppercase = invoice.to_uppercase();
+        let uri = format!("liquidnetwork:{ADDR}?amount=0.00001&lightning={uppercase}");
+        let unified = parse_payment(&uri).unwrap();
+        assert_eq!(unified.kind, PaymentKind::LiquidBip21);
+        assert_eq!(unified.address.as_ref().unwrap().to_string(), ADDR);
+        assert_eq!(unified.amount, Some(1_000));
+        assert_eq!(unified.invoice.as_deref(), Some(invoice));
+        assert!(!unified.is_mainnet());
+
+        // The fallback must be valid and for the same network
+        let err = parse_payment(&format!("liquidnetwork:{ADDR}?lightning=x")).unwrap_err();
+        assert!(matches!(err, PaymentError::Invoice(_)));
+        let err =
+            parse_payment(&format!("liquidnetwork:{ADDR}?lightning=lnbc1pvjluezpp5")).unwrap_err();
+        assert!(matches!(err, PaymentError::Invoice(_)));
+    }
+
+    #[test]
+    fn unrecognized() {
+        let err = parse_payment("bitcoin:bc1q").unwrap_err();
+        assert!(matches!(err, PaymentError::Unrecognized(_)));
+    }
+}
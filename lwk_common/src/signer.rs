@@ -1,9 +1,7 @@
-use std::str::FromStr;
-
 use elements::{
     bitcoin::{
         self,
-        bip32::{DerivationPath, Fingerprint, Xpub},
+        bip32::{ChildNumber, DerivationPath, Fingerprint, KeySource, Xpub},
         XKeyIdentifier,
     },
     pset::PartiallySignedTransaction,
@@ -20,6 +18,20 @@ pub trait Signer {
     /// returns how many signatures were added or overwritten
     fn sign(&self, pset: &mut PartiallySignedTransaction) -> Result<u32, Self::Error>;
 
+    /// Like [`Signer::sign()`], but signing only the inputs at the given `indices`
+    ///
+    /// Useful for multi-party transactions, where a signer must sign only the inputs it is
+    /// authorized for. The default implementation signs all the inputs, signers able to restrict
+    /// what they sign should override it.
+    fn sign_inputs(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        indices: &[usize],
+    ) -> Result<u32, Self::Error> {
+        let _ = indices;
+        self.sign(pset)
+    }
+
     /// Derive an xpub from the master, path can contains hardened derivations
     fn derive_xpub(&self, path: &DerivationPath) -> Result<Xpub, Self::Error>;
 
@@ -41,18 +53,35 @@ pub trait Signer {
         Ok(self.xpub()?.fingerprint())
     }
 
-    /// Return keyorigin and xpub, like "[73c5da0a/84h/1h/0h]tpub..."
-    fn keyorigin_xpub(&self, bip: Bip, is_mainnet: bool) -> Result<String, Self::Error> {
+    /// Return keyorigin and xpub, like [`Signer::keyorigin_xpub()`] but without serializing them
+    fn xpub_with_origin(
+        &self,
+        bip: Bip,
+        is_mainnet: bool,
+    ) -> Result<(KeySource, Xpub), Self::Error> {
         let coin_type = if is_mainnet { 1776 } else { 1 };
-        let path = match bip {
-            Bip::Bip84 => format!("84h/{coin_type}h/0h"),
-            Bip::Bip49 => format!("49h/{coin_type}h/0h"),
-            Bip::Bip87 => format!("87h/{coin_type}h/0h"),
-        };
+        let path: DerivationPath = [bip.purpose(), coin_type, 0]
+            .iter()
+            .map(|i| ChildNumber::from_hardened_idx(*i).expect("static"))
+            .collect::<Vec<_>>()
+            .into();
 
         let fingerprint = self.fingerprint()?;
-        let xpub =
-            self.derive_xpub(&DerivationPath::from_str(&format!("m/{path}")).expect("static"))?; // TODO avoid string use ChildNumber directly
+        let xpub = self.derive_xpub(&path)?;
+        Ok(((fingerprint, path), xpub))
+    }
+
+    /// Return keyorigin and xpub, like "[73c5da0a/84h/1h/0h]tpub..."
+    fn keyorigin_xpub(&self, bip: Bip, is_mainnet: bool) -> Result<String, Self::Error> {
+        let ((fingerprint, path), xpub) = self.xpub_with_origin(bip, is_mainnet)?;
+        let path: Vec<_> = path
+            .into_iter()
+            .map(|c| match c {
+                ChildNumber::Hardened { index } => format!("{index}h"),
+                ChildNumber::Normal { index } => format!("{index}"),
+            })
+            .collect();
+        let path = path.join("/");
         let keyorigin_xpub = format!("[{fingerprint}/{path}]{xpub}");
         Ok(keyorigin_xpub)
     }
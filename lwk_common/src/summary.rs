@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use elements::pset::PartiallySignedTransaction;
+use elements::{Address, AddressParams, AssetId};
+use elements_miniscript::{ConfidentialDescriptor, DescriptorPublicKey};
+
+use crate::precision::Precision;
+use crate::{burn_script, is_mine, pset_balance, pset_issuances, Error};
+
+/// Render a one-line human readable summary of the PSET, for logs and confirmation dialogs
+///
+/// The summary contains the outputs not belonging to `descriptor`, the issuances, the net effect
+/// on the wallet and the fee, eg `Send 0.5 L-BTC to tlq1...; net -0.50000120 L-BTC; fee 120 sats`.
+/// Amounts of the assets in `asset_tickers` are shown with their ticker and precision, the
+/// others as satoshi followed by the asset id.
+pub fn pset_summary(
+    pset: &PartiallySignedTransaction,
+    descriptor: &ConfidentialDescriptor<DescriptorPublicKey>,
+    params: &'static AddressParams,
+    asset_tickers: &BTreeMap<AssetId, (String, Precision)>,
+) -> Result<String, Error> {
+    let balance = pset_balance(pset, descriptor)?;
+    let amount = |sats: i64, asset: &AssetId| match asset_tickers.get(asset) {
+        Some((ticker, precision)) => format!("{} {ticker}", precision.sats_to_string(sats)),
+        None => format!("{sats} {asset}"),
+    };
+
+    let mut sections = vec![];
+
+    let recipients: Vec<_> = pset
+        .outputs()
+        .iter()
+        .filter(|o| !o.script_pubkey.is_empty())
+        .filter(|o| !is_mine(&o.script_pubkey, descriptor, &o.bip32_derivation).unwrap_or(false))
+        .map(|o| {
+            let value = match (o.amount, o.asset) {
+                (Some(sats), Some(asset)) => amount(sats as i64, &asset),
+                _ => "a blinded amount".to_string(),
+            };
+            let destination = if o.script_pubkey == burn_script() {
+                "burn".to_string()
+            } else {
+                let blinder = o.blinding_key.map(|k| k.inner);
+                Address::from_script(&o.script_pubkey, blinder, params)
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| format!("{:x}", o.script_pubkey))
+            };
+            format!("{value} to {destination}")
+        })
+        .collect();
+    if !recipients.is_empty() {
+        sections.push(format!("Send {}", recipients.join(", ")));
+    }
+
+    for issuance in pset_issuances(pset) {
+        let kind = if issuance.is_issuance() {
+            "issue"
+        } else if issuance.is_reissuance() {
+            "reissue"
+        } else {
+            continue;
+        };
+        if let (Some(asset), Some(sats)) = (issuance.asset(), issuance.asset_satoshi()) {
+            if sats > 0 {
+                sections.push(format!("{kind} {}", amount(sats as i64, &asset)));
+            }
+        }
+    }
+
+    let net: Vec<_> = balance
+        .balances
+        .iter()
+        .filter(|(_, sats)| **sats != 0)
+        .map(|(asset, sats)| {
+            let sign = if *sats > 0 { "+" } else { "" };
+            format!("{sign}{}", amount(*sats, asset))
+        })
+        .collect();
+    if !net.is_empty() {
+        sections.push(format!("net {}", net.join(", ")));
+    }
+
+    sections.push(format!("fee {} sats", balance.fee));
+    Ok(sections.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use elements::bitcoin::PublicKey;
+    use elements::confidential::{Asset, Nonce, Value};
+    use elements::pset::{Input, Output, PartiallySignedTransaction};
+    use elements::{Address, AddressParams, AssetId, OutPoint, Script, TxOut, TxOutWitness};
+    use elements_miniscript::{ConfidentialDescriptor, DescriptorPublicKey};
+
+    use super::pset_summary;
+    use crate::precision::Precision;
+    use crate::pset_issuances;
+
+    const ADDR: &str = "tlq1qq2xvpcvfup5j8zscjq05u2wxxjcyewk7979f3mmz5l7uw5pqmx6xf5xy50hsn6vhkm5euwt72x878eq6zxx2z58hd7zrsg9qn";
+    const POLICY_ASSET: &str = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+
+    fn descriptor() -> ConfidentialDescriptor<DescriptorPublicKey> {
+        include_str!("../test_data/pset_details/descriptor")
+            .parse()
+            .unwrap()
+    }
+
+    /// A PSET spending a coin not in the wallet and sending 1000 sats to [`ADDR`]
+    fn send_pset() -> PartiallySignedTransaction {
+        let policy_asset = AssetId::from_str(POLICY_ASSET).unwrap();
+        let address = Address::from_str(ADDR).unwrap();
+        let mut pset = PartiallySignedTransaction::new_v2();
+
+        let mut input = Input::from_prevout(OutPoint::default());
+        input.witness_utxo = Some(TxOut {
+            asset: Asset::Explicit(policy_asset),
+            value: Value::Explicit(1_120),
+            nonce: Nonce::Null,
+            script_pubkey: Script::new(),
+            witness: TxOutWitness::default(),
+        });
+        pset.add_input(input);
+
+        let blinding_key = address.blinding_pubkey.map(PublicKey::new);
+        let script = address.script_pubkey();
+        pset.add_output(Output::new_explicit(
+            script,
+            1_000,
+            policy_asset,
+            blinding_key,
+        ));
+        pset.add_output(Output::new_explicit(Script::new(), 120, policy_asset, None));
+        pset
+    }
+
+    fn lbtc() -> BTreeMap<AssetId, (String, Precision)> {
+        let policy_asset = AssetId::from_str(POLICY_ASSET).unwrap();
+        let precision = Precision::new(8).unwrap();
+        BTreeMap::from([(policy_asset, ("L-BTC".to_string(), precision))])
+    }
+
+    #[test]
+    fn summary_send() {
+        let pset = send_pset();
+        let params = &AddressParams::LIQUID_TESTNET;
+        let summary = pset_summary(&pset, &descriptor(), params, &lbtc()).unwrap();
+        assert_eq!(
+            summary,
+            format!("Send 0.00001000 L-BTC to {ADDR}; fee 120 sats")
+        );
+
+        // Without tickers amounts are in satoshi
+        let summary = pset_summary(&pset, &descriptor(), params, &BTreeMap::new()).unwrap();
+        assert_eq!(
+            summary,
+            format!("Send 1000 {POLICY_ASSET} to {ADDR}; fee 120 sats")
+        );
+
+        // Net effect of a PSET spending wallet coins
+        let pset: PartiallySignedTransaction =
+            include_str!("../test_data/pset_details/pset2.base64")
+                .parse()
+                .unwrap();
+        let asset =
+            AssetId::from_str("38fca2d939696061a8f76d4e6b5eecd54e3b4221c846f24a6b279e79952850a5")
+                .unwrap();
+        let tickers = BTreeMap::from([(asset, ("TEST".to_string(), Precision::new(0).unwrap()))]);
+        let summary = pset_summary(&pset, &descriptor(), params, &tickers).unwrap();
+        assert!(summary.contains("net "), "{summary}");
+        assert!(summary.contains("-1 TEST"), "{summary}");
+        assert!(summary.contains("; fee "), "{summary}");
+    }
+
+    #[test]
+    fn summary_issuance() {
+        let mut pset = send_pset();
+        pset.inputs_mut()[0].issuance_value_amount = Some(10);
+        let asset = pset_issuances(&pset)[0].asset().unwrap();
+        let address = Address::from_str(ADDR).unwrap();
+        let blinding_key = address.blinding_pubkey.map(PublicKey::new);
+        pset.add_output(Output::new_explicit(
+            address.script_pubkey(),
+            10,
+            asset,
+            blinding_key,
+        ));
+
+        let mut tickers = lbtc();
+        tickers.insert(asset, ("FOO".to_string(), Precision::new(0).unwrap()));
+        let params = &AddressParams::LIQUID_TESTNET;
+        let summary = pset_summary(&pset, &descriptor(), params, &tickers).unwrap();
+        assert_eq!(
+            summary,
+            format!(
+                "Send 0.00001000 L-BTC to {ADDR}, 10 FOO to {ADDR}; issue 10 FOO; fee 120 sats"
+            )
+        );
+    }
+}
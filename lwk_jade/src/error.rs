@@ -110,6 +110,9 @@ pub enum Error {
     #[error("Unsupported descriptor variant, only multi or sortedmulti are supported")]
     UnsupportedDescriptorVariant,
 
+    #[error("Unsupported miniscript fragment '{0}', Jade registers only multi or sortedmulti policies on Liquid, other policies can be signed without registering them")]
+    UnsupportedMiniscriptFragment(String),
+
     #[error("Slip 77 master blinding keys must be 32 bytes")]
     Slip77MasterBlindingKeyInvalidSize,
 
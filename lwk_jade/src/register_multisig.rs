@@ -87,7 +87,11 @@ impl TryFrom<&ConfidentialDescriptor<DescriptorPublicKey>> for JadeDescriptor {
                             signers.push(pk.try_into()?);
                         }
                     } else {
-                        return Err(Error::UnsupportedDescriptorVariant);
+                        // Jade signs inputs of any wsh policy given the witness script, but on
+                        // Liquid it can register only multisigs, used to recognize the change
+                        let policy = x.to_string();
+                        let fragment = policy.split('(').next().unwrap_or_default();
+                        return Err(Error::UnsupportedMiniscriptFragment(fragment.to_string()));
                     }
                 }
             },
@@ -240,6 +244,7 @@ mod test {
     use crate::register_multisig::MultisigSigner;
 
     use super::{JadeDescriptor, RegisterMultisigParams};
+    use crate::Error;
 
     #[test]
     fn parse_register_multisig() {
@@ -292,4 +297,31 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn unsupported_miniscript_fragment() {
+        let a= "tpubDDCNstnPhbdd4vwbw5UWK3vRQSF1WXQkvBHpNXpKJAkwFYjwu735EH3GVf53qwbWimzewDUv68MUmRDgYtQ1AU8FRCPkazfuaBp7LaEaohG";
+        let b  = "tpubDDExQpZg2tziZ7ACSBCYsY3rYxAZtTRBgWwioRLYqgNBguH6rMHN1D8epTxUQUB5kM5nxkEtr2SNic6PJLPubcGMR6S2fmDZTzL9dHpU7ka";
+        let slip77_key = "9c8e4f05c7711a98c838be228bcb84924d4570ca53f35fa1c793e58841d47023";
+        let policies = [
+            (
+                "thresh",
+                format!("thresh(2,pk({a}/<0;1>/*),s:pk({b}/<0;1>/*))"),
+            ),
+            (
+                "or_d",
+                format!("or_d(pk({a}/<0;1>/*),and_v(v:pk({b}/<0;1>/*),older(144)))"),
+            ),
+        ];
+        for (fragment, policy) in policies {
+            let desc = format!("ct(slip77({slip77_key}),elwsh({policy}))");
+            let desc: ConfidentialDescriptor<DescriptorPublicKey> = desc.parse().unwrap();
+            let err = JadeDescriptor::try_from(&desc).unwrap_err();
+            match &err {
+                Error::UnsupportedMiniscriptFragment(f) => assert_eq!(f, fragment),
+                e => panic!("unexpected error {e}"),
+            }
+            assert!(err.to_string().contains("signed without registering"));
+        }
+    }
 }
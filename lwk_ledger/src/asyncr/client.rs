@@ -1,5 +1,7 @@
 use core::fmt::Debug;
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Mutex;
 
 use crate::{
     apdu::{APDUCmdVec, StatusWord},
@@ -23,11 +25,29 @@ use elements_miniscript::slip77::MasterBlindingKey;
 #[derive(Debug)]
 pub struct LiquidClient<T: Transport> {
     transport: T,
+
+    /// Xpubs already returned by the device, without displaying them
+    ///
+    /// Keyed by the full path, hardened and non-hardened steps are different [`ChildNumber`]s.
+    ///
+    /// [`ChildNumber`]: elements_miniscript::elements::bitcoin::bip32::ChildNumber
+    xpub_cache: Mutex<HashMap<DerivationPath, Xpub>>,
 }
 
 impl<T: Transport> LiquidClient<T> {
     pub fn new(transport: T) -> Self {
-        Self { transport }
+        Self {
+            transport,
+            xpub_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forget the xpubs returned by the device, next requests will query it again
+    pub fn clear_xpub_cache(&self) {
+        self.xpub_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
     }
 
     async fn make_request(
@@ -125,15 +145,26 @@ impl<T: Transport> LiquidClient<T> {
         path: &DerivationPath,
         display: bool,
     ) -> Result<Xpub, LiquidClientError<T::Error>> {
+        if !display {
+            let cache = self.xpub_cache.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(xpub) = cache.get(path) {
+                return Ok(*xpub);
+            }
+        }
         let cmd = command::get_extended_pubkey(path, display);
-        self.make_request(&cmd, None).await.and_then(|data| {
+        let xpub = self.make_request(&cmd, None).await.and_then(|data| {
             Xpub::from_str(&String::from_utf8_lossy(&data)).map_err(|_| {
                 LiquidClientError::UnexpectedResult {
                     command: cmd.ins,
                     data,
                 }
             })
-        })
+        })?;
+        self.xpub_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.clone(), xpub);
+        Ok(xpub)
     }
 
     /// Registers the given wallet policy, returns the wallet ID and HMAC.
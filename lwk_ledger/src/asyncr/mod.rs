@@ -7,7 +7,9 @@ use elements_miniscript::{
 };
 pub use transport_tcp::TransportTcp;
 
-use crate::{parse_multisig, AddressType, Error, Version, WalletPolicy, WalletPubKey};
+use crate::{
+    add_partial_signature, parse_multisig, AddressType, Error, Version, WalletPolicy, WalletPubKey,
+};
 
 mod client;
 mod transport_tcp;
@@ -82,7 +84,7 @@ impl Ledger<TransportTcp> {
                         // Do we care about the descriptor blinding key here?
                         let name = "".to_string();
                         let version = Version::V2;
-                        // TODO: cache xpubs
+                        // The client caches the xpubs, inputs of the same account query the device once
                         let xpub = self
                             .client
                             .get_extended_pubkey(&path, false)
@@ -183,18 +185,7 @@ impl Ledger<TransportTcp> {
             // Add sigs to pset
             for (input_idx, sig) in partial_sigs {
                 let input = &mut pset.inputs_mut()[input_idx];
-                for (public_key, (fp, _origin)) in &input.bip32_derivation {
-                    if fp == &master_fp {
-                        // TODO: user the pubkey from PartialSignature to insert in partial_sigs
-                        let sig_vec = match sig {
-                            PartialSignature::Sig(_, sig) => sig.to_vec(),
-                            _ => panic!("FIXME: support taproot sig or raise error"),
-                        };
-                        input.partial_sigs.insert(*public_key, sig_vec);
-                        // FIXME: handle cases where we have multiple pubkeys with master fingerprint
-                        break;
-                    }
-                }
+                add_partial_signature(input, &master_fp, sig)?;
             }
         }
 
@@ -1,5 +1,7 @@
 use core::fmt::Debug;
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Mutex;
 
 use elements_miniscript::elements::bitcoin::{
     bip32::{DerivationPath, Fingerprint, Xpub},
@@ -25,11 +27,29 @@ use crate::{
 #[derive(Debug)]
 pub struct LiquidClient<T: Transport> {
     transport: T,
+
+    /// Xpubs already returned by the device, without displaying them
+    ///
+    /// Keyed by the full path, hardened and non-hardened steps are different [`ChildNumber`]s.
+    ///
+    /// [`ChildNumber`]: elements_miniscript::elements::bitcoin::bip32::ChildNumber
+    xpub_cache: Mutex<HashMap<DerivationPath, Xpub>>,
 }
 
 impl<T: Transport> LiquidClient<T> {
     pub fn new(transport: T) -> Self {
-        Self { transport }
+        Self {
+            transport,
+            xpub_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forget the xpubs returned by the device, next requests will query it again
+    pub fn clear_xpub_cache(&self) {
+        self.xpub_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
     }
 
     fn make_request(
@@ -118,20 +138,33 @@ impl<T: Transport> LiquidClient<T> {
 
     /// Retrieve the bip32 extended pubkey derived with the given path
     /// and optionally display it on screen
+    ///
+    /// Xpubs not displayed are cached, see [`LiquidClient::clear_xpub_cache()`].
     pub fn get_extended_pubkey(
         &self,
         path: &DerivationPath,
         display: bool,
     ) -> Result<Xpub, LiquidClientError<T::Error>> {
+        if !display {
+            let cache = self.xpub_cache.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(xpub) = cache.get(path) {
+                return Ok(*xpub);
+            }
+        }
         let cmd = command::get_extended_pubkey(path, display);
-        self.make_request(&cmd, None).and_then(|data| {
+        let xpub = self.make_request(&cmd, None).and_then(|data| {
             Xpub::from_str(&String::from_utf8_lossy(&data)).map_err(|_| {
                 LiquidClientError::UnexpectedResult {
                     command: cmd.ins,
                     data,
                 }
             })
-        })
+        })?;
+        self.xpub_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.clone(), xpub);
+        Ok(xpub)
     }
 
     /// Registers the given wallet policy, returns the wallet ID and HMAC.
@@ -362,3 +395,55 @@ pub trait Transport {
     type Error: Debug;
     fn exchange(&self, command: &APDUCmdVec) -> Result<(StatusWord, Vec<u8>), Self::Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use elements_miniscript::elements::bitcoin::bip32::DerivationPath;
+
+    use super::{LiquidClient, Transport};
+    use crate::apdu::{APDUCmdVec, StatusWord};
+
+    /// Answers every command with the same xpub, counting the exchanges
+    struct MockTransport {
+        calls: Cell<usize>,
+    }
+
+    impl Transport for MockTransport {
+        type Error = ();
+        fn exchange(&self, _command: &APDUCmdVec) -> Result<(StatusWord, Vec<u8>), ()> {
+            self.calls.set(self.calls.get() + 1);
+            let xpub = "tpubDCwYjpDhUdPGP5rS3wgNg13mTrrjBuG8V9VpWbyptX6TRPbNoZVXsoVUSkCjmQ8jJycjuDKBb9eataSymXakTTaGifxR6kmVsfFehH1ZgJT";
+            Ok((StatusWord::OK, xpub.as_bytes().to_vec()))
+        }
+    }
+
+    #[test]
+    fn xpub_cache() {
+        let client = LiquidClient::new(MockTransport {
+            calls: Cell::new(0),
+        });
+        let calls = || client.transport.calls.get();
+
+        let account: DerivationPath = "m/84h/1h/0h".parse().unwrap();
+        for _ in 0..10 {
+            client.get_extended_pubkey(&account, false).unwrap();
+        }
+        assert_eq!(calls(), 1);
+
+        // Same indexes but not hardened
+        let unhardened: DerivationPath = "m/84/1/0".parse().unwrap();
+        client.get_extended_pubkey(&unhardened, false).unwrap();
+        assert_eq!(calls(), 2);
+
+        // Displaying always queries the device
+        client.get_extended_pubkey(&account, true).unwrap();
+        assert_eq!(calls(), 3);
+
+        client.clear_xpub_cache();
+        client.get_extended_pubkey(&account, false).unwrap();
+        client.get_extended_pubkey(&unhardened, false).unwrap();
+        assert_eq!(calls(), 5);
+    }
+}
@@ -24,15 +24,23 @@ pub use ledger_emulator::TestLedgerEmulator;
 // https://github.com/LedgerHQ/app-bitcoin-new/tree/master/bitcoin_client_rs
 pub use client::LiquidClient;
 use client::Transport;
+use error::LiquidClientError;
 pub use psbt::PartialSignature;
 pub use transport_tcp::TransportTcp;
+
+#[cfg(feature = "serial")]
+pub use transport_hid::TransportHID;
 pub use wallet::{AddressType, Version, WalletPolicy, WalletPubKey};
 
 use elements_miniscript::confidential::slip77;
 use elements_miniscript::elements::bitcoin::bip32::{
     ChildNumber, DerivationPath, Fingerprint, Xpub,
 };
-use elements_miniscript::elements::pset::PartiallySignedTransaction;
+use elements_miniscript::elements::bitcoin::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use elements_miniscript::elements::bitcoin::sign_message::MessageSignature;
+use elements_miniscript::elements::hashes::Hash;
+use elements_miniscript::elements::pset::{Input, PartiallySignedTransaction};
+use elements_miniscript::elements::taproot::TapLeafHash;
 use elements_miniscript::elements::{
     bitcoin::key::PublicKey,
     opcodes::{
@@ -40,7 +48,7 @@ use elements_miniscript::elements::{
         All,
     },
     script::Instruction,
-    Script,
+    SchnorrSig, SchnorrSighashType, Script,
 };
 
 use lwk_common::Signer;
@@ -60,16 +68,44 @@ impl Ledger<TransportTcp> {
 
 #[cfg(feature = "serial")]
 impl Ledger<transport_hid::TransportHID> {
-    pub fn new_hid() -> Self {
-        let h = ledger_transport_hid::hidapi::HidApi::new().expect("unable to get HIDAPI");
-        let hid = ledger_transport_hid::TransportNativeHID::new(&h).unwrap();
+    /// Connect to the first Ledger device found via USB HID
+    pub fn new_hid() -> Result<Self, Error> {
+        let h = ledger_transport_hid::hidapi::HidApi::new()
+            .map_err(|e| LiquidClientError::ClientError(format!("unable to get HIDAPI: {e}")))?;
+        let hid = ledger_transport_hid::TransportNativeHID::new(&h)
+            .map_err(|e| LiquidClientError::ClientError(e.to_string()))?;
         let client = LiquidClient::new(transport_hid::TransportHID::new(hid));
-        Self { client }
+        Ok(Self { client })
     }
 }
 
 pub type Error = error::LiquidClientError<TransportTcp>;
 
+impl<T: Transport> Ledger<T> {
+    /// Sign `message` with the key derived at `path`, returning a BIP137 message signature
+    ///
+    /// The signature is over the "Bitcoin Signed Message" hash of `message`, the public key can
+    /// be recovered with [`MessageSignature::recover_pubkey()`].
+    pub fn sign_message(
+        &self,
+        message: &str,
+        path: &DerivationPath,
+    ) -> Result<MessageSignature, LiquidClientError<T::Error>> {
+        let (header, signature) = self.client.sign_message(message.as_bytes(), path)?;
+        let invalid = || LiquidClientError::InvalidResponse(format!("signature header {header}"));
+        // 27 + 4 + recovery id for compressed keys
+        let recovery_id = header
+            .checked_sub(31)
+            .filter(|id| *id < 4)
+            .ok_or_else(invalid)?;
+        let recovery_id = RecoveryId::from_i32(recovery_id as i32).map_err(|_| invalid())?;
+        let signature =
+            RecoverableSignature::from_compact(&signature.serialize_compact(), recovery_id)
+                .map_err(|_| invalid())?;
+        Ok(MessageSignature::new(signature, true))
+    }
+}
+
 impl<T: Transport> Signer for &Ledger<T> {
     type Error = crate::Error;
 
@@ -125,7 +161,7 @@ impl<T: Transport> Signer for &Ledger<T> {
                         // Do we care about the descriptor blinding key here?
                         let name = "".to_string();
                         let version = Version::V2;
-                        // TODO: cache xpubs
+                        // The client caches the xpubs, inputs of the same account query the device once
                         let xpub = self
                             .client
                             .get_extended_pubkey(&path, false)
@@ -220,18 +256,7 @@ impl<T: Transport> Signer for &Ledger<T> {
             // Add sigs to pset
             for (input_idx, sig) in partial_sigs {
                 let input = &mut pset.inputs_mut()[input_idx];
-                for (public_key, (fp, _origin)) in &input.bip32_derivation {
-                    if fp == &master_fp {
-                        // TODO: user the pubkey from PartialSignature to insert in partial_sigs
-                        let sig_vec = match sig {
-                            PartialSignature::Sig(_, sig) => sig.to_vec(),
-                            _ => panic!("FIXME: support taproot sig or raise error"),
-                        };
-                        input.partial_sigs.insert(*public_key, sig_vec);
-                        // FIXME: handle cases where we have multiple pubkeys with master fingerprint
-                        break;
-                    }
-                }
+                add_partial_signature(input, &master_fp, sig)?;
             }
         }
 
@@ -278,6 +303,48 @@ impl<T: Transport> Signer for Ledger<T> {
     }
 }
 
+/// Add a signature returned by the Ledger to the PSET input
+///
+/// ECDSA signatures go in `partial_sigs`, schnorr signatures in `tap_key_sig` for key path spends
+/// or in `tap_script_sigs` for script path spends.
+pub(crate) fn add_partial_signature(
+    input: &mut Input,
+    master_fp: &Fingerprint,
+    sig: PartialSignature,
+) -> Result<(), Error> {
+    match sig {
+        PartialSignature::Sig(_, sig) => {
+            // TODO: user the pubkey from PartialSignature to insert in partial_sigs
+            let public_key = input
+                .bip32_derivation
+                .iter()
+                .find(|(_, (fp, _))| fp == master_fp)
+                .map(|(public_key, _)| *public_key);
+            // FIXME: handle cases where we have multiple pubkeys with master fingerprint
+            if let Some(public_key) = public_key {
+                input.partial_sigs.insert(public_key, sig.to_vec());
+            }
+        }
+        PartialSignature::TapScriptSig(key, leaf_hash, sig) => {
+            let hash_ty = SchnorrSighashType::from_u8(sig.sighash_type as u8).ok_or_else(|| {
+                Error::InvalidResponse(format!("Unsupported sighash {}", sig.sighash_type))
+            })?;
+            let sig = SchnorrSig {
+                sig: sig.signature,
+                hash_ty,
+            };
+            match leaf_hash {
+                None => input.tap_key_sig = Some(sig),
+                Some(leaf_hash) => {
+                    let leaf_hash = TapLeafHash::from_byte_array(leaf_hash.to_byte_array());
+                    input.tap_script_sigs.insert((key, leaf_hash), sig);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 // "duplicated" from Jade
 // taken and adapted from:
 // https://github.com/rust-bitcoin/rust-bitcoin/blob/37daf4620c71dc9332c3e08885cf9de696204bca/bitcoin/src/blockdata/script/borrowed.rs#L266
@@ -344,3 +411,53 @@ fn parse_multisig(script: &Script) -> Option<(u32, Vec<PublicKey>)> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use elements_miniscript::elements::bitcoin::bip32::Fingerprint;
+    use elements_miniscript::elements::bitcoin::secp256k1::{Keypair, Message, Secp256k1};
+    use elements_miniscript::elements::pset::{Input, PartiallySignedTransaction};
+    use elements_miniscript::elements::{OutPoint, SchnorrSighashType};
+
+    use super::{add_partial_signature, PartialSignature};
+
+    fn count_tap_sigs(input: &Input) -> usize {
+        input.tap_key_sig.iter().count() + input.tap_script_sigs.len()
+    }
+
+    #[test]
+    fn taproot_partial_signatures() {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_slice(&secp, &[1u8; 32]).unwrap();
+        let (key, _) = keypair.x_only_public_key();
+        let sig = secp.sign_schnorr_no_aux_rand(&Message::from_digest([2u8; 32]), &keypair);
+        let fp = Fingerprint::default();
+
+        let mut pset = PartiallySignedTransaction::new_v2();
+        pset.add_input(Input::from_prevout(OutPoint::default()));
+
+        // Key path, as returned by the Ledger: key augment length, key, signature
+        let mut slice = vec![32];
+        slice.extend(key.serialize());
+        slice.extend(sig.as_ref());
+        let partial_sig = PartialSignature::from_slice(&slice).unwrap();
+        let input = &mut pset.inputs_mut()[0];
+        add_partial_signature(input, &fp, partial_sig).unwrap();
+        assert_eq!(count_tap_sigs(input), 1);
+        let tap_key_sig = input.tap_key_sig.unwrap();
+        assert_eq!(tap_key_sig.sig, sig);
+        assert_eq!(tap_key_sig.hash_ty, SchnorrSighashType::Default);
+        assert!(input.partial_sigs.is_empty());
+
+        // Script path, the key is followed by the leaf hash
+        let mut slice = vec![64];
+        slice.extend(key.serialize());
+        slice.extend([3u8; 32]);
+        slice.extend(sig.as_ref());
+        let partial_sig = PartialSignature::from_slice(&slice).unwrap();
+        add_partial_signature(input, &fp, partial_sig).unwrap();
+        assert_eq!(count_tap_sigs(input), 2);
+        let ((k, _), s) = input.tap_script_sigs.iter().next().unwrap();
+        assert_eq!((*k, s.sig), (key, sig));
+    }
+}
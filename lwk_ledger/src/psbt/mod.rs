@@ -54,7 +54,7 @@ impl PartialSignature {
         } else if key_augment_len == 32 {
             let key = XOnlyPublicKey::from_slice(&slice[1..33])
                 .map_err(PartialSignatureError::XOnlyPubKey)?;
-            let sig = taproot::Signature::from_slice(&slice[65..])
+            let sig = taproot::Signature::from_slice(&slice[33..])
                 .map_err(PartialSignatureError::TaprootSig)?;
             Ok(Self::TapScriptSig(key, None, sig))
         } else {
@@ -7,6 +7,12 @@ use std::error::Error;
 /// Transport with the Ledger device.
 pub struct TransportHID(TransportNativeHID);
 
+impl std::fmt::Debug for TransportHID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TransportHID")
+    }
+}
+
 impl TransportHID {
     pub fn new(t: TransportNativeHID) -> Self {
         Self(t)
@@ -20,6 +20,10 @@ pub struct TransportTcp {
 impl TransportTcp {
     pub fn new(port: u16) -> Result<Self, Box<dyn Error>> {
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port);
+        Self::from_socket(addr)
+    }
+
+    pub fn from_socket(addr: SocketAddr) -> Result<Self, Box<dyn Error>> {
         let stream = TcpStream::connect(addr)?;
         Ok(Self {
             connection: Mutex::new(stream),
@@ -1,4 +1,6 @@
 use elements_miniscript::elements::bitcoin::bip32::DerivationPath;
+use elements_miniscript::elements::bitcoin::secp256k1::Secp256k1;
+use elements_miniscript::elements::bitcoin::sign_message::signed_msg_hash;
 use elements_miniscript::elements::pset::PartiallySignedTransaction;
 use elements_miniscript::elements::AddressParams;
 use lwk_containers::testcontainers::clients;
@@ -130,6 +132,31 @@ fn test_ledger_commands() {
     assert_eq!(sig, expected);
 }
 
+#[test]
+fn test_ledger_sign_message() {
+    let docker = clients::Cli::default();
+    let ledger = LedgerEmulator::new().expect("test");
+    let container = docker.run(ledger);
+    let port = container.get_host_port_ipv4(LEDGER_EMULATOR_PORT);
+    let ledger = Ledger::new(port);
+
+    let path: DerivationPath = "m/44h/1h/0h/0/0".parse().unwrap();
+    let message = "Hello world!";
+    let signature = ledger.sign_message(message, &path).unwrap();
+    assert!(signature.compressed);
+
+    let secp = Secp256k1::verification_only();
+    let msg_hash = signed_msg_hash(message);
+    let pubkey = signature.recover_pubkey(&secp, msg_hash).unwrap();
+    let xpub = ledger.client.get_extended_pubkey(&path, false).unwrap();
+    assert_eq!(pubkey.inner, xpub.public_key);
+
+    // A different message recovers a different key
+    let msg_hash = signed_msg_hash("Hello world");
+    let pubkey = signature.recover_pubkey(&secp, msg_hash).unwrap();
+    assert_ne!(pubkey.inner, xpub.public_key);
+}
+
 #[cfg(feature = "serial")]
 #[ignore = "requires hardware ledger connected via usb"]
 #[test]
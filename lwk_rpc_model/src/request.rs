@@ -39,6 +39,13 @@ pub struct WalletLoad {
     /// The read-only descriptor describing the wallet outputs
     pub descriptor: String,
 
+    /// Optional descriptor for the change (internal) outputs
+    ///
+    /// If set, `descriptor` must be the receive (external) descriptor ending with `/0/*` and this
+    /// a descriptor with the same keys and blinding key ending with `/1/*`. If they have the same
+    /// script they are combined in a single multipath descriptor
+    pub change_descriptor: Option<String>,
+
     /// The name given to the wallet, will be needed for calls related to the wallet
     pub name: String,
 }
@@ -57,7 +64,19 @@ pub struct SignerLoadSoftware {
     pub name: String,
 
     /// The mnemonic (12 or 24 words)
-    pub mnemonic: String,
+    ///
+    /// Either this or `mnemonic_file` must be set
+    pub mnemonic: Option<String>,
+
+    /// Path of a file containing the mnemonic, read by the server
+    ///
+    /// Avoids sending the mnemonic in the request when client and server are on the same
+    /// machine. If the signer is persisted, the path is persisted instead of the mnemonic, so
+    /// the file is needed also at following restarts.
+    pub mnemonic_file: Option<String>,
+
+    /// Optional BIP39 passphrase, also known as 25th word
+    pub passphrase: Option<String>,
 
     /// Whether to persist the software signer
     pub persist: bool,
@@ -74,6 +93,24 @@ pub struct SignerLoadJade {
 
     /// If set, instead of looking for physical jade, try to connect to the emulator at the following port
     pub emulator: Option<SocketAddr>,
+
+    /// If set, loading fails if the signer fingerprint is different, 4 bytes as 8 hex characters
+    pub expected_fingerprint: Option<String>,
+}
+
+/// Load a signer in the server
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SignerLoadLedger {
+    /// The name of the signer, will be needed to reference it in other calls
+    pub name: String,
+
+    /// Connect to the Ledger emulator (speculos) at the following address
+    ///
+    /// If not set, connect to a physical device via USB HID, which needs the `serial` feature
+    pub emulator: Option<SocketAddr>,
+
+    /// If set, loading fails if the signer fingerprint is different, 4 bytes as 8 hex characters
+    pub expected_fingerprint: Option<String>,
 }
 
 /// Load a signer in the server
@@ -119,6 +156,36 @@ pub struct WalletAddress {
     pub with_uri_qr: Option<u8>,
 }
 
+/// Request consecutive receive addresses of a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletAddressBatch {
+    /// The wallet name
+    pub name: String,
+
+    /// The derivation index of the first address, if missing the first unused index is used
+    pub start_index: Option<u32>,
+
+    /// The number of addresses, at most 1000
+    pub count: u32,
+
+    /// Whether to return a QR code of every address encoded as text
+    #[serde(default)]
+    pub with_text_qr: bool,
+
+    /// Return a image QR code of every address encoded as uri with the given pixel per module
+    pub with_uri_qr: Option<u8>,
+}
+
+/// Request to check which addresses belong to a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletAddressInfoBatch {
+    /// The wallet name
+    pub name: String,
+
+    /// The addresses to check
+    pub addresses: Vec<String>,
+}
+
 /// The balance of a wallet
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct WalletBalance {
@@ -127,6 +194,10 @@ pub struct WalletBalance {
 
     /// Replace asset ids with tickers when possible
     pub with_tickers: bool,
+
+    /// Round the amounts down to their order of magnitude, eg 1234 to 1000
+    #[serde(default)]
+    pub hide_amounts: bool,
 }
 
 /// Send a transaction from a wallet
@@ -136,6 +207,9 @@ pub struct WalletSendMany {
     pub name: String,
 
     /// Recipient addressees
+    ///
+    /// An output carries a single asset, the same address can appear with different assets and
+    /// receives an output for each.
     pub addressees: Vec<UnvalidatedAddressee>,
 
     /// Optional fee rate in sat/kvB
@@ -147,6 +221,37 @@ pub struct WalletSendMany {
     pub enable_ct_discount: bool,
 }
 
+/// Request to send to many recipients, at the fee rate estimated to confirm within a target
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletSendManySmartFee {
+    /// The wallet name creating the transaction
+    pub name: String,
+
+    /// Recipient addressees
+    pub addressees: Vec<UnvalidatedAddressee>,
+
+    /// The number of blocks in which the transaction should confirm
+    pub target_blocks: u32,
+}
+
+/// Send to a payment string from a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletSendToPayment {
+    /// The wallet name creating the transaction
+    pub name: String,
+
+    /// A Liquid address or a Liquid BIP21 URI
+    pub uri: String,
+
+    /// The amount to send in satoshi, overriding the one in the URI if any
+    ///
+    /// Required if the URI does not contain an amount
+    pub amount_override: Option<u64>,
+
+    /// Optional fee rate in sat/kvB
+    pub fee_rate: Option<f32>,
+}
+
 ///  An addressee which has yet to be validated
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct UnvalidatedAddressee {
@@ -190,6 +295,22 @@ pub struct SignerSinglesigDescriptor {
     pub singlesig_kind: String,
 }
 
+/// A request to derive the singlesig descriptor of an account of a signer
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SignerAccountDescriptor {
+    /// The signer name
+    pub name: String,
+
+    /// The account number, the last hardened step of the derivation path
+    pub account: u32,
+
+    /// The descriptor blinding key
+    pub descriptor_blinding_key: String,
+
+    /// The singlesig kind
+    pub singlesig_kind: String,
+}
+
 /// A request containing information to create a multi signature descriptor wallet
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct WalletMultisigDescriptor {
@@ -204,6 +325,10 @@ pub struct WalletMultisigDescriptor {
 
     /// The partecipants in the multisig wallet xpubs with key origin
     pub keyorigin_xpubs: Vec<String>,
+
+    /// The names of loaded signers partecipating in the multisig wallet, their bip87 xpubs are used
+    #[serde(default)]
+    pub signers: Vec<String>,
 }
 
 /// Request to register a multisig wallet on a signer
@@ -249,6 +374,75 @@ pub struct WalletBroadcast {
     pub pset: String,
 }
 
+/// Request to preview the transaction that broadcasting a PSET would produce
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletPreviewTx {
+    /// The wallet name
+    pub name: String,
+
+    /// The PSET in base64, it must be complete
+    pub pset: String,
+}
+
+/// Request to add the wallet details to a PSET created elsewhere, so that signers can sign it
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletAnnotatePset {
+    /// The wallet name
+    pub name: String,
+
+    /// The PSET in base64
+    pub pset: String,
+}
+
+/// Request to blind the outputs of a PSET created elsewhere
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletBlindPset {
+    /// The wallet name
+    pub name: String,
+
+    /// The unblinded PSET in base64
+    pub pset: String,
+}
+
+/// Request to add the missing previous outputs data to the inputs of a PSET
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletHydratePset {
+    /// The wallet name
+    pub name: String,
+
+    /// The PSET in base64
+    pub pset: String,
+
+    /// Indexes of other inputs to hydrate, besides the ones spending wallet outputs
+    ///
+    /// Their previous transactions are downloaded if they are not wallet transactions
+    #[serde(default)]
+    pub inputs: Vec<usize>,
+}
+
+/// Request to create a proof of reserves of the wallet UTXOs
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletProofOfReserves {
+    /// The wallet name
+    pub name: String,
+
+    /// The challenge message the proof commits to
+    pub challenge: String,
+}
+
+/// Request to verify a signed proof of reserves
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletVerifyProofOfReserves {
+    /// The wallet name
+    pub name: String,
+
+    /// The challenge message the proof commits to
+    pub challenge: String,
+
+    /// The signed proof, a PSET in base64
+    pub pset: String,
+}
+
 /// Request details for a wallet
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct WalletDetails {
@@ -256,6 +450,20 @@ pub struct WalletDetails {
     pub name: String,
 }
 
+/// Request the spending policy of a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletPolicy {
+    /// The wallet name
+    pub name: String,
+}
+
+/// Request the descriptor of a wallet, checking that it can be shared as a watch-only descriptor
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletPublicDescriptor {
+    /// The wallet name
+    pub name: String,
+}
+
 /// Request to do an issuance
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct WalletIssue {
@@ -281,6 +489,38 @@ pub struct WalletIssue {
     pub fee_rate: Option<f32>,
 }
 
+/// An issuance in a [`WalletIssueMany`] request
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IssuanceSpec {
+    /// The number of units of the asset created
+    pub satoshi_asset: u64,
+
+    /// The address receiving the asset, if missing a receiving address from the wallet doing the issuance is used
+    pub address_asset: Option<String>,
+
+    /// The number of reissuance token to be created
+    pub satoshi_token: u64,
+
+    /// The address receiving the reissuance token, if missing a receiving address from the wallet doing the issuance is used
+    pub address_token: Option<String>,
+
+    /// The contract defininig asset metadata, such as name, ticker and precision. See [`AssetContract`] request to create
+    pub contract: Option<String>,
+}
+
+/// Request to issue multiple assets in a single transaction
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletIssueMany {
+    /// The wallet name doing the issuances
+    pub name: String,
+
+    /// The issuances, each one uses a distinct input of the transaction
+    pub issuances: Vec<IssuanceSpec>,
+
+    /// The optional fee rate
+    pub fee_rate: Option<f32>,
+}
+
 /// Request to do a reissuance
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct WalletReissue {
@@ -309,9 +549,53 @@ pub struct WalletBurn {
     /// The asset to burn
     pub asset: String,
 
-    /// The number of units of the asset to burn
+    /// The number of units of the asset to burn, must be 0 if `burn_all` is true
     pub satoshi_asset: u64,
 
+    /// Burn the whole wallet balance of the asset
+    #[serde(default)]
+    pub burn_all: bool,
+
+    /// The optional fee rate
+    pub fee_rate: Option<f32>,
+}
+
+/// Request to peg out L-BTC to the bitcoin mainchain
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletPegout {
+    /// The wallet name
+    pub name: String,
+
+    /// The bitcoin address receiving the funds on the mainchain
+    pub mainchain_address: String,
+
+    /// The number of satoshi to peg out
+    pub satoshi: u64,
+
+    /// The optional fee rate
+    pub fee_rate: Option<f32>,
+}
+
+/// Request to claim a pegin, converting bitcoin sent to a pegin address into L-BTC
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletClaimPegin {
+    /// The wallet name
+    pub name: String,
+
+    /// The mainchain transaction sending to the pegin address, in hex
+    pub bitcoin_tx: String,
+
+    /// The output of the mainchain transaction sending to the pegin address
+    pub vout: u32,
+
+    /// The proof of inclusion of the mainchain transaction, in hex
+    ///
+    /// As returned by `bitcoin-cli gettxoutproof`
+    pub txout_proof: String,
+
+    /// The wallet script pubkey committed in the pegin address, in hex
+    pub claim_script: String,
+
     /// The optional fee rate
     pub fee_rate: Option<f32>,
 }
@@ -366,6 +650,43 @@ pub struct WalletPsetDetails {
 pub struct WalletUtxos {
     /// The wallet name
     pub name: String,
+
+    /// Return only the UTXOs of this asset
+    pub asset: Option<String>,
+
+    /// Return only the UTXOs with at least this number of confirmations
+    pub min_confirmations: Option<u32>,
+
+    /// Include the derivation path and the key origins of every UTXO
+    #[serde(default)]
+    pub with_derivation: bool,
+}
+
+/// Request to apply an update created outside the server, for instance by an external indexer
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletApplyUpdate {
+    /// The wallet name
+    pub name: String,
+
+    /// The serialized update in hex
+    pub update_hex: String,
+}
+
+/// Request the tip of the chain scanned for the wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletTip {
+    /// The wallet name
+    pub name: String,
+}
+
+/// Request to get the small incoming policy asset UTXOs not originating from the wallet itself
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletDustDeposits {
+    /// The wallet name
+    pub name: String,
+
+    /// Return only the policy asset UTXOs with a value in satoshi below this
+    pub threshold: u64,
 }
 
 /// Request to get the wallet transactions
@@ -376,6 +697,17 @@ pub struct WalletTxs {
 
     /// Replace asset ids with tickers when possible
     pub with_tickers: bool,
+
+    /// Round the amounts and the fees down to their order of magnitude, eg 1234 to 1000, and
+    /// omit the unblinded urls, which reveal the amounts
+    #[serde(default)]
+    pub hide_amounts: bool,
+
+    /// Skip this number of transactions, for pagination
+    pub offset: Option<usize>,
+
+    /// Return at most this number of transactions, for pagination
+    pub limit: Option<usize>,
 }
 
 /// Request to get a transaction
@@ -432,6 +764,22 @@ pub struct AssetPublish {
     pub asset_id: String,
 }
 
+/// Request to verify a contract commits to an asset
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AssetVerify {
+    /// Asset ID in hex
+    pub asset_id: String,
+
+    /// Contract to verify
+    pub contract: String,
+
+    /// Issuance transaction in hex
+    pub issuance_tx: String,
+
+    /// Also fetch the proof from the contract domain and check it authorizes the asset
+    pub domain_proof: bool,
+}
+
 /// Request to obtain jade identifiers
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SignerJadeId {
@@ -465,6 +813,56 @@ pub struct WalletSetAddrMemo {
     pub memo: String,
 }
 
+/// Request to set a wallet metadata value
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletSetMeta {
+    /// The wallet name
+    pub name: String,
+
+    /// The namespace, eg the name of the integrating application
+    pub namespace: String,
+
+    /// The key within the namespace
+    pub key: String,
+
+    /// The value, empty to remove the entry
+    pub value: String,
+}
+
+/// Request to get the wallet metadata in a namespace
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletGetMeta {
+    /// The wallet name
+    pub name: String,
+
+    /// The namespace
+    pub namespace: String,
+
+    /// Return only the entry with this key
+    pub key: Option<String>,
+}
+
+/// Request to diagnose a wallet scan
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletDiagnose {
+    /// The wallet name
+    pub name: String,
+}
+
+/// Request the scan scope of a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletScanScope {
+    /// The wallet name
+    pub name: String,
+}
+
+/// Request the assets issued by a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletIssuedAssets {
+    /// The wallet name
+    pub name: String,
+}
+
 /// Request to get an AMP2 wallet descriptor
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Amp2Descriptor {
@@ -486,6 +884,37 @@ pub struct Amp2Cosign {
     pub pset: String,
 }
 
+/// Request the status of an AMP2 cosign request
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Amp2CosignStatus {
+    /// The PSET sent for cosigning, or the id assigned by the AMP2 server to the cosign request
+    pub pset_or_id: String,
+}
+
+/// Request to convert a PSET to another version
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetConvertVersion {
+    /// The PSET in base64
+    pub pset: String,
+
+    /// The version to convert to
+    pub target: u32,
+}
+
+/// Request to check if the outputs of a PSET have been blinded
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetBlindingStatus {
+    /// The PSET in base64
+    pub pset: String,
+}
+
+/// Request to parse a payment string
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ParsePayment {
+    /// A Liquid address, a Liquid BIP21 URI or a Lightning invoice
+    pub uri: String,
+}
+
 #[cfg(test)]
 mod test {
     use schemars::schema_for;
@@ -496,8 +925,15 @@ mod test {
     fn test_json_schema() {
         let schema = schema_for!(WalletLoad);
         assert_eq!(
-            r#"{"$schema":"http://json-schema.org/draft-07/schema#","title":"WalletLoad","description":"Request to load a wallet in the server, returning [`response::Wallet`]","type":"object","required":["descriptor","name"],"properties":{"descriptor":{"description":"The read-only descriptor describing the wallet outputs","type":"string"},"name":{"description":"The name given to the wallet, will be needed for calls related to the wallet","type":"string"}}}"#,
+            r#"{"$schema":"http://json-schema.org/draft-07/schema#","title":"WalletLoad","description":"Request to load a wallet in the server, returning [`response::Wallet`]","type":"object","required":["descriptor","name"],"properties":{"change_descriptor":{"description":"Optional descriptor for the change (internal) outputs\n\nIf set, `descriptor` must be the receive (external) descriptor ending with `/0/*` and this a descriptor with the same keys and blinding key ending with `/1/*`. If they have the same script they are combined in a single multipath descriptor","type":["string","null"]},"descriptor":{"description":"The read-only descriptor describing the wallet outputs","type":"string"},"name":{"description":"The name given to the wallet, will be needed for calls related to the wallet","type":"string"}}}"#,
             serde_json::to_string(&schema).unwrap()
         );
     }
+
+    #[test]
+    fn test_wallet_burn_default() {
+        let json = r#"{"name":"w1","asset":"aa","satoshi_asset":10}"#;
+        let r: WalletBurn = serde_json::from_str(json).unwrap();
+        assert!(!r.burn_all);
+    }
 }
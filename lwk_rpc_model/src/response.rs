@@ -5,7 +5,7 @@ use crate::request;
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// An empty response.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -74,6 +74,10 @@ pub struct Signer {
 
     /// The fingerprint of the signer, 4 bytes returned as 8 hex characters
     pub fingerprint: String,
+
+    /// Signer type: "software", "jade", "ledger", "jade-id" or "external"
+    #[serde(rename = "type")]
+    pub type_: String,
 }
 
 /// Address response
@@ -95,6 +99,55 @@ pub struct WalletAddress {
     /// QR code image encoded as uri
     #[serde(skip_serializing_if = "Option::is_none")]
     pub uri_qr: Option<String>,
+
+    /// Network the address belongs to, clients should check it before sending to the address
+    #[serde(default)]
+    pub network: String,
+}
+
+/// A PSET created at an estimated fee rate
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletSendManySmartFee {
+    /// The PSET in base64
+    pub pset: String,
+
+    /// The fee rate used in sat/kvB
+    pub fee_rate: f32,
+
+    /// Whether the fee rate is the default one, because the server couldn't estimate it
+    pub is_fallback: bool,
+}
+
+/// Ownership of an address checked against a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AddressInfo {
+    /// The address, as in the request
+    pub address: String,
+
+    /// Whether the address belongs to the wallet, among the scanned addresses
+    pub is_mine: bool,
+
+    /// The index of the descriptor path, 0 for receive addresses, 1 for change addresses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_index: Option<u32>,
+
+    /// The derivation index of the address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+}
+
+/// Consecutive receive addresses of a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletAddressBatch {
+    /// The addresses, in order of derivation index
+    pub addresses: Vec<WalletAddress>,
+}
+
+/// Response with the ownership of the requested addresses
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletAddressInfoBatch {
+    /// The addresses, in the order of the request
+    pub addresses: Vec<AddressInfo>,
 }
 
 /// Balance respone
@@ -102,6 +155,28 @@ pub struct WalletAddress {
 pub struct WalletBalance {
     /// A map of the balance of every asset in the wallet
     pub balance: HashMap<String, i64>,
+
+    /// Network of the balance, an L-BTC balance on a test network has no value
+    #[serde(default)]
+    pub network: String,
+}
+
+/// The tip of the chain scanned for a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletTip {
+    /// Height of the last block scanned
+    pub height: u32,
+
+    /// Hash of the last block scanned
+    pub hash: String,
+
+    /// Timestamp of the last block scanned, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u32>,
+
+    /// Network of the scanned chain
+    #[serde(default)]
+    pub network: String,
 }
 
 /// PSET response
@@ -109,6 +184,10 @@ pub struct WalletBalance {
 pub struct Pset {
     /// The PSET in base64 format
     pub pset: String,
+
+    /// Network the PSET is built for, clients should check it before signing
+    #[serde(default)]
+    pub network: String,
 }
 
 /// Response containing a single signature descriptor
@@ -125,6 +204,50 @@ pub struct WalletMultisigDescriptor {
     pub descriptor: String,
 }
 
+/// A group of keys of which at least `threshold` must sign
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct KeysThreshold {
+    /// The number of signatures required
+    pub threshold: usize,
+
+    /// The keys, as in the descriptor
+    pub keys: Vec<String>,
+}
+
+/// A way to spend the wallet outputs
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SpendPath {
+    /// The groups of keys that must all provide their signatures
+    pub keys: Vec<KeysThreshold>,
+
+    /// The relative timelock (`older`) of the spent outputs, in consensus encoding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub older: Option<u32>,
+
+    /// The absolute timelock (`after`) of the transaction, in consensus encoding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<u32>,
+
+    /// The hash locks whose preimage must be revealed, eg `sha256(<hash>)`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub hashes: Vec<String>,
+}
+
+/// The spending policy of a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletPolicy {
+    /// The ways to spend the wallet outputs, any of them is sufficient
+    pub spend_paths: Vec<SpendPath>,
+}
+
+/// The public descriptor of a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletPublicDescriptor {
+    /// The wallet descriptor, it has a view key and no slip77 master blinding key, it can be
+    /// loaded to see the wallet balance and transactions
+    pub descriptor: String,
+}
+
 /// A response containing an xpub with keyorigin
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SignerXpub {
@@ -132,6 +255,30 @@ pub struct SignerXpub {
     pub keyorigin_xpub: String,
 }
 
+/// A derivation standard supported for xpubs
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct XpubKind {
+    /// The kind, to be used in [`request::SignerXpub`], eg "bip84"
+    pub kind: String,
+
+    /// The derivation path template, eg "m/84h/<coin_type>h/0h"
+    ///
+    /// The coin type is 1776 for mainnet and 1 for testnet and regtest
+    pub path: String,
+
+    /// Whether the xpub is meant for singlesig wallets
+    pub singlesig: bool,
+
+    /// Whether the xpub is meant for multisig wallets
+    pub multisig: bool,
+}
+
+/// The derivation standards supported for xpubs
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SignerXpubKinds {
+    pub kinds: Vec<XpubKind>,
+}
+
 /// The response of a broadcast
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct WalletBroadcast {
@@ -139,6 +286,22 @@ pub struct WalletBroadcast {
     pub txid: String,
 }
 
+/// The transaction that broadcasting a PSET would produce
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletPreviewTx {
+    /// The transaction id
+    pub txid: String,
+
+    /// The virtual size of the transaction
+    pub vsize: usize,
+
+    /// The virtual size of the transaction with discounted fees for Confidential Transactions
+    pub discount_vsize: usize,
+
+    /// The transaction in hex
+    pub hex: String,
+}
+
 /// A response of a JSON contract containing asset metadata and validated according to the contract rules
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct AssetContract {
@@ -206,6 +369,25 @@ pub struct SignerDetails {
     /// Signer type
     #[serde(rename = "type")]
     pub type_: String,
+
+    /// Features supported by the signer
+    pub capabilities: SignerCapabilities,
+}
+
+/// Features supported by a signer, depending on its type
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct SignerCapabilities {
+    /// Whether the signer can sign arbitrary messages
+    pub sign_message: bool,
+
+    /// Whether the signer can derive xpubs
+    pub derive_xpub: bool,
+
+    /// Whether the signer can export the SLIP77 master blinding key
+    pub slip77: bool,
+
+    /// Whether the signer can sign taproot inputs
+    pub taproot: bool,
 }
 
 /// Details of a wallet
@@ -300,8 +482,50 @@ pub struct WalletPsetDetails {
     /// Reissuance contained in the PSET
     pub reissuances: Vec<Reissuance>,
 
+    /// Outputs sent outside the wallet, grouped by address
+    pub recipients: Vec<PsetRecipient>,
+
+    /// For multisig wallets, the estimated vsize of the transaction with all the required
+    /// signatures, to check the fee before collecting them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_final_vsize: Option<usize>,
+
     /// Warnings
     pub warnings: String,
+
+    /// Network the PSET is built for, clients should check it before signing
+    #[serde(default)]
+    pub network: String,
+}
+
+/// The amounts proven by a proof of reserves
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletVerifyProofOfReserves {
+    /// Amounts of the assets controlled by the wallet signers, in satoshi
+    pub reserves: HashMap<String, u64>,
+}
+
+/// An address receiving outputs of a PSET
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetRecipient {
+    /// The address, or the script pubkey in hex if it has no address form
+    pub address: String,
+
+    /// The outputs sent to the address, an output carries a single asset
+    pub outputs: Vec<RecipientOutput>,
+}
+
+/// An output sent to a [`PsetRecipient`]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RecipientOutput {
+    /// Index of the output in the transaction
+    pub vout: u32,
+
+    /// The asset, None if blinded
+    pub asset: Option<String>,
+
+    /// The amount, None if blinded
+    pub satoshi: Option<u64>,
 }
 
 /// Unspent Transaction Output
@@ -316,6 +540,10 @@ pub struct Utxo {
     /// Height
     pub height: Option<u32>,
 
+    /// Number of confirmations, 0 if unconfirmed
+    #[serde(default)]
+    pub confirmations: u32,
+
     /// Output script pubkey
     pub script_pubkey: String,
 
@@ -324,6 +552,14 @@ pub struct Utxo {
 
     /// Output value in satoshi
     pub value: u64,
+
+    /// Derivation path of the output relative to the wallet xpubs, eg "0/5"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
+
+    /// Key origin of every key in the output script, eg "[73c5da0a/84'/1'/0'/0/5]"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_origin: Option<Vec<String>>,
 }
 
 /// Wallet unspent transaction outputs
@@ -331,6 +567,83 @@ pub struct Utxo {
 pub struct WalletUtxos {
     /// UTXOs
     pub utxos: Vec<Utxo>,
+
+    /// Network of the UTXOs
+    #[serde(default)]
+    pub network: String,
+}
+
+/// Balance and utxos of a wallet scanned once, without loading it in the server
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletSyncOnce {
+    /// A map of the balance of every asset in the wallet
+    pub balance: HashMap<String, i64>,
+
+    /// UTXOs
+    pub utxos: Vec<Utxo>,
+
+    /// The network of the wallet
+    pub network: String,
+}
+
+/// Result of a scan with an increased gap limit
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletDiagnose {
+    /// Number of unused addresses checked after the last used one, for each chain
+    pub gap_limit: u32,
+
+    /// Highest external address index with activity not found by the normal scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_index: Option<u32>,
+
+    /// Highest internal (change) address index with activity not found by the normal scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_index: Option<u32>,
+
+    /// Transactions not found by the normal scan
+    pub txids: Vec<String>,
+
+    /// What to do to see the missing transactions, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+/// Number of scripts derived by a full scan of a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletScanScope {
+    /// Number of external scripts derived
+    pub external_scanned: u32,
+
+    /// Number of internal scripts derived, zero if the descriptor has no internal chain
+    pub internal_scanned: u32,
+}
+
+/// An asset issued by a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IssuedAsset {
+    /// Asset id
+    pub asset_id: String,
+
+    /// Reissuance token id
+    pub token_id: String,
+
+    /// Units of the asset created by the issuance and the reissuances done by the wallet,
+    /// burned units are not subtracted.
+    /// Not present if some of the amounts are confidential.
+    pub issued_supply: Option<u64>,
+
+    /// Reissuance tokens currently owned by the wallet
+    pub reissuance_token_balance: u64,
+
+    /// The contract committed in the issuance, if the asset is known to the server
+    pub contract: Option<String>,
+}
+
+/// Assets issued by a wallet
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletIssuedAssets {
+    /// The issued assets
+    pub assets: Vec<IssuedAsset>,
 }
 
 /// Transaction
@@ -367,6 +680,10 @@ pub struct Tx {
 pub struct WalletTxs {
     /// Transactions
     pub txs: Vec<Tx>,
+
+    /// Network of the transactions
+    #[serde(default)]
+    pub network: String,
 }
 
 /// Transaction
@@ -374,6 +691,10 @@ pub struct WalletTxs {
 pub struct WalletTx {
     /// Transaction in hex
     pub tx: String,
+
+    /// Network of the transaction
+    #[serde(default)]
+    pub network: String,
 }
 
 /// Details of an asset
@@ -384,6 +705,10 @@ pub struct AssetDetails {
 
     /// Ticker of the asset
     pub ticker: String,
+
+    /// Network where the asset has been issued
+    #[serde(default)]
+    pub network: String,
 }
 
 /// Asset details
@@ -457,6 +782,78 @@ pub struct Amp2Cosign {
     pub pset: String,
 }
 
+/// Wallet metadata in a namespace
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WalletGetMeta {
+    /// The metadata entries, by key
+    pub entries: BTreeMap<String, String>,
+}
+
+/// Status of an AMP2 cosign request
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Amp2CosignStatus {
+    /// Whether AMP2 has cosigned, if false ask again later
+    pub cosigned: bool,
+
+    /// The cosigned PSET, only when cosigned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pset: Option<String>,
+}
+
+/// A PSET converted to another version
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetConvertVersion {
+    /// The converted PSET in base64
+    pub pset: String,
+
+    /// The version of the given PSET
+    pub from_version: u32,
+
+    /// The version of the converted PSET
+    pub version: u32,
+}
+
+/// Whether the outputs of a PSET have been blinded
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PsetBlindingStatus {
+    /// True if every output with a blinding key has been blinded, so the PSET can be signed
+    pub all_outputs_blinded: bool,
+
+    /// Indexes of the outputs with a blinding key that have not been blinded
+    pub missing_output_indices: Vec<usize>,
+}
+
+/// A parsed payment string
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ParsePayment {
+    /// The kind of payment: "liquid_address", "liquid_bip21" or "lightning_invoice"
+    pub kind: String,
+
+    /// The Liquid address to pay
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    /// The amount to pay in satoshi
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+
+    /// The asset to pay (32 bytes as 64 hex chars)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+
+    /// The Lightning invoice to pay, also the fallback of BIP21 URIs in unified QR codes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<String>,
+
+    /// The label for the recipient, from BIP21 URIs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// The message describing the payment, from BIP21 URIs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
 impl std::fmt::Display for WalletType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
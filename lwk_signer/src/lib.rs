@@ -12,8 +12,10 @@ pub use bip39;
 
 use elements_miniscript::bitcoin::bip32::{self, DerivationPath, Fingerprint};
 use elements_miniscript::elements::bitcoin::bip32::Xpub;
-use elements_miniscript::elements::pset::PartiallySignedTransaction;
+use elements_miniscript::elements::pset::{Input, PartiallySignedTransaction};
 use lwk_common::Signer;
+#[cfg(any(feature = "jade", feature = "ledger"))]
+use {elements_miniscript::bitcoin::PublicKey, std::collections::HashSet};
 
 /// Possible errors when signing with [`AnySigner`]
 #[derive(thiserror::Error, Debug)]
@@ -31,6 +33,20 @@ pub enum SignerError {
 
     #[error(transparent)]
     Bip32Error(#[from] bip32::Error),
+
+    #[error("Input #{0} has no witness_utxo, the signer needs the previous output to sign")]
+    MissingWitnessUtxo(usize),
+}
+
+/// The outcome of [`AnySigner::sign_detailed()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignSummary {
+    /// The number of signatures added to the PSET
+    pub signatures_added: u32,
+
+    /// The inputs which gained at least a signature, ECDSA or schnorr, with the fingerprint of
+    /// the signer
+    pub signed_inputs: Vec<(usize, Fingerprint)>,
 }
 
 /// A signer that can be a software signer [`SwSigner`] or a [`lwk_jade::Jade`]
@@ -46,6 +62,229 @@ pub enum AnySigner {
         lwk_ledger::Ledger<lwk_ledger::TransportTcp>,
         elements_miniscript::bitcoin::XKeyIdentifier,
     ),
+
+    /// A physical Ledger device connected via USB HID
+    #[cfg(feature = "ledger_hid")]
+    LedgerHid(
+        lwk_ledger::Ledger<lwk_ledger::TransportHID>,
+        elements_miniscript::bitcoin::XKeyIdentifier,
+    ),
+}
+
+impl AnySigner {
+    /// The kind of signer: "software", "jade" or "ledger", it doesn't communicate with devices
+    pub fn type_(&self) -> &'static str {
+        match self {
+            AnySigner::Software(_) => "software",
+            #[cfg(feature = "jade")]
+            AnySigner::Jade(_, _) => "jade",
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(_, _) => "ledger",
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(_, _) => "ledger",
+        }
+    }
+
+    /// Whether the signer implements message signing, it doesn't communicate with devices
+    pub fn can_sign_message(&self) -> bool {
+        match self {
+            AnySigner::Software(_) => false,
+            #[cfg(feature = "jade")]
+            AnySigner::Jade(_, _) => true,
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(_, _) => true,
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(_, _) => true,
+        }
+    }
+
+    /// Whether the signer adds schnorr signatures to taproot inputs, it doesn't communicate
+    /// with devices
+    ///
+    /// The software signer and Jade only produce ECDSA signatures. The Ledger app could sign
+    /// taproot inputs, but the wallet policies sent to it are only `wpkh`, `sh(wpkh)` and `wsh`
+    /// multisig.
+    pub fn can_sign_taproot(&self) -> bool {
+        match self {
+            AnySigner::Software(_) => false,
+            #[cfg(feature = "jade")]
+            AnySigner::Jade(_, _) => false,
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(_, _) => false,
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(_, _) => false,
+        }
+    }
+
+    /// Check the inputs to sign have the previous output in `witness_utxo`
+    ///
+    /// All the supported signers sign segwit inputs committing to the previous output amount, so
+    /// they need only `witness_utxo`, not the full previous transaction.
+    fn check_witness_utxos(&self, pset: &PartiallySignedTransaction) -> Result<(), SignerError> {
+        let fingerprint = self.local_fingerprint();
+        for (idx, input) in pset.inputs().iter().enumerate() {
+            let to_sign = input
+                .bip32_derivation
+                .values()
+                .any(|(fp, _)| *fp == fingerprint);
+            if to_sign && input.witness_utxo.is_none() {
+                return Err(SignerError::MissingWitnessUtxo(idx));
+            }
+        }
+        Ok(())
+    }
+
+    /// The fingerprint of the signer, it doesn't communicate with devices
+    fn local_fingerprint(&self) -> Fingerprint {
+        match self {
+            AnySigner::Software(s) => s.fingerprint(),
+            #[cfg(feature = "jade")]
+            AnySigner::Jade(_, id) => fingerprint_from_identifier(id),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(_, id) => fingerprint_from_identifier(id),
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(_, id) => fingerprint_from_identifier(id),
+        }
+    }
+
+    /// The indices of the PSET inputs having a `bip32_derivation` with the signer fingerprint
+    ///
+    /// It doesn't communicate with devices, use it to avoid asking a device to sign a PSET it
+    /// has nothing to sign in. Keys are not derived, so an input with a colliding fingerprint
+    /// is included even if the signer can't sign it.
+    pub fn can_sign(&self, pset: &PartiallySignedTransaction) -> Result<Vec<usize>, SignerError> {
+        let fingerprint = self.local_fingerprint();
+        Ok(pset
+            .inputs()
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| {
+                input
+                    .bip32_derivation
+                    .values()
+                    .any(|(fp, _)| *fp == fingerprint)
+            })
+            .map(|(idx, _)| idx)
+            .collect())
+    }
+
+    /// Sign the PSET like [`Signer::sign()`], reporting also which inputs have been signed
+    ///
+    /// Only the keys the signer controls are used, even if other keys in the wallet have the
+    /// same fingerprint. Devices sign for all the keys with their fingerprint, so the signatures
+    /// for keys that don't derive from the device at their path are discarded.
+    pub fn sign_detailed(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+    ) -> Result<SignSummary, SignerError> {
+        // Fail early with a descriptive error, instead of inside the device
+        self.check_witness_utxos(pset)?;
+        let before: Vec<usize> = pset.inputs().iter().map(input_signatures).collect();
+        #[cfg(any(feature = "jade", feature = "ledger"))]
+        let all: Vec<usize> = (0..pset.inputs().len()).collect();
+        let signatures_added = match self {
+            AnySigner::Software(signer) => signer.sign(pset)?,
+
+            #[cfg(feature = "jade")]
+            AnySigner::Jade(signer, _) => {
+                let keys = self.controlled_keys(pset)?;
+                sign_copy_inputs(pset, &all, &keys, |p| signer.sign(p))?
+            }
+
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(signer, _) => {
+                let keys = self.controlled_keys(pset)?;
+                sign_copy_inputs(pset, &all, &keys, |p| signer.sign(p))?
+            }
+
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(signer, _) => {
+                let keys = self.controlled_keys(pset)?;
+                sign_copy_inputs(pset, &all, &keys, |p| signer.sign(p))?
+            }
+        };
+        // Signatures are only inserted, so the inputs with more of them gained one
+        let fingerprint = self.local_fingerprint();
+        let signed_inputs = pset
+            .inputs()
+            .iter()
+            .zip(before)
+            .enumerate()
+            .filter(|(_, (input, before))| input_signatures(input) > *before)
+            .map(|(idx, _)| (idx, fingerprint))
+            .collect();
+        Ok(SignSummary {
+            signatures_added,
+            signed_inputs,
+        })
+    }
+
+    /// The keys in the PSET inputs `bip32_derivation` that this signer controls
+    ///
+    /// Different keys can have the same fingerprint, so entries with the signer fingerprint are
+    /// verified deriving the key at their path.
+    #[cfg(any(feature = "jade", feature = "ledger"))]
+    fn controlled_keys(
+        &self,
+        pset: &PartiallySignedTransaction,
+    ) -> Result<HashSet<PublicKey>, SignerError> {
+        let fingerprint = self.local_fingerprint();
+        let mut keys = HashSet::new();
+        for input in pset.inputs() {
+            for (public_key, (fp, path)) in input.bip32_derivation.iter() {
+                if fp == &fingerprint && !keys.contains(public_key) {
+                    let xpub = Signer::derive_xpub(&self, path)?;
+                    if xpub.public_key == public_key.inner {
+                        keys.insert(*public_key);
+                    }
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// The number of signatures in a PSET input, both ECDSA and schnorr ones
+fn input_signatures(input: &Input) -> usize {
+    input.partial_sigs.len() + input.tap_key_sig.is_some() as usize + input.tap_script_sigs.len()
+}
+
+/// Sign a copy of the PSET with `sign`, then copy into `pset` only the signatures of `indices`
+/// made with `keys`
+///
+/// For signers which can't restrict the inputs and the keys they sign with.
+#[cfg(any(feature = "jade", feature = "ledger"))]
+fn sign_copy_inputs<E>(
+    pset: &mut PartiallySignedTransaction,
+    indices: &[usize],
+    keys: &HashSet<PublicKey>,
+    sign: impl FnOnce(&mut PartiallySignedTransaction) -> Result<u32, E>,
+) -> Result<u32, E> {
+    let mut signed = pset.clone();
+    sign(&mut signed)?;
+    let mut signature_added = 0;
+    for i in indices {
+        let sigs = signed.inputs()[*i].partial_sigs.clone();
+        for (public_key, sig) in sigs {
+            if !keys.contains(&public_key) {
+                continue;
+            }
+            if pset.inputs_mut()[*i]
+                .partial_sigs
+                .insert(public_key, sig)
+                .is_none()
+            {
+                signature_added += 1;
+            }
+        }
+    }
+    Ok(signature_added)
+}
+
+#[cfg(any(feature = "jade", feature = "ledger"))]
+fn fingerprint_from_identifier(id: &elements_miniscript::bitcoin::XKeyIdentifier) -> Fingerprint {
+    let bytes: [u8; 4] = id[..4].try_into().expect("4 is the fingerprint length");
+    bytes.into()
 }
 
 impl Signer for AnySigner {
@@ -55,6 +294,14 @@ impl Signer for AnySigner {
         Signer::sign(&self, pset)
     }
 
+    fn sign_inputs(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        indices: &[usize],
+    ) -> Result<u32, Self::Error> {
+        Signer::sign_inputs(&self, pset, indices)
+    }
+
     fn derive_xpub(&self, path: &DerivationPath) -> Result<Xpub, Self::Error> {
         Signer::derive_xpub(&self, path)
     }
@@ -73,15 +320,44 @@ impl Signer for AnySigner {
 impl Signer for &AnySigner {
     type Error = SignerError;
 
+    /// Sign the PSET, see [`AnySigner::sign_detailed()`]
     fn sign(&self, pset: &mut PartiallySignedTransaction) -> Result<u32, Self::Error> {
+        Ok(self.sign_detailed(pset)?.signatures_added)
+    }
+
+    /// Sign only the inputs at `indices`
+    ///
+    /// Devices can't restrict the inputs they sign: they are asked to sign the whole PSET, so the
+    /// user confirms all of it on the device, and only the signatures of `indices` are kept.
+    fn sign_inputs(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        indices: &[usize],
+    ) -> Result<u32, Self::Error> {
+        if let Some(i) = indices.iter().find(|i| **i >= pset.inputs().len()) {
+            return Err(SignError::InputIndexOutOfRange(*i).into());
+        }
+        self.check_witness_utxos(pset)?;
         Ok(match self {
-            AnySigner::Software(signer) => signer.sign(pset)?,
+            AnySigner::Software(signer) => signer.sign_inputs(pset, indices)?,
 
             #[cfg(feature = "jade")]
-            AnySigner::Jade(signer, _) => signer.sign(pset)?,
+            AnySigner::Jade(signer, _) => {
+                let keys = self.controlled_keys(pset)?;
+                sign_copy_inputs(pset, indices, &keys, |p| signer.sign(p))?
+            }
 
             #[cfg(feature = "ledger")]
-            AnySigner::Ledger(signer, _) => signer.sign(pset)?,
+            AnySigner::Ledger(signer, _) => {
+                let keys = self.controlled_keys(pset)?;
+                sign_copy_inputs(pset, indices, &keys, |p| signer.sign(p))?
+            }
+
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(signer, _) => {
+                let keys = self.controlled_keys(pset)?;
+                sign_copy_inputs(pset, indices, &keys, |p| signer.sign(p))?
+            }
         })
     }
 
@@ -94,6 +370,9 @@ impl Signer for &AnySigner {
 
             #[cfg(feature = "ledger")]
             AnySigner::Ledger(s, _) => s.derive_xpub(path)?,
+
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(s, _) => s.derive_xpub(path)?,
         })
     }
 
@@ -108,6 +387,9 @@ impl Signer for &AnySigner {
 
             #[cfg(feature = "ledger")]
             AnySigner::Ledger(s, _) => s.slip77_master_blinding_key()?,
+
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(s, _) => s.slip77_master_blinding_key()?,
         })
     }
 
@@ -120,6 +402,101 @@ impl Signer for &AnySigner {
 
             #[cfg(feature = "ledger")]
             AnySigner::Ledger(s, _) => s.fingerprint()?,
+
+            #[cfg(feature = "ledger_hid")]
+            AnySigner::LedgerHid(s, _) => s.fingerprint()?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use elements_miniscript::bitcoin::bip32::Fingerprint;
+    use elements_miniscript::elements::pset::PartiallySignedTransaction;
+    use elements_miniscript::elements::schnorr::SchnorrSig;
+    use elements_miniscript::elements::secp256k1_zkp::schnorr;
+    use elements_miniscript::elements::SchnorrSighashType;
+    use lwk_common::Signer;
+
+    use crate::{input_signatures, AnySigner, SignerError, SwSigner};
+
+    #[test]
+    fn missing_witness_utxo() {
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        let pset_str = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let pset: PartiallySignedTransaction = pset_str.trim().parse().unwrap();
+        assert!(pset.inputs().iter().all(|i| i.non_witness_utxo.is_none()));
+
+        let signer = AnySigner::Software(signer);
+        assert!(signer.sign(&mut pset.clone()).unwrap() > 0);
+
+        // Missing witness_utxo fails before signing
+        let mut missing = pset.clone();
+        for input in missing.inputs_mut() {
+            input.witness_utxo = None;
+        }
+        let err = signer.sign(&mut missing).unwrap_err();
+        assert!(matches!(err, SignerError::MissingWitnessUtxo(_)));
+        assert!(err.to_string().contains("witness_utxo"));
+
+        // Inputs the signer doesn't sign are not checked
+        let (other, _) = SwSigner::random(false).unwrap();
+        let other = AnySigner::Software(other);
+        assert_eq!(other.sign(&mut missing).unwrap(), 0);
+    }
+
+    #[test]
+    fn can_sign() {
+        let pset_str = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let pset: PartiallySignedTransaction = pset_str.trim().parse().unwrap();
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+
+        // The fixture has a single wpkh input, with a key of the test mnemonic
+        let signer = AnySigner::Software(signer);
+        assert_eq!(signer.can_sign(&pset).unwrap(), vec![0]);
+        assert_eq!(signer.sign(&mut pset.clone()).unwrap(), 1);
+
+        let (other, _) = SwSigner::random(false).unwrap();
+        let other = AnySigner::Software(other);
+        assert!(other.can_sign(&pset).unwrap().is_empty());
+        assert_eq!(other.sign(&mut pset.clone()).unwrap(), 0);
+    }
+
+    #[test]
+    fn sign_detailed() {
+        let pset_str = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let pset: PartiallySignedTransaction = pset_str.trim().parse().unwrap();
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        let signer = AnySigner::Software(signer);
+
+        let mut signed = pset.clone();
+        let summary = signer.sign_detailed(&mut signed).unwrap();
+        let fingerprint = Fingerprint::from_str("73c5da0a").unwrap();
+        assert_eq!(summary.signed_inputs, vec![(0, fingerprint)]);
+        assert_eq!(summary.signatures_added, 1);
+        assert_eq!(signed.inputs()[0].partial_sigs.len(), 1);
+
+        // Signing again adds nothing
+        let summary = signer.sign_detailed(&mut signed).unwrap();
+        assert_eq!(summary.signatures_added, 0);
+        assert!(summary.signed_inputs.is_empty());
+    }
+
+    #[test]
+    fn input_signatures_schnorr() {
+        let pset_str = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let pset: PartiallySignedTransaction = pset_str.trim().parse().unwrap();
+        let mut input = pset.inputs()[0].clone();
+        assert_eq!(input_signatures(&input), 0);
+
+        // Schnorr signatures are counted as the ECDSA ones
+        let sig = SchnorrSig {
+            sig: schnorr::Signature::from_slice(&[1u8; 64]).unwrap(),
+            hash_ty: SchnorrSighashType::Default,
+        };
+        input.tap_key_sig = Some(sig);
+        assert_eq!(input_signatures(&input), 1);
+    }
+}
@@ -3,7 +3,7 @@ use elements_miniscript::{
     bitcoin::{self, bip32::DerivationPath, PrivateKey},
     elements::{
         bitcoin::{
-            bip32::{self, Fingerprint, Xpriv, Xpub},
+            bip32::{self, ChainCode, Fingerprint, Xpriv, Xpub},
             Network,
         },
         hashes::Hash,
@@ -16,6 +16,7 @@ use elements_miniscript::{
     slip77::MasterBlindingKey,
 };
 use lwk_common::Signer;
+use zeroize::Zeroize;
 
 /// Possible errors when signing with the software signer [`SwSigner`]
 #[derive(thiserror::Error, Debug)]
@@ -37,6 +38,9 @@ pub enum SignError {
 
     #[error("Cannot derive slip77 key (mnemonic/seed not available)")]
     DeterministicSlip77NotAvailable,
+
+    #[error("Input #{0} does not exist in the PSET")]
+    InputIndexOutOfRange(usize),
 }
 
 /// Possible errors when creating a new software signer [`SwSigner`]
@@ -66,9 +70,16 @@ pub struct SwSigner {
     pub(crate) xprv: Xpriv,
     pub(crate) secp: Secp256k1<All>, // could be sign only, but it is likely the caller already has the All context.
     pub(crate) mnemonic: Option<Mnemonic>,
+    passphrase: Option<String>,
     ecdsa_sign_opt: EcdsaSignOpt,
 }
 
+impl Drop for SwSigner {
+    fn drop(&mut self) {
+        self.erase();
+    }
+}
+
 impl core::fmt::Debug for SwSigner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Signer({})", self.fingerprint())
@@ -81,9 +92,21 @@ impl SwSigner {
     /// Takes also a flag if the network is mainnet so that generated extended keys are in the
     /// correct form xpub/tpub (there is no need to discriminate between regtest and testnet)
     pub fn new(mnemonic: &str, is_mainnet: bool) -> Result<Self, NewError> {
+        Self::new_with_passphrase(mnemonic, None, is_mainnet)
+    }
+
+    /// Creates a new software signer from the given mnemonic and BIP39 passphrase
+    ///
+    /// An empty or missing passphrase gives the same signer of [`SwSigner::new()`]
+    pub fn new_with_passphrase(
+        mnemonic: &str,
+        passphrase: Option<&str>,
+        is_mainnet: bool,
+    ) -> Result<Self, NewError> {
         let secp = Secp256k1::new();
         let mnemonic: Mnemonic = mnemonic.parse()?;
-        let seed = mnemonic.to_seed("");
+        let passphrase = passphrase.filter(|p| !p.is_empty()).map(|p| p.to_string());
+        let mut seed = mnemonic.to_seed(passphrase.as_deref().unwrap_or(""));
 
         let network = if is_mainnet {
             bitcoin::Network::Bitcoin
@@ -91,12 +114,15 @@ impl SwSigner {
             bitcoin::Network::Testnet
         };
 
-        let xprv = Xpriv::new_master(network, &seed)?;
+        let xprv = Xpriv::new_master(network, &seed);
+        seed.zeroize();
+        let xprv = xprv?;
 
         Ok(Self {
             xprv,
             secp,
             mnemonic: Some(mnemonic),
+            passphrase,
             ecdsa_sign_opt: EcdsaSignOpt::default(),
         })
     }
@@ -110,15 +136,48 @@ impl SwSigner {
         Ok((SwSigner::new(&mnemonic.to_string(), is_mainnet)?, mnemonic))
     }
 
-    pub fn from_xprv(xprv: Xpriv) -> Self {
+    /// Creates a new software signer from a master extended private key
+    ///
+    /// The signer can sign PSETs and derive xpubs, but it has no mnemonic, thus [`SwSigner::mnemonic()`]
+    /// and [`SwSigner::seed()`] return `None`.
+    ///
+    /// The SLIP77 master blinding key is derived from the seed, which cannot be obtained from the
+    /// xprv, so `slip77_master_blinding_key()` returns
+    /// [`SignError::DeterministicSlip77NotAvailable`], use a different blinding key in the descriptor.
+    ///
+    /// Like in [`SwSigner::new()`], `is_mainnet` sets the form of the generated extended keys.
+    pub fn from_xprv(mut xprv: Xpriv, is_mainnet: bool) -> Self {
+        xprv.network = if is_mainnet {
+            bitcoin::NetworkKind::Main
+        } else {
+            bitcoin::NetworkKind::Test
+        };
         Self {
             xprv,
             secp: Secp256k1::new(),
             mnemonic: None,
+            passphrase: None,
             ecdsa_sign_opt: EcdsaSignOpt::default(),
         }
     }
 
+    /// Overwrite the secret key material, called on drop
+    ///
+    /// This is best effort: copies made by moving or cloning the signer, or returned by methods
+    /// like [`SwSigner::mnemonic()`] and [`SwSigner::seed()`], are not erased.
+    fn erase(&mut self) {
+        self.xprv.private_key.non_secure_erase();
+        self.xprv.chain_code = ChainCode::from([0u8; 32]);
+        // Prevent the compiler from optimizing away the write above
+        std::hint::black_box(&self.xprv.chain_code);
+        if let Some(mnemonic) = self.mnemonic.as_mut() {
+            mnemonic.zeroize();
+        }
+        if let Some(passphrase) = self.passphrase.as_mut() {
+            passphrase.zeroize();
+        }
+    }
+
     /// Produce "low R" ECDSA signatures (default and recommended option)
     pub fn set_ecdsa_sign_low_r(&mut self) {
         self.ecdsa_sign_opt = EcdsaSignOpt::LowR;
@@ -134,7 +193,13 @@ impl SwSigner {
     }
 
     pub fn seed(&self) -> Option<[u8; 64]> {
-        self.mnemonic.as_ref().map(|m| m.to_seed(""))
+        let passphrase = self.passphrase.as_deref().unwrap_or("");
+        self.mnemonic.as_ref().map(|m| m.to_seed(passphrase))
+    }
+
+    /// The BIP39 passphrase, if the signer has been created with a non empty one
+    pub fn passphrase(&self) -> Option<&str> {
+        self.passphrase.as_deref()
     }
 
     pub fn mnemonic(&self) -> Option<Mnemonic> {
@@ -148,12 +213,13 @@ impl SwSigner {
     pub fn derive_xprv(&self, path: &DerivationPath) -> Result<Xpriv, SignError> {
         Ok(self.xprv.derive_priv(&self.secp, path)?)
     }
-}
-
-impl Signer for SwSigner {
-    type Error = SignError;
 
-    fn sign(&self, pset: &mut PartiallySignedTransaction) -> Result<u32, Self::Error> {
+    /// Sign the inputs for which `to_sign` returns true
+    fn sign_filtered(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        to_sign: impl Fn(usize) -> bool,
+    ) -> Result<u32, SignError> {
         let tx = pset.extract_tx()?;
         let mut sighash_cache = SighashCache::new(&tx);
         let mut signature_added = 0;
@@ -170,12 +236,18 @@ impl Signer for SwSigner {
             messages.push(msg);
         }
 
-        // Fixme: Take a parameter
-        let hash_ty = elements_miniscript::elements::EcdsaSighashType::All;
-
         let signer_fingerprint = self.fingerprint();
-        for (input, msg) in pset.inputs_mut().iter_mut().zip(messages) {
+        for (i, (input, msg)) in pset.inputs_mut().iter_mut().zip(messages).enumerate() {
+            if !to_sign(i) {
+                continue;
+            }
+            // The message has been computed with the input sighash type, SIGHASH_ALL if unset
+            let hash_ty = input
+                .sighash_type
+                .and_then(|t| t.ecdsa_hash_ty())
+                .unwrap_or(elements_miniscript::elements::EcdsaSighashType::All);
             for (want_public_key, (fingerprint, derivation_path)) in input.bip32_derivation.iter() {
+                // Fingerprints can collide, the key derived at the path must match too
                 if &signer_fingerprint == fingerprint {
                     let ext_derived = self.xprv.derive_priv(&self.secp, derivation_path)?;
                     let private_key = PrivateKey::new(ext_derived.private_key, Network::Bitcoin);
@@ -201,6 +273,25 @@ impl Signer for SwSigner {
 
         Ok(signature_added)
     }
+}
+
+impl Signer for SwSigner {
+    type Error = SignError;
+
+    fn sign(&self, pset: &mut PartiallySignedTransaction) -> Result<u32, Self::Error> {
+        self.sign_filtered(pset, |_| true)
+    }
+
+    fn sign_inputs(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        indices: &[usize],
+    ) -> Result<u32, Self::Error> {
+        if let Some(i) = indices.iter().find(|i| **i >= pset.inputs().len()) {
+            return Err(SignError::InputIndexOutOfRange(*i));
+        }
+        self.sign_filtered(pset, |i| indices.contains(&i))
+    }
 
     fn derive_xpub(&self, path: &DerivationPath) -> Result<Xpub, Self::Error> {
         let derived = self.xprv.derive_priv(&self.secp, path)?;
@@ -208,10 +299,12 @@ impl Signer for SwSigner {
     }
 
     fn slip77_master_blinding_key(&self) -> Result<MasterBlindingKey, Self::Error> {
-        let seed = self
+        let mut seed = self
             .seed()
             .ok_or_else(|| SignError::DeterministicSlip77NotAvailable)?;
-        Ok(MasterBlindingKey::from_seed(&seed[..]))
+        let key = MasterBlindingKey::from_seed(&seed[..]);
+        seed.zeroize();
+        Ok(key)
     }
 }
 
@@ -248,15 +341,98 @@ mod tests {
         assert_eq!(xpub, Xpub::from_priv(&secp, &xprv));
     }
 
+    #[test]
+    fn new_signer_with_passphrase() {
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        let empty =
+            SwSigner::new_with_passphrase(lwk_test_util::TEST_MNEMONIC, Some(""), false).unwrap();
+        assert_eq!(empty.xpub(), signer.xpub());
+        assert!(empty.passphrase().is_none());
+
+        let with_passphrase =
+            SwSigner::new_with_passphrase(lwk_test_util::TEST_MNEMONIC, Some("TREZOR"), false)
+                .unwrap();
+        assert_eq!(with_passphrase.passphrase(), Some("TREZOR"));
+        assert_ne!(with_passphrase.fingerprint(), signer.fingerprint());
+        assert_ne!(with_passphrase.xpub(), signer.xpub());
+        assert_ne!(
+            with_passphrase
+                .slip77_master_blinding_key()
+                .unwrap()
+                .as_bytes(),
+            signer.slip77_master_blinding_key().unwrap().as_bytes()
+        );
+        assert!(!format!("{:?}", with_passphrase).contains("TREZOR"));
+    }
+
+    #[test]
+    fn erase() {
+        let mut signer =
+            SwSigner::new_with_passphrase(lwk_test_util::TEST_MNEMONIC, Some("TREZOR"), false)
+                .unwrap();
+        let secret = signer.xprv.private_key.secret_bytes();
+        signer.erase();
+        assert_ne!(signer.xprv.private_key.secret_bytes(), secret);
+        assert_eq!(signer.xprv.chain_code, ChainCode::from([0u8; 32]));
+        assert!(signer.passphrase().unwrap().is_empty());
+        assert_ne!(
+            signer.mnemonic().unwrap().to_string(),
+            lwk_test_util::TEST_MNEMONIC
+        );
+
+        // Dropping erases the signer, and clones are independent
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        let cloned = signer.clone();
+        drop(signer);
+        assert_eq!(cloned.xpub().to_string(), lwk_test_util::TEST_MNEMONIC_XPUB);
+    }
+
+    #[test]
+    fn xpub_with_origin() {
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        for bip in [
+            lwk_common::Bip::Bip49,
+            lwk_common::Bip::Bip84,
+            lwk_common::Bip::Bip87,
+        ] {
+            for is_mainnet in [true, false] {
+                let (key_source, xpub) = signer.xpub_with_origin(bip, is_mainnet).unwrap();
+                let s = signer.keyorigin_xpub(bip, is_mainnet).unwrap();
+                let (parsed_key_source, parsed_xpub) =
+                    lwk_common::keyorigin_xpub_from_str(&s).unwrap();
+                assert_eq!(parsed_key_source, Some(key_source));
+                assert_eq!(parsed_xpub, xpub);
+            }
+        }
+    }
+
     #[test]
     fn from_xprv() {
         use std::str::FromStr;
         let xprv = Xpriv::from_str("tprv8bxtvyWEZW9M4n8ByZVSG2NNP4aeiRdhDZXNEv1eVNtrhLLnc6vJ1nf9DN5cHAoxMwqRR1CD6YXBvw2GncSojF8DknPnQVMgbpkjnKHkrGY").unwrap();
         let xpub = Xpub::from_str("tpubD8ew5PYUhsq1xF9ysDA2fS2Ux66askpbns89XS3wuehFXpbZEVjtCHH1PUhj6KAfCs4iCx5wKgswv1n3we2ZHEs2sP5pw9PnLsCFwiVgdjw").unwrap();
-        let signer = SwSigner::from_xprv(xprv);
+        let signer = SwSigner::from_xprv(xprv, false);
         assert_eq!(signer.xpub(), xpub);
+        assert!(!signer.is_mainnet());
         assert!(signer.mnemonic().is_none());
         assert!(signer.seed().is_none());
+        assert!(matches!(
+            signer.slip77_master_blinding_key(),
+            Err(SignError::DeterministicSlip77NotAvailable)
+        ));
+
+        let mainnet = SwSigner::from_xprv(xprv, true);
+        assert!(mainnet.is_mainnet());
+        assert!(mainnet.xpub().to_string().starts_with("xpub"));
+        assert_eq!(mainnet.fingerprint(), signer.fingerprint());
+
+        // Signs like the signer created from the mnemonic
+        let from_mnemonic = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        let signer = SwSigner::from_xprv(from_mnemonic.xprv, false);
+        assert_eq!(signer.xpub(), from_mnemonic.xpub());
+        let b64 = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let mut pset: PartiallySignedTransaction = b64.parse().unwrap();
+        assert_eq!(signer.sign(&mut pset).unwrap(), 1);
     }
 
     #[test]
@@ -285,4 +461,63 @@ mod tests {
         assert_ne!(sig_low_r, sig_no_grind);
         assert!(sig_low_r.len() < sig_no_grind.len());
     }
+
+    #[test]
+    fn signer_sign_inputs() {
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        let b64 = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let mut pset: PartiallySignedTransaction = b64.parse().unwrap();
+        // A second input signable by the same signer
+        let mut input = pset.inputs()[0].clone();
+        input.previous_output_index += 1;
+        pset.add_input(input);
+
+        let mut signed = pset.clone();
+        assert_eq!(signer.sign_inputs(&mut signed, &[0]).unwrap(), 1);
+        assert_eq!(signed.inputs()[0].partial_sigs.len(), 1);
+        assert!(signed.inputs()[1].partial_sigs.is_empty());
+
+        assert_eq!(signer.sign_inputs(&mut signed, &[1]).unwrap(), 1);
+        assert_eq!(signed.inputs()[1].partial_sigs.len(), 1);
+
+        let mut all = pset.clone();
+        assert_eq!(signer.sign(&mut all).unwrap(), 2);
+        assert_eq!(signed, all);
+
+        let err = signer.sign_inputs(&mut pset.clone(), &[2]).unwrap_err();
+        assert!(matches!(err, SignError::InputIndexOutOfRange(2)));
+    }
+
+    #[test]
+    fn signer_same_fingerprint() {
+        let signer = SwSigner::new(lwk_test_util::TEST_MNEMONIC, false).unwrap();
+        let other = SwSigner::random(false).unwrap().0;
+        let b64 = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let mut pset: PartiallySignedTransaction = b64.parse().unwrap();
+
+        // Add a key of another signer, with the same fingerprint and path of the signer key
+        let (mine, (fingerprint, path)) = pset.inputs()[0]
+            .bip32_derivation
+            .iter()
+            .find(|(_, (f, _))| f == &signer.fingerprint())
+            .map(|(k, s)| (*k, s.clone()))
+            .unwrap();
+        let xpub = other.derive_xpub(&path).unwrap();
+        let colliding = bitcoin::PublicKey::new(xpub.public_key);
+        pset.inputs_mut()[0]
+            .bip32_derivation
+            .insert(colliding, (fingerprint, path.clone()));
+
+        // A second input only with the colliding key
+        let mut input = pset.inputs()[0].clone();
+        input.previous_output_index += 1;
+        input.bip32_derivation.remove(&mine);
+        pset.add_input(input);
+
+        assert_eq!(signer.sign(&mut pset).unwrap(), 1);
+        let sigs = &pset.inputs()[0].partial_sigs;
+        assert_eq!(sigs.len(), 1);
+        assert!(sigs.contains_key(&mine));
+        assert!(pset.inputs()[1].partial_sigs.is_empty());
+    }
 }
@@ -334,6 +334,25 @@ impl TestElectrumServer {
         (mainchain_address, claim_script)
     }
 
+    /// The mainchain address of the pegin committing to the given claim script
+    pub fn elementsd_tweakfedpegscript(&self, claim_script: &str) -> bitcoin::Address {
+        let value: serde_json::Value = self
+            .elementsd
+            .client
+            .call("tweakfedpegscript", &[claim_script.into()])
+            .unwrap();
+        let address = value.get("address").unwrap().as_str().unwrap();
+        bitcoin::Address::from_str(address)
+            .unwrap()
+            .assume_checked()
+    }
+
+    pub fn elementsd_fedpegscript(&self) -> bitcoin::ScriptBuf {
+        let value: serde_json::Value = self.elementsd.client.call("getsidechaininfo", &[]).unwrap();
+        let hex = value.get("fedpegscript").unwrap().as_str().unwrap();
+        bitcoin::ScriptBuf::from_hex(hex).unwrap()
+    }
+
     pub fn elementsd_raw_createpsbt(&self, inputs: Value, outputs: Value) -> String {
         let psbt: serde_json::Value = self
             .elementsd
@@ -1,6 +1,68 @@
-use std::{num::NonZeroU8, path::PathBuf};
+use std::{fmt, num::NonZeroU8, path::PathBuf, sync::Arc};
 
-use tiny_http::Header;
+use serde_json::Value;
+use tiny_http::{Header, Method};
+
+use crate::Request;
+
+type RouteFn = dyn Fn(&Method, &str, Option<Value>) -> Option<Request> + Send + Sync;
+
+/// Maps REST requests to JSON-RPC requests
+///
+/// Only GET and POST requests with a url under `prefix` are REST requests, any other request is
+/// served as usual, so files from `serve_dir` and CORS preflight requests keep working.
+///
+/// The function receives the HTTP method, the url (path and query) without the prefix and the
+/// JSON body if any, and returns the JSON-RPC request to process, or `None` if the url is not a
+/// REST path.
+#[derive(Clone)]
+pub struct RestRouter {
+    prefix: String,
+    route: Arc<RouteFn>,
+}
+
+impl RestRouter {
+    /// Create a router for the urls starting with `prefix`, eg `/api`
+    pub fn new<F>(prefix: &str, route: F) -> Self
+    where
+        F: Fn(&Method, &str, Option<Value>) -> Option<Request> + Send + Sync + 'static,
+    {
+        Self {
+            prefix: prefix.trim_end_matches('/').to_string(),
+            route: Arc::new(route),
+        }
+    }
+
+    /// The url without the prefix, if it's a REST url
+    fn strip_prefix<'a>(&self, url: &'a str) -> Option<&'a str> {
+        url.strip_prefix(self.prefix.as_str())
+            .filter(|rest| rest.starts_with('/'))
+    }
+
+    /// Whether the request must be handled by this router
+    pub fn handles(&self, method: &Method, url: &str) -> bool {
+        matches!(method, Method::Get | Method::Post) && self.strip_prefix(url).is_some()
+    }
+
+    pub fn route(&self, method: &Method, url: &str, body: Option<Value>) -> Option<Request> {
+        let url = self.strip_prefix(url)?;
+        (self.route)(method, url, body)
+    }
+}
+
+impl fmt::Debug for RestRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestRouter")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+/// Default maximum size of a request body, large enough for PSETs with many inputs
+pub const DEFAULT_MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// JSON-RPC responses smaller than this are not compressed, the saving doesn't pay the overhead
+pub const COMPRESSION_MIN_BYTES: usize = 1024;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -10,6 +72,12 @@ pub struct Config {
     pub num_threads: NonZeroU8,
     /// The path to serve HTTP GET requests from.
     pub serve_dir: Option<PathBuf>,
+    /// Serve REST requests on the urls under the router prefix.
+    pub rest: Option<RestRouter>,
+    /// The maximum size of the body of POST and REST requests, larger requests are rejected.
+    pub max_body_bytes: usize,
+    /// Compress JSON-RPC responses with gzip or deflate if the client accepts it.
+    pub compression: bool,
 }
 
 impl Config {
@@ -24,6 +92,9 @@ impl Default for Config {
             headers: Vec::new(),
             num_threads: NonZeroU8::new(4).expect("non-zero"),
             serve_dir: None,
+            rest: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            compression: false,
         }
     }
 }
@@ -32,6 +103,9 @@ pub struct ConfigBuilder {
     headers: Vec<Header>,
     num_threads: NonZeroU8,
     serve_dir: Option<PathBuf>,
+    rest: Option<RestRouter>,
+    max_body_bytes: usize,
+    compression: bool,
 }
 
 impl ConfigBuilder {
@@ -54,11 +128,29 @@ impl ConfigBuilder {
         self
     }
 
+    pub fn with_rest(mut self, router: Option<RestRouter>) -> Self {
+        self.rest = router;
+        self
+    }
+
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
     pub fn build(self) -> Config {
         Config {
             headers: self.headers,
             num_threads: self.num_threads,
             serve_dir: self.serve_dir,
+            rest: self.rest,
+            max_body_bytes: self.max_body_bytes,
+            compression: self.compression,
         }
     }
 }
@@ -69,6 +161,9 @@ impl Default for ConfigBuilder {
             headers: Vec::new(),
             num_threads: NonZeroU8::new(4).expect("non-zero"),
             serve_dir: None,
+            rest: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            compression: false,
         }
     }
 }
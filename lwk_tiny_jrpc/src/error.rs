@@ -44,6 +44,15 @@ pub enum InnerError {
 
     #[error("'jsonrpc' version should be '2.0'")]
     InvalidVersion,
+
+    #[error("Request body is larger than {0} bytes")]
+    BodyTooLarge(usize),
+
+    #[error("Batch request is empty")]
+    EmptyBatch,
+
+    #[error("Invalid request in batch: {0}")]
+    InvalidRequest(String),
 }
 
 impl From<String> for Error {
@@ -83,6 +92,8 @@ impl AsRpcError for InnerError {
             InnerError::WrongContentType => (WRONG_CONTENT_TYPE, None),
             InnerError::ReservedMethodPrefix => (METHOD_RESERVED, None),
             InnerError::InvalidVersion => (INVALID_VERSION, None),
+            InnerError::EmptyBatch | InnerError::InvalidRequest(_) => (INVALID_REQUEST, None),
+            InnerError::BodyTooLarge(_) => (BODY_TOO_LARGE, None),
         };
 
         RpcError {
@@ -123,7 +134,8 @@ impl AsRpcError for Error {
 const PARSE_ERROR: i64 = -32_700;
 
 // -32600 	Invalid Request 	The JSON sent is not a valid Request object.
-// const INVALID_REQUEST: i64 = -32_600; // TODO if failing to parse the request object, try to parse as Value and if succesfull return this instead of PARSE_ERROR
+// TODO if failing to parse a single request object, try to parse as Value and if succesfull return this instead of PARSE_ERROR
+const INVALID_REQUEST: i64 = -32_600;
 
 // -32601 	Method not found 	The method does not exist / is not available.
 pub(crate) const METHOD_NOT_FOUND: i64 = -32_601;
@@ -141,6 +153,7 @@ const WRONG_CONTENT_TYPE: i64 = -32_002;
 const METHOD_RESERVED: i64 = -32_003;
 const INVALID_VERSION: i64 = -32_004;
 
+const BODY_TOO_LARGE: i64 = -32_097;
 // GENERIC = -32_098, // TODO remove
 const STOP_ERROR: i64 = -32_099;
 
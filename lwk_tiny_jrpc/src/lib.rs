@@ -4,7 +4,8 @@
 use std::{
     fmt::Display,
     fs::File,
-    io::{ErrorKind, Read},
+    io::{ErrorKind, Read, Write},
+    path::Path,
     str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -14,7 +15,8 @@ use std::{
     time::Duration,
 };
 
-pub use config::Config;
+use config::COMPRESSION_MIN_BYTES;
+pub use config::{Config, RestRouter, DEFAULT_MAX_BODY_BYTES};
 pub use error::Error;
 use error::{AsRpcError, InnerError, METHOD_NOT_FOUND};
 use serde_derive::{Deserialize, Serialize};
@@ -94,6 +96,23 @@ impl JsonRpcServer {
                         }
                     };
 
+                    if let Some(router) = config.rest.as_ref() {
+                        if router.handles(http_request.method(), http_request.url()) {
+                            let stop = handle_rest_request(
+                                http_request,
+                                router,
+                                state.clone(),
+                                func.clone(),
+                                &config.headers,
+                                config.max_body_bytes,
+                            );
+                            if stop {
+                                running.store(false, Ordering::SeqCst);
+                            }
+                            continue;
+                        }
+                    }
+
                     // check request method
                     match http_request.method() {
                         tiny_http::Method::Get => {
@@ -115,7 +134,7 @@ impl JsonRpcServer {
                             if path.is_dir() {
                                 path.push("index.html");
                             }
-                            match File::open(path) {
+                            match File::open(&path) {
                                 Ok(mut file) => {
                                     let mut buf = Vec::new();
                                     match file.read_to_end(&mut buf) {
@@ -132,8 +151,8 @@ impl JsonRpcServer {
                                             continue;
                                         }
                                     }
-                                    // todo: content-type headers, this is non-trivial and not strictly necessary right now
-                                    let response = HttpResponse::from_data(buf);
+                                    let response = HttpResponse::from_data(buf)
+                                        .with_header(content_type_header(&path));
                                     let message = "File for GET request";
                                     send_http_response(http_request, response, message);
                                 }
@@ -170,33 +189,60 @@ impl JsonRpcServer {
                         }
                         tiny_http::Method::Post => {
                             // validate/parse the jsonrpc POST request
-                            let response = match validate_jsonrpc_request(&mut http_request) {
-                                Ok(request) => {
+                            let result = match validate_jsonrpc_request(
+                                &mut http_request,
+                                config.max_body_bytes,
+                            ) {
+                                Ok(Requests::Single(request)) => {
                                     // handle the request
-                                    let id = request.id.clone();
-                                    match handle_jsonrpc_request(
+                                    let response = process_jsonrpc_request(
                                         request,
                                         state.clone(),
                                         func.clone(),
-                                    ) {
-                                        Ok(response) => response,
-                                        Err(Error::Stop) => {
-                                            running.store(false, Ordering::SeqCst);
-                                            Response::from_error(id, Error::Stop)
+                                        &running,
+                                    );
+                                    send_jsonrpc_response(http_request, response, &config)
+                                }
+                                Ok(Requests::Batch(requests)) => {
+                                    // notifications, without id, get no response
+                                    let responses: Vec<_> = requests
+                                        .into_iter()
+                                        .filter_map(|request| match request {
+                                            Ok(request) => {
+                                                let notification = request.id.is_none();
+                                                let response = process_jsonrpc_request(
+                                                    request,
+                                                    state.clone(),
+                                                    func.clone(),
+                                                    &running,
+                                                );
+                                                (!notification).then_some(response)
+                                            }
+                                            Err(err) => Some(Response::from_error(None, err)),
+                                        })
+                                        .collect();
+                                    if responses.is_empty() {
+                                        let mut response = HttpResponse::empty(204);
+                                        for header in config.headers.iter() {
+                                            response.add_header(header.clone());
                                         }
-                                        Err(err) => Response::from_error(id, err),
+                                        send_http_response(
+                                            http_request,
+                                            response,
+                                            "Batch of notifications",
+                                        );
+                                        Ok(())
+                                    } else {
+                                        send_jsonrpc_response(http_request, responses, &config)
                                     }
                                 }
                                 Err(err) => {
                                     // no id since we couldn't validate the request...
-                                    Response::from_error(None, err)
+                                    let response = Response::from_error(None, err);
+                                    send_jsonrpc_response(http_request, response, &config)
                                 }
                             };
-
-                            // send the response
-                            if let Err(err) =
-                                send_jsonrpc_response(http_request, response, &config.headers)
-                            {
+                            if let Err(err) = result {
                                 log::error!("send_response error: {}", err);
                             }
                         }
@@ -256,7 +302,18 @@ where
     }
 }
 
-fn validate_jsonrpc_request(http_request: &mut tiny_http::Request) -> Result<Request, InnerError> {
+/// The body of a JSON-RPC POST request
+enum Requests {
+    Single(Request),
+
+    /// A batch of requests, each one is validated independently
+    Batch(Vec<Result<Request, InnerError>>),
+}
+
+fn validate_jsonrpc_request(
+    http_request: &mut tiny_http::Request,
+    max_body_bytes: usize,
+) -> Result<Requests, InnerError> {
     log::debug!(
         "received request - method: {:?}, url: {:?}, headers: {:?}",
         http_request.method(),
@@ -283,12 +340,66 @@ fn validate_jsonrpc_request(http_request: &mut tiny_http::Request) -> Result<Req
     }
 
     // parse json into request
-    let mut s = String::new(); // todo: performance
-    http_request.as_reader().read_to_string(&mut s)?;
+    let s = read_body(http_request, max_body_bytes)?;
 
-    let request: Request = serde_json::from_str(&s)?;
+    let value: Value = serde_json::from_str(&s)?;
+    match value {
+        Value::Array(values) if values.is_empty() => Err(InnerError::EmptyBatch),
+        Value::Array(values) => Ok(Requests::Batch(
+            values
+                .into_iter()
+                .map(|v| {
+                    serde_json::from_value(v).map_err(|e| InnerError::InvalidRequest(e.to_string()))
+                })
+                .collect(),
+        )),
+        value => Ok(Requests::Single(serde_json::from_value(value)?)),
+    }
+}
+
+/// Read the request body, failing early if it is larger than `max_body_bytes`
+fn read_body(
+    http_request: &mut tiny_http::Request,
+    max_body_bytes: usize,
+) -> Result<String, InnerError> {
+    if http_request
+        .body_length()
+        .is_some_and(|l| l > max_body_bytes)
+    {
+        return Err(InnerError::BodyTooLarge(max_body_bytes));
+    }
+    // the length is not declared with chunked encoding, so limit the reader too
+    let mut s = String::new(); // todo: performance
+    http_request
+        .as_reader()
+        .take(max_body_bytes as u64 + 1)
+        .read_to_string(&mut s)?;
+    if s.len() > max_body_bytes {
+        return Err(InnerError::BodyTooLarge(max_body_bytes));
+    }
+    Ok(s)
+}
 
-    Ok(request)
+/// Handle the request and build its response, stopping the server on [`Error::Stop`]
+fn process_jsonrpc_request<F, T>(
+    request: Request,
+    state: Arc<Mutex<T>>,
+    process: F,
+    running: &AtomicBool,
+) -> Response
+where
+    F: Fn(Request, Arc<Mutex<T>>) -> Result<Response, Error> + Clone + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    let id = request.id.clone();
+    match handle_jsonrpc_request(request, state, process) {
+        Ok(response) => response,
+        Err(Error::Stop) => {
+            running.store(false, Ordering::SeqCst);
+            Response::from_error(id, Error::Stop)
+        }
+        Err(err) => Response::from_error(id, err),
+    }
 }
 
 fn handle_jsonrpc_request<F, T>(
@@ -325,19 +436,255 @@ where
     Ok(response)
 }
 
+/// Handle a REST request, returns true if the server should stop
+fn handle_rest_request<F, T>(
+    mut http_request: tiny_http::Request,
+    router: &RestRouter,
+    state: Arc<Mutex<T>>,
+    func: F,
+    headers: &[Header],
+    max_body_bytes: usize,
+) -> bool
+where
+    F: Fn(Request, Arc<Mutex<T>>) -> Result<Response, Error> + Clone + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    let body = match read_body(&mut http_request, max_body_bytes) {
+        Ok(body) => body,
+        Err(e @ InnerError::BodyTooLarge(_)) => {
+            let message = format!("413: {e}");
+            let response = HttpResponse::from_string(&message).with_status_code(413);
+            send_http_response(http_request, response, &message);
+            return false;
+        }
+        Err(e) => {
+            let message = format!("400: Cannot read body: {e}");
+            let response = HttpResponse::from_string(&message).with_status_code(400);
+            send_http_response(http_request, response, &message);
+            return false;
+        }
+    };
+    let body = if body.trim().is_empty() {
+        None
+    } else {
+        match serde_json::from_str(&body) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                let message = format!("400: Invalid JSON body: {e}");
+                let response = HttpResponse::from_string(&message).with_status_code(400);
+                send_http_response(http_request, response, &message);
+                return false;
+            }
+        }
+    };
+
+    let if_none_match = http_request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("If-None-Match"))
+        .map(|h| h.value.as_str().to_string());
+
+    let Some(request) = router.route(http_request.method(), http_request.url(), body) else {
+        let message = "404: Not found";
+        let response = HttpResponse::from_string(message).with_status_code(404);
+        send_http_response(http_request, response, message);
+        return false;
+    };
+
+    let id = request.id.clone();
+    let (response, stop) = match handle_jsonrpc_request(request, state, func) {
+        Ok(response) => (response, false),
+        Err(Error::Stop) => (Response::from_error(id, Error::Stop), true),
+        Err(err) => (Response::from_error(id, err), false),
+    };
+    let etag = response.etag.map(|etag| format!("\"{etag}\""));
+    if let (Some(etag), Some(if_none_match)) = (etag.as_ref(), if_none_match) {
+        if response.result.is_some()
+            && if_none_match
+                .split(',')
+                .any(|t| t.trim() == etag.as_str() || t.trim() == "*")
+        {
+            let mut response = HttpResponse::empty(304);
+            response.add_header(etag_header(etag));
+            for header in headers.iter() {
+                response.add_header(header.clone());
+            }
+            send_http_response(http_request, response, "REST not modified");
+            return stop;
+        }
+    }
+    let (status, value) = match (response.result, response.error) {
+        (Some(result), _) => (200, result),
+        (None, Some(error)) => {
+            let status = if error.code == METHOD_NOT_FOUND {
+                404
+            } else {
+                400
+            };
+            (status, serde_json::to_value(error).unwrap_or(Value::Null))
+        }
+        (None, None) => (200, Value::Null),
+    };
+    let mut response = HttpResponse::from_string(value.to_string()).with_status_code(status);
+    let content_type = Header::from_str("Content-Type: application/json").expect("valid header");
+    response.add_header(content_type);
+    if let Some(etag) = etag.filter(|_| status == 200) {
+        response.add_header(etag_header(&etag));
+    }
+    for header in headers.iter() {
+        response.add_header(header.clone());
+    }
+    send_http_response(http_request, response, "REST response");
+    stop
+}
+
+/// The `Content-Type` header of a served file, inferred from its extension
+///
+/// Browsers are strict on some types, for instance streaming compilation of wasm modules
+/// requires `application/wasm`.
+fn content_type_header(path: &Path) -> Header {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let content_type = match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("wasm") => "application/wasm",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("ico") => "image/x-icon",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    };
+    Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).expect("valid header")
+}
+
+fn etag_header(etag: &str) -> Header {
+    Header::from_bytes(&b"ETag"[..], etag.as_bytes()).expect("valid header")
+}
+
+/// Send a single response or a batch of responses
 fn send_jsonrpc_response(
     request: tiny_http::Request,
-    response: Response,
-    headers: &[Header],
+    response: impl serde::Serialize,
+    config: &Config,
 ) -> Result<(), InnerError> {
     let data = serde_json::to_string(&response)?;
-    let mut response = HttpResponse::from_string(data);
-    for header in headers.iter() {
+    let encoding = if config.compression && data.len() >= COMPRESSION_MIN_BYTES {
+        accepted_encoding(&request)
+    } else {
+        None
+    };
+    let mut response = match encoding {
+        Some(encoding) => {
+            // same content type `from_string` sets on uncompressed responses
+            let content_type = Header::from_bytes("Content-Type", "text/plain; charset=UTF-8")
+                .expect("valid header");
+            let content_encoding =
+                Header::from_bytes("Content-Encoding", encoding.as_str()).expect("valid header");
+            HttpResponse::from_data(encoding.compress(data.as_bytes())?)
+                .with_header(content_type)
+                .with_header(content_encoding)
+        }
+        None => HttpResponse::from_string(data),
+    };
+    if config.compression {
+        let vary = Header::from_bytes("Vary", "Accept-Encoding").expect("valid header");
+        response.add_header(vary);
+    }
+    for header in config.headers.iter() {
         response.add_header(header.clone());
     }
     Ok(request.respond(response)?)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let level = flate2::Compression::default();
+        match self {
+            Encoding::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Encoding::Deflate => {
+                // HTTP "deflate" is the zlib format
+                let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// The encoding to compress the response with according to the `Accept-Encoding` header
+fn accepted_encoding(request: &tiny_http::Request) -> Option<Encoding> {
+    let accept = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Accept-Encoding"))?;
+    parse_accept_encoding(accept.value.as_str())
+}
+
+/// The accepted encoding with the highest quality value (`q`), gzip is preferred on ties
+///
+/// A missing quality value is 1, `q=0` means the encoding is not acceptable and `*` gives its
+/// quality value to the encodings not listed.
+fn parse_accept_encoding(value: &str) -> Option<Encoding> {
+    let mut listed = vec![];
+    let mut wildcard = None;
+    for item in value.split(',') {
+        let mut parts = item.split(';').map(str::trim);
+        let name = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let q = parts
+            .find_map(|p| p.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        let encoding = match name.as_str() {
+            "gzip" => Encoding::Gzip,
+            "deflate" => Encoding::Deflate,
+            "*" => {
+                wildcard = Some(q);
+                continue;
+            }
+            _ => continue,
+        };
+        listed.push((encoding, q));
+    }
+    let mut best: Option<(Encoding, f32)> = None;
+    for encoding in [Encoding::Gzip, Encoding::Deflate] {
+        let q = listed
+            .iter()
+            .find(|(e, _)| *e == encoding)
+            .map(|(_, q)| *q)
+            .or(wildcard);
+        match (q, best) {
+            (Some(q), _) if q <= 0.0 => {}
+            (Some(q), None) => best = Some((encoding, q)),
+            (Some(q), Some((_, best_q))) if q > best_q => best = Some((encoding, q)),
+            _ => {}
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Request {
     pub jsonrpc: String,
@@ -346,7 +693,11 @@ pub struct Request {
     pub params: Option<Value>,
 }
 
+/// A JSON-RPC response
+///
+/// Build it with [`Response::result`] or [`Response::error`], fields may be added in the future.
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct Response {
     pub jsonrpc: String,
     pub id: Option<Id>,
@@ -354,6 +705,9 @@ pub struct Response {
     pub result: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<RpcError>,
+    /// Version of the result, used for conditional REST requests, not part of the JSON-RPC response
+    #[serde(skip)]
+    pub etag: Option<String>,
 }
 
 impl Response {
@@ -363,9 +717,17 @@ impl Response {
             id,
             result: Some(value),
             error: None,
+            etag: None,
         }
     }
 
+    /// Set the ETag of the result, REST requests with a matching `If-None-Match` header get a
+    /// `304 Not Modified` response
+    pub fn with_etag(mut self, etag: String) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+
     pub fn error(id: Option<Id>, code: i64, message: String, data: Option<Value>) -> Self {
         let err = RpcError {
             code,
@@ -377,6 +739,7 @@ impl Response {
             id,
             result: None,
             error: Some(err),
+            etag: None,
         }
     }
 
@@ -386,6 +749,7 @@ impl Response {
             id,
             result: None,
             error: Some(error.as_rpc_error()),
+            etag: None,
         }
     }
 
@@ -415,7 +779,7 @@ impl Display for RpcError {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum Id {
     Number(u64),
@@ -438,6 +802,7 @@ mod test {
                 id: request.id,
                 result: request.params,
                 error: None,
+                etag: None,
             },
             _ => unimplemented!(),
         };
@@ -487,6 +852,186 @@ mod test {
         assert!(response.error.is_some());
     }
 
+    #[test]
+    fn batch() {
+        let addr = "127.0.0.1:0";
+        let server = Server::http(addr).unwrap();
+        let state = Arc::new(Mutex::new(()));
+        let mut rpc = JsonRpcServer::new(server, Config::default(), state, process);
+        let port = rpc.port().unwrap();
+        let url = format!("http://127.0.0.1:{}", port);
+        let post = |body: Value| {
+            reqwest::blocking::Client::new()
+                .post(&url)
+                .json(&body)
+                .send()
+                .unwrap()
+        };
+
+        let resp = post(json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "echo", "params": "a"},
+            {"jsonrpc": "2.0", "method": "echo", "params": "notification"},
+            {"foo": "bar"},
+            {"jsonrpc": "2.0", "id": 2, "method": "rpc.reserved"},
+            {"jsonrpc": "2.0", "id": 3, "method": "echo", "params": "b"},
+        ]));
+        assert_eq!(resp.status(), 200);
+        let responses: Vec<Response> = resp.json().unwrap();
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0].id, Some(Id::Number(1)));
+        assert_eq!(responses[0].result, Some(json!("a")));
+        // One invalid request doesn't fail the others
+        assert_eq!(responses[1].id, None);
+        assert_eq!(responses[1].error.as_ref().unwrap().code, -32_600);
+        assert_eq!(responses[2].id, Some(Id::Number(2)));
+        assert!(responses[2].is_error());
+        assert_eq!(responses[3].result, Some(json!("b")));
+
+        let resp = post(json!([]));
+        let response: Response = resp.json().unwrap();
+        assert_eq!(response.error.unwrap().code, -32_600);
+
+        // A batch of notifications has no response content
+        let resp = post(json!([{"jsonrpc": "2.0", "method": "echo"}]));
+        assert_eq!(resp.status(), 204);
+        assert!(resp.text().unwrap().is_empty());
+
+        rpc.stop();
+        rpc.join_threads();
+    }
+
+    #[test]
+    fn max_body_bytes() {
+        let addr = "127.0.0.1:0";
+        let server = Server::http(addr).unwrap();
+        let state = Arc::new(Mutex::new(()));
+        let config = Config::builder().with_max_body_bytes(100).build();
+        let mut rpc = JsonRpcServer::new(server, config, state, process);
+        let port = rpc.port().unwrap();
+        let url = format!("http://127.0.0.1:{}", port);
+        let post = |params: &str| {
+            let body = json!({"jsonrpc": "2.0", "id": 1, "method": "echo", "params": params});
+            let resp = reqwest::blocking::Client::new()
+                .post(&url)
+                .json(&body)
+                .send()
+                .unwrap();
+            resp.json::<Response>().unwrap()
+        };
+
+        let response = post("small");
+        assert_eq!(response.result, Some(json!("small")));
+
+        let response = post(&"x".repeat(100));
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32_097);
+        assert!(error.message.contains("100 bytes"), "{}", error.message);
+
+        rpc.stop();
+        rpc.join_threads();
+    }
+
+    #[test]
+    fn compression() {
+        let addr = "127.0.0.1:0";
+        let server = Server::http(addr).unwrap();
+        let state = Arc::new(Mutex::new(()));
+        let config = Config::builder().with_compression(true).build();
+        let mut rpc = JsonRpcServer::new(server, config, state, process);
+        let port = rpc.port().unwrap();
+        let url = format!("http://127.0.0.1:{}", port);
+        let post = |params: &str, accept_encoding: &str| {
+            let body = json!({"jsonrpc": "2.0", "id": 1, "method": "echo", "params": params});
+            reqwest::blocking::Client::new()
+                .post(&url)
+                .header("Accept-Encoding", accept_encoding)
+                .json(&body)
+                .send()
+                .unwrap()
+        };
+        let content_encoding = |resp: &reqwest::blocking::Response| {
+            resp.headers()
+                .get("Content-Encoding")
+                .map(|v| v.to_str().unwrap().to_string())
+        };
+
+        let large = "x".repeat(COMPRESSION_MIN_BYTES);
+        let resp = post(&large, "deflate, gzip;q=0.5");
+        assert_eq!(content_encoding(&resp).as_deref(), Some("gzip"));
+        let mut data = String::new();
+        flate2::read::GzDecoder::new(&resp.bytes().unwrap()[..])
+            .read_to_string(&mut data)
+            .unwrap();
+        let response: Response = serde_json::from_str(&data).unwrap();
+        assert_eq!(response.result, Some(json!(large)));
+
+        let resp = post(&large, "deflate");
+        assert_eq!(content_encoding(&resp).as_deref(), Some("deflate"));
+        let mut data = String::new();
+        flate2::read::ZlibDecoder::new(&resp.bytes().unwrap()[..])
+            .read_to_string(&mut data)
+            .unwrap();
+        let response: Response = serde_json::from_str(&data).unwrap();
+        assert_eq!(response.result, Some(json!(large)));
+
+        // Small responses and clients not accepting compression get plain responses
+        let resp = post("small", "gzip");
+        assert_eq!(content_encoding(&resp), None);
+        let response: Response = resp.json().unwrap();
+        assert_eq!(response.result, Some(json!("small")));
+
+        let resp = post(&large, "identity");
+        assert_eq!(content_encoding(&resp), None);
+        let response: Response = resp.json().unwrap();
+        assert_eq!(response.result, Some(json!(large)));
+
+        rpc.stop();
+        rpc.join_threads();
+    }
+
+    #[test]
+    fn accept_encoding() {
+        assert_eq!(parse_accept_encoding("gzip"), Some(Encoding::Gzip));
+        assert_eq!(parse_accept_encoding("deflate, gzip"), Some(Encoding::Gzip));
+        assert_eq!(
+            parse_accept_encoding("br, deflate"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(parse_accept_encoding("*"), Some(Encoding::Gzip));
+        assert_eq!(parse_accept_encoding("GZIP; q=0.8"), Some(Encoding::Gzip));
+        assert_eq!(
+            parse_accept_encoding("gzip;q=0, deflate"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(parse_accept_encoding("gzip;q=0"), None);
+        assert_eq!(
+            parse_accept_encoding("gzip;q=0, *"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(parse_accept_encoding("gzip;q=0, deflate;q=0, *"), None);
+        assert_eq!(parse_accept_encoding("*;q=0"), None);
+        assert_eq!(parse_accept_encoding("identity"), None);
+
+        // The highest quality value is chosen
+        assert_eq!(
+            parse_accept_encoding("gzip;q=0.5, deflate"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(
+            parse_accept_encoding("gzip;q=0.5, deflate;q=0.8"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(
+            parse_accept_encoding("gzip;q=0.8, deflate;q=0.8"),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(
+            parse_accept_encoding("gzip;q=0.2, *;q=0.5"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(parse_accept_encoding(""), None);
+    }
+
     #[test]
     fn response_serialization() {
         // result response must not include error key
@@ -495,6 +1040,7 @@ mod test {
             id: Some(Id::Number(123)),
             result: Some(Value::Bool(true)),
             error: None,
+            etag: None,
         };
         let actual = serde_json::to_value(response).unwrap();
         let expected = json!({
@@ -515,6 +1061,7 @@ mod test {
                 message: "Sunlifter".into(),
                 data: None,
             }),
+            etag: None,
         };
         let actual = serde_json::to_value(response).unwrap();
         let expected = json!({
@@ -565,6 +1112,77 @@ mod test {
         assert!(resp.bytes().unwrap().is_empty());
     }
 
+    #[test]
+    fn rest() {
+        let addr = "127.0.0.1:0";
+        let server = Server::http(addr).unwrap();
+        let state = Arc::new(Mutex::new(()));
+        let router = RestRouter::new("/api", |method, url, body| match (method, url) {
+            (tiny_http::Method::Get, "/echo") => Some(Request {
+                jsonrpc: "2.0".into(),
+                id: None,
+                method: "echo".into(),
+                params: Some(json!("get")),
+            }),
+            (tiny_http::Method::Post, "/echo") => Some(Request {
+                jsonrpc: "2.0".into(),
+                id: None,
+                method: "echo".into(),
+                params: body,
+            }),
+            _ => None,
+        });
+        let dir = tempfile::tempdir().unwrap();
+        make_file(dir.path().to_path_buf(), "echo".into(), b"file");
+        let config = Config::builder()
+            .with_rest(Some(router))
+            .with_serve_dir(Some(dir.path().to_path_buf()))
+            .build();
+        let mut rpc = JsonRpcServer::new(server, config, state, process);
+        let port = rpc.port().unwrap();
+        let base = format!("http://127.0.0.1:{}", port);
+
+        let resp = reqwest::blocking::get(format!("{base}/api/echo")).unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.json::<Value>().unwrap(), json!("get"));
+
+        let resp = reqwest::blocking::Client::new()
+            .post(format!("{base}/api/echo"))
+            .json(&json!({"a": 1}))
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.json::<Value>().unwrap(), json!({"a": 1}));
+
+        let resp = reqwest::blocking::get(format!("{base}/api/missing")).unwrap();
+        assert_eq!(resp.status(), 404);
+
+        // Urls outside the prefix are still served from `serve_dir`
+        let resp = reqwest::blocking::get(format!("{base}/echo")).unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().unwrap(), "file");
+        let resp = reqwest::blocking::get(format!("{base}/apiecho")).unwrap();
+        assert_eq!(resp.status(), 404);
+
+        // CORS preflight requests are not REST requests
+        let resp = reqwest::blocking::Client::new()
+            .request(reqwest::Method::OPTIONS, format!("{base}/api/echo"))
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 204);
+
+        // JSON-RPC is still served on `/`
+        let client = Client::simple_http(&base[7..], None, None).unwrap();
+        let params = to_raw_value("rpc").unwrap();
+        let response = client
+            .send_request(client.build_request("echo", Some(&params)))
+            .unwrap();
+        assert_eq!(response.result.unwrap().get(), "\"rpc\"");
+
+        rpc.stop();
+        rpc.join_threads();
+    }
+
     fn make_file(dir_path: PathBuf, file_name: String, data: &[u8]) -> File {
         let mut path = dir_path;
         path.push(file_name);
@@ -592,20 +1210,47 @@ mod test {
 
         // create files to GET
         let file_types = [
-            ("html", "<!doctype html>".as_bytes()),
-            ("css", include_bytes!("../test/data/file.css")),
-            ("js", include_bytes!("../test/data/file.js")),
-            ("ico", include_bytes!("../test/data/file.ico")),
-            ("jpg", include_bytes!("../test/data/file.jpg")),
-            ("png", include_bytes!("../test/data/file.png")),
-            ("svg", include_bytes!("../test/data/file.svg")),
+            (
+                "html",
+                "<!doctype html>".as_bytes(),
+                "text/html; charset=utf-8",
+            ),
+            (
+                "css",
+                include_bytes!("../test/data/file.css"),
+                "text/css; charset=utf-8",
+            ),
+            (
+                "js",
+                include_bytes!("../test/data/file.js"),
+                "text/javascript; charset=utf-8",
+            ),
+            (
+                "ico",
+                include_bytes!("../test/data/file.ico"),
+                "image/x-icon",
+            ),
+            ("jpg", include_bytes!("../test/data/file.jpg"), "image/jpeg"),
+            ("png", include_bytes!("../test/data/file.png"), "image/png"),
+            (
+                "svg",
+                include_bytes!("../test/data/file.svg"),
+                "image/svg+xml",
+            ),
+            ("wasm", b"\0asm\x01\0\0\0", "application/wasm"),
+            ("bin", b"\x01\x02", "application/octet-stream"),
         ];
-        for (ext, data) in file_types.into_iter() {
+        for (ext, data, content_type) in file_types.into_iter() {
             let file_name = format!("file.{}", ext);
             let url = format!("http://127.0.0.1:{}/{}", port, file_name);
             make_file(dir_path.clone(), file_name, data);
             let resp = reqwest::blocking::get(url).unwrap();
             assert_eq!(resp.status(), 200);
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                content_type,
+                "{ext}"
+            );
             assert_eq!(&resp.bytes().unwrap()[..], data);
         }
 
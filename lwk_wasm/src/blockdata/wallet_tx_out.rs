@@ -24,6 +24,10 @@ pub enum Chain {
 
     /// Internal address, used for the change
     Internal,
+
+    /// Further path of a multipath descriptor, after the external and internal ones,
+    /// use `WalletTxOut.pathIndex()` to get which one
+    Path,
 }
 
 impl From<lwk_wollet::Chain> for Chain {
@@ -31,6 +35,7 @@ impl From<lwk_wollet::Chain> for Chain {
         match value {
             lwk_wollet::Chain::External => Chain::External,
             lwk_wollet::Chain::Internal => Chain::Internal,
+            lwk_wollet::Chain::Path(_) => Chain::Path,
         }
     }
 }
@@ -63,6 +68,14 @@ impl WalletTxOut {
     pub fn ext_int(&self) -> Chain {
         self.inner.ext_int.into()
     }
+
+    /// Index of the path in the multipath descriptor, eg 2 for `.../<0;1;2>/*`
+    ///
+    /// Distinguishes the paths when `extInt` is `Chain.Path`
+    #[wasm_bindgen(js_name = pathIndex)]
+    pub fn path_index(&self) -> u32 {
+        self.inner.ext_int.path_index()
+    }
 }
 
 /// An optional wallet transaction output. Could be None when it's not possible to unblind.
@@ -123,6 +136,8 @@ mod tests {
 
         assert_eq!(wallet_tx_out.height(), el.height);
 
+        assert_eq!(wallet_tx_out.path_index(), 0);
+
         assert_eq!(wallet_tx_out.unblinded(), el.unblinded.into());
 
         assert_eq!(wallet_tx_out.wildcard_index(), el.wildcard_index);
@@ -0,0 +1,206 @@
+use std::str::FromStr;
+
+use crate::{Bip, Error, Pset, WolletDescriptor, Xpub};
+use lwk_wollet::{
+    bitcoin::{bip32, hashes::hex::FromHex},
+    elements::pset::PartiallySignedTransaction,
+    elements_miniscript::slip77,
+};
+use wasm_bindgen::prelude::*;
+
+/// The operations a signer delegates to the code holding the seed
+///
+/// Like [`lwk_jade::asyncr::Stream`] abstracts the transport to the Jade, this trait abstracts
+/// where the keys are held. Values are exchanged as strings, so the seed never enters
+/// Rust-managed memory. Methods are synchronous because [`lwk_common::Signer`] is.
+pub trait SignerCallbacks {
+    /// Sign the given base64 PSET, returning the signed PSET in base64
+    fn sign(&self, pset: &str) -> Result<String, String>;
+
+    /// Derive the xpub at the given path, eg `84'/1'/0'`, empty for the master xpub
+    fn derive_xpub(&self, path: &str) -> Result<String, String>;
+
+    /// Return the slip77 master blinding key in hex
+    fn slip77_master_blinding_key(&self) -> Result<String, String>;
+}
+
+#[wasm_bindgen]
+extern "C" {
+    /// A JS object with the `sign`, `deriveXpub` and `slip77MasterBlindingKey` methods
+    ///
+    /// Methods have the signatures of [`SignerCallbacks`] and throw in case of errors.
+    pub type JsSignerCallbacks;
+
+    #[wasm_bindgen(method, catch, js_name = sign)]
+    fn js_sign(this: &JsSignerCallbacks, pset: &str) -> Result<String, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = deriveXpub)]
+    fn js_derive_xpub(this: &JsSignerCallbacks, path: &str) -> Result<String, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = slip77MasterBlindingKey)]
+    fn js_slip77_master_blinding_key(this: &JsSignerCallbacks) -> Result<String, JsValue>;
+}
+
+impl SignerCallbacks for JsSignerCallbacks {
+    fn sign(&self, pset: &str) -> Result<String, String> {
+        self.js_sign(pset).map_err(|e| format!("{e:?}"))
+    }
+
+    fn derive_xpub(&self, path: &str) -> Result<String, String> {
+        self.js_derive_xpub(path).map_err(|e| format!("{e:?}"))
+    }
+
+    fn slip77_master_blinding_key(&self) -> Result<String, String> {
+        self.js_slip77_master_blinding_key()
+            .map_err(|e| format!("{e:?}"))
+    }
+}
+
+/// Implements [`lwk_common::Signer`] over [`SignerCallbacks`]
+pub(crate) struct BridgeSigner<C>(pub(crate) C);
+
+fn count_signatures(pset: &PartiallySignedTransaction) -> usize {
+    pset.inputs().iter().map(|i| i.partial_sigs.len()).sum()
+}
+
+impl<C: SignerCallbacks> lwk_common::Signer for BridgeSigner<C> {
+    type Error = String;
+
+    fn sign(&self, pset: &mut PartiallySignedTransaction) -> Result<u32, Self::Error> {
+        let signed = self.0.sign(&pset.to_string())?;
+        let signed = PartiallySignedTransaction::from_str(signed.trim())
+            .map_err(|e| format!("Invalid signed pset: {e}"))?;
+        let before = count_signatures(pset);
+        // Merging, instead of replacing, makes sure the callback only added data
+        pset.merge(signed)
+            .map_err(|e| format!("Signed pset does not match: {e}"))?;
+        Ok(count_signatures(pset).saturating_sub(before) as u32)
+    }
+
+    fn derive_xpub(&self, path: &bip32::DerivationPath) -> Result<bip32::Xpub, Self::Error> {
+        let xpub = self.0.derive_xpub(&path.to_string())?;
+        bip32::Xpub::from_str(xpub.trim()).map_err(|e| format!("Invalid xpub: {e}"))
+    }
+
+    fn slip77_master_blinding_key(&self) -> Result<slip77::MasterBlindingKey, Self::Error> {
+        let key = self.0.slip77_master_blinding_key()?;
+        let bytes = <[u8; 32]>::from_hex(key.trim())
+            .map_err(|e| format!("Invalid slip77 master blinding key: {e}"))?;
+        Ok(slip77::MasterBlindingKey::from(bytes))
+    }
+}
+
+/// A signer whose seed is held by JS code, signing through [`JsSignerCallbacks`]
+#[wasm_bindgen]
+pub struct JsSigner {
+    inner: BridgeSigner<JsSignerCallbacks>,
+}
+
+#[wasm_bindgen]
+impl JsSigner {
+    /// Creates a `JsSigner`
+    #[wasm_bindgen(constructor)]
+    pub fn new(callbacks: JsSignerCallbacks) -> JsSigner {
+        Self {
+            inner: BridgeSigner(callbacks),
+        }
+    }
+
+    /// Sign and consume the given PSET, returning the signed one
+    pub fn sign(&self, pset: Pset) -> Result<Pset, Error> {
+        let mut pset: PartiallySignedTransaction = pset.into();
+        let added = lwk_common::Signer::sign(&self.inner, &mut pset).map_err(Error::Generic)?;
+        if added == 0 {
+            return Err(Error::Generic("No signature added".to_string()));
+        }
+        Ok(pset.into())
+    }
+
+    #[wasm_bindgen(js_name = wpkhSlip77Descriptor)]
+    pub fn wpkh_slip77_descriptor(&self) -> Result<WolletDescriptor, Error> {
+        let is_mainnet = lwk_common::Signer::is_mainnet(&self.inner).map_err(Error::Generic)?;
+        let script_variant = lwk_common::Singlesig::Wpkh;
+        let blinding_variant = lwk_common::DescriptorBlindingKey::Slip77;
+        let desc_str =
+            lwk_common::singlesig_desc(&self.inner, script_variant, blinding_variant, is_mainnet)
+                .map_err(Error::Generic)?;
+
+        WolletDescriptor::new(&desc_str)
+    }
+
+    #[wasm_bindgen(js_name = getMasterXpub)]
+    pub fn get_master_xpub(&self) -> Result<Xpub, Error> {
+        let xpub = lwk_common::Signer::xpub(&self.inner).map_err(Error::Generic)?;
+        Ok(xpub.into())
+    }
+
+    #[wasm_bindgen(js_name = keyoriginXpub)]
+    pub fn keyorigin_xpub(&self, bip: Bip) -> Result<String, Error> {
+        let is_mainnet = lwk_common::Signer::is_mainnet(&self.inner).map_err(Error::Generic)?;
+        lwk_common::Signer::keyorigin_xpub(&self.inner, bip.into(), is_mainnet)
+            .map_err(Error::Generic)
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{BridgeSigner, SignerCallbacks};
+    use lwk_common::Signer;
+    use lwk_signer::SwSigner;
+    use lwk_wollet::bitcoin::bip32::DerivationPath;
+    use lwk_wollet::elements::pset::PartiallySignedTransaction;
+    use wasm_bindgen_test::*;
+
+    /// Callbacks backed by a software signer, in place of the JS code
+    struct MockCallbacks(SwSigner);
+
+    impl SignerCallbacks for MockCallbacks {
+        fn sign(&self, pset: &str) -> Result<String, String> {
+            let mut pset = PartiallySignedTransaction::from_str(pset).unwrap();
+            self.0.sign(&mut pset).map_err(|e| e.to_string())?;
+            Ok(pset.to_string())
+        }
+
+        fn derive_xpub(&self, path: &str) -> Result<String, String> {
+            let path = DerivationPath::from_str(path).map_err(|e| e.to_string())?;
+            let xpub = self.0.derive_xpub(&path).map_err(|e| e.to_string())?;
+            Ok(xpub.to_string())
+        }
+
+        fn slip77_master_blinding_key(&self) -> Result<String, String> {
+            let key = self.0.slip77_master_blinding_key().unwrap();
+            Ok(key.to_string())
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn bridge_signer() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let sw_signer = SwSigner::new(mnemonic, false).unwrap();
+        let signer = BridgeSigner(MockCallbacks(SwSigner::new(mnemonic, false).unwrap()));
+
+        let pset_string = include_str!("../../lwk_jade/test_data/pset_to_be_signed.base64");
+        let pset = PartiallySignedTransaction::from_str(pset_string.trim()).unwrap();
+
+        let mut expected = pset.clone();
+        let expected_added = sw_signer.sign(&mut expected).unwrap();
+        let mut signed = pset.clone();
+        let added = signer.sign(&mut signed).unwrap();
+        assert!(added > 0);
+        assert_eq!(added, expected_added);
+        assert_eq!(signed, expected);
+
+        let path = DerivationPath::from_str("m/84h/1h/0h").unwrap();
+        assert_eq!(
+            signer.derive_xpub(&path).unwrap(),
+            sw_signer.derive_xpub(&path).unwrap()
+        );
+        assert_eq!(signer.fingerprint().unwrap().to_string(), "73c5da0a");
+        assert_eq!(
+            signer.slip77_master_blinding_key().unwrap(),
+            sw_signer.slip77_master_blinding_key().unwrap()
+        );
+    }
+}
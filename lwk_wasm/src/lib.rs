@@ -10,6 +10,8 @@ mod error;
 mod esplora;
 #[cfg(all(feature = "serial", target_arch = "wasm32"))]
 mod jade;
+#[cfg(target_arch = "wasm32")]
+mod js_signer;
 mod mnemonic;
 mod network;
 mod precision;
@@ -39,6 +41,8 @@ pub(crate) use error::Error;
 pub use esplora::EsploraClient;
 #[cfg(all(feature = "serial", target_arch = "wasm32"))]
 pub use jade::{Jade, Singlesig};
+#[cfg(target_arch = "wasm32")]
+pub use js_signer::{JsSigner, JsSignerCallbacks, SignerCallbacks};
 pub use mnemonic::Mnemonic;
 pub use network::Network;
 pub use precision::Precision;
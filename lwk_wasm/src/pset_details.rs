@@ -93,6 +93,11 @@ impl PsetBalance {
         let serializer = Serializer::new().serialize_large_number_types_as_bigints(true);
         Ok(self.inner.balances.serialize(&serializer)?)
     }
+
+    /// Warnings about parts of the PSET that could not be taken into account
+    pub fn warnings(&self) -> Vec<String> {
+        self.inner.warnings().to_vec()
+    }
 }
 
 #[wasm_bindgen]
@@ -10,7 +10,7 @@
 //! AMP2 is under development, expect breaking changes.
 //! </div>
 
-use crate::WolletDescriptor;
+use crate::{ElementsNetwork, WolletDescriptor};
 use elements::bitcoin::bip32::{KeySource, Xpub};
 use elements::pset::PartiallySignedTransaction;
 use lwk_common::keyorigin_xpub_from_str;
@@ -69,6 +69,62 @@ pub struct CosignResponse {
     pub pset: PartiallySignedTransaction,
 }
 
+#[derive(Serialize)]
+struct CosignStatusRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum CosignStatusInner {
+    Pending,
+    Cosigned { pset: String },
+}
+
+/// The status of a cosign request
+pub enum CosignStatus {
+    /// The AMP2 server has not cosigned yet, ask again later
+    Pending,
+
+    /// The AMP2 server has cosigned, the PSET contains its signatures
+    Cosigned(PartiallySignedTransaction),
+}
+
+/// A cosign request, identified by the PSET or by the id assigned by the AMP2 server
+pub enum CosignRef<'a> {
+    Pset(&'a PartiallySignedTransaction),
+    Id(&'a str),
+}
+
+impl From<CosignRef<'_>> for CosignStatusRequest {
+    fn from(r: CosignRef<'_>) -> Self {
+        match r {
+            CosignRef::Pset(pset) => Self {
+                pset: Some(pset.to_string()),
+                id: None,
+            },
+            CosignRef::Id(id) => Self {
+                pset: None,
+                id: Some(id.to_string()),
+            },
+        }
+    }
+}
+
+impl TryFrom<CosignStatusInner> for CosignStatus {
+    type Error = crate::Error;
+
+    fn try_from(r: CosignStatusInner) -> Result<CosignStatus, Self::Error> {
+        Ok(match r {
+            CosignStatusInner::Pending => CosignStatus::Pending,
+            CosignStatusInner::Cosigned { pset } => CosignStatus::Cosigned(pset.parse()?),
+        })
+    }
+}
+
 impl TryFrom<CosignResponseInner> for CosignResponse {
     type Error = crate::Error;
 
@@ -86,6 +142,32 @@ impl Amp2 {
         }
     }
 
+    /// Get the AMP2 context for a server with the given keyorigin xpub and url
+    ///
+    /// This allows to use AMP2 servers other than the Liquid Testnet one, for instance on Liquid
+    /// mainnet.
+    pub fn new(server_keyorigin_xpub: &str, url: &str) -> Result<Self, crate::Error> {
+        let (keysource, _xpub) = keyorigin_xpub_from_str(server_keyorigin_xpub)?;
+        keysource.ok_or_else(|| crate::Error::MissingKeyorigin)?;
+        Ok(Self {
+            server_key: server_keyorigin_xpub.into(),
+            url: url.into(),
+        })
+    }
+
+    /// Get the AMP2 context for the given network
+    ///
+    /// Only Liquid Testnet is available, the AMP2 server key for Liquid mainnet is not published
+    /// yet: use [`Amp2::new()`] with the server key and url.
+    pub fn for_network(network: &ElementsNetwork) -> Result<Self, crate::Error> {
+        match network {
+            ElementsNetwork::LiquidTestnet => Ok(Self::new_testnet()),
+            ElementsNetwork::Liquid | ElementsNetwork::ElementsRegtest { .. } => {
+                Err(crate::Error::Amp2Unavailable)
+            }
+        }
+    }
+
     /// Get an AMP2 wallet descriptor from the keyorigin xpub string obtained from a signer
     pub fn descriptor_from_str(
         &self,
@@ -174,6 +256,33 @@ impl Amp2 {
             .json()?;
         j.try_into()
     }
+
+    /// Ask the AMP2 server whether it has cosigned a PSET
+    ///
+    /// Use this to poll for cosign requests that need time or approval from the AMP2 server.
+    pub async fn cosign_status(&self, r: CosignRef<'_>) -> Result<CosignStatus, crate::Error> {
+        let body = CosignStatusRequest::from(r);
+        let j: CosignStatusInner = reqwest::Client::new()
+            .post(format!("{}/wallets/sign/status", self.url))
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        j.try_into()
+    }
+
+    /// Ask the AMP2 server whether it has cosigned a PSET
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn blocking_cosign_status(&self, r: CosignRef<'_>) -> Result<CosignStatus, crate::Error> {
+        let body = CosignStatusRequest::from(r);
+        let j: CosignStatusInner = reqwest::blocking::Client::new()
+            .post(format!("{}/wallets/sign/status", self.url))
+            .json(&body)
+            .send()?
+            .json()?;
+        j.try_into()
+    }
 }
 
 #[cfg(test)]
@@ -213,6 +322,107 @@ mod test {
         assert_eq!(desc1.descriptor().to_string(), expected);
     }
 
+    #[test]
+    fn amp2_for_network() {
+        let (keysource, xpub) = user_key();
+        let amp2 = Amp2::for_network(&ElementsNetwork::LiquidTestnet).unwrap();
+        let expected = Amp2::new_testnet().descriptor(keysource.clone(), xpub);
+        let desc = amp2.descriptor(keysource, xpub);
+        assert_eq!(
+            desc.descriptor().to_string(),
+            expected.descriptor().to_string()
+        );
+
+        let err = Amp2::for_network(&ElementsNetwork::Liquid).unwrap_err();
+        assert!(matches!(err, crate::Error::Amp2Unavailable));
+        let regtest = ElementsNetwork::default_regtest();
+        assert!(Amp2::for_network(&regtest).is_err());
+    }
+
+    #[test]
+    fn amp2_new() {
+        let (keysource, xpub) = user_key();
+        let amp2 = Amp2::new(KEYORIGIN_XPUB_TESTNET, URL_TESTNET).unwrap();
+        let expected = Amp2::new_testnet().descriptor(keysource.clone(), xpub);
+        let desc = amp2.descriptor(keysource, xpub);
+        assert_eq!(
+            desc.descriptor().to_string(),
+            expected.descriptor().to_string()
+        );
+
+        let err = Amp2::new(XPUB_TESTNET, URL_TESTNET).unwrap_err();
+        assert!(matches!(err, crate::Error::MissingKeyorigin));
+        assert!(Amp2::new("not a key", URL_TESTNET).is_err());
+    }
+
+    /// Serve the given bodies, one per connection, returning the requests received
+    fn mock_server(bodies: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                let mut request = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(l) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = l.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                    request.push_str(&line);
+                }
+                let mut payload = vec![0u8; content_length];
+                reader.read_exact(&mut payload).unwrap();
+                request.push_str(&String::from_utf8(payload).unwrap());
+                requests.push(request);
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn amp2_cosign_status() {
+        let pset = PartiallySignedTransaction::new_v2();
+        let bodies = vec![
+            r#"{"status":"pending"}"#.to_string(),
+            format!(r#"{{"status":"cosigned","pset":"{pset}"}}"#),
+        ];
+        let (url, handle) = mock_server(bodies);
+        let amp2 = Amp2 {
+            server_key: KEYORIGIN_XPUB_TESTNET.into(),
+            url,
+        };
+
+        let r = amp2.blocking_cosign_status(CosignRef::Pset(&pset)).unwrap();
+        assert!(matches!(r, CosignStatus::Pending));
+        let r = amp2.blocking_cosign_status(CosignRef::Id("abc")).unwrap();
+        match r {
+            CosignStatus::Cosigned(cosigned) => assert_eq!(cosigned, pset),
+            CosignStatus::Pending => panic!("expected cosigned"),
+        }
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("POST /wallets/sign/status"));
+        assert!(requests[0].ends_with(&format!(r#"{{"pset":"{pset}"}}"#)));
+        assert!(requests[1].ends_with(r#"{"id":"abc"}"#));
+    }
+
     #[ignore]
     #[tokio::test]
     async fn amp2_network_calls() {
@@ -188,7 +188,8 @@ impl EsploraClient {
             height_blockhash,
             height_timestamp,
             tip,
-        } = if self.waterfalls {
+        } = if self.waterfalls && descriptor.change_descriptor().is_none() {
+            // Waterfalls scan a single descriptor, separate change descriptors are scanned by script
             match self.get_history_waterfalls(&descriptor, wollet).await {
                 Ok(d) => d,
                 Err(Error::UsingWaterfallsWithElip151) => {
@@ -283,11 +284,10 @@ impl EsploraClient {
         store: &Store,
     ) -> Result<Data, Error> {
         let mut data = Data::default();
-        for descriptor in descriptor.descriptor().clone().into_single_descriptors()? {
+        for (chain, descriptor) in descriptor.single_descriptors()? {
             let mut batch_count = 0;
-            let chain: Chain = (&descriptor).try_into().unwrap_or(Chain::External);
             loop {
-                let batch = store.get_script_batch(batch_count, &descriptor)?;
+                let batch = store.get_chain_script_batch(batch_count, &descriptor, chain)?;
 
                 let s: Vec<_> = batch.value.iter().map(|e| &e.0).collect();
                 let result: Vec<Vec<History>> = self.get_scripts_history(&s).await?;
@@ -301,14 +301,7 @@ impl EsploraClient {
                     .map(|(i, _)| i as u32)
                     .max();
                 if let Some(max) = max {
-                    match chain {
-                        Chain::External => {
-                            data.last_unused.external = 1 + max + batch_count * BATCH_SIZE
-                        }
-                        Chain::Internal => {
-                            data.last_unused.internal = 1 + max + batch_count * BATCH_SIZE
-                        }
-                    }
+                    data.last_unused[chain] = 1 + max + batch_count * BATCH_SIZE;
                 };
 
                 let flattened: Vec<History> = result.into_iter().flatten().collect();
@@ -395,9 +388,9 @@ impl EsploraClient {
 
         for (desc, chain_history) in waterfalls_result.txs_seen.iter() {
             let desc: elements_miniscript::Descriptor<DescriptorPublicKey> = desc.parse()?;
-            let chain: Chain = (&desc)
-                .try_into()
-                .map_err(|_| Error::Generic("Cannot determine chain from desc".into()))?;
+            let chain = descriptor
+                .chain_of(&desc)
+                .ok_or_else(|| Error::Generic("Cannot determine chain from desc".into()))?;
             let max = chain_history
                 .iter()
                 .enumerate()
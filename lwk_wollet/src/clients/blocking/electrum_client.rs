@@ -214,6 +214,12 @@ impl BlockchainBackend for ElectrumClient {
         Ok(result)
     }
 
+    fn estimate_fee_rate(&self, target_blocks: u32) -> Result<Option<f32>, Error> {
+        // The server returns BTC/kvb, or -1 if it can't estimate
+        let btc_per_kvb = self.client.estimate_fee(target_blocks as usize)?;
+        Ok((btc_per_kvb > 0.0).then(|| (btc_per_kvb * 100_000_000.0) as f32))
+    }
+
     fn get_headers(
         &self,
         heights: &[Height],
@@ -1,4 +1,4 @@
-use crate::{clients::try_unblind, Chain, ElementsNetwork, Error, WalletTxOut, WolletDescriptor};
+use crate::{clients::try_unblind, ElementsNetwork, Error, WalletTxOut, WolletDescriptor};
 
 use std::collections::HashMap;
 
@@ -95,11 +95,11 @@ impl ElementsRpcClient {
         // TODO: make this more efficient
         let params = self.network.address_params();
         let mut spk_map = HashMap::new();
-        for i in 0..range {
-            let spk_ext = desc.address(i, params)?.script_pubkey();
-            let spk_int = desc.change(i, params)?.script_pubkey();
-            spk_map.insert(spk_ext, (Chain::External, i));
-            spk_map.insert(spk_int, (Chain::Internal, i));
+        for (chain, _) in desc.single_descriptors()? {
+            for i in 0..range {
+                let spk = desc.chain_address(chain, i, params)?.script_pubkey();
+                spk_map.insert(spk, (chain, i));
+            }
         }
 
         for u in r.unspents {
@@ -54,6 +54,14 @@ pub trait BlockchainBackend {
         HashSet::new()
     }
 
+    /// Estimate the fee rate in sats/kvb to confirm within `target_blocks`
+    ///
+    /// Returns `None` if the backend can't estimate it.
+    fn estimate_fee_rate(&self, target_blocks: u32) -> Result<Option<f32>, Error> {
+        let _ = target_blocks;
+        Ok(None)
+    }
+
     fn get_history<S: WolletState>(
         &mut self,
         descriptor: &WolletDescriptor,
@@ -61,11 +69,10 @@ pub trait BlockchainBackend {
     ) -> Result<Data, Error> {
         let mut data = Data::default();
 
-        for descriptor in descriptor.descriptor().clone().into_single_descriptors()? {
+        for (chain, descriptor) in descriptor.single_descriptors()? {
             let mut batch_count = 0;
-            let chain: Chain = (&descriptor).try_into().unwrap_or(Chain::External);
             loop {
-                let batch = state.get_script_batch(batch_count, &descriptor)?;
+                let batch = state.get_chain_script_batch(batch_count, &descriptor, chain)?;
 
                 let s: Vec<_> = batch.value.iter().map(|e| &e.0).collect();
                 let result: Vec<Vec<History>> = self.get_scripts_history(&s)?;
@@ -79,14 +86,7 @@ pub trait BlockchainBackend {
                     .map(|(i, _)| i as u32)
                     .max();
                 if let Some(max) = max {
-                    match chain {
-                        Chain::External => {
-                            data.last_unused.external = 1 + max + batch_count * BATCH_SIZE
-                        }
-                        Chain::Internal => {
-                            data.last_unused.internal = 1 + max + batch_count * BATCH_SIZE
-                        }
-                    }
+                    data.last_unused[chain] = 1 + max + batch_count * BATCH_SIZE;
                 };
 
                 let flattened: Vec<History> = result.into_iter().flatten().collect();
@@ -136,7 +136,10 @@ pub trait BlockchainBackend {
             height_blockhash,
             height_timestamp: _height_timestamp,
             tip: _,
-        } = if self.capabilities().contains(&Capability::Waterfalls) {
+        } = if self.capabilities().contains(&Capability::Waterfalls)
+            && descriptor.change_descriptor().is_none()
+        {
+            // Waterfalls scan a single descriptor, separate change descriptors are scanned by script
             match self.get_history_waterfalls(&descriptor, state) {
                 Ok(d) => d,
                 Err(Error::UsingWaterfallsWithElip151) => self.get_history(&descriptor, state)?,
@@ -11,7 +11,7 @@ use elements::{BlockHash, Txid};
 use lwk_common::derive_blinding_key;
 use serde::Deserialize;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     ops::{Index, IndexMut},
 };
 
@@ -24,6 +24,9 @@ pub mod asyncr;
 pub struct LastUnused {
     pub internal: u32,
     pub external: u32,
+
+    /// Further paths of multipath descriptors, by path index
+    pub paths: BTreeMap<u32, u32>,
 }
 
 impl Index<Chain> for LastUnused {
@@ -33,6 +36,7 @@ impl Index<Chain> for LastUnused {
         match index {
             Chain::External => &self.external,
             Chain::Internal => &self.internal,
+            Chain::Path(i) => self.paths.get(&i).unwrap_or(&0),
         }
     }
 }
@@ -42,6 +46,7 @@ impl IndexMut<Chain> for LastUnused {
         match index {
             Chain::External => &mut self.external,
             Chain::Internal => &mut self.internal,
+            Chain::Path(i) => self.paths.entry(i).or_default(),
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::elements::{AddressParams, AssetId};
+use crate::elements::{bitcoin, AddressParams, AssetId};
 use crate::error::Error;
 use std::str::FromStr;
 
@@ -43,6 +43,15 @@ impl ElementsNetwork {
         }
     }
 
+    /// Return the bitcoin network of the mainchain, where pegins come from
+    pub fn bitcoin_network(&self) -> bitcoin::Network {
+        match self {
+            ElementsNetwork::Liquid => bitcoin::Network::Bitcoin,
+            ElementsNetwork::LiquidTestnet => bitcoin::Network::Testnet,
+            ElementsNetwork::ElementsRegtest { .. } => bitcoin::Network::Regtest,
+        }
+    }
+
     pub fn default_regtest() -> ElementsNetwork {
         let policy_asset =
             AssetId::from_str("5ac9f65c0efcc4775e0baec4ec03abdde22473cd3cf33c0419ca290e0751b225")
@@ -1,4 +1,4 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
 
 use aes_gcm_siv::aead::generic_array::GenericArray;
 use aes_gcm_siv::Aes256GcmSiv;
@@ -6,11 +6,12 @@ use aes_gcm_siv::KeyInit;
 use elements::bitcoin::{bip32::ChildNumber, WitnessVersion};
 use elements::hashes::{sha256t_hash_newtype, Hash};
 use elements::{bitcoin, Address, AddressParams, Script};
+use elements_miniscript::miniscript::decode::Terminal;
 use elements_miniscript::BtcDescriptor;
 use elements_miniscript::{
     confidential::Key,
-    descriptor::{DescriptorSecretKey, Wildcard},
-    ConfidentialDescriptor, Descriptor, DescriptorPublicKey, ForEachKey,
+    descriptor::{DescriptorSecretKey, ShInner, Wildcard, Wsh, WshInner},
+    ConfidentialDescriptor, Descriptor, DescriptorPublicKey, ForEachKey, Segwitv0,
 };
 use serde::{Deserialize, Serialize};
 
@@ -25,19 +26,323 @@ sha256t_hash_newtype! {
     pub struct EncryptionKeyHash(_);
 }
 
+/// Timelocks required to spend from a descriptor, in consensus encoding
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Timelocks {
+    /// Relative timelock (`older`), to be set in the inputs `nSequence`
+    pub older: Option<u32>,
+
+    /// Absolute timelock (`after`), to be set in the transaction `nLockTime`
+    pub after: Option<u32>,
+}
+
+impl Timelocks {
+    /// Timelocks needed to satisfy both `self` and `other`
+    fn and(self, other: Self) -> Self {
+        Self {
+            older: self.older.max(other.older),
+            after: self.after.max(other.after),
+        }
+    }
+
+    /// Timelocks needed to satisfy either `self` or `other`, the least restrictive is chosen
+    fn or(self, other: Self) -> Self {
+        if other.cost() < self.cost() {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn cost(&self) -> (usize, Option<u32>) {
+        let count = self.older.is_some() as usize + self.after.is_some() as usize;
+        (count, self.older.max(self.after))
+    }
+}
+
+fn wsh_timelocks(wsh: &Wsh<DescriptorPublicKey>) -> Timelocks {
+    match wsh.as_inner() {
+        WshInner::Ms(ms) => ms_timelocks(&ms.node),
+        WshInner::SortedMulti(_) => Timelocks::default(),
+    }
+}
+
+fn descriptor_timelocks(descriptor: &Descriptor<DescriptorPublicKey>) -> Timelocks {
+    match descriptor {
+        Descriptor::Wsh(wsh) => wsh_timelocks(wsh),
+        Descriptor::Sh(sh) => match sh.as_inner() {
+            ShInner::Wsh(wsh) => wsh_timelocks(wsh),
+            _ => Timelocks::default(),
+        },
+        _ => Timelocks::default(),
+    }
+}
+
+fn ms_timelocks(node: &Terminal<DescriptorPublicKey, Segwitv0>) -> Timelocks {
+    match node {
+        Terminal::Older(s) => Timelocks {
+            older: Some(s.to_consensus_u32()),
+            after: None,
+        },
+        Terminal::After(l) => Timelocks {
+            older: None,
+            after: Some(l.to_consensus_u32()),
+        },
+        Terminal::Alt(a)
+        | Terminal::Swap(a)
+        | Terminal::Check(a)
+        | Terminal::DupIf(a)
+        | Terminal::Verify(a)
+        | Terminal::NonZero(a)
+        | Terminal::ZeroNotEqual(a) => ms_timelocks(&a.node),
+        Terminal::AndV(a, b) | Terminal::AndB(a, b) => {
+            ms_timelocks(&a.node).and(ms_timelocks(&b.node))
+        }
+        Terminal::AndOr(a, b, c) => ms_timelocks(&a.node)
+            .and(ms_timelocks(&b.node))
+            .or(ms_timelocks(&c.node)),
+        Terminal::OrB(a, b) | Terminal::OrD(a, b) | Terminal::OrC(a, b) | Terminal::OrI(a, b) => {
+            ms_timelocks(&a.node).or(ms_timelocks(&b.node))
+        }
+        Terminal::Thresh(k, subs) => {
+            // Satisfy the k least restrictive sub-policies
+            let mut timelocks: Vec<_> = subs.iter().map(|s| ms_timelocks(&s.node)).collect();
+            timelocks.sort_by_key(|t| t.cost());
+            timelocks
+                .into_iter()
+                .take(*k)
+                .fold(Timelocks::default(), Timelocks::and)
+        }
+        _ => Timelocks::default(),
+    }
+}
+
+/// A group of keys of which at least `threshold` must sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeysThreshold {
+    pub threshold: usize,
+    pub keys: Vec<String>,
+}
+
+/// A way to spend from a descriptor, with the signatures, timelocks and preimages it requires
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpendPath {
+    /// Every group must provide its signatures
+    pub keys: Vec<KeysThreshold>,
+
+    /// Relative timelock (`older`), in consensus encoding
+    pub older: Option<u32>,
+
+    /// Absolute timelock (`after`), in consensus encoding
+    pub after: Option<u32>,
+
+    /// Hash locks whose preimage must be revealed, eg `sha256(<hash>)`
+    pub hashes: Vec<String>,
+}
+
+impl SpendPath {
+    fn keys(threshold: usize, keys: &[DescriptorPublicKey]) -> Self {
+        Self {
+            keys: vec![KeysThreshold {
+                threshold,
+                keys: keys.iter().map(|k| k.to_string()).collect(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn hash(hash: String) -> Self {
+        Self {
+            hashes: vec![hash],
+            ..Default::default()
+        }
+    }
+
+    /// The path satisfying both `self` and `other`
+    fn and(mut self, other: &Self) -> Self {
+        self.keys.extend(other.keys.iter().cloned());
+        self.older = self.older.max(other.older);
+        self.after = self.after.max(other.after);
+        self.hashes.extend(other.hashes.iter().cloned());
+        self
+    }
+
+    /// The single key signing this path, if it has no other condition
+    fn single_key(&self) -> Option<&String> {
+        match (
+            &self.keys[..],
+            self.older,
+            self.after,
+            self.hashes.is_empty(),
+        ) {
+            ([k], None, None, true) if k.threshold == 1 && k.keys.len() == 1 => k.keys.first(),
+            _ => None,
+        }
+    }
+}
+
+/// Maximum number of spend paths returned for a descriptor
+///
+/// The paths of `thresh` and `and` fragments grow combinatorially, the ones exceeding this are
+/// dropped.
+const MAX_SPEND_PATHS: usize = 1000;
+
+/// Paths satisfying one of `a` and one of `b`
+fn and_paths(a: Vec<SpendPath>, b: &[SpendPath]) -> Vec<SpendPath> {
+    a.into_iter()
+        .flat_map(|x| b.iter().map(move |y| x.clone().and(y)))
+        .take(MAX_SPEND_PATHS)
+        .collect()
+}
+
+/// The first `max` ways, in lexicographic order, to choose `k` elements from `0..n`
+fn combinations(n: usize, k: usize, max: usize) -> Vec<Vec<usize>> {
+    let mut result = vec![];
+    if k > n {
+        return result;
+    }
+    let mut c: Vec<usize> = (0..k).collect();
+    while result.len() < max {
+        result.push(c.clone());
+        // Increment the last element that can be, and reset the following ones
+        let Some(i) = (0..k).rev().find(|&i| c[i] < n - k + i) else {
+            break;
+        };
+        c[i] += 1;
+        let start = c[i];
+        for (offset, x) in c[i + 1..].iter_mut().enumerate() {
+            *x = start + offset + 1;
+        }
+    }
+    result
+}
+
+fn ms_spend_paths(node: &Terminal<DescriptorPublicKey, Segwitv0>) -> Vec<SpendPath> {
+    match node {
+        Terminal::True => vec![SpendPath::default()],
+        Terminal::False => vec![],
+        Terminal::PkK(pk) | Terminal::PkH(pk) => vec![SpendPath::keys(1, &[pk.clone()])],
+        Terminal::Multi(k, pks) => vec![SpendPath::keys(*k, pks)],
+        Terminal::Older(s) => vec![SpendPath {
+            older: Some(s.to_consensus_u32()),
+            ..Default::default()
+        }],
+        Terminal::After(l) => vec![SpendPath {
+            after: Some(l.to_consensus_u32()),
+            ..Default::default()
+        }],
+        Terminal::Sha256(h) => vec![SpendPath::hash(format!("sha256({h})"))],
+        Terminal::Hash256(h) => vec![SpendPath::hash(format!("hash256({h})"))],
+        Terminal::Ripemd160(h) => vec![SpendPath::hash(format!("ripemd160({h})"))],
+        Terminal::Hash160(h) => vec![SpendPath::hash(format!("hash160({h})"))],
+        Terminal::Alt(a)
+        | Terminal::Swap(a)
+        | Terminal::Check(a)
+        | Terminal::DupIf(a)
+        | Terminal::Verify(a)
+        | Terminal::NonZero(a)
+        | Terminal::ZeroNotEqual(a) => ms_spend_paths(&a.node),
+        Terminal::AndV(a, b) | Terminal::AndB(a, b) => {
+            and_paths(ms_spend_paths(&a.node), &ms_spend_paths(&b.node))
+        }
+        Terminal::AndOr(a, b, c) => {
+            let mut paths = and_paths(ms_spend_paths(&a.node), &ms_spend_paths(&b.node));
+            paths.extend(ms_spend_paths(&c.node));
+            paths.truncate(MAX_SPEND_PATHS);
+            paths
+        }
+        Terminal::OrB(a, b) | Terminal::OrD(a, b) | Terminal::OrC(a, b) | Terminal::OrI(a, b) => {
+            let mut paths = ms_spend_paths(&a.node);
+            paths.extend(ms_spend_paths(&b.node));
+            paths.truncate(MAX_SPEND_PATHS);
+            paths
+        }
+        Terminal::Thresh(k, subs) => {
+            let subs: Vec<_> = subs.iter().map(|s| ms_spend_paths(&s.node)).collect();
+            let single_keys: Option<Vec<_>> = subs
+                .iter()
+                .map(|p| match &p[..] {
+                    [p] => p.single_key().cloned(),
+                    _ => None,
+                })
+                .collect();
+            if let Some(keys) = single_keys {
+                // A threshold of keys, as a multi
+                return vec![SpendPath {
+                    keys: vec![KeysThreshold {
+                        threshold: *k,
+                        keys,
+                    }],
+                    ..Default::default()
+                }];
+            }
+            combinations(subs.len(), *k, MAX_SPEND_PATHS)
+                .into_iter()
+                .flat_map(|c| {
+                    c.into_iter().fold(vec![SpendPath::default()], |acc, i| {
+                        and_paths(acc, &subs[i])
+                    })
+                })
+                .take(MAX_SPEND_PATHS)
+                .collect()
+        }
+        // Raw public key hashes can't be parsed from descriptors
+        _ => vec![],
+    }
+}
+
+fn wsh_spend_paths(wsh: &Wsh<DescriptorPublicKey>) -> Vec<SpendPath> {
+    match wsh.as_inner() {
+        WshInner::Ms(ms) => ms_spend_paths(&ms.node),
+        WshInner::SortedMulti(x) => vec![SpendPath::keys(x.k, &x.pks)],
+    }
+}
+
+fn descriptor_spend_paths(descriptor: &Descriptor<DescriptorPublicKey>) -> Vec<SpendPath> {
+    match descriptor {
+        Descriptor::Wsh(wsh) => wsh_spend_paths(wsh),
+        Descriptor::Sh(sh) => match sh.as_inner() {
+            ShInner::Wsh(wsh) => wsh_spend_paths(wsh),
+            _ => all_keys_spend_paths(descriptor),
+        },
+        _ => all_keys_spend_paths(descriptor),
+    }
+}
+
+/// A single path signed by all the keys, for descriptors without a policy, like `wpkh`
+fn all_keys_spend_paths(descriptor: &Descriptor<DescriptorPublicKey>) -> Vec<SpendPath> {
+    let mut keys = vec![];
+    descriptor.for_each_key(|k| {
+        keys.push(k.clone());
+        true
+    });
+    vec![SpendPath::keys(keys.len(), &keys)]
+}
+
 #[derive(Debug, Clone)]
 /// A wrapper that contains only the subset of CT descriptors handled by wollet
-pub struct WolletDescriptor(ConfidentialDescriptor<DescriptorPublicKey>);
+///
+/// The internal (change) chain is usually derived by the same descriptor, either a multipath
+/// descriptor or a descriptor without change. Wallets created with [`WolletDescriptor::from_descriptors`]
+/// can also have a separate change descriptor, with the same keys and blinding key.
+pub struct WolletDescriptor(
+    ConfidentialDescriptor<DescriptorPublicKey>,
+    Option<ConfidentialDescriptor<DescriptorPublicKey>>,
+);
 
 impl Display for WolletDescriptor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(&self.0, f)
+        match &self.1 {
+            // Same format parsed by `from_str_relaxed`
+            Some(change) => write!(f, "{}\n{}", self.0, change),
+            None => Display::fmt(&self.0, f),
+        }
     }
 }
 
 impl std::hash::Hash for WolletDescriptor {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.0.to_string().hash(state);
+        self.to_string().hash(state);
     }
 }
 
@@ -63,10 +368,13 @@ impl TryFrom<ConfidentialDescriptor<DescriptorPublicKey>> for WolletDescriptor {
         if desc.descriptor.is_multipath() {
             let descriptors = desc.descriptor.clone().into_single_descriptors()?;
 
-            if descriptors.len() > 2 {
+            // The path index is encoded in a byte in the cache
+            if descriptors.len() > MAX_MULTIPATH_LEN {
                 return Err(Self::Error::UnsupportedMultipathDescriptor);
             }
 
+            // Every key must derive the `i`-th path with `i`, so that all the keys share the
+            // same paths and the chain can be determined from the derivation path
             for (i, desc) in descriptors.iter().enumerate() {
                 let r = desc.for_each_key(|k| {
                     if let Some(path) = k.full_derivation_path() {
@@ -82,7 +390,7 @@ impl TryFrom<ConfidentialDescriptor<DescriptorPublicKey>> for WolletDescriptor {
             }
         }
         match desc.descriptor.desc_type().segwit_version() {
-            Some(WitnessVersion::V0) => Ok(WolletDescriptor(desc)),
+            Some(WitnessVersion::V0) => Ok(WolletDescriptor(desc, None)),
             _ => Err(Self::Error::UnsupportedDescriptorNonV0),
         }
     }
@@ -96,6 +404,9 @@ impl FromStr for WolletDescriptor {
     }
 }
 
+/// Maximum number of paths in a multipath descriptor
+const MAX_MULTIPATH_LEN: usize = 256;
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
 pub enum Chain {
     /// External address, shown when asked for a payment.
@@ -104,26 +415,56 @@ pub enum Chain {
 
     /// Internal address, used for the change
     Internal,
+
+    /// Further path of a multipath descriptor, after the external and internal ones
+    ///
+    /// Contains the index of the path, eg 2 for `.../<0;1;2>/*`
+    Path(u32),
+}
+
+impl Chain {
+    /// The chain of the path with the given index in a multipath descriptor
+    pub fn from_path_index(index: u32) -> Self {
+        match index {
+            0 => Chain::External,
+            1 => Chain::Internal,
+            i => Chain::Path(i),
+        }
+    }
+
+    /// The index of the path in a multipath descriptor
+    pub fn path_index(&self) -> u32 {
+        match self {
+            Chain::External => 0,
+            Chain::Internal => 1,
+            Chain::Path(i) => *i,
+        }
+    }
 }
 
 impl TryFrom<&Descriptor<DescriptorPublicKey>> for Chain {
     type Error = ();
 
     fn try_from(value: &Descriptor<DescriptorPublicKey>) -> Result<Self, Self::Error> {
-        let mut ext_int = None;
-        // can keys have different derivation path???
-        value.for_each_key(|k| {
-            if let Some(path) = k.full_derivation_path() {
-                ext_int = path.into_iter().last().cloned();
-            }
-            false
-        });
-        match ext_int {
+        match last_child(value) {
             None => Err(()),
             Some(ext_int) => Ok(ext_int.try_into()?),
         }
     }
 }
+
+/// The last derivation step of the keys in a single (non multipath) descriptor
+fn last_child(desc: &Descriptor<DescriptorPublicKey>) -> Option<ChildNumber> {
+    let mut last = None;
+    // can keys have different derivation path???
+    desc.for_each_key(|k| {
+        if let Some(path) = k.full_derivation_path() {
+            last = path.into_iter().last().cloned();
+        }
+        false
+    });
+    last
+}
 impl TryFrom<ChildNumber> for Chain {
     type Error = ();
 
@@ -137,10 +478,16 @@ impl TryFrom<ChildNumber> for Chain {
 }
 
 impl WolletDescriptor {
+    /// The descriptor, for wallets with a separate change descriptor the external one
     pub fn descriptor(&self) -> &Descriptor<DescriptorPublicKey> {
         &self.0.descriptor
     }
 
+    /// The separate change descriptor, if any
+    pub fn change_descriptor(&self) -> Option<&ConfidentialDescriptor<DescriptorPublicKey>> {
+        self.1.as_ref()
+    }
+
     /// Return wether the descriptor has a blinding key derived with [Elip151](https://github.com/ElementsProject/ELIPs/blob/main/elip-0151.mediawiki)
     pub fn is_elip151(&self) -> bool {
         if let Ok(elip151_key) = Key::from_elip151(&self.0.descriptor) {
@@ -150,6 +497,21 @@ impl WolletDescriptor {
         }
     }
 
+    /// Check that the descriptor can be shared as a watch-only descriptor
+    ///
+    /// No conversion is done: a descriptor with a view key can already be shared as it is, since
+    /// it can be loaded to see the wallet balance and transactions but it has no secret blinding
+    /// material other than the view key. Slip77 descriptors can't be shared without their master
+    /// blinding key, from which the blinding key of every script is derived, and there is no view
+    /// key they could be converted to, so they return an error.
+    pub fn check_shareable(&self) -> Result<(), crate::error::Error> {
+        match &self.0.key {
+            Key::Slip77(_) => Err(crate::error::Error::PublicDescriptorSlip77),
+            Key::Bare(_) => Err(crate::error::Error::BlindingBareUnsupported),
+            Key::View(_) => Ok(()),
+        }
+    }
+
     /// Strip key origin information from the bitcoin descriptor and return it without checksum
     pub fn bitcoin_descriptor_without_key_origin(&self) -> String {
         let desc = self.0.descriptor.to_string();
@@ -169,9 +531,20 @@ impl WolletDescriptor {
         result
     }
 
-    /// return the single descriptor if not multipath, if multipath returns the internal or the
-    /// external descriptor accordint to `int_or_ext`
-    fn inner_descriptor_if_available(&self, ext_int: Chain) -> WolletDescriptor {
+    /// return the single descriptor if not multipath, if multipath returns the descriptor of the
+    /// path corresponding to `ext_int`
+    fn inner_descriptor_if_available(
+        &self,
+        ext_int: Chain,
+    ) -> Result<WolletDescriptor, crate::error::Error> {
+        if let Some(change) = &self.1 {
+            let descriptor = match ext_int {
+                Chain::External => self.0.clone(),
+                Chain::Internal => change.clone(),
+                Chain::Path(i) => return Err(crate::error::Error::MissingMultipathPath(i)),
+            };
+            return Ok(WolletDescriptor(descriptor, None));
+        }
         let mut descriptors = self
             .0
             .descriptor
@@ -182,27 +555,75 @@ impl WolletDescriptor {
         let descriptor = if descriptors.len() == 1 {
             descriptors.pop().expect("inside len==1 branch")
         } else {
-            match ext_int {
-                Chain::External => descriptors.remove(0),
-                Chain::Internal => descriptors.remove(1),
-            }
+            let index = ext_int.path_index();
+            descriptors
+                .into_iter()
+                .nth(index as usize)
+                .ok_or(crate::error::Error::MissingMultipathPath(index))?
         };
-        WolletDescriptor(ConfidentialDescriptor {
-            key: self.0.key.clone(),
-            descriptor,
+        Ok(WolletDescriptor(
+            ConfidentialDescriptor {
+                key: self.0.key.clone(),
+                descriptor,
+            },
+            None,
+        ))
+    }
+
+    /// The single (non multipath) descriptors, with the chain they derive
+    ///
+    /// A multipath descriptor has a descriptor for each path, a wallet with a separate change
+    /// descriptor has the external and internal ones, otherwise there is only one.
+    pub(crate) fn single_descriptors(
+        &self,
+    ) -> Result<Vec<(Chain, Descriptor<DescriptorPublicKey>)>, crate::error::Error> {
+        if let Some(change) = &self.1 {
+            return Ok(vec![
+                (Chain::External, self.0.descriptor.clone()),
+                (Chain::Internal, change.descriptor.clone()),
+            ]);
+        }
+        let descriptors = self.0.descriptor.clone().into_single_descriptors()?;
+        Ok(if self.0.descriptor.is_multipath() {
+            descriptors
+                .into_iter()
+                .enumerate()
+                .map(|(i, d)| (Chain::from_path_index(i as u32), d))
+                .collect()
+        } else {
+            descriptors
+                .into_iter()
+                .map(|d| ((&d).try_into().unwrap_or(Chain::External), d))
+                .collect()
         })
     }
 
-    pub fn single_bitcoin_descriptors(&self) -> Vec<String> {
-        let d = self.inner_descriptor_if_available(Chain::External);
-        let d = to_bitcoin_descriptor(&d.descriptor().to_string());
-        let mut v = vec![d];
-        if self.0.descriptor.is_multipath() {
-            let d = self.inner_descriptor_if_available(Chain::Internal);
-            let d = to_bitcoin_descriptor(&d.descriptor().to_string());
-            v.push(d);
+    /// The chain derived by `single`, one of the single descriptors of this descriptor
+    pub(crate) fn chain_of(&self, single: &Descriptor<DescriptorPublicKey>) -> Option<Chain> {
+        if let Some(change) = &self.1 {
+            return if single == &self.0.descriptor {
+                Some(Chain::External)
+            } else if single == &change.descriptor {
+                Some(Chain::Internal)
+            } else {
+                None
+            };
+        }
+        match last_child(single)? {
+            // The `i`-th path of a multipath descriptor ends with `i`
+            ChildNumber::Normal { index } if self.0.descriptor.is_multipath() => {
+                Some(Chain::from_path_index(index))
+            }
+            last => last.try_into().ok(),
         }
-        v
+    }
+
+    pub fn single_bitcoin_descriptors(&self) -> Vec<String> {
+        self.single_descriptors()
+            .expect("already done in TryFrom")
+            .iter()
+            .map(|(_, d)| to_bitcoin_descriptor(&d.to_string()))
+            .collect()
     }
 
     pub fn change(
@@ -227,6 +648,18 @@ impl WolletDescriptor {
         self.inner_address(index, params, Chain::External)
     }
 
+    /// Get the address with the given index of the path corresponding to `ext_int`
+    ///
+    /// Useful for multipath descriptors with more than the external and internal paths.
+    pub fn chain_address(
+        &self,
+        ext_int: Chain,
+        index: u32,
+        params: &'static AddressParams,
+    ) -> Result<Address, crate::error::Error> {
+        self.inner_address(index, params, ext_int)
+    }
+
     fn inner_address(
         &self,
         index: u32,
@@ -234,7 +667,7 @@ impl WolletDescriptor {
         ext_int: Chain,
     ) -> Result<Address, crate::error::Error> {
         Ok(self
-            .inner_descriptor_if_available(ext_int)
+            .inner_descriptor_if_available(ext_int)?
             .0
             .at_derivation_index(index)?
             .address(&crate::EC, params)?)
@@ -242,12 +675,7 @@ impl WolletDescriptor {
 
     /// Get a scriptpubkey
     pub fn script_pubkey(&self, ext_int: Chain, index: u32) -> Result<Script, crate::error::Error> {
-        let v = self.0.descriptor.clone().into_single_descriptors()?;
-        let d = match ext_int {
-            Chain::External => v.first().expect("at least on descriptor"),
-            Chain::Internal => v.last().expect("at least on descriptor"),
-        };
-        Ok(d.at_derivation_index(index)?.script_pubkey())
+        Ok(self.definite_descriptor(ext_int, index)?.script_pubkey())
     }
 
     /// Get a definite descriptor
@@ -256,10 +684,33 @@ impl WolletDescriptor {
         ext_int: Chain,
         index: u32,
     ) -> Result<Descriptor<elements_miniscript::DefiniteDescriptorKey>, crate::Error> {
-        let desc = self.inner_descriptor_if_available(ext_int);
+        let desc = self.inner_descriptor_if_available(ext_int)?;
         Ok(desc.descriptor().at_derivation_index(index)?)
     }
 
+    /// Minimal timelocks needed to spend from this descriptor
+    ///
+    /// Only `wsh` and `sh(wsh)` descriptors can have timelocks, the spending path requiring the least
+    /// timelocks is considered. With a separate change descriptor, the timelocks of both are
+    /// needed to spend any wallet coin.
+    pub(crate) fn timelocks(&self) -> Timelocks {
+        let timelocks = descriptor_timelocks(&self.0.descriptor);
+        match &self.1 {
+            Some(change) => timelocks.and(descriptor_timelocks(&change.descriptor)),
+            None => timelocks,
+        }
+    }
+
+    /// The ways to spend from this descriptor, eg the branches of a miniscript policy
+    ///
+    /// Paths of `thresh` policies are expanded for every combination of their sub-policies,
+    /// except when these are single keys, which are reported as a group of keys.
+    ///
+    /// With a separate change descriptor, the paths of the external descriptor are returned.
+    pub fn spend_paths(&self) -> Vec<SpendPath> {
+        descriptor_spend_paths(self.descriptor())
+    }
+
     /// Try also to parse it as a non-multipath descriptor specified on 2 lines,
     /// like the format exported by the Green Wallet
     pub fn from_str_relaxed(desc: &str) -> Result<WolletDescriptor, crate::Error> {
@@ -269,6 +720,75 @@ impl WolletDescriptor {
         }
     }
 
+    /// Create a descriptor from separate external (receive) and internal (change) descriptors
+    ///
+    /// The two descriptors must have the same keys and blinding key, the external one must
+    /// derive `/0/*` and the internal one `/1/*`. If they have the same script they are combined
+    /// in a single multipath descriptor, otherwise the internal one is kept as a separate change
+    /// descriptor.
+    pub fn from_descriptors(
+        external: &str,
+        internal: &str,
+    ) -> Result<WolletDescriptor, crate::Error> {
+        let external = external.trim();
+        let internal = internal.trim();
+        let WolletDescriptor(ext, _) = WolletDescriptor::from_str(external)?;
+        let WolletDescriptor(int, _) = WolletDescriptor::from_str(internal)?;
+        if ext.descriptor.is_multipath() || int.descriptor.is_multipath() {
+            return Err(crate::Error::UnsupportedMultipathDescriptor);
+        }
+        if Chain::try_from(&ext.descriptor) != Ok(Chain::External)
+            || Chain::try_from(&int.descriptor) != Ok(Chain::Internal)
+            || ext.key != int.key
+            || descriptor_keys(&ext.descriptor) != descriptor_keys(&int.descriptor)
+        {
+            return Err(crate::Error::ExternalInternalDescriptorsMismatch);
+        }
+        let external = remove_checksum_if_any(external);
+        let internal = remove_checksum_if_any(internal);
+        if external.replace("/0/*", "/1/*") == internal {
+            WolletDescriptor::from_str(&external.replace("/0/*", "/<0;1>/*"))
+        } else {
+            Ok(WolletDescriptor(ext, Some(int)))
+        }
+    }
+
+    /// Warnings about key origins inconsistent with the xpubs they refer to
+    ///
+    /// A wrong origin, often due to copy-paste errors, prevents signers from recognizing their
+    /// keys. The origin fingerprint is the one of the master key, so it can be checked only for
+    /// xpubs at depth 0 or 1; for the others only the length and the last step of the origin path
+    /// are checked.
+    pub fn key_origin_warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        self.descriptor().for_each_key(|k| {
+            let (origin, xkey) = match k {
+                DescriptorPublicKey::XPub(x) => (&x.origin, &x.xkey),
+                DescriptorPublicKey::MultiXPub(x) => (&x.origin, &x.xkey),
+                DescriptorPublicKey::Single(_) => return true,
+            };
+            if let Some((fingerprint, path)) = origin {
+                let consistent = path.len() == xkey.depth as usize
+                    && path
+                        .into_iter()
+                        .last()
+                        .map_or(true, |c| *c == xkey.child_number)
+                    && match xkey.depth {
+                        0 => *fingerprint == xkey.fingerprint(),
+                        1 => *fingerprint == xkey.parent_fingerprint,
+                        _ => true,
+                    };
+                if !consistent {
+                    warnings.push(format!(
+                        "key origin [{fingerprint}/{path}] is inconsistent with xpub {xkey}"
+                    ));
+                }
+            }
+            true
+        });
+        warnings
+    }
+
     /// Returns true if all the xpubs in the descriptors are for mainnet
     pub fn is_mainnet(&self) -> bool {
         self.descriptor().for_each_key(|k| match k {
@@ -303,20 +823,20 @@ fn parse_multiline(desc: &str) -> Option<WolletDescriptor> {
     if lines.len() != 2 {
         return None;
     }
-    let first_str = lines[0].trim();
-    let second_str = lines[1].trim();
-    let first = ConfidentialDescriptor::<DescriptorPublicKey>::from_str(first_str);
-    let second = ConfidentialDescriptor::<DescriptorPublicKey>::from_str(second_str);
-    if first.is_err() || second.is_err() {
-        return None;
-    }
-    let first_no_chk = remove_checksum_if_any(first_str);
-    let second_no_chk = remove_checksum_if_any(second_str);
-    if first_no_chk.replace("/0/*", "/1/*") != second_no_chk {
-        return None;
-    }
-    let combined = first_no_chk.replace("/0/*", "/<0;1>/*");
-    WolletDescriptor::from_str(&combined).ok()
+    WolletDescriptor::from_descriptors(lines[0], lines[1]).ok()
+}
+
+/// The keys of a single descriptor, without their derivation paths
+fn descriptor_keys(desc: &Descriptor<DescriptorPublicKey>) -> BTreeSet<String> {
+    let mut keys = BTreeSet::new();
+    desc.for_each_key(|k| {
+        keys.insert(match k {
+            DescriptorPublicKey::XPub(x) => x.xkey.to_string(),
+            k => k.to_string(),
+        });
+        true
+    });
+    keys
 }
 
 fn remove_checksum_if_any(s: &str) -> String {
@@ -346,10 +866,18 @@ mod test {
         str::FromStr,
     };
 
-    use elements::bitcoin;
-    use elements_miniscript::{BtcDescriptor, BtcMiniscript, BtcSegwitv0};
+    use elements::{bitcoin, AddressParams};
+    use elements_miniscript::{
+        BtcDescriptor, BtcMiniscript, BtcSegwitv0, Descriptor, DescriptorPublicKey,
+    };
 
-    use crate::{descriptor::remove_checksum_if_any, Chain, WolletDescriptor, EC};
+    use crate::{
+        descriptor::{
+            combinations, descriptor_spend_paths, remove_checksum_if_any, KeysThreshold, Timelocks,
+            MAX_SPEND_PATHS,
+        },
+        Chain, Error, WolletDescriptor, EC,
+    };
 
     #[test]
     fn test_wollet_hash() {
@@ -374,6 +902,170 @@ mod test {
         assert!(desc.is_elip151());
     }
 
+    #[test]
+    fn test_check_shareable() {
+        let xpub = "[759db348/84'/1'/0']tpubDCRMaF33e44pcJj534LXVhFbHibPbJ5vuLhSSPFAw57kYURv4tzXFL6LSnd78bkjqdmE3USedkbpXJUPA1tdzKfuYSL7PianceqAhwL2UkA";
+        let slip77 = "slip77(ab5824f4477b4ebb00a132adfd8eb0b7935cf24f6ac151add5d1913db374ce92)";
+        let desc: WolletDescriptor = format!("ct({slip77},elwpkh({xpub}/<0;1>/*))")
+            .parse()
+            .unwrap();
+        let err = desc.check_shareable().unwrap_err();
+        assert_eq!(err.to_string(), Error::PublicDescriptorSlip77.to_string());
+
+        let view_key = "ab5824f4477b4ebb00a132adfd8eb0b7935cf24f6ac151add5d1913db374ce92";
+        let desc: WolletDescriptor = format!("ct({view_key},elwpkh({xpub}/<0;1>/*))")
+            .parse()
+            .unwrap();
+        desc.check_shareable().unwrap();
+        let public = desc.to_string();
+        assert!(!public.contains("slip77"), "{public}");
+        assert!(public.contains(xpub), "{public}");
+
+        // The shared descriptor can be loaded and derives the same addresses
+        let public_desc = WolletDescriptor::from_str(&public).unwrap();
+        assert_eq!(public_desc.to_string(), desc.to_string());
+        let params = &AddressParams::LIQUID_TESTNET;
+        for i in 0..3 {
+            let address = public_desc.address(i, params).unwrap();
+            assert_eq!(address, desc.address(i, params).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_spend_paths() {
+        let view_key = "slip77(ab5824f4477b4ebb00a132adfd8eb0b7935cf24f6ac151add5d1913db374ce92)";
+        let a = "[e6b7814d/87'/1'/0']tpubDDmvBugC5YMK3UDKjcym7ED8Vfv8aLiX83Tcbecc783VFPEDqBigmzF52uFMyh89bXaf7jAporM1LcoaMcLdKeV4m7ixNAchpMQCL569Ldv/<0;1>/*";
+        let b = "[a5a0841e/87'/1'/0']tpubDDZCCwQJyHksYEfUHb59Mr4ZCo1ndMt4Ys8rXF7RLhmfttU9AYybscFyCmWRVQUxffjGYQe8dtmGchA91PhLUCkH3H7D7Nx1CJLrv5W9tTs/<0;1>/*";
+        let spend_paths = |ms: &str| {
+            let desc: WolletDescriptor = format!("ct({view_key},elwsh({ms}))").parse().unwrap();
+            desc.spend_paths()
+        };
+        let keys = |threshold: usize, keys: &[&str]| KeysThreshold {
+            threshold,
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+        };
+
+        let desc: WolletDescriptor = format!("ct({view_key},elwpkh({a}))").parse().unwrap();
+        let paths = desc.spend_paths();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].keys, vec![keys(1, &[a])]);
+
+        let paths = spend_paths(&format!("sortedmulti(1,{a},{b})"));
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].keys, vec![keys(1, &[a, b])]);
+
+        // A threshold of single keys is a group of keys
+        let paths = spend_paths(&format!("thresh(2,pk({a}),s:pk({b}))"));
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].keys, vec![keys(2, &[a, b])]);
+
+        // A threshold with a timelock has a path for each combination
+        let paths = spend_paths(&format!("thresh(2,pk({a}),s:pk({b}),sln:older(10))"));
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths.iter().filter(|p| p.older == Some(10)).count(), 2);
+
+        // The fedpeg script: the federation, or the emergency keys after 4032 blocks
+        let fedpeg = remove_checksum_if_any(lwk_test_util::FED_PEG_DESC);
+        let fedpeg: Descriptor<DescriptorPublicKey> = format!("el{fedpeg}").parse().unwrap();
+        let paths = descriptor_spend_paths(&fedpeg);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].keys.len(), 1);
+        assert_eq!(paths[0].keys[0].threshold, 11);
+        assert_eq!(paths[0].keys[0].keys.len(), 15);
+        assert_eq!(paths[0].older, None);
+        assert_eq!(paths[1].keys.len(), 1);
+        assert_eq!(paths[1].keys[0].threshold, 2);
+        assert_eq!(paths[1].keys[0].keys.len(), 3);
+        assert_eq!(paths[1].older, Some(4032));
+        assert_eq!(paths[1].after, None);
+
+        // Nested segwit has the paths of the inner wsh
+        let c = a.replace("/<0;1>/*", "/7/<0;1>/*");
+        let desc: WolletDescriptor = format!("ct({view_key},elsh(wsh(multi(2,{a},{b},{c}))))")
+            .parse()
+            .unwrap();
+        let paths = desc.spend_paths();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].keys, vec![keys(2, &[a, b, &c])]);
+
+        // Large thresholds are not fully expanded
+        let key_subs: Vec<_> = (0..15)
+            .map(|i| {
+                let k = a.replace("/<0;1>/*", &format!("/{i}/<0;1>/*"));
+                if i == 0 {
+                    format!("pk({k})")
+                } else {
+                    format!("s:pk({k})")
+                }
+            })
+            .collect();
+        let timelock_subs: Vec<_> = (1..=5).map(|i| format!("sln:older({i})")).collect();
+        let subs = [key_subs, timelock_subs].concat().join(",");
+        let paths = spend_paths(&format!("thresh(10,{subs})"));
+        assert_eq!(paths.len(), MAX_SPEND_PATHS);
+        assert!(paths.iter().all(|p| p.keys.len() >= 5));
+    }
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(combinations(3, 0, 10), vec![Vec::<usize>::new()]);
+        assert_eq!(
+            combinations(3, 2, 10),
+            vec![vec![0, 1], vec![0, 2], vec![1, 2]]
+        );
+        assert_eq!(combinations(4, 2, 10).len(), 6);
+        assert_eq!(combinations(4, 2, 2), vec![vec![0, 1], vec![0, 2]]);
+        assert!(combinations(2, 3, 10).is_empty());
+        assert_eq!(combinations(40, 20, 100).len(), 100);
+    }
+
+    #[test]
+    fn test_timelocks() {
+        let view_key = "slip77(ab5824f4477b4ebb00a132adfd8eb0b7935cf24f6ac151add5d1913db374ce92)";
+        let a = "[e6b7814d/87'/1'/0']tpubDDmvBugC5YMK3UDKjcym7ED8Vfv8aLiX83Tcbecc783VFPEDqBigmzF52uFMyh89bXaf7jAporM1LcoaMcLdKeV4m7ixNAchpMQCL569Ldv/<0;1>/*";
+        let b = "[a5a0841e/87'/1'/0']tpubDDZCCwQJyHksYEfUHb59Mr4ZCo1ndMt4Ys8rXF7RLhmfttU9AYybscFyCmWRVQUxffjGYQe8dtmGchA91PhLUCkH3H7D7Nx1CJLrv5W9tTs/<0;1>/*";
+        let timelocks = |ms: &str| {
+            let desc: WolletDescriptor = format!("ct({view_key},elwsh({ms}))").parse().unwrap();
+            desc.timelocks()
+        };
+
+        let desc: WolletDescriptor = format!("ct({view_key},elwpkh({a}))").parse().unwrap();
+        assert_eq!(desc.timelocks(), Timelocks::default());
+        assert_eq!(
+            timelocks(&format!("multi(1,{a},{b})")),
+            Timelocks::default()
+        );
+
+        let t = timelocks(&format!("and_v(v:pk({a}),older(10))"));
+        assert_eq!(t.older, Some(10));
+        assert_eq!(t.after, None);
+
+        let t = timelocks(&format!("and_v(v:pk({a}),after(100))"));
+        assert_eq!(t.older, None);
+        assert_eq!(t.after, Some(100));
+
+        let t = timelocks(&format!("and_v(and_v(v:pk({a}),v:older(10)),after(100))"));
+        assert_eq!(t.older, Some(10));
+        assert_eq!(t.after, Some(100));
+
+        // A branch without timelocks
+        let t = timelocks(&format!("or_d(pk({a}),and_v(v:pk({b}),older(10)))"));
+        assert_eq!(t, Timelocks::default());
+
+        // The least restrictive branch is chosen
+        let t = timelocks(&format!(
+            "or_i(and_v(v:pk({a}),older(20)),and_v(v:pk({b}),older(10)))"
+        ));
+        assert_eq!(t.older, Some(10));
+
+        // Nested segwit has the timelocks of the inner wsh
+        let desc: WolletDescriptor =
+            format!("ct({view_key},elsh(wsh(and_v(v:pk({a}),older(10)))))")
+                .parse()
+                .unwrap();
+        assert_eq!(desc.timelocks().older, Some(10));
+    }
+
     #[test]
     fn test_strip() {
         let desc_str = "ct(slip77(ab5824f4477b4ebb00a132adfd8eb0b7935cf24f6ac151add5d1913db374ce92),elwpkh([759db348/84'/1'/0']tpubDCRMaF33e44pcJj534LXVhFbHibPbJ5vuLhSSPFAw57kYURv4tzXFL6LSnd78bkjqdmE3USedkbpXJUPA1tdzKfuYSL7PianceqAhwL2UkA/<0;1>/*))#cch6wrnp";
@@ -426,6 +1118,82 @@ mod test {
         assert!(WolletDescriptor::from_str_relaxed(&fail_more_lines).is_err());
     }
 
+    #[test]
+    fn test_from_descriptors() {
+        let external = "ct(slip77(460830d85d4b299a9406c5899748354937c81b6fdb94f110f8729c9ba2994412),elwpkh([28b3f14e/84'/1'/0']tpubDC2Q4xK4XH72GM7MowNuajyWVbigRLBWKswyP5T88hpPwu5nGqJWnda8zhJEFt71av73Hm8mUMMFSz9acNVzz8b1UbdSHCDXKTbSv5eEytu/0/*))#srt8g93f";
+        let internal = "ct(slip77(460830d85d4b299a9406c5899748354937c81b6fdb94f110f8729c9ba2994412),elwpkh([28b3f14e/84'/1'/0']tpubDC2Q4xK4XH72GM7MowNuajyWVbigRLBWKswyP5T88hpPwu5nGqJWnda8zhJEFt71av73Hm8mUMMFSz9acNVzz8b1UbdSHCDXKTbSv5eEytu/1/*))#9z93s6yk";
+        let desc = WolletDescriptor::from_descriptors(external, internal).unwrap();
+        assert!(desc.to_string().contains("/<0;1>/*"));
+        let multiline = WolletDescriptor::from_str_relaxed(&format!("{external}\n{internal}"));
+        assert_eq!(desc.to_string(), multiline.unwrap().to_string());
+
+        // Swapped chains
+        let err = WolletDescriptor::from_descriptors(internal, external).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::ExternalInternalDescriptorsMismatch
+        ));
+
+        // Different blinding key
+        let other_blinding = internal
+            .split('#')
+            .next()
+            .unwrap()
+            .replace("460830", "460831");
+        let err = WolletDescriptor::from_descriptors(external, &other_blinding).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::ExternalInternalDescriptorsMismatch
+        ));
+
+        // Different script, kept as a separate change descriptor
+        let other_script = internal
+            .split('#')
+            .next()
+            .unwrap()
+            .replace("elwpkh(", "elsh(wpkh(")
+            .replace("/1/*))", "/1/*)))");
+        let desc = WolletDescriptor::from_descriptors(external, &other_script).unwrap();
+        assert_eq!(
+            desc.to_string(),
+            format!("{external}\n{}", desc.change_descriptor().unwrap())
+        );
+        let multiline = WolletDescriptor::from_str_relaxed(&desc.to_string()).unwrap();
+        assert_eq!(desc.to_string(), multiline.to_string());
+        let change = desc.change_descriptor().unwrap();
+        assert!(change.to_string().starts_with(&other_script));
+        let singles = desc.single_descriptors().unwrap();
+        assert_eq!(singles[0], (Chain::External, desc.descriptor().clone()));
+        assert_eq!(singles[1], (Chain::Internal, change.descriptor.clone()));
+        assert_eq!(desc.chain_of(&change.descriptor), Some(Chain::Internal));
+        assert_eq!(desc.single_bitcoin_descriptors().len(), 2);
+        let params = &AddressParams::ELEMENTS;
+        assert_ne!(
+            desc.address(0, params).unwrap().script_pubkey(),
+            desc.change(0, params).unwrap().script_pubkey()
+        );
+        assert_eq!(
+            desc.change(0, params).unwrap().to_string(),
+            WolletDescriptor::from_str(&other_script)
+                .unwrap()
+                .address(0, params)
+                .unwrap()
+                .to_string()
+        );
+        assert!(matches!(
+            desc.chain_address(Chain::Path(2), 0, params).unwrap_err(),
+            crate::Error::MissingMultipathPath(2)
+        ));
+
+        // Different keys
+        let other_keys = "ct(slip77(460830d85d4b299a9406c5899748354937c81b6fdb94f110f8729c9ba2994412),elwpkh(tpubD6NzVbkrYhZ4Was8nwnZi7eiWUNJq2LFpPSCMQLioUfUtT1e72GkRbmVeRAZc26j5MRUz2hRLsaVHJfs6L7ppNfLUrm9btQTuaEsLrT7D87/1/*))";
+        let err = WolletDescriptor::from_descriptors(external, other_keys).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::ExternalInternalDescriptorsMismatch
+        ));
+    }
+
     #[test]
     fn test_is_mainnet() {
         let tpub = "tpubDC2Q4xK4XH72GM7MowNuajyWVbigRLBWKswyP5T88hpPwu5nGqJWnda8zhJEFt71av73Hm8mUMMFSz9acNVzz8b1UbdSHCDXKTbSv5eEytu";
@@ -488,6 +1256,95 @@ mod test {
         assert_eq!(a, s);
     }
 
+    #[test]
+    fn test_multipath_three_paths() {
+        let xpub = "tpubDC2Q4xK4XH72GM7MowNuajyWVbigRLBWKswyP5T88hpPwu5nGqJWnda8zhJEFt71av73Hm8mUMMFSz9acNVzz8b1UbdSHCDXKTbSv5eEytu";
+        let d = format!("ct(elip151,elwpkh({xpub}/<0;1;2>/*))");
+        let d = WolletDescriptor::from_str(&d).unwrap();
+        let params = &elements::AddressParams::ELEMENTS;
+
+        let ds = d.single_bitcoin_descriptors();
+        assert_eq!(ds.len(), 3);
+        assert!(ds[2].starts_with(&format!("wpkh({xpub}/2/*)")));
+
+        let a = d.chain_address(Chain::Path(2), 4, params).unwrap();
+        let s = d.script_pubkey(Chain::Path(2), 4).unwrap();
+        assert_eq!(a.script_pubkey(), s);
+        assert_ne!(s, d.script_pubkey(Chain::Internal, 4).unwrap());
+        assert_eq!(
+            d.change(4, params).unwrap().script_pubkey(),
+            d.script_pubkey(Chain::Internal, 4).unwrap()
+        );
+
+        let chains: Vec<_> = d
+            .single_descriptors()
+            .unwrap()
+            .into_iter()
+            .map(|(c, _)| c)
+            .collect();
+        assert_eq!(chains, [Chain::External, Chain::Internal, Chain::Path(2)]);
+        for i in 0..3 {
+            assert_eq!(Chain::from_path_index(i).path_index(), i);
+        }
+
+        let err = d.script_pubkey(Chain::Path(3), 0).unwrap_err();
+        assert!(matches!(err, crate::Error::MissingMultipathPath(3)));
+
+        // All the keys must have the same paths
+        let xpub2 = "tpubDD7tXK8KeQ3YY83yWq755fHY2JW8Ha8Q765tknUM5rSvjPcGWfUppDFMpQ1ScziKfW3ZNtZvAD7M3u7bSs7HofjTD3KP3YxPK7X6hwV8Rk2";
+        let d = format!("ct(elip151,elwsh(multi(2,{xpub}/<0;1;2>/*,{xpub2}/<0;1;3>/*)))");
+        let err = WolletDescriptor::from_str(&d).unwrap_err();
+        assert!(matches!(err, crate::Error::UnsupportedMultipathDescriptor));
+    }
+
+    #[test]
+    fn test_key_origin_warnings() {
+        use elements::bitcoin::bip32::{DerivationPath, Xpriv, Xpub};
+        use elements::bitcoin::NetworkKind;
+
+        let secp = &EC;
+        let master = Xpriv::new_master(NetworkKind::Test, &[1u8; 32]).unwrap();
+        let fingerprint = master.fingerprint(secp);
+        let path = DerivationPath::from_str("m/84'").unwrap();
+        let xpub = Xpub::from_priv(secp, &master.derive_priv(secp, &path).unwrap());
+        let desc = |origin: &str| {
+            let d = format!("ct(elip151,elwpkh([{origin}]{xpub}/<0;1>/*))");
+            WolletDescriptor::from_str(&d).unwrap()
+        };
+
+        assert!(desc(&format!("{fingerprint}/84'"))
+            .key_origin_warnings()
+            .is_empty());
+
+        // Wrong fingerprint
+        let w = desc("00000000/84'").key_origin_warnings();
+        assert_eq!(w.len(), 1);
+        assert!(w[0].contains("[00000000/84']"));
+
+        // Wrong path, length or last step
+        assert_eq!(
+            desc(&format!("{fingerprint}/84'/0'"))
+                .key_origin_warnings()
+                .len(),
+            1
+        );
+        assert_eq!(
+            desc(&format!("{fingerprint}/49'"))
+                .key_origin_warnings()
+                .len(),
+            1
+        );
+
+        // Master key
+        let xpub = Xpub::from_priv(secp, &master);
+        let d = format!("ct(elip151,elwpkh([{fingerprint}]{xpub}/<0;1>/*))");
+        let d = WolletDescriptor::from_str(&d).unwrap();
+        assert!(d.key_origin_warnings().is_empty());
+        let d = format!("ct(elip151,elwpkh([00000000]{xpub}/<0;1>/*))");
+        let d = WolletDescriptor::from_str(&d).unwrap();
+        assert_eq!(d.key_origin_warnings().len(), 1);
+    }
+
     #[test]
     fn get_pegin_address() {
         let d: BtcDescriptor<bitcoin::PublicKey> =
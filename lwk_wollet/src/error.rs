@@ -69,6 +69,9 @@ pub enum Error {
     #[error(transparent)]
     DescConversion(#[from] elements_miniscript::descriptor::ConversionError),
 
+    #[error(transparent)]
+    Sighash(#[from] elements_miniscript::psbt::SighashError),
+
     #[error(transparent)]
     Unblind(#[from] crate::elements::UnblindError),
 
@@ -100,6 +103,9 @@ pub enum Error {
     #[error("Address must be confidential")]
     NotConfidentialAddress,
 
+    #[error(transparent)]
+    AddressParse(#[from] lwk_common::address::AddressParseError),
+
     #[error("Insufficient funds")]
     InsufficientFunds,
 
@@ -146,10 +152,16 @@ pub enum Error {
     UnsupportedDescriptorWithoutWildcard,
 
     #[error(
-        "Multipath descriptor must have only the external/internal multipath (eg '.../<0;1>/*')"
+        "Multipath descriptor paths must be consecutive and start from 0 (eg '.../<0;1>/*' or '.../<0;1;2>/*')"
     )]
     UnsupportedMultipathDescriptor,
 
+    #[error("The descriptor has no path {0}")]
+    MissingMultipathPath(u32),
+
+    #[error("External and internal descriptors must have the same keys and blinding key, and derive the chains '/0/*' and '/1/*'")]
+    ExternalInternalDescriptorsMismatch,
+
     #[error("Descriptor with segwit not v0 is not supported")]
     UnsupportedDescriptorNonV0, // TODO add non supported descriptor type as field or split it further: UnsupportedDescriptorPreSegwit, UnsupportedDescriptorTaproot, UnsupportedDescriptorUnknownSegwitVersion
 
@@ -162,9 +174,17 @@ pub enum Error {
     #[error("Private blinding key not available")]
     MissingPrivateBlindingKey,
 
+    #[error(
+        "Descriptors with a slip77 blinding key can't be shared without the master blinding key"
+    )]
+    PublicDescriptorSlip77,
+
     #[error("Contract does not commit to asset id")]
     ContractDoesNotCommitToAssetId,
 
+    #[error("Contract is not canonical, re-serializing it gives different values")]
+    ContractNotCanonical,
+
     #[error("Update height {update_tip_height} too old (internal height {store_tip_height})")]
     UpdateHeightTooOld {
         update_tip_height: u32,
@@ -177,9 +197,55 @@ pub enum Error {
         update_status: u64,
     },
 
+    #[error("Update contains script {0} at {1:?}/{2} not derived from the wallet descriptor")]
+    UpdateOnDifferentDescriptor(String, crate::Chain, u32),
+
+    #[error("Input #{0} is neither a wallet input nor explicit, its blinding factors are unknown")]
+    InputSecretsUnknown(usize),
+
+    #[error("Output #{0} is already blinded, the PSET must be blinded in a single step")]
+    OutputAlreadyBlinded(usize),
+
     #[error("An issuance has already being set on this tx builder")]
     IssuanceAlreadySet,
 
+    #[error(
+        "Issuing {needed} assets requires {needed} inputs, but only {available} are available"
+    )]
+    InsufficientInputsForIssuances { needed: usize, available: usize },
+
+    #[error("Change of {satoshi} sats is below the dust threshold of {dust} sats")]
+    DustChange { satoshi: u64, dust: u64 },
+
+    #[error(
+        "Fee rate {requested} sats/kvb is below the minimum relay fee rate {minimum} sats/kvb"
+    )]
+    BelowMinRelayFee { requested: f32, minimum: f32 },
+
+    #[error("Cannot set the sighash of {0}, it's not an input of the transaction")]
+    MissingInputForSighash(elements::OutPoint),
+
+    #[error("Timelock not met, it can be spent at height {required_height}")]
+    TimelockNotMet { required_height: u32 },
+
+    #[error("Invalid mainchain address: {0}")]
+    InvalidMainchainAddress(String),
+
+    #[error("Pegouts on Liquid require a PAK proof, which is not supported")]
+    PegoutPakUnsupported,
+
+    #[error("Invalid pegin txout proof")]
+    InvalidPeginTxoutProof,
+
+    #[error("Pegin txout proof does not contain the transaction {0}")]
+    PeginProofMismatch(crate::bitcoin::Txid),
+
+    #[error("Output {0} of the pegin transaction does not pay to the claim script")]
+    PeginOutputMismatch(u32),
+
+    #[error("Pegin claim script does not belong to the wallet")]
+    PeginClaimScriptNotMine,
+
     #[error("Blockchain backend have not implemented waterfalls method")]
     WaterfallsUnimplemented,
 
@@ -192,6 +258,12 @@ pub enum Error {
     #[error("Cannot parse server recipient key")]
     CannotParseRecipientKey,
 
+    #[error("AMP2 methods are not available for this network")]
+    Amp2Unavailable,
+
+    #[error("Invalid proof of reserves: {0}")]
+    InvalidProofOfReserves(String),
+
     #[cfg(feature = "electrum")]
     #[error(transparent)]
     Url(#[from] crate::clients::blocking::electrum_client::UrlError),
@@ -94,6 +94,7 @@ pub mod pegin;
 mod persister;
 mod pset_create;
 mod registry;
+mod reserves;
 mod store;
 mod tx_builder;
 mod update;
@@ -102,16 +103,20 @@ mod wollet;
 
 pub use crate::clients::{Capability, History};
 pub use crate::config::ElementsNetwork;
-pub use crate::descriptor::{Chain, WolletDescriptor};
+pub use crate::descriptor::{Chain, KeysThreshold, SpendPath, WolletDescriptor};
 pub use crate::error::Error;
 pub use crate::model::{
-    AddressResult, ExternalUtxo, IssuanceDetails, Recipient, UnvalidatedRecipient, WalletTx,
-    WalletTxOut,
+    AddressResult, ExternalUtxo, IssuanceDetails, IssuanceSpec, Recipient, ScanScope,
+    UnvalidatedRecipient, WalletTx, WalletTxOut,
 };
-pub use crate::pegin::fed_peg_script;
+pub use crate::pegin::{fed_peg_script, pegout_script, tweak_fed_peg_script};
 pub use crate::persister::{FsPersister, NoPersist, PersistError, Persister};
 pub use crate::registry::{asset_ids, issuance_ids, Contract, Entity};
-pub use crate::tx_builder::{TxBuilder, WolletTxBuilder};
+pub use crate::reserves::challenge_outpoint;
+pub use crate::tx_builder::{
+    DustChangePolicy, MinFeeRatePolicy, TxBuilder, WolletTxBuilder, DUST_CHANGE_THRESHOLD,
+    MIN_RELAY_FEE_RATE,
+};
 pub use crate::update::{DownloadTxResult, Update};
 pub use crate::util::EC;
 pub use crate::wollet::{Tip, Wollet};
@@ -3,7 +3,7 @@ use crate::elements::{Address, AssetId, OutPoint, Script, Transaction, TxOutSecr
 use crate::pset_create::validate_address;
 use crate::secp256k1::PublicKey;
 use crate::store::Timestamp;
-use crate::{ElementsNetwork, Error};
+use crate::{Contract, ElementsNetwork, Error};
 use elements::bitcoin;
 use lwk_common::burn_script;
 use serde::{Deserialize, Serialize};
@@ -54,6 +54,25 @@ pub struct WalletTx {
     pub outputs: Vec<Option<WalletTxOut>>,
 }
 
+/// The parameters of an asset issuance, see [`crate::TxBuilder::issue_assets()`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IssuanceSpec {
+    /// The number of units of the asset created
+    pub asset_sats: u64,
+
+    /// The address receiving the asset, if none an external address of the wallet is used
+    pub asset_receiver: Option<Address>,
+
+    /// The number of reissuance tokens created
+    pub token_sats: u64,
+
+    /// The address receiving the reissuance tokens, if none an external address of the wallet is used
+    pub token_receiver: Option<Address>,
+
+    /// The contract committed in the asset id
+    pub contract: Option<Contract>,
+}
+
 /// A recipient of a transaction.
 ///
 /// Note that, since it doesn't use the [`Address`] but the [`Script`] and the [`PublicKey`] it's
@@ -192,6 +211,17 @@ impl AddressResult {
     }
 }
 
+/// Value returned from [`crate::Wollet::scan_scope()`], containing the number of scripts derived
+/// by a full scan for each chain
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanScope {
+    /// Number of external scripts derived
+    pub external_scanned: u32,
+
+    /// Number of internal scripts derived, zero if the descriptor has no internal chain
+    pub internal_scanned: u32,
+}
+
 /// Value returned from [`crate::Wollet::pegin_address()`], containing the bitcoin address
 /// and the derivation index used to derive the elements script pubkey used to create the commit for the pegin address
 #[derive(Debug, Clone)]
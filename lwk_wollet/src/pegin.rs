@@ -1,8 +1,158 @@
 use std::collections::HashMap;
 
-use elements::{bitcoin, BlockHeader};
+use elements::bitcoin::hashes::{hmac, sha256, Hash, HashEngine};
+use elements::bitcoin::opcodes::all::{OP_DEPTH, OP_ELSE};
+use elements::bitcoin::script::Instruction;
+use elements::bitcoin::secp256k1::{PublicKey, Scalar};
+use elements::opcodes::all::OP_RETURN;
+use elements::script::Builder;
+use elements::{bitcoin, BlockHeader, Script};
 
-use crate::{ElementsNetwork, Error};
+use crate::{ElementsNetwork, Error, EC};
+
+/// A pegin to claim, see [`crate::TxBuilder::claim_pegin()`]
+#[derive(Debug, Clone)]
+pub(crate) struct PeginClaim {
+    tx: bitcoin::Transaction,
+    vout: u32,
+    txout_proof: Vec<u8>,
+    claim_script: Script,
+}
+
+impl PeginClaim {
+    /// Fails if `vout` is not an output of `tx`, if the output is not locked by `fed_peg_script`
+    /// tweaked with `claim_script` or if `txout_proof` does not prove `tx`
+    pub(crate) fn new(
+        tx: bitcoin::Transaction,
+        vout: u32,
+        txout_proof: Vec<u8>,
+        claim_script: Script,
+        fed_peg_script: &bitcoin::Script,
+    ) -> Result<Self, Error> {
+        let output = tx.output.get(vout as usize).ok_or(Error::MissingVout)?;
+        let witness_script = tweak_fed_peg_script(fed_peg_script, &claim_script)?;
+        let p2wsh = bitcoin::ScriptBuf::new_p2wsh(&witness_script.wscript_hash());
+        // Legacy federations wrap the pegin script in p2sh
+        let p2sh_p2wsh = bitcoin::ScriptBuf::new_p2sh(&p2wsh.script_hash());
+        if output.script_pubkey != p2wsh && output.script_pubkey != p2sh_p2wsh {
+            return Err(Error::PeginOutputMismatch(vout));
+        }
+        let txid = tx.compute_txid();
+        let merkle_block: bitcoin::MerkleBlock = bitcoin::consensus::deserialize(&txout_proof)
+            .map_err(|_| Error::InvalidPeginTxoutProof)?;
+        let mut matches = vec![];
+        let mut indexes = vec![];
+        merkle_block
+            .extract_matches(&mut matches, &mut indexes)
+            .map_err(|_| Error::InvalidPeginTxoutProof)?;
+        if !matches.contains(&txid) {
+            return Err(Error::PeginProofMismatch(txid));
+        }
+        Ok(Self {
+            tx,
+            vout,
+            txout_proof,
+            claim_script,
+        })
+    }
+
+    pub(crate) fn txid(&self) -> bitcoin::Txid {
+        self.tx.compute_txid()
+    }
+
+    pub(crate) fn vout(&self) -> u32 {
+        self.vout
+    }
+
+    pub(crate) fn value(&self) -> u64 {
+        self.tx.output[self.vout as usize].value.to_sat()
+    }
+
+    pub(crate) fn tx(&self) -> &bitcoin::Transaction {
+        &self.tx
+    }
+
+    pub(crate) fn txout_proof(&self) -> &[u8] {
+        &self.txout_proof
+    }
+
+    pub(crate) fn claim_script(&self) -> &Script {
+        &self.claim_script
+    }
+
+    /// The pegin witness of the claiming input, as built by elements `CreatePeginWitness`
+    pub(crate) fn witness(&self, network: ElementsNetwork) -> Vec<Vec<u8>> {
+        // Only the fields covered by the txid matter, the witness is stripped to save space
+        let mut tx = self.tx.clone();
+        for input in tx.input.iter_mut() {
+            input.witness.clear();
+        }
+        vec![
+            self.value().to_le_bytes().to_vec(),
+            elements::encode::serialize(&network.policy_asset()),
+            bitcoin::consensus::serialize(&parent_genesis_hash(network)),
+            self.claim_script.to_bytes(),
+            bitcoin::consensus::serialize(&tx),
+            self.txout_proof.clone(),
+        ]
+    }
+}
+
+/// Returns the federation peg script committing to `claim_script`, as elements `calculate_contract`
+///
+/// Every public key in the script is tweaked with `HMAC-SHA256(key, claim_script)`, legacy
+/// federation scripts (starting with `OP_DEPTH`) only have the keys before `OP_ELSE` tweaked.
+pub fn tweak_fed_peg_script(
+    fed_peg_script: &bitcoin::Script,
+    claim_script: &Script,
+) -> Result<bitcoin::ScriptBuf, Error> {
+    let legacy = fed_peg_script.as_bytes().first() == Some(&OP_DEPTH.to_u8());
+    let mut tweaked = fed_peg_script.to_bytes();
+    for instruction in fed_peg_script.instruction_indices() {
+        let (idx, instruction) = instruction.map_err(|e| Error::Generic(e.to_string()))?;
+        match instruction {
+            Instruction::PushBytes(data) if data.len() == 33 => {
+                let key = PublicKey::from_slice(data.as_bytes())?;
+                let mut engine = hmac::HmacEngine::<sha256::Hash>::new(data.as_bytes());
+                engine.input(claim_script.as_bytes());
+                let tweak = hmac::Hmac::<sha256::Hash>::from_engine(engine).to_byte_array();
+                let tweak =
+                    Scalar::from_be_bytes(tweak).map_err(|e| Error::Generic(e.to_string()))?;
+                let key = key.add_exp_tweak(&EC, &tweak)?;
+                // The key follows the push opcode and its length, if any
+                let start = idx
+                    + match fed_peg_script.as_bytes()[idx] {
+                        0x4c => 2, // OP_PUSHDATA1
+                        0x4d => 3, // OP_PUSHDATA2
+                        0x4e => 5, // OP_PUSHDATA4
+                        _ => 1,
+                    };
+                tweaked[start..start + 33].copy_from_slice(&key.serialize());
+            }
+            Instruction::Op(op) if legacy && op == OP_ELSE => break,
+            _ => (),
+        }
+    }
+    Ok(bitcoin::ScriptBuf::from_bytes(tweaked))
+}
+
+/// Returns the script pubkey of an output pegging out to `mainchain_address`
+///
+/// It's `OP_RETURN <mainchain genesis hash> <mainchain script pubkey>`, networks enforcing PAK
+/// (like Liquid) also require a PAK proof in the script which is not included here.
+pub fn pegout_script(network: ElementsNetwork, mainchain_address: &bitcoin::Address) -> Script {
+    let genesis_hash = parent_genesis_hash(network);
+    Builder::new()
+        .push_opcode(OP_RETURN)
+        .push_slice(genesis_hash.as_byte_array())
+        .push_slice(mainchain_address.script_pubkey().as_bytes())
+        .into_script()
+}
+
+/// Returns the genesis block hash of the mainchain
+pub(crate) fn parent_genesis_hash(network: ElementsNetwork) -> bitcoin::BlockHash {
+    bitcoin::blockdata::constants::genesis_block(network.bitcoin_network()).block_hash()
+}
 
 /// Returns the height of the block containing full federation parameters
 ///
@@ -40,7 +190,12 @@ pub fn fetch_last_full_header<B: crate::clients::blocking::BlockchainBackend>(
 mod test {
     use crate::ElementsNetwork;
 
-    use super::{fed_peg_script, height_with_fed_peg_script};
+    use elements::bitcoin;
+
+    use super::{
+        fed_peg_script, height_with_fed_peg_script, parent_genesis_hash, pegout_script,
+        tweak_fed_peg_script,
+    };
 
     // TODO move in test util
     const FED_PEG_SCRIPT: &str = "5b21020e0338c96a8870479f2396c373cc7696ba124e8635d41b0ea581112b678172612102675333a4e4b8fb51d9d4e22fa5a8eaced3fdac8a8cbf9be8c030f75712e6af992102896807d54bc55c24981f24a453c60ad3e8993d693732288068a23df3d9f50d4821029e51a5ef5db3137051de8323b001749932f2ff0d34c82e96a2c2461de96ae56c2102a4e1a9638d46923272c266631d94d36bdb03a64ee0e14c7518e49d2f29bc401021031c41fdbcebe17bec8d49816e00ca1b5ac34766b91c9f2ac37d39c63e5e008afb2103079e252e85abffd3c401a69b087e590a9b86f33f574f08129ccbd3521ecf516b2103111cf405b627e22135b3b3733a4a34aa5723fb0f58379a16d32861bf576b0ec2210318f331b3e5d38156da6633b31929c5b220349859cc9ca3d33fb4e68aa08401742103230dae6b4ac93480aeab26d000841298e3b8f6157028e47b0897c1e025165de121035abff4281ff00660f99ab27bb53e6b33689c2cd8dcd364bc3c90ca5aea0d71a62103bd45cddfacf2083b14310ae4a84e25de61e451637346325222747b157446614c2103cc297026b06c71cbfa52089149157b5ff23de027ac5ab781800a578192d175462103d3bde5d63bdb3a6379b461be64dad45eabff42f758543a9645afd42f6d4248282103ed1e8d5109c9ed66f7941bc53cc71137baa76d50d274bda8d5e8ffbd6e61fe9a5fae736402c00fb269522103aab896d53a8e7d6433137bbba940f9c521e085dd07e60994579b64a6d992cf79210291b7d0b1b692f8f524516ed950872e5da10fb1b808b5a526dedc6fed1cf29807210386aa9372fbab374593466bc5451dc59954e90787f08060964d95c87ef34ca5bb53ae68";
@@ -53,10 +208,46 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_pegout_script() {
+        let network = ElementsNetwork::default_regtest();
+        let address =
+            bitcoin::Address::p2wsh(&bitcoin::ScriptBuf::new(), bitcoin::Network::Regtest);
+        let script = pegout_script(network, &address);
+        assert!(script.is_op_return());
+
+        let genesis_hash = "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
+        assert_eq!(
+            parent_genesis_hash(network).to_string(),
+            genesis_hash.to_string()
+        );
+        let spk = address.script_pubkey();
+        let mut expected = vec![0x6a, 32];
+        expected.extend(bitcoin::consensus::serialize(&parent_genesis_hash(network)));
+        expected.push(spk.len() as u8);
+        expected.extend(spk.as_bytes());
+        assert_eq!(script.as_bytes(), &expected[..]);
+    }
+
     #[test]
     fn test_fed_peg_script() {
         let header = lwk_test_util::liquid_block_header_2_963_520();
         let script = fed_peg_script(&header).unwrap();
         assert_eq!(script.to_hex_string(), FED_PEG_SCRIPT);
     }
+
+    #[test]
+    fn test_tweak_fed_peg_script() {
+        let desc: crate::WolletDescriptor = lwk_test_util::PEGIN_TEST_DESC.parse().unwrap();
+        let claim_script = desc
+            .address(0, ElementsNetwork::LiquidTestnet.address_params())
+            .unwrap()
+            .script_pubkey();
+        let fed_peg_script = bitcoin::ScriptBuf::from_hex(FED_PEG_SCRIPT).unwrap();
+
+        let script = tweak_fed_peg_script(&fed_peg_script, &claim_script).unwrap();
+        assert_ne!(script, fed_peg_script);
+        let address = bitcoin::Address::p2wsh(&script, bitcoin::Network::Testnet);
+        assert_eq!(address.to_string(), lwk_test_util::PEGIN_TEST_ADDR);
+    }
 }
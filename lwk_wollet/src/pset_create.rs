@@ -5,7 +5,7 @@ use crate::elements::pset::{Input, Output, PartiallySignedTransaction};
 use crate::elements::{Address, AssetId, OutPoint, Transaction, TxOut, TxOutSecrets, Txid};
 use crate::error::Error;
 use crate::hashes::Hash;
-use crate::model::{Recipient, WalletTxOut};
+use crate::model::{IssuanceSpec, Recipient, WalletTxOut};
 use crate::registry::Contract;
 use crate::wollet::Wollet;
 use crate::ElementsNetwork;
@@ -17,7 +17,7 @@ use std::collections::HashMap;
 // We make issuance and reissuance are mutually exclusive for simplicity
 pub enum IssuanceRequest {
     None,
-    Issuance(u64, Option<Address>, u64, Option<Address>, Option<Contract>),
+    Issuance(Vec<IssuanceSpec>),
     Reissuance(AssetId, u64, Option<Address>, Option<Transaction>),
 }
 
@@ -40,7 +40,7 @@ impl Wollet {
             .clone())
     }
 
-    fn get_txout(&self, outpoint: &OutPoint) -> Result<TxOut, Error> {
+    pub(crate) fn get_txout(&self, outpoint: &OutPoint) -> Result<TxOut, Error> {
         Ok(self
             .get_tx(&outpoint.txid)?
             .output
@@ -150,7 +150,7 @@ impl Wollet {
 
         if let Some(contract) = contract.as_ref() {
             let issuance_prevout = OutPoint::new(input.previous_txid, input.previous_output_index);
-            let contract = serde_json::to_string(&contract)?;
+            let contract = contract.to_canonical_json()?;
             pset.add_asset_metadata(asset, &AssetMetadata::new(contract, issuance_prevout));
         }
 
@@ -206,7 +206,7 @@ fn convert_pubkey(pk: crate::elements::secp256k1_zkp::PublicKey) -> BitcoinPubli
 
 pub(crate) fn validate_address(address: &str, network: ElementsNetwork) -> Result<Address, Error> {
     let params = network.address_params();
-    let address = Address::parse_with_params(address, params)?;
+    let address = lwk_common::address::parse_address(address, params, false)?;
     if address.blinding_pubkey.is_none() {
         return Err(Error::NotConfidentialAddress);
     };
@@ -225,6 +225,7 @@ mod test {
         assert_eq!(addr.to_string(), testnet_address);
 
         let network = ElementsNetwork::Liquid;
-        assert!(validate_address(testnet_address, network).is_err())
+        let err = validate_address(testnet_address, network).unwrap_err();
+        assert!(err.to_string().contains("is for liquid-testnet"), "{err}");
     }
 }
@@ -2,7 +2,8 @@ use std::str::FromStr;
 
 use crate::domain::verify_domain_name;
 use crate::elements::hashes::{sha256, Hash};
-use crate::elements::{AssetId, ContractHash, OutPoint};
+use crate::elements::secp256k1_zkp::ZERO_TWEAK;
+use crate::elements::{AssetId, ContractHash, OutPoint, Transaction};
 use crate::error::Error;
 use crate::util::{serde_from_hex, serde_to_hex, verify_pubkey};
 use once_cell::sync::Lazy;
@@ -49,8 +50,32 @@ pub struct Contract {
 }
 
 impl Contract {
+    /// Parse the contract from a JSON value
+    ///
+    /// Fields can be in any order, and unknown fields are ignored, but re-serializing must give
+    /// back the same values for the known fields, otherwise the contract hash would not commit
+    /// to the given values.
     pub fn from_value(value: &Value) -> Result<Self, Error> {
-        Ok(serde_json::from_value(value.clone())?)
+        let contract: Contract = serde_json::from_value(value.clone())?;
+        if let Value::Object(reserialized) = serde_json::to_value(&contract)? {
+            for (key, v) in reserialized.iter() {
+                if value.get(key) != Some(v) {
+                    return Err(Error::ContractNotCanonical);
+                }
+            }
+        }
+        Ok(contract)
+    }
+
+    /// Serialize the contract in the canonical form expected by the registry
+    ///
+    /// Fields are sorted lexicographically without whitespaces, this is the serialization
+    /// committed by the contract hash and it must be used when embedding the contract, for
+    /// instance in the PSET asset metadata.
+    pub fn to_canonical_json(&self) -> Result<String, Error> {
+        // serializing a `Value` sorts the keys
+        let value = serde_json::to_value(self)?;
+        Ok(serde_json::to_string(&value)?)
     }
 
     pub fn validate(&self) -> Result<(), Error> {
@@ -79,8 +104,34 @@ impl Contract {
     }
 
     pub fn contract_hash(&self) -> Result<ContractHash, Error> {
-        let value = serde_json::to_value(self)?;
-        contract_json_hash(&value)
+        let contract_str = self.to_canonical_json()?;
+        Ok(contract_str_hash(&contract_str))
+    }
+
+    /// Verify that this contract is the one committed by `asset_id`, issued in `issuance_tx`
+    ///
+    /// The asset id is recomputed from the contract and the prevout of the issuance input, and it
+    /// must match `asset_id`. Use this to reject spoofed contracts before trusting their metadata.
+    pub fn verify_against(
+        &self,
+        asset_id: AssetId,
+        issuance_tx: &Transaction,
+    ) -> Result<(), Error> {
+        let txin = issuance_tx
+            .input
+            .iter()
+            .find(|txin| {
+                txin.has_issuance()
+                    && txin.asset_issuance.asset_blinding_nonce == ZERO_TWEAK
+                    && txin.issuance_ids().0 == asset_id
+            })
+            .ok_or(Error::MissingIssuance)?;
+        // the asset id does not depend on the issuance being confidential
+        let (asset_id_contract, _) = issuance_ids(self, txin.previous_output, false)?;
+        if asset_id_contract != asset_id {
+            return Err(Error::ContractDoesNotCommitToAssetId);
+        }
+        Ok(())
     }
 }
 
@@ -123,12 +174,15 @@ pub fn issuance_ids(
 
 pub fn contract_json_hash(contract: &Value) -> Result<ContractHash, Error> {
     let contract_str = serde_json::to_string(contract)?;
+    Ok(contract_str_hash(&contract_str))
+}
 
+fn contract_str_hash(contract_str: &str) -> ContractHash {
     // use the ContractHash representation for correct (reverse) hex encoding,
     // but use a single SHA256 instead of the double hash assumed by
     // ContractHash::hash()
     let hash = sha256::Hash::hash(contract_str.as_bytes());
-    Ok(ContractHash::from_raw_hash(hash))
+    ContractHash::from_raw_hash(hash)
 }
 
 #[cfg(test)]
@@ -204,4 +258,64 @@ mod tests {
         contract.version = 1;
         assert!(asset_ids(&tx.input[0], &contract).is_err());
     }
+
+    #[test]
+    fn test_verify_against() {
+        let contract_string = "{\"entity\":{\"domain\":\"tether.to\"},\"issuer_pubkey\":\"0337cceec0beea0232ebe14cba0197a9fbd45fcf2ec946749de920e71434c2b904\",\"name\":\"Tether USD\",\"precision\":8,\"ticker\":\"USDt\",\"version\":0}";
+        let tx_hex = include_str!("../tests/data/usdt-issuance-tx.hex");
+        let tx: elements::Transaction =
+            elements::encode::deserialize(&Vec::<u8>::from_hex(tx_hex).unwrap()).unwrap();
+        let asset_usdt =
+            AssetId::from_str("ce091c998b83c78bb71a632313ba3760f1763d9cfcffae02258ffa9865a37bd2")
+                .unwrap();
+        let token_usdt =
+            AssetId::from_str("59fe4d2127ba9f16bd6850a3e6271a166e7ed2e1669f6c107d655791c94ee98f")
+                .unwrap();
+
+        let mut contract = Contract::from_str(contract_string).unwrap();
+        contract.verify_against(asset_usdt, &tx).unwrap();
+
+        // The reissuance token is not issued with a contract
+        assert!(matches!(
+            contract.verify_against(token_usdt, &tx),
+            Err(Error::MissingIssuance)
+        ));
+
+        // A spoofed contract does not commit to the asset
+        contract.ticker = "USDT".to_string();
+        assert!(matches!(
+            contract.verify_against(asset_usdt, &tx),
+            Err(Error::ContractDoesNotCommitToAssetId)
+        ));
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        let canonical = "{\"entity\":{\"domain\":\"tether.to\"},\"issuer_pubkey\":\"0337cceec0beea0232ebe14cba0197a9fbd45fcf2ec946749de920e71434c2b904\",\"name\":\"Tether USD\",\"precision\":8,\"ticker\":\"USDt\",\"version\":0}";
+        let non_canonical = r#"{
+            "version": 0,
+            "ticker": "USDt",
+            "name": "Tether USD",
+            "precision": 8,
+            "issuer_pubkey": "0337cceec0beea0232ebe14cba0197a9fbd45fcf2ec946749de920e71434c2b904",
+            "entity": { "domain": "tether.to" }
+        }"#;
+        let contract = Contract::from_str(non_canonical).unwrap();
+        assert_eq!(contract.to_canonical_json().unwrap(), canonical);
+        assert_eq!(
+            contract.contract_hash().unwrap().to_string(),
+            "3c7f0a53c2ff5b99590620d7f6604a7a3a7bfbaaa6aa61f7bfc7833ca03cde82"
+        );
+
+        // Unknown fields are ignored
+        let with_nonce = canonical.replace("\"version\":0", "\"nonce\":\"1\",\"version\":0");
+        assert_eq!(Contract::from_str(&with_nonce).unwrap(), contract);
+
+        // Values that would change re-serializing are rejected
+        let upper_pubkey = canonical.replace("0337cceec0", "0337CCEEC0");
+        assert!(matches!(
+            Contract::from_str(&upper_pubkey),
+            Err(Error::ContractNotCanonical)
+        ));
+    }
 }
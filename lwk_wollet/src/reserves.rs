@@ -0,0 +1,163 @@
+//! Proof of reserves, adapting BIP127 to Liquid
+//!
+//! A proof is a PSET spending the wallet UTXOs together with a "challenge" input, whose previous
+//! output is derived from a challenge message and does not exist. The transaction can't be
+//! broadcast, but the signatures of the wallet inputs commit to the challenge input, proving
+//! control of the UTXOs after the challenge has been chosen.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use elements::confidential::{Asset, Nonce, Value};
+use elements::opcodes::all::OP_PUSHNUM_1;
+use elements::pset::{Input, Output, PartiallySignedTransaction};
+use elements::script::Builder;
+use elements::sighash::SighashCache;
+use elements::{AssetId, BlockHash, EcdsaSighashType, OutPoint, TxOut, TxOutWitness, Txid};
+use elements_miniscript::psbt::PsbtExt;
+use elements_miniscript::ElementsSig;
+use lwk_common::burn_script;
+
+use crate::hashes::{sha256, Hash};
+use crate::secp256k1::ecdsa::Signature;
+use crate::{Error, WalletTxOut, Wollet, EC};
+
+/// Prefix of the message hashed to obtain the challenge txid
+const CHALLENGE_PREFIX: &str = "Proof-of-Reserves: ";
+
+/// The outpoint spent by the first input of a proof of reserves for `challenge`
+///
+/// The txid is the sha256 of `"Proof-of-Reserves: " || challenge`.
+pub fn challenge_outpoint(challenge: &str) -> OutPoint {
+    let hash = sha256::Hash::hash(format!("{CHALLENGE_PREFIX}{challenge}").as_bytes());
+    OutPoint::new(Txid::from_byte_array(hash.to_byte_array()), 0)
+}
+
+/// The previous output of the challenge input, explicit and anyone can spend, so that signers can
+/// compute the sighashes of the other inputs
+fn challenge_txout(policy_asset: AssetId) -> TxOut {
+    TxOut {
+        asset: Asset::Explicit(policy_asset),
+        value: Value::Explicit(0),
+        nonce: Nonce::Null,
+        script_pubkey: Builder::new().push_opcode(OP_PUSHNUM_1).into_script(),
+        witness: TxOutWitness::default(),
+    }
+}
+
+/// Parse a signature as contained in `partial_sigs`, DER followed by the sighash type
+fn parse_sig(raw: &[u8]) -> Option<ElementsSig> {
+    let (hash_ty, der) = raw.split_last()?;
+    let hash_ty = EcdsaSighashType::from_standard(*hash_ty as u32).ok()?;
+    Some((Signature::from_der(der).ok()?, hash_ty))
+}
+
+impl Wollet {
+    /// Create an unsigned proof of reserves of all the wallet UTXOs for the given challenge
+    ///
+    /// Once signed, it can be checked with [`Wollet::verify_proof_of_reserves()`].
+    pub fn proof_of_reserves(&self, challenge: &str) -> Result<PartiallySignedTransaction, Error> {
+        let utxos = self.utxos()?;
+        if utxos.is_empty() {
+            return Err(Error::InsufficientFunds);
+        }
+        let policy_asset = self.policy_asset();
+        let mut pset = PartiallySignedTransaction::new_v2();
+
+        let mut input = Input::from_prevout(challenge_outpoint(challenge));
+        input.witness_utxo = Some(challenge_txout(policy_asset));
+        pset.add_input(input);
+
+        let mut inp_txout_sec = HashMap::new();
+        let mut inp_weight = 0;
+        for utxo in utxos.iter() {
+            self.add_input(&mut pset, &mut inp_txout_sec, &mut inp_weight, utxo)?;
+        }
+
+        // A single unspendable output, the transaction is not meant to be valid
+        pset.add_output(Output::new_explicit(burn_script(), 0, policy_asset, None));
+        Ok(pset)
+    }
+
+    /// Verify a signed proof of reserves for the given challenge, returning the proven amounts
+    ///
+    /// The first input must spend the challenge outpoint and the outputs must be unspendable.
+    /// The other inputs must be distinct unspent outputs of this wallet, with valid `SIGHASH_ALL`
+    /// signatures satisfying the descriptor.
+    pub fn verify_proof_of_reserves(
+        &self,
+        challenge: &str,
+        pset: &PartiallySignedTransaction,
+    ) -> Result<BTreeMap<AssetId, u64>, Error> {
+        let invalid = |reason: String| Error::InvalidProofOfReserves(reason);
+
+        let first = pset
+            .inputs()
+            .first()
+            .ok_or_else(|| invalid("no inputs".to_string()))?;
+        let first = OutPoint::new(first.previous_txid, first.previous_output_index);
+        if first != challenge_outpoint(challenge) {
+            return Err(invalid("the first input is not the challenge".to_string()));
+        }
+        if let Some(vout) = pset
+            .outputs()
+            .iter()
+            .position(|o| !o.script_pubkey.is_op_return())
+        {
+            return Err(invalid(format!("output #{vout} is spendable")));
+        }
+
+        let utxos: HashMap<OutPoint, WalletTxOut> =
+            self.utxos()?.into_iter().map(|u| (u.outpoint, u)).collect();
+        let tx = pset.extract_tx()?;
+        let mut sighash_cache = SighashCache::new(&tx);
+        // genesis hash is not used at all for sighash calculation
+        let genesis_hash = BlockHash::all_zeros();
+        let mut reserves = BTreeMap::new();
+        let mut seen = HashSet::new();
+        for (idx, input) in pset.inputs().iter().enumerate().skip(1) {
+            let outpoint = OutPoint::new(input.previous_txid, input.previous_output_index);
+            // Otherwise the same coin would be counted more than once
+            if !seen.insert(outpoint) {
+                return Err(invalid(format!(
+                    "input #{idx} spends the same output of another input"
+                )));
+            }
+            let utxo = utxos
+                .get(&outpoint)
+                .ok_or_else(|| invalid(format!("input #{idx} is not a wallet unspent output")))?;
+            // The sighash commits to the previous output script and value
+            let txout = self.get_txout(&outpoint)?;
+            let matches = input.witness_utxo.as_ref().map_or(false, |t| {
+                (&t.script_pubkey, t.asset, t.value)
+                    == (&txout.script_pubkey, txout.asset, txout.value)
+            });
+            if !matches {
+                return Err(invalid(format!("input #{idx} has a wrong previous output")));
+            }
+
+            let msg = pset
+                .sighash_msg(idx, &mut sighash_cache, None, genesis_hash)?
+                .to_secp_msg();
+            let mut sigs = HashMap::new();
+            for (public_key, raw) in input.partial_sigs.iter() {
+                let sig = parse_sig(raw)
+                    .filter(|(sig, hash_ty)| {
+                        *hash_ty == EcdsaSighashType::All
+                            && EC.verify_ecdsa(&msg, sig, &public_key.inner).is_ok()
+                    })
+                    .ok_or_else(|| invalid(format!("input #{idx} has an invalid signature")))?;
+                sigs.insert(*public_key, sig);
+            }
+            let descriptor = self
+                .wollet_descriptor()
+                .definite_descriptor(utxo.ext_int, utxo.wildcard_index)?
+                .derived_descriptor(&EC)?;
+            if descriptor.get_satisfaction(&sigs).is_err() {
+                return Err(invalid(format!("input #{idx} has not enough signatures")));
+            }
+
+            *reserves.entry(utxo.unblinded.asset).or_default() += utxo.unblinded.value;
+        }
+        Ok(reserves)
+    }
+}
@@ -5,7 +5,7 @@ use crate::Error;
 use elements::bitcoin::bip32::ChildNumber;
 use elements_miniscript::{Descriptor, DescriptorPublicKey};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::atomic::{AtomicU32, Ordering};
 
 pub const BATCH_SIZE: u32 = 20;
@@ -42,6 +42,10 @@ pub struct RawCache {
 
     /// last unused index for internal addresses (changes) for current descriptor
     pub last_unused_internal: AtomicU32,
+
+    /// last unused index for the further paths of a multipath descriptor, by path index
+    #[serde(default)]
+    pub last_unused_paths: BTreeMap<u32, u32>,
 }
 
 impl Default for RawCache {
@@ -55,6 +59,7 @@ impl Default for RawCache {
             tip: (0, BlockHash::all_zeros()),
             last_unused_internal: 0.into(),
             last_unused_external: 0.into(),
+            last_unused_paths: BTreeMap::default(),
             timestamps: HashMap::default(),
         }
     }
@@ -95,6 +100,8 @@ impl std::hash::Hash for RawCache {
         self.last_unused_internal
             .load(Ordering::Relaxed)
             .hash(state);
+
+        self.last_unused_paths.hash(state);
     }
 }
 
@@ -114,6 +121,20 @@ impl Store {
         &self,
         batch: u32,
         descriptor: &Descriptor<DescriptorPublicKey>, // non confidential (we need only script_pubkey), non multipath (we need to be able to derive with index)
+    ) -> Result<ScriptBatch, Error> {
+        let ext_int: Chain = descriptor.try_into().unwrap_or(Chain::External);
+        self.get_chain_script_batch(batch, descriptor, ext_int)
+    }
+
+    /// Like [`Store::get_script_batch`], but with the chain given explicitly
+    ///
+    /// Needed for the further paths of a multipath descriptor, which can't be inferred from the
+    /// descriptor alone.
+    pub fn get_chain_script_batch(
+        &self,
+        batch: u32,
+        descriptor: &Descriptor<DescriptorPublicKey>,
+        ext_int: Chain,
     ) -> Result<ScriptBatch, Error> {
         let mut result = ScriptBatch {
             cached: true,
@@ -122,7 +143,6 @@ impl Store {
 
         let start = batch * BATCH_SIZE;
         let end = start + BATCH_SIZE;
-        let ext_int: Chain = descriptor.try_into().unwrap_or(Chain::External);
         for j in start..end {
             let child = ChildNumber::from_normal_idx(j)?;
             let (script, cached) = self.get_or_derive(ext_int, child, descriptor)?;
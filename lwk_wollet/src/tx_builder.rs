@@ -1,17 +1,22 @@
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
 use elements::{
-    confidential::Value,
+    bitcoin,
+    confidential::{Asset, AssetBlindingFactor, Nonce, Value, ValueBlindingFactor},
     issuance::ContractHash,
     pset::{Output, PartiallySignedTransaction},
     secp256k1_zkp::ZERO_TWEAK,
-    Address, AssetId, Script, Transaction,
+    Address, AssetId, EcdsaSighashType, LockTime, OutPoint, Script, Sequence, Transaction, TxOut,
+    TxOutSecrets, TxOutWitness, Txid,
 };
 use rand::thread_rng;
 
 use crate::{
+    descriptor::Timelocks,
     hashes::Hash,
-    model::{ExternalUtxo, IssuanceDetails, Recipient},
+    model::{ExternalUtxo, IssuanceDetails, IssuanceSpec, Recipient},
+    pegin::{parent_genesis_hash, pegout_script, PeginClaim},
     pset_create::{validate_address, IssuanceRequest},
     Contract, ElementsNetwork, Error, UnvalidatedRecipient, Wollet, EC,
 };
@@ -50,6 +55,105 @@ pub fn extract_issuances(tx: &Transaction) -> Vec<IssuanceDetails> {
     r
 }
 
+/// L-BTC change below this value is considered dust
+pub const DUST_CHANGE_THRESHOLD: u64 = 546;
+
+/// What to do when the L-BTC change is below [`DUST_CHANGE_THRESHOLD`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DustChangePolicy {
+    /// Drop the change output and add its value to the fee
+    ///
+    /// The change is kept if it's the only output to blind.
+    #[default]
+    AddToFee,
+
+    /// Fail with [`Error::DustChange`]
+    Error,
+
+    /// Keep the change output anyway
+    ForceChange,
+}
+
+/// Default minimum fee rate in sats/kvb, the Liquid minimum relay fee of 0.1 sat/vb
+pub const MIN_RELAY_FEE_RATE: f32 = 100.0;
+
+/// What to do when the fee rate is below the minimum fee rate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MinFeeRatePolicy {
+    /// Use the minimum fee rate instead, logging a warning
+    #[default]
+    Clamp,
+
+    /// Fail with [`Error::BelowMinRelayFee`]
+    Error,
+}
+
+/// Set the `nSequence` of the wallet inputs and the `nLockTime` to satisfy `timelocks`
+///
+/// Returns an error if the height-based timelocks can't be satisfied in the next block.
+fn set_timelocks(
+    pset: &mut PartiallySignedTransaction,
+    wollet: &Wollet,
+    timelocks: Timelocks,
+) -> Result<(), Error> {
+    if timelocks == Timelocks::default() {
+        return Ok(());
+    }
+    let tip_height = wollet.tip().height();
+    let utxo_heights: HashMap<_, _> = wollet
+        .utxos()?
+        .into_iter()
+        .map(|u| (u.outpoint, u.height))
+        .collect();
+
+    if let Some(after) = timelocks.after {
+        let locktime = LockTime::from_consensus(after);
+        if locktime.is_block_height() && after > tip_height {
+            return Err(Error::TimelockNotMet {
+                required_height: after,
+            });
+        }
+        pset.global.tx_data.fallback_locktime = Some(locktime);
+    }
+
+    // A non final sequence is needed for the locktime to be enforced
+    let sequence = timelocks
+        .older
+        .map(Sequence::from_consensus)
+        .unwrap_or(Sequence::ENABLE_LOCKTIME_NO_RBF);
+    for input in pset.inputs_mut() {
+        let outpoint = OutPoint::new(input.previous_txid, input.previous_output_index);
+        let height = match utxo_heights.get(&outpoint) {
+            Some(height) => height,
+            None => continue, // Not a wallet input
+        };
+        if let Some(required_height) = older_required_height(timelocks, *height, tip_height) {
+            if required_height > tip_height {
+                return Err(Error::TimelockNotMet { required_height });
+            }
+        }
+        input.sequence = Some(sequence);
+    }
+    Ok(())
+}
+
+/// Height at which a utxo confirmed at `height` satisfies the relative timelock in `timelocks`
+///
+/// Returns `None` if there is no height-based relative timelock.
+fn older_required_height(
+    timelocks: Timelocks,
+    height: Option<u32>,
+    tip_height: u32,
+) -> Option<u32> {
+    let older = timelocks.older?;
+    if !Sequence::from_consensus(older).is_height_locked() {
+        return None;
+    }
+    // An unconfirmed utxo is at best confirmed in the next block
+    let utxo_height = height.unwrap_or(tip_height + 1);
+    Some(utxo_height + (older & 0xffff) - 1)
+}
+
 /// "Clone" of Wollet.add_input
 fn add_external_input(
     pset: &mut PartiallySignedTransaction,
@@ -74,6 +178,53 @@ fn add_external_input(
     *inp_weight += utxo.max_weight_to_satisfy;
 }
 
+/// Add the input claiming the pegin, returns the claimed value
+fn add_pegin_input(
+    pset: &mut PartiallySignedTransaction,
+    inp_txout_sec: &mut HashMap<usize, elements::TxOutSecrets>,
+    inp_weight: &mut usize,
+    pegin: &PeginClaim,
+    wollet: &Wollet,
+) -> Result<u64, Error> {
+    let desc = match wollet.definite_descriptor(pegin.claim_script()) {
+        Err(Error::ScriptNotMine) => return Err(Error::PeginClaimScriptNotMine),
+        r => r?,
+    };
+    let network = wollet.network();
+    let policy_asset = network.policy_asset();
+    let value = pegin.value();
+    let txid = Txid::from_raw_hash(pegin.txid().to_raw_hash());
+    let mut input = elements::pset::Input::from_prevout(OutPoint::new(txid, pegin.vout()));
+    // Flag the input as a pegin
+    input.previous_output_index |= 1 << 30;
+    // The claim script "spends" an explicit L-BTC output of the claimed value
+    input.witness_utxo = Some(TxOut {
+        asset: Asset::Explicit(policy_asset),
+        value: Value::Explicit(value),
+        nonce: Nonce::Null,
+        script_pubkey: pegin.claim_script().clone(),
+        witness: TxOutWitness::default(),
+    });
+    input.pegin_tx = Some(pegin.tx().clone());
+    input.pegin_txout_proof = Some(pegin.txout_proof().to_vec());
+    input.pegin_genesis_hash = Some(parent_genesis_hash(network));
+    input.pegin_claim_script = Some(pegin.claim_script().clone());
+    input.pegin_value = Some(value);
+    input.pegin_witness = Some(pegin.witness(network));
+
+    pset.add_input(input);
+    let idx = pset.inputs().len() - 1;
+    let secrets = TxOutSecrets::new(
+        policy_asset,
+        AssetBlindingFactor::zero(),
+        value,
+        ValueBlindingFactor::zero(),
+    );
+    inp_txout_sec.insert(idx, secrets);
+    *inp_weight += desc.max_weight_to_satisfy()?;
+    Ok(value)
+}
+
 /// A transaction builder
 ///
 /// See [`WolletTxBuilder`] for usage from rust.
@@ -89,11 +240,17 @@ pub struct TxBuilder {
     network: ElementsNetwork,
     recipients: Vec<Recipient>,
     fee_rate: f32,
+    min_fee_rate: f32,
+    min_fee_rate_policy: MinFeeRatePolicy,
     ct_discount: bool,
     issuance_request: IssuanceRequest,
     drain_lbtc: bool,
     drain_to: Option<Address>,
     external_utxos: Vec<ExternalUtxo>,
+    pegins: Vec<PeginClaim>,
+    dust_change_policy: DustChangePolicy,
+    timelocks: Timelocks,
+    input_sighash: HashMap<OutPoint, EcdsaSighashType>,
 }
 
 impl TxBuilder {
@@ -103,11 +260,17 @@ impl TxBuilder {
             network,
             recipients: vec![],
             fee_rate: 100.0,
+            min_fee_rate: MIN_RELAY_FEE_RATE,
+            min_fee_rate_policy: MinFeeRatePolicy::default(),
             ct_discount: false,
             issuance_request: IssuanceRequest::None,
             drain_lbtc: false,
             drain_to: None,
             external_utxos: vec![],
+            pegins: vec![],
+            dust_change_policy: DustChangePolicy::default(),
+            timelocks: Timelocks::default(),
+            input_sighash: HashMap::new(),
         }
     }
 
@@ -115,7 +278,25 @@ impl TxBuilder {
         self.network
     }
 
+    /// The recipients added so far
+    pub fn recipients(&self) -> &[Recipient] {
+        &self.recipients
+    }
+
+    /// The fee rate in sats/kvb set with [`TxBuilder::fee_rate()`], before checking the minimum
+    pub fn get_fee_rate(&self) -> f32 {
+        self.fee_rate
+    }
+
+    /// Whether ELIP200 discounted fees are used
+    pub fn ct_discount(&self) -> bool {
+        self.ct_discount
+    }
+
     /// Add recipient to the internal list
+    ///
+    /// Each recipient gets its own output, since an output carries a single asset: adding
+    /// several assets for the same address creates several outputs with the same script pubkey.
     pub fn add_recipient(
         self,
         address: &Address,
@@ -170,6 +351,33 @@ impl TxBuilder {
         self.add_unvalidated_recipient(&rec)
     }
 
+    /// Add an output pegging out `satoshi` L-BTC to the bitcoin `mainchain_address`
+    ///
+    /// The output is explicit, see [`pegout_script()`] for its script pubkey.
+    ///
+    /// Liquid mainnet requires a PAK proof in pegouts, which is not supported, so an error is
+    /// returned on that network.
+    pub fn pegout(self, mainchain_address: &str, satoshi: u64) -> Result<Self, Error> {
+        let network = self.network();
+        if network == ElementsNetwork::Liquid {
+            return Err(Error::PegoutPakUnsupported);
+        }
+        if satoshi == 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let address = bitcoin::Address::from_str(mainchain_address)
+            .map_err(|e| Error::InvalidMainchainAddress(e.to_string()))?
+            .require_network(network.bitcoin_network())
+            .map_err(|e| Error::InvalidMainchainAddress(e.to_string()))?;
+        let recipient = Recipient {
+            satoshi,
+            script_pubkey: pegout_script(network, &address),
+            blinding_pubkey: None,
+            asset: network.policy_asset(),
+        };
+        Ok(self.add_validated_recipient(recipient))
+    }
+
     /// Fee rate in sats/kvb
     /// Multiply sats/vb value by 1000 i.e. 1.0 sat/byte = 1000.0 sat/kvb
     pub fn fee_rate(mut self, fee_rate: Option<f32>) -> Self {
@@ -179,6 +387,42 @@ impl TxBuilder {
         self
     }
 
+    /// Minimum fee rate in sats/kvb, default [`MIN_RELAY_FEE_RATE`]
+    ///
+    /// Transactions paying less than the minimum relay fee are rejected at broadcast, set a
+    /// different value for networks with a different relay policy.
+    pub fn min_fee_rate(mut self, min_fee_rate: f32) -> Self {
+        self.min_fee_rate = min_fee_rate;
+        self
+    }
+
+    /// Set what to do when the fee rate is below the minimum, default [`MinFeeRatePolicy::Clamp`]
+    pub fn min_fee_rate_policy(mut self, policy: MinFeeRatePolicy) -> Self {
+        self.min_fee_rate_policy = policy;
+        self
+    }
+
+    /// The fee rate to use, checked against the minimum fee rate
+    fn checked_fee_rate(&self) -> Result<f32, Error> {
+        if self.fee_rate >= self.min_fee_rate {
+            return Ok(self.fee_rate);
+        }
+        match self.min_fee_rate_policy {
+            MinFeeRatePolicy::Clamp => {
+                log::warn!(
+                    "fee rate {} is below the minimum {}, using the minimum",
+                    self.fee_rate,
+                    self.min_fee_rate
+                );
+                Ok(self.min_fee_rate)
+            }
+            MinFeeRatePolicy::Error => Err(Error::BelowMinRelayFee {
+                requested: self.fee_rate,
+                minimum: self.min_fee_rate,
+            }),
+        }
+    }
+
     /// Use ELIP200 discounted fees for Confidential Transactions
     ///
     /// Note: if ELIP200 was not activated by miners and nodes relaying transactions, using
@@ -194,6 +438,46 @@ impl TxBuilder {
         self
     }
 
+    /// Set what to do when the L-BTC change is dust, default [`DustChangePolicy::AddToFee`]
+    ///
+    /// It doesn't apply when draining with [`TxBuilder::drain_lbtc_to()`], the drain output
+    /// always receives the excess L-BTC.
+    ///
+    /// The fee actually paid is the value of the fee output of the resulting PSET, which with
+    /// [`DustChangePolicy::AddToFee`] could be higher than the one computed from the fee rate.
+    pub fn dust_change_policy(mut self, policy: DustChangePolicy) -> Self {
+        self.dust_change_policy = policy;
+        self
+    }
+
+    /// Set the `nSequence` of the wallet inputs
+    ///
+    /// By default the minimal relative timelock (`older`) needed to spend from the wallet
+    /// descriptor is used, use this to spend with a more restrictive path.
+    pub fn sequence(mut self, sequence: Sequence) -> Self {
+        self.timelocks.older = Some(sequence.to_consensus_u32());
+        self
+    }
+
+    /// Set the transaction `nLockTime`
+    ///
+    /// By default the minimal absolute timelock (`after`) needed to spend from the wallet
+    /// descriptor is used, use this to spend with a more restrictive path.
+    pub fn locktime(mut self, locktime: LockTime) -> Self {
+        self.timelocks.after = Some(locktime.to_consensus_u32());
+        self
+    }
+
+    /// Set the sighash type of the signatures for the input spending `outpoint`
+    ///
+    /// By default inputs are signed with `SIGHASH_ALL`. The input must end up in the transaction,
+    /// otherwise [`TxBuilder::finish()`] errors, so it should be an external UTXO or a wallet UTXO
+    /// which is always selected, like the L-BTC ones.
+    pub fn input_sighash(mut self, outpoint: OutPoint, sighash: EcdsaSighashType) -> Self {
+        self.input_sighash.insert(outpoint, sighash);
+        self
+    }
+
     /// Issue an asset
     ///
     /// There will be `asset_sats` units of this asset that will be received by
@@ -207,32 +491,46 @@ impl TxBuilder {
     ///
     /// Can't be used if `reissue_asset` has been called
     pub fn issue_asset(
-        mut self,
+        self,
         asset_sats: u64,
         asset_receiver: Option<Address>,
         token_sats: u64,
         token_receiver: Option<Address>,
         contract: Option<Contract>,
     ) -> Result<Self, Error> {
-        if !matches!(self.issuance_request, IssuanceRequest::None) {
-            return Err(Error::IssuanceAlreadySet);
-        }
-        if let Some(addr) = asset_receiver.as_ref() {
-            validate_address(&addr.to_string(), self.network())?;
-        }
-        if let Some(addr) = token_receiver.as_ref() {
-            validate_address(&addr.to_string(), self.network())?;
-        }
-        if asset_sats == 0 {
-            return Err(Error::InvalidAmount);
-        }
-        self.issuance_request = IssuanceRequest::Issuance(
+        self.issue_assets(vec![IssuanceSpec {
             asset_sats,
             asset_receiver,
             token_sats,
             token_receiver,
             contract,
-        );
+        }])
+    }
+
+    /// Issue multiple assets in the same transaction
+    ///
+    /// Each issuance is set on a distinct input, see [`TxBuilder::issue_asset()`].
+    ///
+    /// Can't be used if `reissue_asset` has been called
+    pub fn issue_assets(mut self, issuances: Vec<IssuanceSpec>) -> Result<Self, Error> {
+        if !matches!(self.issuance_request, IssuanceRequest::None) {
+            return Err(Error::IssuanceAlreadySet);
+        }
+        if issuances.is_empty() {
+            return Err(Error::Generic("No issuance specified".to_string()));
+        }
+        for issuance in issuances.iter() {
+            if let Some(addr) = issuance.asset_receiver.as_ref() {
+                validate_address(&addr.to_string(), self.network())?;
+            }
+            if let Some(addr) = issuance.token_receiver.as_ref() {
+                validate_address(&addr.to_string(), self.network())?;
+            }
+            if issuance.asset_sats == 0 {
+                return Err(Error::InvalidAmount);
+            }
+        }
+        self.issuance_request = IssuanceRequest::Issuance(issuances);
         Ok(self)
     }
 
@@ -300,8 +598,41 @@ impl TxBuilder {
         Ok(self)
     }
 
+    /// Claim a pegin, converting the bitcoin sent to a pegin address into L-BTC
+    ///
+    /// `bitcoin_tx` is the mainchain transaction sending to the pegin address at output `vout`,
+    /// `bitcoin_tx_proof` is the serialized merkle block proving its inclusion
+    /// (as returned by `bitcoin-cli gettxoutproof`), `claim_script` is the script pubkey of
+    /// this wallet committed in the pegin address and `fed_peg_script` is the federation peg
+    /// script the pegin address was derived from (see [`crate::fed_peg_script()`]).
+    ///
+    /// Fails if the output does not pay to `fed_peg_script` tweaked with `claim_script`, the
+    /// claim script must also belong to the wallet finishing the transaction.
+    ///
+    /// The mainchain transaction must be buried enough (102 blocks) for the claim to be valid.
+    pub fn claim_pegin(
+        mut self,
+        bitcoin_tx: bitcoin::Transaction,
+        vout: u32,
+        bitcoin_tx_proof: Vec<u8>,
+        claim_script: Script,
+        fed_peg_script: &bitcoin::Script,
+    ) -> Result<Self, Error> {
+        let pegin = PeginClaim::new(
+            bitcoin_tx,
+            vout,
+            bitcoin_tx_proof,
+            claim_script,
+            fed_peg_script,
+        )?;
+        self.pegins.push(pegin);
+        Ok(self)
+    }
+
     /// Finish building the transaction
     pub fn finish(self, wollet: &Wollet) -> Result<PartiallySignedTransaction, Error> {
+        let fee_rate = self.checked_fee_rate()?;
+
         // Init PSET
         let mut pset = PartiallySignedTransaction::new_v2();
         let mut inp_txout_sec = HashMap::new();
@@ -364,8 +695,37 @@ impl TxBuilder {
             satoshi_in += utxo.unblinded.value;
         }
 
-        // FIXME: For implementation simplicity now we always add all L-BTC inputs
+        // Add the pegins to claim, they are L-BTC inputs
+        for pegin in &self.pegins {
+            satoshi_in += add_pegin_input(
+                &mut pset,
+                &mut inp_txout_sec,
+                &mut inp_weight,
+                pegin,
+                wollet,
+            )?;
+        }
+
+        // Set the timelocks required by the wallet descriptor, unless explicitly set
+        let required = wollet.wollet_descriptor().timelocks();
+        let timelocks = Timelocks {
+            older: self.timelocks.older.or(required.older),
+            after: self.timelocks.after.or(required.after),
+        };
+
+        // FIXME: For implementation simplicity now we always add all L-BTC inputs,
+        // except the ones that can't be spent yet because of the relative timelock
+        let tip_height = wollet.tip().height();
+        let mut immature_height: Option<u32> = None;
         for utxo in wollet.asset_utxos(&wollet.policy_asset())? {
+            if let Some(required_height) = older_required_height(timelocks, utxo.height, tip_height)
+            {
+                if required_height > tip_height {
+                    immature_height =
+                        Some(immature_height.map_or(required_height, |h| h.min(required_height)));
+                    continue;
+                }
+            }
             wollet.add_input(&mut pset, &mut inp_txout_sec, &mut inp_weight, &utxo)?;
             satoshi_in += utxo.unblinded.value;
         }
@@ -373,38 +733,55 @@ impl TxBuilder {
         // Set (re)issuance data
         match self.issuance_request {
             IssuanceRequest::None => {}
-            IssuanceRequest::Issuance(
-                satoshi_asset,
-                address_asset,
-                satoshi_token,
-                address_token,
-                contract,
-            ) => {
-                // At least a L-BTC input for the fee was added.
-                let idx = 0;
-                let (asset, token) =
-                    wollet.set_issuance(&mut pset, idx, satoshi_asset, satoshi_token, contract)?;
-
-                let addressee = match address_asset {
-                    Some(address) => Recipient::from_address(satoshi_asset, &address, asset),
-                    None => wollet.addressee_external(
+            IssuanceRequest::Issuance(issuances) => {
+                // At least a L-BTC input for the fee was added,
+                // but each issuance needs a distinct input.
+                let available = pset.inputs().len();
+                if available < issuances.len() {
+                    return Err(Error::InsufficientInputsForIssuances {
+                        needed: issuances.len(),
+                        available,
+                    });
+                }
+                for (idx, issuance) in issuances.into_iter().enumerate() {
+                    let IssuanceSpec {
+                        asset_sats: satoshi_asset,
+                        asset_receiver: address_asset,
+                        token_sats: satoshi_token,
+                        token_receiver: address_token,
+                        contract,
+                    } = issuance;
+                    let (asset, token) = wollet.set_issuance(
+                        &mut pset,
+                        idx,
                         satoshi_asset,
-                        asset,
-                        &mut last_unused_external,
-                    )?,
-                };
-                wollet.add_output(&mut pset, &addressee)?;
+                        satoshi_token,
+                        contract,
+                    )?;
 
-                if satoshi_token > 0 {
-                    let addressee = match address_token {
-                        Some(address) => Recipient::from_address(satoshi_token, &address, token),
+                    let addressee = match address_asset {
+                        Some(address) => Recipient::from_address(satoshi_asset, &address, asset),
                         None => wollet.addressee_external(
-                            satoshi_token,
-                            token,
+                            satoshi_asset,
+                            asset,
                             &mut last_unused_external,
                         )?,
                     };
                     wollet.add_output(&mut pset, &addressee)?;
+
+                    if satoshi_token > 0 {
+                        let addressee = match address_token {
+                            Some(address) => {
+                                Recipient::from_address(satoshi_token, &address, token)
+                            }
+                            None => wollet.addressee_external(
+                                satoshi_token,
+                                token,
+                                &mut last_unused_external,
+                            )?,
+                        };
+                        wollet.add_output(&mut pset, &addressee)?;
+                    }
                 }
             }
             IssuanceRequest::Reissuance(asset, satoshi_asset, address_asset, issuance_tx) => {
@@ -469,13 +846,20 @@ impl TxBuilder {
             }
         }
 
+        set_timelocks(&mut pset, wollet, timelocks)?;
+
         // Add a temporary fee, and always add a change or drain output,
         // then we'll tweak those values to match the given fee rate.
         let temp_fee = 1;
         if satoshi_in <= (satoshi_out + temp_fee) {
-            return Err(Error::InsufficientFunds);
+            return Err(match immature_height {
+                // Funds might be available once the skipped utxos mature
+                Some(required_height) => Error::TimelockNotMet { required_height },
+                None => Error::InsufficientFunds,
+            });
         }
         let satoshi_change = satoshi_in - satoshi_out - temp_fee;
+        let is_drain = self.drain_to.is_some();
         let addressee = if let Some(address) = self.drain_to {
             Recipient::from_address(satoshi_change, &address, wollet.policy_asset())
         } else {
@@ -485,6 +869,7 @@ impl TxBuilder {
                 &mut last_unused_internal,
             )?
         };
+        let change_idx = pset.n_outputs();
         wollet.add_output(&mut pset, &addressee)?;
         let fee_output =
             Output::new_explicit(Script::default(), temp_fee, wollet.policy_asset(), None);
@@ -506,17 +891,41 @@ impl TxBuilder {
         };
 
         let vsize = (weight + 4 - 1) / 4;
-        let fee = (vsize as f32 * self.fee_rate / 1000.0).ceil() as u64;
+        let mut fee = (vsize as f32 * fee_rate / 1000.0).ceil() as u64;
         if satoshi_in <= (satoshi_out + fee) {
             return Err(Error::InsufficientFunds);
         }
         let satoshi_change = satoshi_in - satoshi_out - fee;
+        let mut drop_change = false;
+        if satoshi_change < DUST_CHANGE_THRESHOLD && !is_drain {
+            match self.dust_change_policy {
+                DustChangePolicy::AddToFee => {
+                    // Blinding requires at least one blinded output
+                    drop_change = pset
+                        .outputs()
+                        .iter()
+                        .enumerate()
+                        .any(|(i, o)| i != change_idx && o.blinding_key.is_some());
+                }
+                DustChangePolicy::Error => {
+                    return Err(Error::DustChange {
+                        satoshi: satoshi_change,
+                        dust: DUST_CHANGE_THRESHOLD,
+                    })
+                }
+                DustChangePolicy::ForceChange => {}
+            }
+        }
+
         // Replace change and fee outputs
+        if drop_change {
+            fee += satoshi_change;
+            pset.remove_output(change_idx);
+        } else {
+            pset.outputs_mut()[change_idx].amount = Some(satoshi_change);
+        }
         let n_outputs = pset.n_outputs();
-        let outputs = pset.outputs_mut();
-        let change_output = &mut outputs[n_outputs - 2]; // index check: we always have the lbtc change and the fee output at least
-        change_output.amount = Some(satoshi_change);
-        let fee_output = &mut outputs[n_outputs - 1];
+        let fee_output = &mut pset.outputs_mut()[n_outputs - 1];
         fee_output.amount = Some(fee);
 
         // TODO inputs/outputs(except fee) randomization, not trivial because of blinder_index on inputs
@@ -525,6 +934,17 @@ impl TxBuilder {
         let mut rng = thread_rng();
         pset.blind_last(&mut rng, &EC, &inp_txout_sec)?;
 
+        for (outpoint, sighash) in self.input_sighash {
+            let input = pset
+                .inputs_mut()
+                .iter_mut()
+                .find(|i| {
+                    i.previous_txid == outpoint.txid && i.previous_output_index == outpoint.vout
+                })
+                .ok_or(Error::MissingInputForSighash(outpoint))?;
+            input.sighash_type = Some(sighash.into());
+        }
+
         // Add details to the pset from our descriptor, like bip32derivation and keyorigin
         wollet.add_details(&mut pset)?;
 
@@ -612,6 +1032,62 @@ impl<'a> WolletTxBuilder<'a> {
         })
     }
 
+    /// Wrapper of [`TxBuilder::pegout()`]
+    pub fn pegout(self, mainchain_address: &str, satoshi: u64) -> Result<Self, Error> {
+        Ok(Self {
+            wollet: self.wollet,
+            inner: self.inner.pegout(mainchain_address, satoshi)?,
+        })
+    }
+
+    /// Wrapper of [`TxBuilder::dust_change_policy()`]
+    pub fn dust_change_policy(self, policy: DustChangePolicy) -> Self {
+        Self {
+            wollet: self.wollet,
+            inner: self.inner.dust_change_policy(policy),
+        }
+    }
+
+    /// Wrapper of [`TxBuilder::sequence()`]
+    pub fn sequence(self, sequence: Sequence) -> Self {
+        Self {
+            wollet: self.wollet,
+            inner: self.inner.sequence(sequence),
+        }
+    }
+
+    /// Wrapper of [`TxBuilder::locktime()`]
+    pub fn locktime(self, locktime: LockTime) -> Self {
+        Self {
+            wollet: self.wollet,
+            inner: self.inner.locktime(locktime),
+        }
+    }
+
+    /// Wrapper of [`TxBuilder::input_sighash()`]
+    pub fn input_sighash(self, outpoint: OutPoint, sighash: EcdsaSighashType) -> Self {
+        Self {
+            wollet: self.wollet,
+            inner: self.inner.input_sighash(outpoint, sighash),
+        }
+    }
+
+    /// Wrapper of [`TxBuilder::min_fee_rate()`]
+    pub fn min_fee_rate(self, min_fee_rate: f32) -> Self {
+        Self {
+            wollet: self.wollet,
+            inner: self.inner.min_fee_rate(min_fee_rate),
+        }
+    }
+
+    /// Wrapper of [`TxBuilder::min_fee_rate_policy()`]
+    pub fn min_fee_rate_policy(self, policy: MinFeeRatePolicy) -> Self {
+        Self {
+            wollet: self.wollet,
+            inner: self.inner.min_fee_rate_policy(policy),
+        }
+    }
+
     /// Wrapper of [`TxBuilder::fee_rate()`]
     pub fn fee_rate(self, fee_rate: Option<f32>) -> Self {
         Self {
@@ -657,6 +1133,14 @@ impl<'a> WolletTxBuilder<'a> {
         })
     }
 
+    /// Wrapper of [`TxBuilder::issue_assets()`]
+    pub fn issue_assets(self, issuances: Vec<IssuanceSpec>) -> Result<Self, Error> {
+        Ok(Self {
+            wollet: self.wollet,
+            inner: self.inner.issue_assets(issuances)?,
+        })
+    }
+
     /// Wrapper of [`TxBuilder::reissue_asset()`]
     pub fn reissue_asset(
         self,
@@ -699,4 +1183,25 @@ impl<'a> WolletTxBuilder<'a> {
             inner: self.inner.add_external_utxos(utxos)?,
         })
     }
+
+    /// Wrapper of [`TxBuilder::claim_pegin()`]
+    pub fn claim_pegin(
+        self,
+        bitcoin_tx: bitcoin::Transaction,
+        vout: u32,
+        bitcoin_tx_proof: Vec<u8>,
+        claim_script: Script,
+        fed_peg_script: &bitcoin::Script,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            wollet: self.wollet,
+            inner: self.inner.claim_pegin(
+                bitcoin_tx,
+                vout,
+                bitcoin_tx_proof,
+                claim_script,
+                fed_peg_script,
+            )?,
+        })
+    }
 }
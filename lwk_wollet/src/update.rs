@@ -12,7 +12,7 @@ use elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
 use elements::encode::{Decodable, Encodable};
 use elements::{BlockHeader, TxInWitness, TxOutWitness};
 use rand::{thread_rng, Rng};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic;
 
 /// Transactions downloaded and unblinded
@@ -139,6 +139,25 @@ impl Wollet {
         self.apply_update_inner(update, false)
     }
 
+    /// Check the scripts in the update are derived from the wallet descriptor
+    ///
+    /// Useful for updates not created by this wallet, for instance by an external indexer.
+    pub fn check_update(&self, update: &Update) -> Result<(), Error> {
+        let descriptor = self.wollet_descriptor();
+        for (script, (chain, child)) in update.scripts.iter() {
+            let index = u32::from(*child);
+            let derived = descriptor.script_pubkey(*chain, index).ok();
+            if derived.as_ref() != Some(script) {
+                return Err(Error::UpdateOnDifferentDescriptor(
+                    format!("{script:x}"),
+                    *chain,
+                    index,
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn apply_update_inner(&mut self, update: Update, do_persist: bool) -> Result<(), Error> {
         // TODO should accept &Update
 
@@ -187,6 +206,7 @@ impl Wollet {
         store.cache.paths.extend(scripts);
         let mut last_used_internal = None;
         let mut last_used_external = None;
+        let mut last_used_paths: BTreeMap<u32, u32> = BTreeMap::new();
         for (txid, _) in txid_height_new {
             if let Some(tx) = store.cache.all_txs.get(&txid) {
                 for (vout, output) in tx.output.iter().enumerate() {
@@ -212,6 +232,10 @@ impl Wollet {
                                 Some(last) if index > last => last_used_internal = Some(index),
                                 _ => {}
                             },
+                            Chain::Path(i) => {
+                                let last = last_used_paths.entry(*i).or_insert(*index);
+                                *last = (*last).max(*index);
+                            }
                         }
                     }
                 }
@@ -229,6 +253,9 @@ impl Wollet {
                 .last_unused_internal
                 .store(last_used_internal + 1, atomic::Ordering::Relaxed);
         }
+        for (i, last_used) in last_used_paths {
+            store.cache.last_unused_paths.insert(i, last_used + 1);
+        }
 
         if do_persist {
             self.persister.push(update)?;
@@ -341,6 +368,19 @@ impl Decodable for EncodableTxOutSecrets {
 }
 
 const UPDATE_MAGIC_BYTES: [u8; 4] = [0x89, 0x61, 0xb8, 0xc8];
+
+/// The latest serialization version written by [`Update::serialize()`]
+///
+/// Older versions are upgraded while decoding: v0 updates lack the wollet status, which is set
+/// to 0. Newer versions can't be decoded, since their fields are unknown.
+///
+/// v2 updates can have scripts of multipath descriptors paths other than the external and
+/// internal ones, updates without them are written as v1 so that older versions can read them.
+const UPDATE_VERSION: u8 = 2;
+
+/// The serialization version of updates having only external and internal scripts
+const UPDATE_VERSION_TWO_CHAINS: u8 = 1;
+
 impl Encodable for Update {
     fn consensus_encode<W: std::io::Write>(
         &self,
@@ -349,7 +389,16 @@ impl Encodable for Update {
         let mut bytes_written = 0;
 
         bytes_written += UPDATE_MAGIC_BYTES.consensus_encode(&mut w)?; // Magic bytes
-        bytes_written += 1u8.consensus_encode(&mut w)?; // Version
+        let has_other_chains = self
+            .scripts
+            .values()
+            .any(|(chain, _)| matches!(chain, Chain::Path(_)));
+        let version = if has_other_chains {
+            UPDATE_VERSION
+        } else {
+            UPDATE_VERSION_TWO_CHAINS
+        };
+        bytes_written += version.consensus_encode(&mut w)?;
 
         bytes_written += self.wollet_status.consensus_encode(&mut w)?;
 
@@ -379,11 +428,8 @@ impl Encodable for Update {
             elements::encode::VarInt(self.scripts.len() as u64).consensus_encode(&mut w)?;
         for (script, (chain, child_number)) in self.scripts.iter() {
             bytes_written += script.consensus_encode(&mut w)?;
-            bytes_written += match chain {
-                Chain::External => 0u8,
-                Chain::Internal => 1u8,
-            }
-            .consensus_encode(&mut w)?;
+            // Multipath descriptors have at most 256 paths
+            bytes_written += (chain.path_index() as u8).consensus_encode(&mut w)?;
             bytes_written += u32::from(*child_number).consensus_encode(&mut w)?;
         }
 
@@ -401,10 +447,10 @@ impl Decodable for Update {
         }
 
         let version = u8::consensus_decode(&mut d)?;
-        if version > 1 {
+        if version > UPDATE_VERSION {
             return Err(elements::encode::Error::ParseFailed("Unsupported version"));
         }
-        let wollet_status = if version == 1 {
+        let wollet_status = if version >= 1 {
             u64::consensus_decode(&mut d)?
         } else {
             0
@@ -451,9 +497,10 @@ impl Decodable for Update {
             let mut map = HashMap::with_capacity(len as usize);
             for _ in 0..len {
                 let script = Script::consensus_decode(&mut d)?;
-                let chain = match u8::consensus_decode(&mut d)? {
-                    0 => Chain::External,
-                    1 => Chain::Internal,
+                let chain = match (version, u8::consensus_decode(&mut d)?) {
+                    (_, 0) => Chain::External,
+                    (_, 1) => Chain::Internal,
+                    (v, i) if v >= 2 => Chain::Path(i.into()),
                     _ => return Err(elements::encode::Error::ParseFailed("Invalid chain")),
                 };
                 let child_number: ChildNumber = u32::consensus_decode(&mut d)?.into();
@@ -489,6 +536,8 @@ mod test {
 
     use crate::{update::DownloadTxResult, Chain, Update, Wollet, WolletDescriptor};
 
+    use super::{UPDATE_VERSION, UPDATE_VERSION_TWO_CHAINS};
+
     use super::EncodableTxOutSecrets;
 
     pub fn download_tx_result_test_vector() -> DownloadTxResult {
@@ -582,6 +631,29 @@ mod test {
         assert_eq!(update, back)
     }
 
+    #[test]
+    fn test_update_other_chains() {
+        let mut scripts = HashMap::new();
+        scripts.insert(Script::default(), (Chain::Path(2), 5u32.into()));
+        let update = Update {
+            new_txs: DownloadTxResult::default(),
+            txid_height_new: vec![],
+            txid_height_delete: vec![],
+            timestamps: vec![],
+            scripts,
+            tip: lwk_test_util::liquid_block_1().header,
+            wollet_status: 1,
+        };
+        let mut vec = update.serialize().unwrap();
+        assert_eq!(vec[4], UPDATE_VERSION);
+        assert_eq!(Update::deserialize(&vec).unwrap(), update);
+
+        // Older versions only have the external and internal chains
+        vec[4] = UPDATE_VERSION_TWO_CHAINS;
+        let err = Update::deserialize(&vec).unwrap_err();
+        assert!(err.to_string().contains("Invalid chain"), "{err}");
+    }
+
     #[test]
     fn test_update_backward_comp() {
         // Update can be deserialize from v0 or v1 blob, but in the first case the wallet_status will be 0.
@@ -596,6 +668,47 @@ mod test {
         assert_eq!(upd_from_v0, upd_from_v1);
     }
 
+    #[test]
+    fn test_update_upgrade() {
+        let v0 = lwk_test_util::update_test_vector_bytes();
+        let v1 = lwk_test_util::update_test_vector_v1_bytes();
+        assert_eq!(v0[4], 0);
+        assert_eq!(v1[4], UPDATE_VERSION_TWO_CHAINS);
+
+        // Serializing a v0 update again upgrades it to the current version
+        let upd_from_v0 = Update::deserialize(&v0).unwrap();
+        let upgraded = upd_from_v0.serialize().unwrap();
+        assert_eq!(upgraded[4], UPDATE_VERSION_TWO_CHAINS);
+        assert_eq!(Update::deserialize(&upgraded).unwrap(), upd_from_v0);
+
+        // Both versions give the same wallet state on the descriptor they were created with
+        let desc = "ct(slip77(9c8e4f05c7711a98c838be228bcb84924d4570ca53f35fa1c793e58841d47023),elwpkh([73c5da0a/84'/1'/0']tpubDC8msFGeGuwnKG9Upg7DM2b4DaRqg3CUZa5g8v2SRQ6K4NSkxUgd7HsL2XVWbVm39yBA4LAxysQAm397zwQSQoQgewGiYZqrA9DsP4zbQ1M/<0;1>/*))";
+        let desc: WolletDescriptor = desc.parse().unwrap();
+        let state = |update: Update| {
+            let network = crate::ElementsNetwork::default_regtest();
+            let mut wollet = Wollet::without_persist(network, desc.clone()).unwrap();
+            wollet.apply_update(update).unwrap();
+            (
+                wollet.status(),
+                wollet.balance().unwrap(),
+                wollet.transactions().unwrap().len(),
+                wollet.tip().hash(),
+            )
+        };
+        let mut upd_from_v1 = Update::deserialize(&v1).unwrap();
+        // The v1 vector was not created from this wallet status, skip the check
+        upd_from_v1.wollet_status = 0;
+        let state_v0 = state(upd_from_v0);
+        assert_eq!(state_v0, state(upd_from_v1));
+        assert_eq!(state_v0, state(Update::deserialize(&upgraded).unwrap()));
+
+        // Newer versions can't be decoded
+        let mut future = v1.clone();
+        future[4] = UPDATE_VERSION + 1;
+        let err = Update::deserialize(&future).unwrap_err();
+        assert!(err.to_string().contains("Unsupported version"), "{err}");
+    }
+
     #[test]
     fn test_update_decription() {
         let update = Update::deserialize(&lwk_test_util::update_test_vector_bytes()).unwrap();
@@ -2,14 +2,17 @@ use crate::bitcoin::bip32::Fingerprint;
 use crate::clients::LastUnused;
 use crate::config::{Config, ElementsNetwork};
 use crate::descriptor::Chain;
-use crate::elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
+use crate::elements::confidential::{Asset, AssetBlindingFactor, Value, ValueBlindingFactor};
 use crate::elements::pset::PartiallySignedTransaction;
 use crate::elements::secp256k1_zkp::ZERO_TWEAK;
-use crate::elements::{AssetId, BlockHash, OutPoint, Script, Transaction, TxOutSecrets, Txid};
+use crate::elements::{
+    Address, AssetId, BlockHash, OutPoint, Script, Transaction, TxOutSecrets, Txid,
+};
 use crate::error::Error;
 use crate::hashes::Hash;
 use crate::model::{
-    AddressResult, BitcoinAddressResult, ExternalUtxo, IssuanceDetails, WalletTx, WalletTxOut,
+    AddressResult, BitcoinAddressResult, ExternalUtxo, IssuanceDetails, ScanScope, WalletTx,
+    WalletTxOut,
 };
 use crate::persister::PersistError;
 use crate::store::{Height, ScriptBatch, Store, Timestamp, BATCH_SIZE};
@@ -24,7 +27,9 @@ use elements_miniscript::{
     ConfidentialDescriptor, DefiniteDescriptorKey, Descriptor, DescriptorPublicKey,
 };
 use fxhash::FxHasher;
-use lwk_common::{burn_script, pset_balance, pset_issuances, pset_signatures, PsetDetails};
+use lwk_common::{
+    burn_script, pset_balance_with_scripts, pset_issuances, pset_signatures, PsetDetails,
+};
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::Hasher;
@@ -61,6 +66,29 @@ pub trait WolletState {
         batch: u32,
         descriptor: &Descriptor<DescriptorPublicKey>,
     ) -> Result<ScriptBatch, Error>;
+    /// Like [`WolletState::get_script_batch`], but with the chain given explicitly
+    fn get_chain_script_batch(
+        &self,
+        batch: u32,
+        descriptor: &Descriptor<DescriptorPublicKey>,
+        ext_int: Chain,
+    ) -> Result<ScriptBatch, Error> {
+        let mut result = ScriptBatch {
+            cached: true,
+            ..Default::default()
+        };
+
+        let start = batch * BATCH_SIZE;
+        let end = start + BATCH_SIZE;
+        for j in start..end {
+            let child = ChildNumber::from_normal_idx(j)?;
+            let (script, cached) = self.get_or_derive(ext_int, child, descriptor)?;
+            result.cached = cached;
+            result.value.push((script, (ext_int, child)));
+        }
+
+        Ok(result)
+    }
     fn get_or_derive(
         &self,
         ext_int: Chain,
@@ -83,22 +111,8 @@ impl WolletState for WolletConciseState {
         batch: u32,
         descriptor: &Descriptor<DescriptorPublicKey>, // non confidential (we need only script_pubkey), non multipath (we need to be able to derive with index)
     ) -> Result<ScriptBatch, Error> {
-        let mut result = ScriptBatch {
-            cached: true,
-            ..Default::default()
-        };
-
-        let start = batch * BATCH_SIZE;
-        let end = start + BATCH_SIZE;
         let ext_int: Chain = descriptor.try_into().unwrap_or(Chain::External);
-        for j in start..end {
-            let child = ChildNumber::from_normal_idx(j)?;
-            let (script, cached) = self.get_or_derive(ext_int, child, descriptor)?;
-            result.cached = cached;
-            result.value.push((script, (ext_int, child)));
-        }
-
-        Ok(result)
+        self.get_chain_script_batch(batch, descriptor, ext_int)
     }
 
     fn get_or_derive(
@@ -164,6 +178,16 @@ impl WolletState for Wollet {
         self.store.get_script_batch(batch, descriptor)
     }
 
+    fn get_chain_script_batch(
+        &self,
+        batch: u32,
+        descriptor: &Descriptor<DescriptorPublicKey>,
+        ext_int: Chain,
+    ) -> Result<ScriptBatch, Error> {
+        self.store
+            .get_chain_script_batch(batch, descriptor, ext_int)
+    }
+
     fn get_or_derive(
         &self,
         ext_int: Chain,
@@ -202,6 +226,7 @@ impl WolletState for Wollet {
                 .cache
                 .last_unused_external
                 .load(atomic::Ordering::Relaxed),
+            paths: self.store.cache.last_unused_paths.clone(),
         }
     }
 
@@ -271,10 +296,24 @@ impl Wollet {
             last_unused: LastUnused {
                 internal: cache.last_unused_internal.load(atomic::Ordering::Relaxed),
                 external: cache.last_unused_external.load(atomic::Ordering::Relaxed),
+                paths: cache.last_unused_paths.clone(),
             },
         }
     }
 
+    /// Create a new wallet from separate external (receive) and internal (change) descriptors
+    ///
+    /// See [`WolletDescriptor::from_descriptors()`] for the requirements on the descriptors.
+    pub fn from_descriptors(
+        network: ElementsNetwork,
+        persister: Arc<dyn Persister + Send + Sync>,
+        external: &str,
+        internal: &str,
+    ) -> Result<Self, Error> {
+        let descriptor = WolletDescriptor::from_descriptors(external, internal)?;
+        Self::new(network, persister, descriptor)
+    }
+
     /// Create a new wallet persisting on file system
     pub fn with_fs_persist<P: AsRef<Path>>(
         network: ElementsNetwork,
@@ -332,6 +371,27 @@ impl Wollet {
         }
     }
 
+    /// Get the number of scripts a full scan derives for each chain
+    ///
+    /// Scripts are derived in batches until a batch without transactions is found, so the scope
+    /// is the highest used index plus the gap limit, rounded up to the batch size.
+    pub fn scan_scope(&self) -> ScanScope {
+        let scanned = |last_unused: u32| {
+            let used_batches = last_unused.div_ceil(BATCH_SIZE);
+            (used_batches + 1) * BATCH_SIZE
+        };
+        let last_unused = self.last_unused();
+        let has_internal = self.descriptor.single_bitcoin_descriptors().len() > 1;
+        ScanScope {
+            external_scanned: scanned(last_unused.external),
+            internal_scanned: if has_internal {
+                scanned(last_unused.internal)
+            } else {
+                0
+            },
+        }
+    }
+
     /// Get a wallet address
     ///
     /// If Some return the address at the given index,
@@ -358,11 +418,7 @@ impl Wollet {
         fed_desc: BtcDescriptor<bitcoin::PublicKey>,
     ) -> Result<BitcoinAddressResult, Error> {
         let index = self.unwrap_or_last_unused(index);
-        let network = match self.network() {
-            ElementsNetwork::Liquid => bitcoin::Network::Bitcoin,
-            ElementsNetwork::LiquidTestnet => bitcoin::Network::Testnet,
-            ElementsNetwork::ElementsRegtest { policy_asset: _ } => bitcoin::Network::Regtest,
-        };
+        let network = self.network().bitcoin_network();
 
         let address = self.descriptor.pegin_address(index, network, fed_desc)?;
         Ok(BitcoinAddressResult::new(address, index))
@@ -459,6 +515,37 @@ impl Wollet {
         Ok(utxos)
     }
 
+    /// Get the policy asset UTXOs below `threshold` received from transactions not spending
+    /// wallet outputs
+    ///
+    /// These are likely unsolicited deposits, such as dust sent to link addresses together,
+    /// that should not be spent with the other UTXOs. Change outputs are never included.
+    /// Other assets are never included, since their amounts are not comparable with `threshold`.
+    pub fn dust_deposits(&self, threshold: u64) -> Result<Vec<WalletTxOut>, Error> {
+        let txos = self.txos()?;
+        let policy_asset = self.policy_asset();
+        let mut dust = vec![];
+        for utxo in self.utxos()? {
+            if utxo.unblinded.asset != policy_asset || utxo.unblinded.value >= threshold {
+                continue;
+            }
+            let tx = self
+                .store
+                .cache
+                .all_txs
+                .get(&utxo.outpoint.txid)
+                .ok_or_else(|| Error::Generic(format!("dust_deposits no tx {}", utxo.outpoint)))?;
+            if tx
+                .input
+                .iter()
+                .all(|i| !txos.contains_key(&i.previous_output))
+            {
+                dust.push(utxo);
+            }
+        }
+        Ok(dust)
+    }
+
     fn txos(&self) -> Result<HashMap<OutPoint, WalletTxOut>, Error> {
         Ok(self
             .txos_inner(false)?
@@ -621,12 +708,39 @@ impl Wollet {
     /// Get the PSET details with respect to the wallet
     pub fn get_details(&self, pset: &PartiallySignedTransaction) -> Result<PsetDetails, Error> {
         Ok(PsetDetails {
-            balance: pset_balance(pset, self.descriptor())?,
+            balance: pset_balance_with_scripts(pset, self.descriptor(), |s| {
+                self.store.cache.paths.contains_key(s)
+            })?,
             sig_details: pset_signatures(pset),
             issuances: pset_issuances(pset),
         })
     }
 
+    /// Get the chain and the derivation index of each of the `addresses` belonging to the wallet
+    ///
+    /// Addresses are looked up among the scripts derived while scanning, so each address costs a
+    /// map lookup and, if it's a wallet address, a derivation to check the blinding key.
+    /// Addresses beyond the scanned range are not recognized.
+    pub fn addresses_index(
+        &self,
+        addresses: &[Address],
+    ) -> Result<Vec<Option<(Chain, u32)>>, Error> {
+        let params = self.config.address_params();
+        let mut result = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let index = match self.index(&address.script_pubkey()) {
+                Ok((chain, index)) => {
+                    let derived = self.descriptor.chain_address(chain, index, params)?;
+                    (derived.blinding_pubkey == address.blinding_pubkey).then_some((chain, index))
+                }
+                Err(Error::ScriptNotMine) => None,
+                Err(e) => return Err(e),
+            };
+            result.push(index);
+        }
+        Ok(result)
+    }
+
     pub(crate) fn index(&self, script_pubkey: &Script) -> Result<(Chain, u32), Error> {
         let (ext_int, index) = self
             .store
@@ -698,6 +812,145 @@ impl Wollet {
         Ok(())
     }
 
+    /// Annotate a PSET created elsewhere, so that signers can recognize the wallet inputs and outputs
+    ///
+    /// Inputs spending wallet outputs get their `witness_utxo`, if missing, from the wallet
+    /// transactions. Then, as in [`Wollet::add_details()`], `bip32_derivation`,
+    /// `witness_script`/`redeem_script` and the global xpubs are added for the inputs and outputs
+    /// of the wallet.
+    pub fn annotate_pset(&self, pset: &mut PartiallySignedTransaction) -> Result<(), Error> {
+        for input in pset.inputs_mut() {
+            if input.witness_utxo.is_some() {
+                continue;
+            }
+            let txout = self
+                .store
+                .cache
+                .all_txs
+                .get(&input.previous_txid)
+                .and_then(|tx| tx.output.get(input.previous_output_index as usize));
+            if let Some(txout) = txout {
+                if self.store.cache.paths.contains_key(&txout.script_pubkey) {
+                    // As for the inputs added by the tx builder, keep the rangeproof separately
+                    let mut txout = txout.clone();
+                    input.in_utxo_rangeproof = txout.witness.rangeproof.take();
+                    input.witness_utxo = Some(txout);
+                }
+            }
+        }
+        self.add_details(pset)
+    }
+
+    /// Attach the previous transactions as `non_witness_utxo` to the inputs spending wallet outputs
+    ///
+    /// Needed by signers requiring the full previous transaction, the transactions are taken from
+    /// the wallet ones, so no network call is made.
+    pub fn add_non_witness_utxos(&self, pset: &mut PartiallySignedTransaction) {
+        for input in pset.inputs_mut() {
+            if input.non_witness_utxo.is_some() {
+                continue;
+            }
+            if let Some(tx) = self.store.cache.all_txs.get(&input.previous_txid) {
+                let is_mine = tx
+                    .output
+                    .get(input.previous_output_index as usize)
+                    .map_or(false, |o| {
+                        self.store.cache.paths.contains_key(&o.script_pubkey)
+                    });
+                if is_mine {
+                    input.non_witness_utxo = Some(tx.clone());
+                }
+            }
+        }
+    }
+
+    /// Attach the previous outputs data to the PSET inputs missing it, fetching it if needed
+    ///
+    /// Only the inputs spending wallet outputs and the ones at the `inputs` indexes are hydrated,
+    /// the others are left unchanged. They get `witness_utxo` and `non_witness_utxo` from the
+    /// previous transaction, which is taken from the wallet transactions if present, otherwise
+    /// it's downloaded with `client`. Pegin inputs are left unchanged.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn hydrate_pset<B: crate::clients::blocking::BlockchainBackend>(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        client: &B,
+        inputs: &[usize],
+    ) -> Result<(), Error> {
+        let mut to_download = vec![];
+        for &idx in inputs {
+            let input = pset
+                .inputs()
+                .get(idx)
+                .ok_or_else(|| Error::Generic(format!("Input #{idx} not in the PSET")))?;
+            let txid = input.previous_txid;
+            if !self.store.cache.all_txs.contains_key(&txid) && !to_download.contains(&txid) {
+                to_download.push(txid);
+            }
+        }
+        let txs = if to_download.is_empty() {
+            vec![]
+        } else {
+            client.get_transactions(&to_download)?
+        };
+        self.hydrate_pset_with_txs(pset, inputs, &txs)
+    }
+
+    /// Attach the previous outputs data to the PSET inputs missing it
+    ///
+    /// As [`Wollet::hydrate_pset()`], but the previous transactions of the inputs at the `inputs`
+    /// indexes which are not wallet transactions are taken from `txs` instead of downloaded.
+    pub fn hydrate_pset_with_txs(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        inputs: &[usize],
+        txs: &[Transaction],
+    ) -> Result<(), Error> {
+        let n_inputs = pset.n_inputs();
+        if let Some(idx) = inputs.iter().find(|&&idx| idx >= n_inputs) {
+            return Err(Error::Generic(format!("Input #{idx} not in the PSET")));
+        }
+        let txs: HashMap<Txid, &Transaction> = txs.iter().map(|tx| (tx.txid(), tx)).collect();
+
+        for (idx, input) in pset.inputs_mut().iter_mut().enumerate() {
+            if input.is_pegin()
+                || (input.witness_utxo.is_some() && input.non_witness_utxo.is_some())
+            {
+                continue;
+            }
+            let txid = input.previous_txid;
+            let vout = input.previous_output_index;
+            let tx = match self.store.cache.all_txs.get(&txid) {
+                Some(tx) => {
+                    let is_mine = tx.output.get(vout as usize).map_or(false, |o| {
+                        self.store.cache.paths.contains_key(&o.script_pubkey)
+                    });
+                    if !is_mine && !inputs.contains(&idx) {
+                        continue;
+                    }
+                    tx
+                }
+                None if inputs.contains(&idx) => *txs.get(&txid).ok_or_else(|| {
+                    Error::Generic(format!("Previous transaction of input #{idx} not found"))
+                })?,
+                None => continue,
+            };
+            let txout = tx.output.get(vout as usize).ok_or_else(|| {
+                Error::Generic(format!("Input #{idx} spends a missing output {vout}"))
+            })?;
+            if input.witness_utxo.is_none() {
+                // As for the inputs added by the tx builder, keep the rangeproof separately
+                let mut txout = txout.clone();
+                input.in_utxo_rangeproof = txout.witness.rangeproof.take();
+                input.witness_utxo = Some(txout);
+            }
+            if input.non_witness_utxo.is_none() {
+                input.non_witness_utxo = Some(tx.clone());
+            }
+        }
+        Ok(())
+    }
+
     /// Get the signers' fingerprints involved in this descriptor
     pub fn signers(&self) -> Vec<Fingerprint> {
         let mut signers = vec![];
@@ -730,6 +983,80 @@ impl Wollet {
         Ok(pset.extract_tx()?)
     }
 
+    /// Blind the outputs of a PSET which has not been built by this wallet
+    ///
+    /// Every output with a blinding key gets its commitments and blind proofs, the last one
+    /// balancing the blinding factors. This needs the secrets of all the inputs, which are known
+    /// for the wallet inputs, the other inputs must be explicit. Since the blinding factors of
+    /// outputs blinded by others are unknown, PSETs with some outputs already blinded are rejected.
+    /// Wallet details are added to the PSET as in [`Wollet::add_details()`].
+    pub fn blind_pset(&self, pset: &mut PartiallySignedTransaction) -> Result<(), Error> {
+        for (idx, output) in pset.outputs().iter().enumerate() {
+            if output.asset_comm.is_some() || output.amount_comm.is_some() {
+                return Err(Error::OutputAlreadyBlinded(idx));
+            }
+        }
+
+        let mut inp_txout_sec = HashMap::new();
+        for (idx, input) in pset.inputs().iter().enumerate() {
+            let outpoint = OutPoint::new(input.previous_txid, input.previous_output_index);
+            let secrets = match self.store.cache.unblinded.get(&outpoint) {
+                Some(secrets) => *secrets,
+                None => match input.witness_utxo.as_ref().map(|t| (t.asset, t.value)) {
+                    Some((Asset::Explicit(asset), Value::Explicit(value))) => TxOutSecrets::new(
+                        asset,
+                        AssetBlindingFactor::zero(),
+                        value,
+                        ValueBlindingFactor::zero(),
+                    ),
+                    _ => return Err(Error::InputSecretsUnknown(idx)),
+                },
+            };
+            inp_txout_sec.insert(idx, secrets);
+        }
+
+        // All the inputs secrets are known, so any of them can be the blinder
+        for output in pset.outputs_mut() {
+            if output.blinding_key.is_some() && output.blinder_index.is_none() {
+                output.blinder_index = Some(0);
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        pset.blind_last(&mut rng, &EC, &inp_txout_sec)?;
+        self.add_details(pset)
+    }
+
+    /// Estimate the vsize of the transaction once every input has `threshold` signatures
+    ///
+    /// The witness of a multisig input grows with the signatures, so the fee of a multisig spend
+    /// can be checked before collecting them. Finalized inputs are counted as they are, the others
+    /// must have a witness script and get the witness expected by `OP_CHECKMULTISIG`: an empty
+    /// element, `threshold` signatures of the maximum size and the witness script.
+    pub fn estimate_multisig_final_vsize(
+        &self,
+        pset: &PartiallySignedTransaction,
+        threshold: usize,
+    ) -> Result<usize, Error> {
+        // DER encoded signature of the maximum size followed by the sighash byte
+        const MAX_SIG_LEN: usize = 73;
+        let mut tx = pset.extract_tx()?;
+        for (i, (input, txin)) in pset.inputs().iter().zip(tx.input.iter_mut()).enumerate() {
+            if input.final_script_witness.is_some() {
+                continue;
+            }
+            let witness_script = input
+                .witness_script
+                .as_ref()
+                .ok_or_else(|| Error::Generic(format!("Input #{i} has no witness script")))?;
+            let mut witness = vec![vec![]];
+            witness.extend(std::iter::repeat(vec![0u8; MAX_SIG_LEN]).take(threshold));
+            witness.push(witness_script.to_bytes());
+            txin.witness.script_witness = witness;
+        }
+        Ok(tx.vsize())
+    }
+
     pub fn updates(&self) -> Result<Vec<Update>, PersistError> {
         let mut updates = vec![];
         for i in 0.. {
@@ -1006,6 +1333,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_addresses_index() {
+        let view_key = "1111111111111111111111111111111111111111111111111111111111111111";
+        let xpub = "tpubDD7tXK8KeQ3YY83yWq755fHY2JW8Ha8Q765tknUM5rSvjPcGWfUppDFMpQ1ScziKfW3ZNtZvAD7M3u7bSs7HofjTD3KP3YxPK7X6hwV8Rk2";
+        let mut wollet = new_wollet(&format!("ct({view_key},elwpkh({xpub}/<0;1>/*))"));
+        let other = new_wollet(&format!("ct({view_key},elwpkh({xpub}/<2;3>/*))"));
+
+        // Simulate a scan deriving the first scripts of each chain
+        for (chain, count) in [(Chain::External, 20), (Chain::Internal, 5)] {
+            for i in 0..count {
+                let script = wollet.descriptor.script_pubkey(chain, i).unwrap();
+                let child = ChildNumber::from_normal_idx(i).unwrap();
+                wollet.store.cache.paths.insert(script, (chain, child));
+            }
+        }
+
+        let params = wollet.config.address_params();
+        let external = wollet.address(Some(3)).unwrap().address().clone();
+        let internal = wollet.descriptor.change(1, params).unwrap();
+        let not_scanned = wollet.address(Some(25)).unwrap().address().clone();
+        let foreign = other.address(Some(3)).unwrap().address().clone();
+        let unconfidential = external.to_unconfidential();
+        let addresses = [external, foreign, internal, not_scanned, unconfidential];
+
+        let indexes = wollet.addresses_index(&addresses).unwrap();
+        assert_eq!(
+            indexes,
+            vec![
+                Some((Chain::External, 3)),
+                None,
+                Some((Chain::Internal, 1)),
+                None,
+                None
+            ]
+        );
+        assert!(wollet.addresses_index(&[]).unwrap().is_empty());
+    }
+
     #[test]
     fn fixed_addresses_test() {
         let expected = [
@@ -17,7 +17,7 @@ use lwk_test_util::*;
 use lwk_wollet::pegin::fetch_last_full_header;
 use lwk_wollet::*;
 use std::{collections::HashSet, str::FromStr};
-use test_wollet::{generate_signer, test_client_electrum, TestWollet};
+use test_wollet::{generate_signer, multisig_desc, test_client_electrum, TestWollet};
 
 #[test]
 fn liquid_send_jade_signer() {
@@ -253,7 +253,7 @@ fn unsupported_descriptor() {
     let desc_p2tr = format!("ct({view_key},eltr({xpub1}/*))");
     let desc_no_wildcard = format!("ct({view_key},elwpkh({xpub1}))");
 
-    let desc_multi_path_1 = format!("ct({view_key},elwpkh({xpub1}/<0;1;2>/*))");
+    let desc_multi_path_1 = format!("ct({view_key},elwpkh({xpub1}/<0;1;3>/*))");
     let desc_multi_path_2 = format!("ct({view_key},elwpkh({xpub1}/<0;1>/0/*))");
     let desc_multi_path_3 = format!("ct({view_key},elwpkh({xpub1}/<1;0>/*))");
     let desc_multi_path_4 = format!("ct({view_key},elwpkh({xpub1}/<0;2>/*))");
@@ -331,6 +331,72 @@ fn address() {
     wallet.fund(&server, satoshi, last_address, None);
 }
 
+#[test]
+fn scan_scope() {
+    let server = setup();
+
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/*))", view_key, signer.xpub());
+
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+
+    // Nothing used, a single batch is scanned, no internal chain
+    let scope = wallet.wollet.scan_scope();
+    assert_eq!(scope.external_scanned, 20);
+    assert_eq!(scope.internal_scanned, 0);
+
+    let satoshi = 10_000;
+    let address = wallet.address_result(Some(15)).address().clone();
+    wallet.fund(&server, satoshi, Some(address), None);
+    assert_eq!(wallet.wollet.scan_scope().external_scanned, 40);
+
+    let address = wallet.address_result(Some(25)).address().clone();
+    wallet.fund(&server, satoshi, Some(address), None);
+    assert_eq!(wallet.wollet.scan_scope().external_scanned, 60);
+
+    // With an internal chain
+    let desc = format!("ct({},elwpkh({}/<0;1>/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let wallet = TestWollet::new(client, &desc);
+    let scope = wallet.wollet.scan_scope();
+    assert_eq!(scope.external_scanned, 20);
+    assert_eq!(scope.internal_scanned, 20);
+}
+
+#[test]
+fn multipath_three_paths() {
+    let server = setup();
+
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/<0;1;2>/*))", view_key, signer.xpub());
+    let wd = WolletDescriptor::from_str(&desc).unwrap();
+    assert_eq!(wd.single_bitcoin_descriptors().len(), 3);
+    let params = ElementsNetwork::default_regtest().address_params();
+
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    let address = wd.chain_address(Chain::Path(2), 3, params).unwrap();
+    wallet.fund(&server, 10_000, Some(address.clone()), None);
+
+    let utxos = wallet.wollet.utxos().unwrap();
+    assert_eq!(utxos.len(), 1);
+    assert_eq!(utxos[0].ext_int, Chain::Path(2));
+    assert_eq!(utxos[0].wildcard_index, 3);
+    assert_eq!(utxos[0].script_pubkey, address.script_pubkey());
+
+    // The third path doesn't affect the receive and change addresses
+    assert_eq!(wallet.address_result(None).index(), 0);
+
+    // A new wallet finds the funds with a full scan
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.sync();
+    assert_eq!(wallet.balance(&wallet.policy_asset()), 10_000);
+}
+
 #[test]
 fn different_blinding_keys() {
     // Two wallet with same "bitcoin" descriptor but different blinding keys
@@ -858,6 +924,56 @@ fn jade_sign_wollet_pset() {
     wallet.send(&mut pset);
 }
 
+#[test]
+fn jade_sign_colliding_key() {
+    let server = setup();
+    let mnemonic = TEST_MNEMONIC;
+    let signer = SwSigner::new(mnemonic, false).unwrap();
+    let slip77_key = "9c8e4f05c7711a98c838be228bcb84924d4570ca53f35fa1c793e58841d47023";
+    let desc_str = format!("ct(slip77({}),elwpkh({}/*))", slip77_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc_str);
+
+    wallet.fund_btc(&server);
+
+    let my_addr = wallet.address();
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&my_addr, 1000)
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    // Replace the wallet key with another key having the same fingerprint and path,
+    // the device signs for it anyway, since it only looks at the fingerprint
+    let path = DerivationPath::from_str("m/0/1").unwrap();
+    let colliding =
+        elements::bitcoin::PublicKey::new(signer.derive_xpub(&path).unwrap().public_key);
+    let input = &mut pset.inputs_mut()[0];
+    let (key, origin) = input.bip32_derivation.pop_first().unwrap();
+    assert_eq!(origin.0, signer.fingerprint());
+    assert_ne!(key, colliding);
+    input.bip32_derivation.insert(colliding, origin);
+
+    let docker = Cli::default();
+    let jade_init = jade_setup(&docker, mnemonic);
+    let xpub_identifier = jade_init.jade.identifier().unwrap();
+    let jade_signer = AnySigner::Jade(jade_init.jade, xpub_identifier);
+
+    // The device signs with the key at the path, which is not the colliding key
+    let mut signed = pset.clone();
+    assert_eq!(jade_signer.sign(&mut signed).unwrap(), 0);
+    assert!(signed.inputs()[0].partial_sigs.is_empty());
+
+    // With the wallet key the input is signed
+    let input = &mut pset.inputs_mut()[0];
+    let origin = input.bip32_derivation.remove(&colliding).unwrap();
+    input.bip32_derivation.insert(key, origin);
+    let summary = jade_signer.sign_detailed(&mut pset).unwrap();
+    assert_eq!(summary.signatures_added, 1);
+    assert!(pset.inputs()[0].partial_sigs.contains_key(&key));
+}
+
 #[test]
 fn jade_single_sig() {
     let server = setup();
@@ -1280,6 +1396,380 @@ fn claim_pegin() {
     let _txid = server.elementsd_sendrawtransaction(&tx_hex);
 }
 
+#[test]
+fn claim_pegin_wollet() {
+    let server = setup_with_bitcoind();
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+
+    // Send bitcoin to the pegin address committing to a wallet script
+    let claim_script = wallet.address().script_pubkey();
+    let mainchain_address = server.elementsd_tweakfedpegscript(&claim_script.as_bytes().to_hex());
+    server.bitcoind_generate(101);
+    let satoshi = 100_000_000;
+    let txid = server.bitcoind_sendtoaddress(&mainchain_address, satoshi);
+    let tx = server.bitcoind_getrawtransaction(txid);
+    let vout = tx
+        .output
+        .iter()
+        .position(|o| o.script_pubkey == mainchain_address.script_pubkey())
+        .unwrap() as u32;
+    server.bitcoind_generate(101);
+    let proof = Vec::<u8>::from_hex(&server.bitcoind_gettxoutproof(txid)).unwrap();
+    server.elementsd_generate(2);
+
+    let fed_peg_script = server.elementsd_fedpegscript();
+
+    // A wrong output or proof is rejected
+    assert!(wallet
+        .tx_builder()
+        .claim_pegin(
+            tx.clone(),
+            99,
+            proof.clone(),
+            claim_script.clone(),
+            &fed_peg_script
+        )
+        .is_err());
+    assert!(wallet
+        .tx_builder()
+        .claim_pegin(
+            tx.clone(),
+            vout,
+            vec![1, 2, 3],
+            claim_script.clone(),
+            &fed_peg_script
+        )
+        .is_err());
+
+    // The output must commit to the claim script
+    let other_script = wallet.address().script_pubkey();
+    let err = wallet
+        .tx_builder()
+        .claim_pegin(
+            tx.clone(),
+            vout,
+            proof.clone(),
+            other_script,
+            &fed_peg_script,
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::PeginOutputMismatch(v) if v == vout));
+
+    // The claim script must belong to the wallet
+    let other_wallet = TestWollet::new(
+        test_client_electrum(&server.electrs.electrum_url),
+        &format!(
+            "ct({},elwpkh({}/*))",
+            generate_view_key(),
+            generate_signer().xpub()
+        ),
+    );
+    let err = other_wallet
+        .tx_builder()
+        .claim_pegin(
+            tx.clone(),
+            vout,
+            proof.clone(),
+            claim_script.clone(),
+            &fed_peg_script,
+        )
+        .unwrap()
+        .finish()
+        .unwrap_err();
+    assert!(matches!(err, Error::PeginClaimScriptNotMine));
+
+    let mut pset = wallet
+        .tx_builder()
+        .claim_pegin(tx, vout, proof, claim_script, &fed_peg_script)
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert!(pset.inputs()[0].is_pegin());
+
+    let details = wallet.wollet.get_details(&pset).unwrap();
+    let fee = details.balance.fee;
+    let policy_asset = wallet.policy_asset();
+    assert_eq!(
+        *details.balance.balances.get(&policy_asset).unwrap(),
+        (satoshi - fee) as i64
+    );
+
+    wallet.sign(&signer, &mut pset);
+    wallet.send(&mut pset);
+    assert_eq!(wallet.balance(&policy_asset), satoshi - fee);
+}
+
+#[test]
+fn pegout() {
+    let server = setup();
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+
+    let script = bitcoin::ScriptBuf::new();
+    let mainchain_address = bitcoin::Address::p2wsh(&script, bitcoin::Network::Regtest);
+    let mainnet_address = bitcoin::Address::p2wsh(&script, bitcoin::Network::Bitcoin);
+
+    // Invalid address or amount
+    let address = mainchain_address.to_string();
+    assert!(wallet.tx_builder().pegout(&address, 0).is_err());
+    assert!(wallet
+        .tx_builder()
+        .pegout("not an address", 10_000)
+        .is_err());
+    let err = wallet
+        .tx_builder()
+        .pegout(&mainnet_address.to_string(), 10_000)
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidMainchainAddress(_)));
+
+    // Liquid requires PAK proofs, which are not supported
+    let err = TxBuilder::new(ElementsNetwork::Liquid)
+        .pegout(&mainnet_address.to_string(), 10_000)
+        .unwrap_err();
+    assert!(matches!(err, Error::PegoutPakUnsupported));
+
+    let satoshi = 10_000;
+    let mut pset = wallet
+        .tx_builder()
+        .pegout(&address, satoshi)
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let network = wallet.wollet.network();
+    let expected = pegout_script(network, &mainchain_address);
+    let output = pset
+        .outputs()
+        .iter()
+        .find(|o| o.script_pubkey == expected)
+        .unwrap();
+    assert_eq!(output.amount, Some(satoshi));
+    assert_eq!(output.asset, Some(wallet.policy_asset()));
+    assert!(output.blinding_key.is_none());
+
+    let details = wallet.wollet.get_details(&pset).unwrap();
+    let fee = details.balance.fee;
+    let balance_before = wallet.balance_btc();
+    assert_eq!(
+        *details
+            .balance
+            .balances
+            .get(&wallet.policy_asset())
+            .unwrap(),
+        -((satoshi + fee) as i64)
+    );
+
+    wallet.sign(&signer, &mut pset);
+    wallet.send(&mut pset);
+    assert_eq!(wallet.balance_btc(), balance_before - satoshi - fee);
+}
+
+#[test]
+fn dust_change_policy() {
+    let server = setup();
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+    let balance = wallet.balance_btc();
+    let policy_asset = wallet.policy_asset();
+    let node_address = server.elementsd_getnewaddress();
+
+    // Compute the amount to send to have a sub-dust change
+    let pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let fee = wallet.wollet.get_details(&pset).unwrap().balance.fee;
+    let dust_change = 100;
+    let satoshi = balance - fee - dust_change;
+
+    // Error
+    let err = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, satoshi)
+        .unwrap()
+        .dust_change_policy(DustChangePolicy::Error)
+        .finish()
+        .unwrap_err();
+    assert!(matches!(err, Error::DustChange { .. }));
+
+    // ForceChange
+    let pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, satoshi)
+        .unwrap()
+        .dust_change_policy(DustChangePolicy::ForceChange)
+        .finish()
+        .unwrap();
+    assert_eq!(pset.n_outputs(), 3);
+    let details = wallet.wollet.get_details(&pset).unwrap();
+    let change = balance - satoshi - details.balance.fee;
+    assert!(change < DUST_CHANGE_THRESHOLD);
+    assert_eq!(
+        *details.balance.balances.get(&policy_asset).unwrap(),
+        -((satoshi + details.balance.fee) as i64)
+    );
+
+    // The drain output is kept, whatever the policy
+    let drain_address = server.elementsd_getnewaddress();
+    for policy in [DustChangePolicy::AddToFee, DustChangePolicy::Error] {
+        let pset = wallet
+            .tx_builder()
+            .add_lbtc_recipient(&node_address, satoshi)
+            .unwrap()
+            .drain_lbtc_wallet()
+            .drain_lbtc_to(drain_address.clone())
+            .dust_change_policy(policy)
+            .finish()
+            .unwrap();
+        assert_eq!(pset.n_outputs(), 3);
+        let fee = wallet.wollet.get_details(&pset).unwrap().balance.fee;
+        let outputs = pset.outputs();
+        let recipient = outputs.iter().find(|o| o.amount == Some(satoshi)).unwrap();
+        assert_eq!(recipient.script_pubkey, node_address.script_pubkey());
+        let drain = outputs
+            .iter()
+            .find(|o| o.script_pubkey == drain_address.script_pubkey())
+            .unwrap();
+        assert_eq!(drain.amount, Some(balance - satoshi - fee));
+    }
+
+    // AddToFee (default)
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, satoshi)
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(pset.n_outputs(), 2);
+    let details = wallet.wollet.get_details(&pset).unwrap();
+    assert_eq!(details.balance.fee, balance - satoshi);
+    assert_eq!(
+        *details.balance.balances.get(&policy_asset).unwrap(),
+        -(balance as i64)
+    );
+
+    wallet.sign(&signer, &mut pset);
+    wallet.send(&mut pset);
+    assert_eq!(wallet.balance_btc(), 0);
+}
+
+#[test]
+fn min_fee_rate() {
+    let server = setup();
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+    let node_address = server.elementsd_getnewaddress();
+
+    // Clamp (default)
+    let pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .fee_rate(Some(10.0))
+        .finish()
+        .unwrap();
+    assert_fee_rate(compute_fee_rate(&pset), Some(MIN_RELAY_FEE_RATE));
+
+    // Error
+    let err = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .fee_rate(Some(10.0))
+        .min_fee_rate_policy(MinFeeRatePolicy::Error)
+        .finish()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::BelowMinRelayFee {
+            requested,
+            minimum,
+        } if requested == 10.0 && minimum == MIN_RELAY_FEE_RATE
+    ));
+
+    // A lower floor, for networks with a lower minimum relay fee
+    let pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .fee_rate(Some(60.0))
+        .min_fee_rate(50.0)
+        .min_fee_rate_policy(MinFeeRatePolicy::Error)
+        .finish()
+        .unwrap();
+    assert_fee_rate(compute_fee_rate(&pset), Some(60.0));
+}
+
+#[test]
+fn spend_csv_timelock() {
+    let server = setup();
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let older = 10;
+    let desc = format!(
+        "ct({view_key},elwsh(and_v(v:pk({}/*),older({older}))))",
+        signer.xpub()
+    );
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+
+    // Confirm the funding transaction
+    let height = server.elementsd_height() as u32 + 1;
+    server.elementsd_generate(1);
+    wallet.wait_height(height);
+
+    let node_address = server.elementsd_getnewaddress();
+    let err = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .finish()
+        .unwrap_err();
+    let expected_height = height + older - 1;
+    assert!(matches!(
+        err,
+        Error::TimelockNotMet { required_height } if required_height == expected_height
+    ));
+
+    server.elementsd_generate(older - 1);
+    wallet.wait_height(expected_height);
+
+    // The new unconfirmed utxo is not mature and it's not selected
+    wallet.fund_btc(&server);
+    assert_eq!(wallet.wollet.utxos().unwrap().len(), 2);
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(pset.inputs().len(), 1);
+    let sequence = elements::Sequence::from_height(older as u16);
+    assert!(pset.inputs().iter().all(|i| i.sequence == Some(sequence)));
+
+    wallet.sign(&signer, &mut pset);
+    wallet.send(&mut pset);
+}
+
 #[test]
 fn test_fetch_full_header_regtest() {
     let server = setup();
@@ -1757,3 +2247,393 @@ fn test_persistence_reload_after_only_tip() {
 
     TestWollet::check_persistence(wallet);
 }
+
+#[test]
+fn annotate_pset() {
+    let server = setup();
+
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    // The key origin is needed to add the global xpubs
+    let xpub = signer
+        .keyorigin_xpub(lwk_common::Bip::Bip84, false)
+        .unwrap();
+    let desc = format!("ct({},elwpkh({}/<0;1>/*))", view_key, xpub);
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+
+    let node_address = server.elementsd_getnewaddress();
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    // Remove what signers need to recognize the wallet inputs, as some PSET creators do
+    pset.global.xpub.clear();
+    for input in pset.inputs_mut() {
+        input.bip32_derivation.clear();
+        input.witness_script = None;
+        input.witness_utxo = None;
+    }
+    for output in pset.outputs_mut() {
+        output.bip32_derivation.clear();
+    }
+    let signer = AnySigner::Software(signer);
+    assert_eq!(signer.sign(&mut pset.clone()).unwrap_or(0), 0);
+
+    wallet.wollet.annotate_pset(&mut pset).unwrap();
+    assert!(!pset.global.xpub.is_empty());
+    for input in pset.inputs() {
+        assert!(input.witness_utxo.is_some());
+        assert_eq!(input.bip32_derivation.len(), 1);
+    }
+    // The change output
+    assert!(pset
+        .outputs()
+        .iter()
+        .any(|o| !o.bip32_derivation.is_empty()));
+
+    // The previous transactions, for signers requiring them
+    assert!(pset.inputs().iter().all(|i| i.non_witness_utxo.is_none()));
+    wallet.wollet.add_non_witness_utxos(&mut pset);
+    for input in pset.inputs() {
+        let tx = input.non_witness_utxo.as_ref().unwrap();
+        assert_eq!(tx.txid(), input.previous_txid);
+    }
+
+    wallet.sign(&signer, &mut pset);
+    wallet.send(&mut pset);
+}
+
+#[test]
+fn hydrate_pset() {
+    let server = setup();
+
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/<0;1>/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+
+    let node_address = server.elementsd_getnewaddress();
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .finish()
+        .unwrap();
+    pset.inputs_mut()[0].witness_utxo = None;
+    pset.inputs_mut()[0].in_utxo_rangeproof = None;
+    assert!(wallet.wollet.get_details(&pset).is_err());
+    let stripped = pset.clone();
+
+    // The wallet inputs are hydrated from the wallet transactions
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    wallet.wollet.hydrate_pset(&mut pset, &client, &[]).unwrap();
+    let tx = pset.inputs()[0].non_witness_utxo.as_ref().unwrap();
+    assert_eq!(tx.txid(), pset.inputs()[0].previous_txid);
+    assert!(wallet.wollet.get_details(&pset).is_ok());
+
+    // A wallet without the transactions doesn't know the inputs are its own, they are left
+    // unchanged unless requested, in that case it has to download them
+    let mut pset = stripped;
+    let descriptor: WolletDescriptor = desc.parse().unwrap();
+    let network = ElementsNetwork::default_regtest();
+    let wollet = Wollet::without_persist(network, descriptor).unwrap();
+    wollet.hydrate_pset(&mut pset, &client, &[]).unwrap();
+    assert!(pset.inputs()[0].witness_utxo.is_none());
+    assert!(pset.inputs()[0].non_witness_utxo.is_none());
+    let err = wollet.hydrate_pset(&mut pset, &client, &[9]).unwrap_err();
+    assert_eq!(err.to_string(), "Input #9 not in the PSET");
+    let inputs: Vec<usize> = (0..pset.n_inputs()).collect();
+    wollet.hydrate_pset(&mut pset, &client, &inputs).unwrap();
+    for input in pset.inputs() {
+        let tx = input.non_witness_utxo.as_ref().unwrap();
+        assert_eq!(tx.txid(), input.previous_txid);
+        assert!(input.witness_utxo.is_some());
+    }
+    let details = wallet.wollet.get_details(&pset).unwrap();
+    assert!(details.balance.fee > 0);
+
+    wallet.sign(&signer, &mut pset);
+    wallet.send(&mut pset);
+}
+
+#[test]
+fn input_sighash() {
+    let server = setup();
+
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/<0;1>/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+
+    let utxos = wallet.wollet.utxos().unwrap();
+    assert_eq!(utxos.len(), 1);
+    let outpoint = utxos[0].outpoint;
+    let sighash = elements::EcdsaSighashType::SinglePlusAnyoneCanPay;
+
+    let node_address = server.elementsd_getnewaddress();
+    let err = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .input_sighash(elements::OutPoint::default(), sighash)
+        .finish()
+        .unwrap_err();
+    assert!(matches!(err, Error::MissingInputForSighash(_)));
+
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .input_sighash(outpoint, sighash)
+        .finish()
+        .unwrap();
+    assert_eq!(pset.inputs()[0].sighash_type, Some(sighash.into()));
+
+    let signer = AnySigner::Software(signer);
+    wallet.sign(&signer, &mut pset);
+    let sigs: Vec<_> = pset.inputs()[0].partial_sigs.values().collect();
+    assert_eq!(sigs.len(), 1);
+    assert_eq!(*sigs[0].last().unwrap(), sighash as u8);
+
+    wallet.send(&mut pset);
+}
+
+#[test]
+fn proof_of_reserves() {
+    let server = setup();
+
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/<0;1>/*))", view_key, signer.xpub());
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+    wallet.fund_btc(&server);
+    let policy_asset = wallet.wollet.policy_asset();
+    let balance = *wallet.wollet.balance().unwrap().get(&policy_asset).unwrap();
+
+    let challenge = "exchange audit 2024";
+    let mut pset = wallet.wollet.proof_of_reserves(challenge).unwrap();
+    assert_eq!(pset.inputs().len(), 3);
+
+    // Unsigned proofs are not valid
+    let err = wallet
+        .wollet
+        .verify_proof_of_reserves(challenge, &pset)
+        .unwrap_err();
+    assert!(err.to_string().contains("not enough signatures"), "{err}");
+
+    wallet.sign(&signer, &mut pset);
+    let reserves = wallet
+        .wollet
+        .verify_proof_of_reserves(challenge, &pset)
+        .unwrap();
+    assert_eq!(reserves.get(&policy_asset), Some(&balance));
+
+    // The proof is bound to the challenge
+    let err = wallet
+        .wollet
+        .verify_proof_of_reserves("another challenge", &pset)
+        .unwrap_err();
+    assert!(err.to_string().contains("challenge"), "{err}");
+
+    // Repeating a coin doesn't inflate the reserves, even if signed
+    let mut duplicated = wallet.wollet.proof_of_reserves(challenge).unwrap();
+    duplicated.add_input(duplicated.inputs()[1].clone());
+    wallet.sign(&signer, &mut duplicated);
+    let err = wallet
+        .wollet
+        .verify_proof_of_reserves(challenge, &duplicated)
+        .unwrap_err();
+    assert!(err.to_string().contains("same output"), "{err}");
+
+    // Once a coin is spent the proof is no longer valid
+    let node_address = server.elementsd_getnewaddress();
+    let signer = AnySigner::Software(signer);
+    wallet.send_btc(&[&signer], None, Some((node_address, 10_000)));
+    let err = wallet
+        .wollet
+        .verify_proof_of_reserves(challenge, &pset)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("not a wallet unspent output"),
+        "{err}"
+    );
+}
+
+#[test]
+fn estimate_multisig_final_vsize() {
+    let server = setup();
+
+    let signers: Vec<_> = (0..3)
+        .map(|_| AnySigner::Software(generate_signer()))
+        .collect();
+    let signers_ref: Vec<_> = signers.iter().collect();
+    let desc = multisig_desc(&signers_ref, 2);
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+    wallet.fund_btc(&server);
+
+    let node_address = server.elementsd_getnewaddress();
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 1_500_000)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let n_inputs = pset.n_inputs();
+    assert_eq!(n_inputs, 2);
+    let estimate = wallet
+        .wollet
+        .estimate_multisig_final_vsize(&pset, 2)
+        .unwrap();
+
+    // More signatures make a bigger witness
+    let estimate_3 = wallet
+        .wollet
+        .estimate_multisig_final_vsize(&pset, 3)
+        .unwrap();
+    assert!(estimate_3 > estimate);
+
+    // Signing doesn't change the estimate
+    wallet.sign(&signers[0], &mut pset);
+    wallet.sign(&signers[2], &mut pset);
+    assert_eq!(
+        wallet
+            .wollet
+            .estimate_multisig_final_vsize(&pset, 2)
+            .unwrap(),
+        estimate
+    );
+
+    // Signatures can be shorter than the maximum size
+    let tx = wallet.wollet.finalize(&mut pset.clone()).unwrap();
+    let actual = tx.vsize();
+    assert!(estimate >= actual, "{estimate} < {actual}");
+    assert!(estimate - actual <= n_inputs, "{estimate} - {actual}");
+
+    // Finalized inputs are counted as they are
+    let mut finalized = pset.clone();
+    wallet.wollet.finalize(&mut finalized).unwrap();
+    assert_eq!(
+        wallet
+            .wollet
+            .estimate_multisig_final_vsize(&finalized, 2)
+            .unwrap(),
+        actual
+    );
+    wallet.send(&mut pset);
+}
+
+#[test]
+fn blind_pset() {
+    let server = setup();
+    let signer = generate_signer();
+    let view_key = generate_view_key();
+    let desc = format!("ct({},elwpkh({}/*))", view_key, signer.xpub());
+    let signers = [&AnySigner::Software(signer)];
+
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc);
+    wallet.fund_btc(&server);
+
+    let node_address = server.elementsd_getnewaddress();
+    let blinded = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let expected = wallet.wollet.get_details(&blinded).unwrap();
+
+    // Strip the blinding data, as in a PSET built by someone else
+    let mut pset = blinded.clone();
+    for output in pset.outputs_mut() {
+        output.asset_comm = None;
+        output.amount_comm = None;
+        output.value_rangeproof = None;
+        output.asset_surjection_proof = None;
+        output.ecdh_pubkey = None;
+        output.blind_value_proof = None;
+        output.blind_asset_proof = None;
+        output.blinder_index = None;
+    }
+    let status = lwk_common::pset_blinding_status(&pset);
+    assert!(!status.all_outputs_blinded);
+    assert!(wallet.wollet.get_details(&pset).is_err());
+
+    wallet.wollet.blind_pset(&mut pset).unwrap();
+    assert!(lwk_common::pset_blinding_status(&pset).all_outputs_blinded);
+    // The blind proofs are verified computing the balance
+    let details = wallet.wollet.get_details(&pset).unwrap();
+    assert_eq!(details.balance.balances, expected.balance.balances);
+    assert_eq!(details.balance.fee, expected.balance.fee);
+
+    // Blinding twice is rejected
+    let err = wallet.wollet.blind_pset(&mut pset.clone()).unwrap_err();
+    assert!(matches!(err, Error::OutputAlreadyBlinded(0)), "{err}");
+
+    for signer in signers {
+        wallet.sign(signer, &mut pset);
+    }
+    wallet.send(&mut pset);
+}
+
+#[test]
+fn jade_sign_thresh_policy() {
+    let server = setup();
+    let mnemonic = TEST_MNEMONIC;
+    let docker = Cli::default();
+    let jade_init = jade_setup(&docker, mnemonic);
+    let xpub_identifier = jade_init.jade.identifier().unwrap();
+    let jade_signer = AnySigner::Jade(jade_init.jade, xpub_identifier);
+    let sw_signers: Vec<_> = (0..2)
+        .map(|_| AnySigner::Software(generate_signer()))
+        .collect();
+
+    let keys: Vec<_> = std::iter::once(&jade_signer)
+        .chain(sw_signers.iter())
+        .map(|s| {
+            let fingerprint = s.fingerprint().unwrap();
+            let path_str = "/84h/1h/0h";
+            let path = DerivationPath::from_str(&format!("m{path_str}")).unwrap();
+            let xpub = s.derive_xpub(&path).unwrap();
+            format!("[{fingerprint}{path_str}]{xpub}/<0;1>/*")
+        })
+        .collect();
+    let slip77_key = "9c8e4f05c7711a98c838be228bcb84924d4570ca53f35fa1c793e58841d47023";
+    let desc_str = format!(
+        "ct(slip77({slip77_key}),elwsh(thresh(2,pk({}),s:pk({}),s:pk({}))))",
+        keys[0], keys[1], keys[2]
+    );
+
+    // Jade can't register the policy, but can sign its inputs
+    let desc: WolletDescriptor = desc_str.parse().unwrap();
+    let err = lwk_jade::register_multisig::JadeDescriptor::try_from(desc.as_ref()).unwrap_err();
+    assert!(err.to_string().contains("'thresh'"), "{err}");
+
+    let client = test_client_electrum(&server.electrs.electrum_url);
+    let mut wallet = TestWollet::new(client, &desc_str);
+    wallet.fund_btc(&server);
+
+    let node_address = server.elementsd_getnewaddress();
+    let mut pset = wallet
+        .tx_builder()
+        .add_lbtc_recipient(&node_address, 10_000)
+        .unwrap()
+        .finish()
+        .unwrap();
+    wallet.sign(&jade_signer, &mut pset);
+    wallet.sign(&sw_signers[0], &mut pset);
+    wallet.send(&mut pset);
+}
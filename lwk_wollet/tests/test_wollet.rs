@@ -139,7 +139,7 @@ impl<C: BlockchainBackend> TestWollet<C> {
         *balance.get(asset).unwrap_or(&0u64)
     }
 
-    fn balance_btc(&mut self) -> u64 {
+    pub fn balance_btc(&mut self) -> u64 {
         self.balance(&self.wollet.policy_asset())
     }
 